@@ -0,0 +1,284 @@
+//! Deals with argument specs: describing the arguments a command takes,
+//! and parsing them into typed values instead of indexing into words.
+
+use crate::backend::{ArgumentError, Command, CommandType, Time};
+use serenity::model::prelude::UserId;
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// The type an argument has to parse as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A user mention or ID
+    User,
+    /// A duration, such as `1h30m`
+    Time,
+    /// A whole number
+    Integer,
+    /// Any single word
+    Word,
+}
+
+impl Display for ArgKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgKind::User => write!(f, "user"),
+            ArgKind::Time => write!(f, "duration"),
+            ArgKind::Integer => write!(f, "number"),
+            ArgKind::Word => write!(f, "word"),
+        }
+    }
+}
+
+/// A single argument of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arg {
+    /// The name of the argument, which is also its key if it's a keyword argument
+    pub name: &'static str,
+    /// The type the argument has to parse as
+    pub kind: ArgKind,
+    /// Whether the argument has to be given
+    pub required: bool,
+}
+
+impl Arg {
+    /// Creates an argument that has to be given.
+    pub const fn required(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+        }
+    }
+    /// Creates an argument that can be left out.
+    pub const fn optional(name: &'static str, kind: ArgKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+        }
+    }
+}
+
+/// A typed argument value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgValue {
+    /// A user ID
+    User(UserId),
+    /// A duration
+    Time(Time),
+    /// A whole number
+    Integer(i64),
+    /// A single word
+    Word(String),
+}
+
+impl ArgValue {
+    /// Parses a word as the given kind of argument.
+    pub fn parse(kind: ArgKind, word: &str) -> Option<Self> {
+        match kind {
+            ArgKind::User => UserId::from_str(word).ok().map(ArgValue::User),
+            ArgKind::Time => Time::from_str(word).ok().map(ArgValue::Time),
+            ArgKind::Integer => word.parse().ok().map(ArgValue::Integer),
+            ArgKind::Word => Some(ArgValue::Word(word.to_owned())),
+        }
+    }
+}
+
+/// Describes every argument a command takes:
+/// positional arguments in order, `key:value` keyword arguments anywhere,
+/// and optionally the rest of the words as free text.
+///
+/// Arguments are numbered from 1 in that same order, positional first,
+/// matching the order they're written in the command's usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgSpec {
+    /// Arguments given in order
+    pub positional: &'static [Arg],
+    /// Arguments given as `key:value`
+    pub keywords: &'static [Arg],
+    /// The name of the free text after the positional arguments, if any is allowed
+    pub rest: Option<&'static str>,
+}
+
+/// The arguments of a command, parsed against an [`ArgSpec`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParsedArgs {
+    values: Vec<(&'static str, ArgValue)>,
+    /// The free text after the positional arguments, words joined by single spaces
+    pub rest: String,
+}
+
+impl ParsedArgs {
+    /// Gets an argument by name, if it was given.
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        self.values
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value)
+    }
+    /// Gets a user argument by name, if it was given.
+    pub fn user(&self, name: &str) -> Option<UserId> {
+        match self.get(name)? {
+            ArgValue::User(user) => Some(*user),
+            _ => None,
+        }
+    }
+    /// Gets a duration argument by name, if it was given.
+    pub fn time(&self, name: &str) -> Option<Time> {
+        match self.get(name)? {
+            ArgValue::Time(time) => Some(*time),
+            _ => None,
+        }
+    }
+    /// Gets a number argument by name, if it was given.
+    pub fn integer(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            ArgValue::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+    /// Gets a word argument by name, if it was given.
+    pub fn word(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ArgValue::Word(word) => Some(word),
+            _ => None,
+        }
+    }
+}
+
+/// Represents arguments that don't fit an [`ArgSpec`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgSpecError {
+    /// A required argument wasn't given
+    Missing {
+        #[doc = "the position of the argument, starting at 1"]
+        position: usize,
+        #[doc = "the name of the argument"]
+        name: &'static str,
+    },
+    /// An argument didn't parse as its type
+    WrongType {
+        #[doc = "the position of the argument, starting at 1"]
+        position: usize,
+        #[doc = "the name of the argument"]
+        name: &'static str,
+        #[doc = "the type the argument has to parse as"]
+        kind: ArgKind,
+        #[doc = "what was given instead"]
+        found: String,
+    },
+    /// More words were given than the command takes
+    Extra {
+        #[doc = "the first word that wasn't expected"]
+        found: String,
+    },
+}
+
+impl ArgSpecError {
+    /// Turns the error into a [`Command::NotValid`] showing the command's usage.
+    pub fn into_command(self, command: CommandType) -> Command {
+        match self {
+            ArgSpecError::Missing { position, .. } => {
+                ArgumentError::missing(command, position).into()
+            }
+            ArgSpecError::WrongType { position, .. } => {
+                ArgumentError::new(command, position).into()
+            }
+            ArgSpecError::Extra { found } => Command::NotValid(format!(
+                "too many arguments, starting at `{found}`; usage: `{}`",
+                command.usage()
+            )),
+        }
+    }
+}
+
+impl Display for ArgSpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgSpecError::Missing { position, name } => {
+                write!(f, "argument {position} ({name}) is missing")
+            }
+            ArgSpecError::WrongType {
+                position,
+                name,
+                kind,
+                found,
+            } => write!(
+                f,
+                "argument {position} ({name}) has to be a {kind}, but was `{found}`"
+            ),
+            ArgSpecError::Extra { found } => {
+                write!(f, "too many arguments, starting at `{found}`")
+            }
+        }
+    }
+}
+
+impl Error for ArgSpecError {}
+
+impl ArgSpec {
+    /// Parses a command's arguments, not including the command's name.
+    pub fn parse(&self, args: &[String]) -> Result<ParsedArgs, ArgSpecError> {
+        let mut parsed = ParsedArgs::default();
+        let mut words = Vec::new();
+        for word in args {
+            let keyword = word.split_once(':').and_then(|(key, value)| {
+                let index = self
+                    .keywords
+                    .iter()
+                    .position(|arg| arg.name.eq_ignore_ascii_case(key))?;
+                Some((index, value))
+            });
+            let Some((index, value)) = keyword else {
+                words.push(word.as_str());
+                continue;
+            };
+            let arg = self.keywords[index];
+            let position = self.positional.len() + index + 1;
+            parsed
+                .values
+                .push((arg.name, Self::value(arg, position, value)?));
+        }
+        for (index, arg) in self.keywords.iter().enumerate() {
+            if arg.required && parsed.get(arg.name).is_none() {
+                return Err(ArgSpecError::Missing {
+                    position: self.positional.len() + index + 1,
+                    name: arg.name,
+                });
+            }
+        }
+        for (index, arg) in self.positional.iter().enumerate() {
+            match words.get(index) {
+                Some(word) => parsed
+                    .values
+                    .push((arg.name, Self::value(*arg, index + 1, word)?)),
+                None if arg.required => {
+                    return Err(ArgSpecError::Missing {
+                        position: index + 1,
+                        name: arg.name,
+                    })
+                }
+                None => {}
+            }
+        }
+        let rest = words.get(self.positional.len()..).unwrap_or_default();
+        match (self.rest, rest.first()) {
+            (None, Some(found)) => {
+                return Err(ArgSpecError::Extra {
+                    found: (*found).to_owned(),
+                })
+            }
+            (Some(_), _) => parsed.rest = rest.join(" "),
+            (None, None) => {}
+        }
+        Ok(parsed)
+    }
+    fn value(arg: Arg, position: usize, word: &str) -> Result<ArgValue, ArgSpecError> {
+        ArgValue::parse(arg.kind, word).ok_or_else(|| ArgSpecError::WrongType {
+            position,
+            name: arg.name,
+            kind: arg.kind,
+            found: word.to_owned(),
+        })
+    }
+}
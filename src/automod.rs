@@ -0,0 +1,216 @@
+//! Deals with automod: checking messages from non-mods against per-guild rules,
+//! such as a word filter or an invite filter, and removing the ones that break them.
+
+use crate::{
+    database::{query_database, Database},
+    settings::guild_setting,
+    shard::BotShard,
+};
+use eyre::Result;
+use std::fmt::Display;
+
+/// A term in a guild's word filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterTerm {
+    /// The blocked text, in lowercase
+    pub term: String,
+    /// Whether the term only matches whole words, rather than anywhere in a message
+    pub whole_word: bool,
+}
+
+impl FilterTerm {
+    /// Returns whether a message contains the term, ignoring case.
+    pub fn matches(&self, content: &str) -> bool {
+        let content = content.to_lowercase();
+        if !self.whole_word {
+            return content.contains(&self.term);
+        }
+        let is_word_char = |chr: Option<char>| chr.is_some_and(char::is_alphanumeric);
+        content.match_indices(&self.term).any(|(start, term)| {
+            let before = content[..start].chars().next_back();
+            let after = content[start + term.len()..].chars().next();
+            !is_word_char(before) && !is_word_char(after)
+        })
+    }
+}
+
+/// A change to a guild's invite filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InviteFilterAction {
+    /// Turns the filter on or off
+    Enable(bool),
+    /// Allows or disallows an invite code
+    Allow(String, bool),
+}
+
+/// A change to a guild's word filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Blocks a term
+    Add(FilterTerm),
+    /// Unblocks a term
+    Remove(String),
+    /// Lists every blocked term
+    List,
+}
+
+impl Display for FilterTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.whole_word {
+            true => write!(f, "`{}` (whole word)", self.term),
+            false => write!(f, "`{}` (anywhere)", self.term),
+        }
+    }
+}
+
+/// Gets every term in a guild's word filter.
+pub fn filter_terms(db: &Database, guild_id: u64) -> Result<Vec<FilterTerm>> {
+    let mut statement =
+        db.prepare("SELECT term, whole_word FROM word_filter WHERE guild = (?1) ORDER BY term")?;
+    let terms = statement
+        .query_map([guild_id], |row| {
+            Ok(FilterTerm {
+                term: row.get(0)?,
+                whole_word: row.get(1)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(terms)
+}
+
+/// Adds a term to a guild's word filter, replacing it if it's already there.
+pub fn add_filter_term(db: &Database, guild_id: u64, term: &FilterTerm) -> Result<()> {
+    db.execute(
+        "INSERT OR REPLACE INTO word_filter (guild, term, whole_word) VALUES ((?1), (?2), (?3))",
+        (guild_id, &term.term, term.whole_word),
+    )?;
+    Ok(())
+}
+
+/// Removes a term from a guild's word filter, returning whether it was there.
+pub fn remove_filter_term(db: &Database, guild_id: u64, term: &str) -> Result<bool> {
+    let removed = db.execute(
+        "DELETE FROM word_filter WHERE guild = (?1) AND term = (?2)",
+        (guild_id, term.to_lowercase()),
+    )?;
+    Ok(removed > 0)
+}
+
+/// The setting holding whether invite links are removed.
+pub const INVITE_FILTER: &str = "invite_filter";
+
+/// The ways a discord invite link can start, without the scheme.
+const INVITE_PREFIXES: [&str; 5] = [
+    "discord.gg/",
+    "discord.com/invite/",
+    "discordapp.com/invite/",
+    "discord.me/",
+    "discord.io/",
+];
+
+/// Finds the codes of every discord invite link in a message,
+/// with or without `https://` or `www.`.
+/// Codes are returned as written, since they're case-sensitive.
+pub fn find_invites(content: &str) -> Vec<String> {
+    let lowercase = content.to_lowercase();
+    let mut invites = Vec::new();
+    for prefix in INVITE_PREFIXES {
+        for (start, _) in lowercase.match_indices(prefix) {
+            let preceding = lowercase[..start].chars().next_back();
+            if preceding.is_some_and(|chr| chr.is_alphanumeric() || chr == '.' || chr == '-') {
+                let host = &lowercase[..start];
+                if !host.ends_with("www.") && !host.ends_with("ptb.") && !host.ends_with("canary.")
+                {
+                    continue;
+                }
+            }
+            let code = content[start + prefix.len()..]
+                .chars()
+                .take_while(|chr| chr.is_ascii_alphanumeric() || *chr == '-')
+                .collect::<String>();
+            if !code.is_empty() {
+                invites.push(code);
+            }
+        }
+    }
+    invites
+}
+
+/// Returns whether a guild removes invite links.
+pub fn invite_filter_enabled(db: &Database, guild_id: u64) -> Result<bool> {
+    Ok(guild_setting(db, guild_id, INVITE_FILTER)?.is_some_and(|enabled| enabled == "true"))
+}
+
+/// Gets every invite code a guild allows through its invite filter.
+pub fn allowed_invites(db: &Database, guild_id: u64) -> Result<Vec<String>> {
+    let mut statement =
+        db.prepare("SELECT code FROM allowed_invites WHERE guild = (?1) ORDER BY code")?;
+    let codes = statement
+        .query_map([guild_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(codes)
+}
+
+/// Sets whether a guild allows an invite code through its invite filter.
+pub fn set_invite_allowed(db: &Database, guild_id: u64, code: &str, allowed: bool) -> Result<()> {
+    match allowed {
+        true => db.execute(
+            "INSERT OR IGNORE INTO allowed_invites (guild, code) VALUES ((?1), (?2))",
+            (guild_id, code),
+        )?,
+        false => db.execute(
+            "DELETE FROM allowed_invites WHERE guild = (?1) AND code = (?2)",
+            (guild_id, code),
+        )?,
+    };
+    Ok(())
+}
+
+/// Gets the reason a message breaks a guild's automod rules, if it does.
+pub fn violation(db: &Database, guild_id: u64, content: &str) -> Result<Option<String>> {
+    let blocked = filter_terms(db, guild_id)?
+        .into_iter()
+        .any(|term| term.matches(content));
+    if blocked {
+        return Ok(Some("it contained a filtered word".to_owned()));
+    }
+    if invite_filter_enabled(db, guild_id)? {
+        let allowed = allowed_invites(db, guild_id)?;
+        if find_invites(content)
+            .iter()
+            .any(|code| !allowed.contains(code))
+        {
+            return Ok(Some("it contained an invite link".to_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks a message against its guild's automod rules,
+/// deleting it and warning the author if it breaks them.
+/// Returns whether the message was removed.
+/// Messages from bots, mods, and outside of guilds are never removed.
+pub async fn moderate(shard: &BotShard<'_>) -> Result<bool> {
+    let message = shard.original_message();
+    let Ok(guild_id) = shard.guild_id() else {
+        return Ok(false);
+    };
+    if message.author.bot {
+        return Ok(false);
+    }
+    let db = query_database()?;
+    let Some(reason) = violation(&db, guild_id, &message.content)? else {
+        return Ok(false);
+    };
+    if shard.user_is_mod(message.author.id.0).await? {
+        return Ok(false);
+    }
+    message.delete(shard.http_server()).await?;
+    shard
+        .send_message(format!(
+            "{}, your message was removed because {reason}.",
+            message.author
+        ))
+        .await?;
+    Ok(true)
+}
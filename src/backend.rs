@@ -1,17 +1,93 @@
 //! deals with parsing and preforming commands,
 //! particularly with the [`Command`] enum.
 
-use crate::shard::BotShard;
+use crate::{
+    args::{Arg, ArgKind, ArgSpec},
+    automod::{
+        add_filter_term, filter_terms, find_invites, remove_filter_term, set_invite_allowed,
+        FilterAction, FilterTerm, InviteFilterAction, INVITE_FILTER,
+    },
+    casefile::{case_reference, CaseFile},
+    config,
+    database::{
+        backup_database, query_database, query_database_read_only, restore_database, run_select,
+        Database, BACKUP_DIRECTORY,
+    },
+    diagnostics::ProcessStats,
+    emojis::{
+        emoji_name, emoji_pages, parse_emoji_reference, valid_emoji_name, MAX_EMOJI_NAME_LENGTH,
+        MIN_EMOJI_NAME_LENGTH,
+    },
+    giveaways::{
+        add_giveaway, giveaway, pick_winners, winners_message, GiveawayAction, GIVEAWAY_EMOJI,
+        MAX_GIVEAWAY_WINNERS,
+    },
+    history::{history_embed, timeline},
+    keke::{
+        forget_original_nickname, kekeable_users, original_nickname, set_opted_in, NicknameReset,
+    },
+    lockdown::{lock_channels, unlock_channels},
+    massban::{skip_reason, MassBanOutcome, MassBanReport, MAX_MASSBAN},
+    members::{match_members, GoodbyeSetting, WelcomeSetting, AUTOROLE, MAX_MEMBER_MATCHES},
+    modactions::{
+        log_mod_action, recent_mod_actions, user_mod_actions, ModAction, ModLogQuery,
+        MAX_RECENT_ACTIONS,
+    },
+    modlog::{field_value, ModLogSetting},
+    notes::{add_note, note_lines, notes, remove_note, NoteAction},
+    pagination::paginate,
+    permissions::{permission_report, preflight, Preflight},
+    purge::{Purge, BULK_DELETE_LIMIT, MAX_PURGE_SCAN},
+    raid::RaidConfig,
+    reminders::{add_reminder, parse_reminder, ReminderTarget},
+    report::{file_report, report_embed, REPORT_COOLDOWN, REPORT_COOLDOWN_SECONDS},
+    roles::{
+        add_reaction_role, emoji_key, resolve_role, role_refusal, self_role_allowed,
+        set_self_role_allowed, RoleAction,
+    },
+    rules::{
+        format_rule, remove_rule, rule, rule_reference, rule_reminder, rules, set_rule, RuleEdit,
+    },
+    settings::{
+        clear_guild_setting, command_enabled, guild_setting, mod_channel, set_guild_setting,
+        toggle_command, MOD_CHANNEL,
+    },
+    sink::{with_typing, CommandSink, Embed, Response},
+    snipe::{EDIT_SNIPES, SNIPES, SNIPE_NSFW},
+    softban::{softban_notice, SOFTBAN_DELETE_DAYS},
+    spam::SpamConfig,
+    starboard::StarboardSetting,
+    stats::{log_command, CommandStats},
+    tags::{
+        fill_placeholders, remove_tag, set_tag, tag, tag_key, tags, TagAction, RESERVED_TAG_NAMES,
+    },
+    userinfo::{current_timeout, ModerationSummary, UserInfo},
+    warnings::{
+        active_warning_count, add_warning, clear_warnings, escalation_for, escalation_policy,
+        set_escalation, set_warn_expiry, warn_expiry, warnings, Escalation,
+    },
+};
 use chrono::Duration;
 use eyre::Result;
 use indoc::indoc;
-use rand::random;
+use rand::{random, thread_rng, Rng};
 use serenity::{
-    model::prelude::{Timestamp, UserId},
+    model::{
+        prelude::{ChannelId, Timestamp, UserId},
+        Permissions,
+    },
     Error as SerenityError,
 };
 use std::{
-    convert::Infallible, error::Error, fmt::Display, fs as files, num::ParseIntError, str::FromStr,
+    cmp::Ordering,
+    convert::Infallible,
+    error::Error,
+    fmt::Display,
+    future::Future,
+    num::{ParseFloatError, ParseIntError},
+    ops::{Add, Sub},
+    str::FromStr,
+    sync::Mutex,
     time::Duration as StdDuration,
 };
 
@@ -21,6 +97,50 @@ pub const PREFIX: &str = "-";
 /// The ID for the current developer of the bot.
 /// Used to validate [`Command::Dev`] commands.
 pub const CAMILA: u64 = 284883095981916160;
+/// The most coins [`Command::CoinFlip`] will flip at once.
+pub const MAX_COIN_FLIPS: u8 = 20;
+/// The longest nickname discord allows, in characters.
+pub const MAX_NICKNAME_LENGTH: usize = 32;
+/// The `-role` subcommands dealing with self-assignable roles.
+const SELF_ROLE_SUBCOMMANDS: [&str; 4] = ["give", "take", "allow", "disallow"];
+/// The setting holding the sentence posted before the `-da2a` link, if any.
+pub const DA2A_PREAMBLE: &str = "da2a_preamble";
+/// The sentence posted before the `-da2a` link when turned on without any text.
+pub const DEFAULT_DA2A_PREAMBLE: &str = "Please read this before asking:";
+/// The link `-da2a` posts.
+pub const DA2A_LINK: &str = "https://dontasktoask.com/";
+/// The arguments `-ban` takes.
+pub const BAN_ARGS: ArgSpec = ArgSpec {
+    positional: &[Arg::required("user", ArgKind::User)],
+    keywords: &[],
+    rest: Some("reason"),
+};
+/// The arguments `-mute` takes.
+pub const MUTE_ARGS: ArgSpec = ArgSpec {
+    positional: &[
+        Arg::required("user", ArgKind::User),
+        Arg::required("time", ArgKind::Time),
+    ],
+    keywords: &[],
+    rest: Some("reason"),
+};
+
+/// The arguments `-giveaway` takes when starting a giveaway.
+pub const GIVEAWAY_ARGS: ArgSpec = ArgSpec {
+    positional: &[Arg::required("time", ArgKind::Time)],
+    keywords: &[Arg::optional("winners", ArgKind::Integer)],
+    rest: Some("prize"),
+};
+/// The arguments `-purge` takes.
+pub const PURGE_ARGS: ArgSpec = ArgSpec {
+    positional: &[
+        Arg::required("filter", ArgKind::Word),
+        Arg::required("user", ArgKind::User),
+        Arg::required("count", ArgKind::Integer),
+    ],
+    keywords: &[],
+    rest: None,
+};
 
 /// A representation of a given bot command.
 #[derive(Debug, PartialEq, Eq)]
@@ -31,6 +151,8 @@ pub enum Command {
     Mute(UserId, Time, String),
     /// Gives a mod notice to the current channel
     Notice(String),
+    /// Posts a reminder of one of the server's rules
+    NoticeRule(u8),
     /// Gives a message privately to the staff bot channel
     PrivateModMessage {
         #[doc = "The message to send"]
@@ -42,8 +164,12 @@ pub enum Command {
     Xkcd(u64),
     /// Sends, literally, https://dontasktoask.com/
     DontAskToAsk,
+    /// Sets the sentence posted before the `-da2a` link, or turns it off if [`None`]
+    DontAskToAskPreamble(Option<String>),
     /// Help Command
     Help(Option<CommandType>),
+    /// Searches the help for commands matching a term
+    HelpSearch(String),
     /// A suggestion for the bot
     Suggestion(String),
     /// The command wasn't valid (for one reason or another)
@@ -52,301 +178,2525 @@ pub enum Command {
     NotACommand,
     /// A developer command
     Dev(String),
-    /// A single coin flip
-    CoinFlip,
-    /// A randomly generated integer from 0 to [the field]
-    RandomInt(u64),
+    /// Flips [the field] coins
+    CoinFlip(u8),
+    /// A randomly generated integer from the first field to the second, inclusive
+    RandomInt(u64, u64),
     /// Opt into getting keke'd
     Optin,
     /// Opt out of get keke'd
     Optout,
     /// Sends a link to the original "get keke'd" video
     Keke,
+    /// Lists everyone opted into getting keke'd
+    KekeList,
+    /// Summarizes how the bot has been used in the current server
+    Stats,
+    /// Shows how a duration is parsed, to check it before using it
+    TimeParse(Time),
+    /// Sets a member's nickname, or resets it if no name is given
+    Nickname {
+        #[doc = "The member to rename"]
+        user: UserId,
+        #[doc = "The new nickname, or [`None`] to reset it"]
+        name: Option<String>,
+    },
+    /// Gives a role to or takes a role from a member
+    Role {
+        #[doc = "Whether to give or take the role"]
+        action: RoleAction,
+        #[doc = "The member to change"]
+        user: UserId,
+        #[doc = "The role, as an ID, mention, or name"]
+        role: String,
+    },
+    /// Gives a self-assignable role to or takes it from the author
+    SelfRole {
+        #[doc = "Whether to give or take the role"]
+        action: RoleAction,
+        #[doc = "The role, as an ID, mention, or name"]
+        role: String,
+    },
+    /// Allows or disallows a role from being self-assigned
+    SelfRoleAllow {
+        #[doc = "Whether the role can be self-assigned"]
+        allowed: bool,
+        #[doc = "The role, as an ID, mention, or name"]
+        role: String,
+    },
+    /// Gives a role to anyone who reacts to a message with an emoji
+    ReactionRole {
+        #[doc = "The message to watch"]
+        link: MessageLink,
+        #[doc = "The emoji to watch for"]
+        emoji: String,
+        #[doc = "The role, as an ID, mention, or name"]
+        role: String,
+    },
+    /// Changes the starboard settings
+    Starboard(StarboardSetting),
+    /// Changes the welcome message settings
+    Welcome(WelcomeSetting),
+    /// Changes the goodbye message settings
+    Goodbye(GoodbyeSetting),
+    /// Sets the role given to new members, or turns it off if [`None`]
+    Autorole(Option<String>),
+    /// Changes the raid protection settings
+    AntiRaid(AntiRaidSetting),
+    /// Changes the word filter
+    Filter(FilterAction),
+    /// Changes the invite filter
+    InviteFilter(InviteFilterAction),
+    /// Changes a guild's anti-spam settings
+    AntiSpam(Option<SpamConfig>),
+    /// Warns a user
+    Warn(UserId, String),
+    /// Lists a user's warnings
+    Warns(UserId),
+    /// Deletes a user's warnings
+    ClearWarns(UserId),
+    /// Changes how long warnings count for
+    WarnExpiry(Option<Time>),
+    /// Changes what happens automatically after enough warnings
+    Escalation(EscalationSetting),
+    /// Stops everyone from posting in a channel
+    Lockdown {
+        #[doc = "whether every text channel is affected, rather than just this one"]
+        all: bool,
+    },
+    /// Lets everyone post in a locked channel again
+    Unlock {
+        #[doc = "whether every text channel is affected, rather than just this one"]
+        all: bool,
+    },
+    /// Posts an announcement in several channels
+    Announce {
+        #[doc = "the channels to post in"]
+        channels: Vec<ChannelId>,
+        #[doc = "the announcement"]
+        message: String,
+    },
+    /// Reminds the user of something later, by DM or in the channel
+    Remind {
+        #[doc = "where the reminder is delivered"]
+        target: ReminderTarget,
+        #[doc = "how long until the reminder is due"]
+        time: Time,
+        #[doc = "what to remind the user of"]
+        text: String,
+    },
+    /// Starts or rerolls a giveaway
+    Giveaway(GiveawayAction),
+    /// Posts, saves, deletes or lists tags
+    Tag(TagAction),
+    /// Looks up the IDs of members by name
+    UserId(String),
+    /// Shows the details of a channel
+    ChannelInfo(Option<ChannelId>),
+    /// Lists the guild's custom emojis
+    Emojis,
+    /// Copies a custom emoji into the guild
+    StealEmoji {
+        #[doc = "the emoji to copy, as written in a message"]
+        emoji: String,
+        #[doc = "the name to give the copy, if not the original's"]
+        name: Option<String>,
+    },
+    /// Reposts the last message deleted in the channel
+    Snipe,
+    /// Sets whether messages deleted in NSFW channels can be sniped
+    SnipeNsfw(bool),
+    /// Shows the last edit made in the channel
+    EditSnipe,
+    /// Sets up the mod log
+    ModLog(ModLogSetting),
+    /// Looks up logged moderation actions
+    ModLogSearch(ModLogQuery),
+    /// Deletes every message sent after a message
+    PurgeFrom(MessageLink),
+    /// Deletes a user's recent messages in the channel
+    Purge {
+        #[doc = "the user whose messages are deleted"]
+        user: UserId,
+        #[doc = "how many of their messages to delete"]
+        count: usize,
+    },
+    /// Posts the server's rules, or a single rule by number
+    Rules(Option<u8>),
+    /// Sets or deletes one of the server's rules
+    RuleEdit(RuleEdit),
+    /// Flags a message for the mods, with a reason
+    Report {
+        #[doc = "the message being reported"]
+        message_link: MessageLink,
+        #[doc = "why the message is being reported"]
+        reason: String,
+    },
+    /// Adds, lists or deletes private notes about a user
+    Note(NoteAction),
+    /// Shows a summary of a user
+    UserInfo(Option<UserId>),
+    /// Shows everything the mods have done about a user
+    History(UserId),
+    /// Bans several users at once, with a reason
+    MassBan {
+        #[doc = "the users to ban"]
+        users: Vec<UserId>,
+        #[doc = "why they're banned"]
+        reason: String,
+    },
+    /// Bans and immediately unbans a user, clearing their recent messages
+    Softban(UserId, String),
+}
+
+/// Checks whether the bot can use the `required` permission on `target`.
+/// Outside a guild, or if the check itself fails (which is logged), the action goes ahead.
+async fn bot_preflight(sink: &impl CommandSink, required: Permissions, target: u64) -> Preflight {
+    if sink.guild_id().is_none() {
+        return Preflight::Proceed;
+    }
+    let check = async {
+        let permissions = sink.bot_permissions().await?;
+        let positions = sink.role_positions(target).await?;
+        Ok::<_, eyre::Report>(preflight(permissions, required, positions))
+    };
+    check.await.unwrap_or_else(|e| {
+        eprintln!("Unable to check the bot's permissions: {e}");
+        Preflight::Proceed
+    })
+}
+
+/// Returns whether a channel really belongs to the guild the command was sent in,
+/// rather than trusting whatever guild a user typed alongside it.
+async fn in_this_guild(sink: &impl CommandSink, channel_id: u64) -> Result<bool> {
+    Ok(sink.guild_id().is_some() && sink.channel_guild(channel_id).await? == sink.guild_id())
+}
+
+/// Checks whether the command's author may hand out a role through the bot.
+/// Returns why not, if they can't; see [`role_refusal`].
+async fn role_guard(sink: &impl CommandSink, role_id: u64) -> Result<Option<&'static str>> {
+    let role = sink.role_standing(role_id).await?;
+    let positions = sink.role_positions(sink.author_id()).await?;
+    Ok(role_refusal(
+        role,
+        positions.target.unwrap_or_default(),
+        positions.bot,
+    ))
 }
 
 impl Command {
     /// Tells a command that a moderator role is required.
     /// If the role is not present, the command is turned into [`Command::NotValid`],
     /// else the command is returned unchanged.
-    pub async fn requires_mod(self, shard: BotShard<'_>) -> Self {
-        if let Ok(b) = shard.user_is_mod(shard.author().id.0).await {
+    pub async fn requires_mod(self, sink: &impl CommandSink) -> Self {
+        if let Ok(b) = sink.author_is_mod().await {
             match b {
                 true => self,
-                false => match self {
-                    Self::Ban(..) | Self::Mute(..) | Self::Notice(..) => {
-                        Self::NotValid("User is not a moderator!".to_owned())
-                    }
-                    elsewise => elsewise,
-                },
+                false if self.mod_only() => Self::NotValid("User is not a moderator!".to_owned()),
+                false => self,
             }
         } else {
             Self::NotValid("Could not determine whether the user is a mod, so I'm falling back to not allowing it.".to_owned())
         }
     }
+    /// Returns whether a given command can only be used by moderators.
+    /// Most commands are restricted as a whole (see [`CommandType::mod_only`]),
+    /// but some, like `-role`, have subcommands anyone can use.
+    pub fn mod_only(&self) -> bool {
+        CommandType::from(self).mod_only()
+            || matches!(
+                self,
+                Command::Role { .. }
+                    | Command::SelfRoleAllow { .. }
+                    | Command::Tag(TagAction::Add { .. } | TagAction::Remove(_))
+                    | Command::SnipeNsfw(_)
+                    | Command::KekeList
+                    | Command::DontAskToAskPreamble(_)
+                    | Command::RuleEdit(_)
+            )
+    }
     /// Tells a command that being the developer is required.
     /// If the developer did not issue the statement,
     /// the command is turned into [`Command::NotValid`].
-    pub async fn requires_dev(self, shard: BotShard<'_>) -> Self {
-        if shard.author_id().await == CAMILA {
+    pub fn requires_dev(self, sink: &impl CommandSink) -> Self {
+        if sink.author_id() == CAMILA {
             self
         } else {
             Self::NotValid("User is not the dev!".to_owned())
         }
     }
-    /// Parses a command given a [`Context`] and a sent [`Message`].
-    pub async fn parse_from_message(shard: BotShard<'_>) -> Self {
-        if !shard.original_message().content.starts_with(PREFIX) {
+    /// Tells a mod action that the bot has to be able to carry it out:
+    /// it needs the right permission, and has to outrank the target.
+    /// If it can't, the command is turned into [`Command::NotValid`] explaining why.
+    /// If that can't be checked, the command is returned unchanged.
+    pub async fn requires_bot_permissions(self, sink: &impl CommandSink) -> Self {
+        let (required, target) = match &self {
+            Command::Ban(user, _) | Command::Softban(user, _) => (Permissions::BAN_MEMBERS, user.0),
+            Command::Mute(user, ..) => (Permissions::MODERATE_MEMBERS, user.0),
+            _ => return self,
+        };
+        match bot_preflight(sink, required, target).await {
+            Preflight::Proceed => self,
+            preflight => Self::NotValid(preflight.to_string()),
+        }
+    }
+    /// Tells a command that it must be enabled in the guild it was sent in.
+    /// If the command was disabled, it's turned into [`Command::NotValid`].
+    /// Commands sent outside a guild, and [`Command::Dev`] commands, are always enabled.
+    pub fn requires_enabled(self, db: &Database, guild_id: Option<u64>) -> Self {
+        let command_type = CommandType::from(&self);
+        let Some(guild_id) = guild_id else {
+            return self;
+        };
+        if command_type.aliases().is_empty() || command_type == CommandType::Dev {
+            return self;
+        }
+        match command_enabled(db, guild_id, command_type) {
+            Ok(false) => Self::NotValid(format!(
+                "`{PREFIX}{}` has been disabled in this server.",
+                command_type.name()
+            )),
+            _ => self,
+        }
+    }
+    /// Checks a command against where it was sent and who sent it.
+    /// Commands needing elevated permissions, or that are disabled in the guild,
+    /// are turned into [`Command::NotValid`].
+    /// The database is only read before the returned future starts,
+    /// so the future can still be sent between threads.
+    pub fn check_permissions<'a>(
+        self,
+        db: &Database,
+        sink: &'a impl CommandSink,
+    ) -> impl Future<Output = Self> + 'a {
+        let command = self.requires_enabled(db, sink.guild_id());
+        async move {
+            let command = match command {
+                command if command.mod_only() => command.requires_mod(sink).await,
+                command @ Command::Dev(..) => command.requires_dev(sink),
+                command => command,
+            };
+            command.requires_bot_permissions(sink).await
+        }
+    }
+    /// Runs a command however it was sent, be it a message or a slash command.
+    /// Permissions are checked first, and valid commands are recorded in the command log
+    /// at the given unix timestamp.
+    pub async fn dispatch(self, db: Database, sink: &impl CommandSink, now: i64) -> Result<()> {
+        let command = self.check_permissions(&db, sink).await;
+        let command_type = CommandType::from(&command);
+        if let CommandType::NotACommand | CommandType::NotValid | CommandType::Empty = command_type
+        {
+            return command.execute_command(sink).await;
+        }
+        let logged = log_command(&db, sink.guild_id(), sink.author_id(), command_type, now);
+        if let Err(e) = logged {
+            eprintln!("Unable to log command: {e}");
+        }
+        command.execute_command(sink).await
+    }
+    /// Parses a command from the content of a message, given the name of its author.
+    /// Unlike [`Command::check_permissions`], permissions are not checked.
+    pub fn parse(content: &str, author: &str) -> Self {
+        let content = content.trim_start();
+        if !content.starts_with(PREFIX) {
             return Command::NotACommand;
         }
-        let args = shard
-            .original_message()
-            .content
-            .split(|chr: char| chr.is_whitespace())
-            .collect::<Vec<_>>();
+        let args = match tokenize(content) {
+            Ok(args) => args,
+            Err(e) => return Command::NotValid(e.to_string()),
+        };
         if args.is_empty() {
             return Command::NotACommand;
         }
         match args[0]
+            .as_str()
             .strip_prefix(PREFIX)
             .expect("fn returns early if message starts with prefix")
             .parse::<CommandType>()
             .unwrap_or(CommandType::NotValid)
         {
             CommandType::Ban => {
-                let Ok(user_id) = UserId::from_str(args[1]) else {
-                    return Command::NotValid("Given user was not a valid UserID".to_owned());
+                let parsed = match BAN_ARGS.parse(args.get(1..).unwrap_or_default()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_command(CommandType::Ban),
+                };
+                let Some(user_id) = parsed.user("user") else {
+                    return ArgumentError::missing(CommandType::Ban, 1).into();
                 };
-                let reason = vec_str_to_string(&args, Some(1));
-                Command::Ban(user_id, reason).requires_mod(shard).await
+                Command::Ban(user_id, parsed.rest)
             }
             CommandType::Mute => {
-                let Ok(user_id) = UserId::from_str(args[1]) else {
-                    return Command::NotValid("Given user was not a valid UserID".to_owned());
+                let parsed = match MUTE_ARGS.parse(args.get(1..).unwrap_or_default()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_command(CommandType::Mute),
                 };
-                let Ok(time) = Time::from_str(args[2]) else {
-                    return Command::NotValid("Given time was invalid!".to_owned());
+                let (Some(user_id), Some(time)) = (parsed.user("user"), parsed.time("time")) else {
+                    return ArgumentError::missing(CommandType::Mute, 1).into();
                 };
-                Command::Mute(user_id, time, vec_str_to_string(&args, Some(3)))
-                    .requires_mod(shard)
-                    .await
-            }
-            CommandType::Notice => {
-                Command::Notice(vec_str_to_string(&args, Some(1)))
-                    .requires_mod(shard)
-                    .await
+                Command::Mute(user_id, time, parsed.rest)
             }
+            CommandType::Notice => match rule_reference(args.get(1..).unwrap_or_default()) {
+                Some(Ok(number)) => Command::NoticeRule(number),
+                Some(Err(())) => ArgumentError::new(CommandType::Notice, 1).into(),
+                None => Command::Notice(vec_string_to_string(&args, Some(1))),
+            },
             CommandType::PrivateModMessage => Command::PrivateModMessage {
-                message: vec_str_to_string(&args, Some(1)),
-                user: shard.original_message().author.name.clone(),
+                message: vec_string_to_string(&args, Some(1)),
+                user: author.to_owned(),
             },
             CommandType::Xkcd => {
-                Command::Xkcd(xkcd_from_string(&vec_str_to_string(&args, Some(1))))
+                Command::Xkcd(xkcd_from_string(&vec_string_to_string(&args, Some(1))))
+            }
+            CommandType::DontAskToAsk => {
+                match args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+                    Some("preamble") => {
+                        match args.get(2).map(|arg| arg.to_lowercase()).as_deref() {
+                            None => ArgumentError::missing(CommandType::DontAskToAsk, 2).into(),
+                            Some("off") => Command::DontAskToAskPreamble(None),
+                            Some("on") => Command::DontAskToAskPreamble(Some(
+                                DEFAULT_DA2A_PREAMBLE.to_owned(),
+                            )),
+                            Some(_) => Command::DontAskToAskPreamble(Some(vec_string_to_string(
+                                &args,
+                                Some(2),
+                            ))),
+                        }
+                    }
+                    _ => Command::DontAskToAsk,
+                }
+            }
+            CommandType::NotValid => {
+                let name = args[0].strip_prefix(PREFIX).unwrap_or(&args[0]);
+                match CommandType::closest(name) {
+                    Some(alias) => Command::NotValid(format!(
+                        "I couldn't parse the command! Did you mean `{PREFIX}{alias}`?"
+                    )),
+                    None => Command::NotValid("I couldn't parse the command!".to_owned()),
+                }
+            }
+            CommandType::NotACommand | CommandType::Empty => Command::NotACommand,
+            CommandType::Help if args.get(1).is_some_and(|arg| arg == "search") => {
+                Command::HelpSearch(vec_string_to_string(&args, Some(2)))
             }
-            CommandType::DontAskToAsk => Command::DontAskToAsk,
-            CommandType::NotValid => Command::NotValid("I couldn't parse the command!".to_owned()),
-            CommandType::NotACommand => Command::NotACommand,
             CommandType::Help => Command::Help({
                 if args.len() == 1 {
                     None
                 } else {
                     Some(
-                        vec_str_to_string(&args, Some(1))
+                        vec_string_to_string(&args, Some(1))
                             .parse()
                             .expect("Parsing a command is infallible"),
                     )
                 }
             }),
-            CommandType::Suggestion => Command::Suggestion(vec_str_to_string(&args, Some(1))),
-            CommandType::Dev => {
-                Command::Dev(vec_str_to_string(&args, Some(1)))
-                    .requires_dev(shard)
-                    .await
-            }
-            CommandType::CoinFlip => Command::CoinFlip,
+            CommandType::Suggestion => Command::Suggestion(vec_string_to_string(&args, Some(1))),
+            CommandType::Dev => Command::Dev(vec_string_to_string(&args, Some(1))),
+            CommandType::CoinFlip => match args.get(1) {
+                None => Command::CoinFlip(1),
+                Some(arg) => match arg
+                    .strip_prefix("count:")
+                    .and_then(|count| count.parse::<u8>().ok())
+                {
+                    Some(count @ 1..=MAX_COIN_FLIPS) => Command::CoinFlip(count),
+                    Some(_) => Command::NotValid(format!(
+                        "I can only flip between 1 and {MAX_COIN_FLIPS} coins at once!"
+                    )),
+                    None => ArgumentError::new(CommandType::CoinFlip, 1).into(),
+                },
+            },
             CommandType::RandomInt => {
-                if let Ok(int) = vec_str_to_string(&args, Some(1)).parse::<u64>() {
-                    Command::RandomInt(int)
+                let bounds = args[1..]
+                    .iter()
+                    .map(|arg| arg.parse::<u64>())
+                    .collect::<Vec<_>>();
+                match bounds.as_slice() {
+                    [] => ArgumentError::missing(CommandType::RandomInt, 2).into(),
+                    [Ok(max)] => Command::RandomInt(0, *max),
+                    [Err(_)] => ArgumentError::new(CommandType::RandomInt, 2).into(),
+                    [Ok(min), Ok(max)] if min > max => Command::NotValid(format!(
+                        "The minimum ({min}) can't be larger than the maximum ({max})!"
+                    )),
+                    [Ok(min), Ok(max)] => Command::RandomInt(*min, *max),
+                    [Err(_), _] => ArgumentError::new(CommandType::RandomInt, 1).into(),
+                    _ => ArgumentError::new(CommandType::RandomInt, 2).into(),
+                }
+            }
+            CommandType::Optin => Command::Optin,
+            CommandType::Optout => Command::Optout,
+            CommandType::Keke => match args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+                None => Command::Keke,
+                Some("list") => Command::KekeList,
+                Some(_) => ArgumentError::new(CommandType::Keke, 1).into(),
+            },
+            CommandType::Stats => Command::Stats,
+            CommandType::TimeParse => {
+                let time = vec_string_to_string(&args, Some(1));
+                if time.trim().is_empty() {
+                    return ArgumentError::missing(CommandType::TimeParse, 1).into();
+                }
+                match Time::from_str(&time) {
+                    Ok(time) => Command::TimeParse(time),
+                    Err(e) => Command::NotValid(format!("I couldn't parse that duration: {e}")),
+                }
+            }
+            CommandType::Nickname => {
+                let Some(user) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Nickname, 1).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::Nickname, 1).into();
+                };
+                let name = vec_string_to_string(&args, Some(2));
+                match name.as_str() {
+                    "" => ArgumentError::missing(CommandType::Nickname, 2).into(),
+                    "reset" => Command::Nickname { user, name: None },
+                    name if name.chars().count() > MAX_NICKNAME_LENGTH => Command::NotValid(
+                        format!("Nicknames can be at most {MAX_NICKNAME_LENGTH} characters long!"),
+                    ),
+                    name => Command::Nickname {
+                        user,
+                        name: Some(name.to_owned()),
+                    },
+                }
+            }
+            CommandType::Role
+                if args.len() > 1 && SELF_ROLE_SUBCOMMANDS.contains(&args[1].as_str()) =>
+            {
+                let role = vec_string_to_string(&args, Some(2));
+                if role.is_empty() {
+                    return Command::NotValid(format!("You need to name a role to {}!", args[1]));
+                }
+                match args[1].as_str() {
+                    "give" => Command::SelfRole {
+                        action: RoleAction::Add,
+                        role,
+                    },
+                    "take" => Command::SelfRole {
+                        action: RoleAction::Remove,
+                        role,
+                    },
+                    allow => Command::SelfRoleAllow {
+                        allowed: allow == "allow",
+                        role,
+                    },
+                }
+            }
+            CommandType::Role => {
+                let Some(action) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Role, 1).into();
+                };
+                let Ok(action) = RoleAction::from_str(action) else {
+                    return ArgumentError::new(CommandType::Role, 1).into();
+                };
+                let Some(user) = args.get(2) else {
+                    return ArgumentError::missing(CommandType::Role, 2).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::Role, 2).into();
+                };
+                let role = vec_string_to_string(&args, Some(3));
+                if role.is_empty() {
+                    return ArgumentError::missing(CommandType::Role, 3).into();
+                }
+                Command::Role { action, user, role }
+            }
+            CommandType::ReactionRole => {
+                let Some(link) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::ReactionRole, 1).into();
+                };
+                let Ok(link) = MessageLink::from_str(link) else {
+                    return ArgumentError::new(CommandType::ReactionRole, 1).into();
+                };
+                let Some(emoji) = args.get(2) else {
+                    return ArgumentError::missing(CommandType::ReactionRole, 2).into();
+                };
+                let role = vec_string_to_string(&args, Some(3));
+                if role.is_empty() {
+                    return ArgumentError::missing(CommandType::ReactionRole, 3).into();
+                }
+                Command::ReactionRole {
+                    link,
+                    emoji: emoji.to_owned(),
+                    role,
+                }
+            }
+            CommandType::Starboard => {
+                let Some(setting) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Starboard, 1).into();
+                };
+                let value = args.get(2);
+                match (setting.to_lowercase().as_str(), value) {
+                    ("off" | "disable", _) => Command::Starboard(StarboardSetting::Disable),
+                    ("channel" | "threshold", None) => {
+                        ArgumentError::missing(CommandType::Starboard, 2).into()
+                    }
+                    ("channel", Some(channel)) => match channel_from_string(channel) {
+                        Some(channel) => Command::Starboard(StarboardSetting::Channel(channel)),
+                        None => ArgumentError::new(CommandType::Starboard, 2).into(),
+                    },
+                    ("threshold", Some(threshold)) => match threshold.parse() {
+                        Ok(threshold @ 1..) => {
+                            Command::Starboard(StarboardSetting::Threshold(threshold))
+                        }
+                        _ => ArgumentError::new(CommandType::Starboard, 2).into(),
+                    },
+                    _ => ArgumentError::new(CommandType::Starboard, 1).into(),
+                }
+            }
+            CommandType::Welcome => {
+                let Some(setting) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Welcome, 1).into();
+                };
+                let value = vec_string_to_string(&args, Some(2));
+                match (setting.to_lowercase().as_str(), value.as_str()) {
+                    ("off" | "disable", _) => Command::Welcome(WelcomeSetting::Disable),
+                    ("channel" | "message" | "rules", "") => {
+                        ArgumentError::missing(CommandType::Welcome, 2).into()
+                    }
+                    ("channel", channel) => match channel_from_string(channel) {
+                        Some(channel) => Command::Welcome(WelcomeSetting::Channel(channel)),
+                        None => ArgumentError::new(CommandType::Welcome, 2).into(),
+                    },
+                    ("message", message) => {
+                        Command::Welcome(WelcomeSetting::Message(message.to_owned()))
+                    }
+                    ("rules", rules) => Command::Welcome(WelcomeSetting::Rules(rules.to_owned())),
+                    _ => ArgumentError::new(CommandType::Welcome, 1).into(),
+                }
+            }
+            CommandType::Goodbye => {
+                let Some(setting) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Goodbye, 1).into();
+                };
+                let value = vec_string_to_string(&args, Some(2));
+                match (setting.to_lowercase().as_str(), value.as_str()) {
+                    ("off" | "disable", _) => Command::Goodbye(GoodbyeSetting::Disable),
+                    ("channel" | "message", "") => {
+                        ArgumentError::missing(CommandType::Goodbye, 2).into()
+                    }
+                    ("channel", channel) => match channel_from_string(channel) {
+                        Some(channel) => Command::Goodbye(GoodbyeSetting::Channel(channel)),
+                        None => ArgumentError::new(CommandType::Goodbye, 2).into(),
+                    },
+                    ("message", message) => {
+                        Command::Goodbye(GoodbyeSetting::Message(message.to_owned()))
+                    }
+                    _ => ArgumentError::new(CommandType::Goodbye, 1).into(),
+                }
+            }
+            CommandType::Autorole => match vec_string_to_string(&args, Some(1)).as_str() {
+                "" => ArgumentError::missing(CommandType::Autorole, 1).into(),
+                "off" | "disable" => Command::Autorole(None),
+                role => Command::Autorole(Some(role.to_owned())),
+            },
+            CommandType::AntiRaid => {
+                let Some(setting) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::AntiRaid, 1).into();
+                };
+                match setting.to_lowercase().as_str() {
+                    "off" | "disable" => Command::AntiRaid(AntiRaidSetting::Limit(None)),
+                    "alerts" => match args.get(2).map(|channel| channel_from_string(channel)) {
+                        None => ArgumentError::missing(CommandType::AntiRaid, 2).into(),
+                        Some(None) => ArgumentError::new(CommandType::AntiRaid, 2).into(),
+                        Some(Some(channel)) => Command::AntiRaid(AntiRaidSetting::Alerts(channel)),
+                    },
+                    joins => {
+                        let Ok(max_joins) = joins.parse() else {
+                            return ArgumentError::new(CommandType::AntiRaid, 1).into();
+                        };
+                        let Some(seconds) = args.get(2) else {
+                            return ArgumentError::missing(CommandType::AntiRaid, 2).into();
+                        };
+                        match seconds.parse() {
+                            Ok(window_seconds @ 1..) => {
+                                Command::AntiRaid(AntiRaidSetting::Limit(Some(RaidConfig {
+                                    max_joins,
+                                    window_seconds,
+                                })))
+                            }
+                            _ => ArgumentError::new(CommandType::AntiRaid, 2).into(),
+                        }
+                    }
+                }
+            }
+            CommandType::Filter => {
+                let Some(action) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Filter, 1).into();
+                };
+                let term = vec_string_to_string(&args, Some(2)).to_lowercase();
+                match (action.to_lowercase().as_str(), term.as_str()) {
+                    ("list", _) => Command::Filter(FilterAction::List),
+                    ("word" | "anywhere" | "remove", "") => {
+                        ArgumentError::missing(CommandType::Filter, 2).into()
+                    }
+                    ("word" | "anywhere", _) => Command::Filter(FilterAction::Add(FilterTerm {
+                        whole_word: action.eq_ignore_ascii_case("word"),
+                        term,
+                    })),
+                    ("remove", _) => Command::Filter(FilterAction::Remove(term)),
+                    _ => ArgumentError::new(CommandType::Filter, 1).into(),
+                }
+            }
+            CommandType::InviteFilter => {
+                let Some(action) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::InviteFilter, 1).into();
+                };
+                match action.to_lowercase().as_str() {
+                    "on" | "enable" => Command::InviteFilter(InviteFilterAction::Enable(true)),
+                    "off" | "disable" => Command::InviteFilter(InviteFilterAction::Enable(false)),
+                    allow @ ("allow" | "disallow") => {
+                        let Some(invite) = args.get(2) else {
+                            return ArgumentError::missing(CommandType::InviteFilter, 2).into();
+                        };
+                        let code = find_invites(invite)
+                            .pop()
+                            .unwrap_or_else(|| invite.to_owned());
+                        Command::InviteFilter(InviteFilterAction::Allow(code, allow == "allow"))
+                    }
+                    _ => ArgumentError::new(CommandType::InviteFilter, 1).into(),
+                }
+            }
+            CommandType::AntiSpam => {
+                let Some(repeats) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::AntiSpam, 1).into();
+                };
+                if ["off", "disable"].contains(&repeats.to_lowercase().as_str()) {
+                    return Command::AntiSpam(None);
+                }
+                let Ok(max_repeats @ 1..) = repeats.parse() else {
+                    return ArgumentError::new(CommandType::AntiSpam, 1).into();
+                };
+                let Some(messages) = args.get(2) else {
+                    return ArgumentError::missing(CommandType::AntiSpam, 2).into();
+                };
+                let Ok(max_messages @ 1..) = messages.parse() else {
+                    return ArgumentError::new(CommandType::AntiSpam, 2).into();
+                };
+                let Some(seconds) = args.get(3) else {
+                    return ArgumentError::missing(CommandType::AntiSpam, 3).into();
+                };
+                match seconds.parse() {
+                    Ok(window_seconds @ 1..) => Command::AntiSpam(Some(SpamConfig {
+                        max_repeats,
+                        max_messages,
+                        window_seconds,
+                    })),
+                    _ => ArgumentError::new(CommandType::AntiSpam, 3).into(),
+                }
+            }
+            CommandType::Warn => {
+                let Some(user) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Warn, 1).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::Warn, 1).into();
+                };
+                Command::Warn(user, vec_string_to_string(&args, Some(2)))
+            }
+            CommandType::Warns => {
+                let Some(user) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Warns, 1).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::Warns, 1).into();
+                };
+                Command::Warns(user)
+            }
+            CommandType::ClearWarns => {
+                let Some(user) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::ClearWarns, 1).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::ClearWarns, 1).into();
+                };
+                Command::ClearWarns(user)
+            }
+            CommandType::WarnExpiry => {
+                let Some(time) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::WarnExpiry, 1).into();
+                };
+                if ["off", "never"].contains(&time.to_lowercase().as_str()) {
+                    return Command::WarnExpiry(None);
+                }
+                match Time::from_str(time) {
+                    Ok(time) if time.total_seconds() > 0 => Command::WarnExpiry(Some(time)),
+                    _ => ArgumentError::new(CommandType::WarnExpiry, 1).into(),
+                }
+            }
+            CommandType::Escalation => {
+                let Some(warnings) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Escalation, 1).into();
+                };
+                if warnings.to_lowercase() == "list" {
+                    return Command::Escalation(EscalationSetting::List);
+                }
+                let Ok(warnings @ 1..) = warnings.parse() else {
+                    return ArgumentError::new(CommandType::Escalation, 1).into();
+                };
+                let Some(action) = args.get(2) else {
+                    return ArgumentError::missing(CommandType::Escalation, 2).into();
+                };
+                if action.to_lowercase() == "off" {
+                    return Command::Escalation(EscalationSetting::Set(warnings, None));
+                }
+                match Escalation::from_str(action) {
+                    Ok(escalation) => {
+                        Command::Escalation(EscalationSetting::Set(warnings, Some(escalation)))
+                    }
+                    Err(_) => ArgumentError::new(CommandType::Escalation, 2).into(),
+                }
+            }
+            CommandType::Lockdown => match args.get(1).map(|arg| arg.to_lowercase()) {
+                None => Command::Lockdown { all: false },
+                Some(arg) if arg == "all" => Command::Lockdown { all: true },
+                Some(_) => ArgumentError::new(CommandType::Lockdown, 1).into(),
+            },
+            CommandType::Unlock => match args.get(1).map(|arg| arg.to_lowercase()) {
+                None => Command::Unlock { all: false },
+                Some(arg) if arg == "all" => Command::Unlock { all: true },
+                Some(_) => ArgumentError::new(CommandType::Unlock, 1).into(),
+            },
+            CommandType::Announce => {
+                let channels = args
+                    .iter()
+                    .skip(1)
+                    .map_while(|arg| announcement_channel(arg))
+                    .map(ChannelId)
+                    .collect::<Vec<_>>();
+                if channels.is_empty() {
+                    return match args.get(1) {
+                        Some(_) => ArgumentError::new(CommandType::Announce, 1).into(),
+                        None => ArgumentError::missing(CommandType::Announce, 1).into(),
+                    };
+                }
+                let message = vec_string_to_string(&args, Some(channels.len() + 1));
+                if message.is_empty() {
+                    return ArgumentError::missing(CommandType::Announce, 2).into();
+                }
+                Command::Announce { channels, message }
+            }
+            CommandType::Remind => {
+                let Some(target) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Remind, 1).into();
+                };
+                let Ok(target) = ReminderTarget::from_str(target) else {
+                    return ArgumentError::new(CommandType::Remind, 1).into();
+                };
+                parse_reminder(CommandType::Remind, target, &args[2..], 2)
+            }
+            CommandType::RemindMe => parse_reminder(
+                CommandType::RemindMe,
+                ReminderTarget::DirectMessage,
+                &args[1..],
+                1,
+            ),
+            CommandType::Giveaway => {
+                if args
+                    .get(1)
+                    .is_some_and(|arg| arg.eq_ignore_ascii_case("reroll"))
+                {
+                    let Some(id) = args.get(2) else {
+                        return ArgumentError::missing(CommandType::Giveaway, 3).into();
+                    };
+                    let Ok(id) = id.trim_start_matches('#').parse() else {
+                        return ArgumentError::new(CommandType::Giveaway, 3).into();
+                    };
+                    return Command::Giveaway(GiveawayAction::Reroll(id));
+                }
+                let parsed = match GIVEAWAY_ARGS.parse(args.get(1..).unwrap_or_default()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_command(CommandType::Giveaway),
+                };
+                let (Some(time), Ok(winners)) = (
+                    parsed.time("time"),
+                    usize::try_from(parsed.integer("winners").unwrap_or(1)),
+                ) else {
+                    return ArgumentError::new(CommandType::Giveaway, 2).into();
+                };
+                if time.total_seconds() == 0 {
+                    return ArgumentError::new(CommandType::Giveaway, 1).into();
+                }
+                if !(1..=MAX_GIVEAWAY_WINNERS).contains(&winners) {
+                    return Command::NotValid(format!(
+                        "A giveaway can have between 1 and {MAX_GIVEAWAY_WINNERS} winners!"
+                    ));
+                }
+                if parsed.rest.is_empty() {
+                    return ArgumentError::missing(CommandType::Giveaway, 3).into();
+                }
+                Command::Giveaway(GiveawayAction::Start {
+                    time,
+                    winners,
+                    prize: parsed.rest,
+                })
+            }
+            CommandType::Tag => match args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+                None => ArgumentError::missing(CommandType::Tag, 1).into(),
+                Some("list") => Command::Tag(TagAction::List),
+                Some("add") => {
+                    let Some(name) = args.get(2) else {
+                        return ArgumentError::missing(CommandType::Tag, 2).into();
+                    };
+                    if RESERVED_TAG_NAMES.contains(&tag_key(name).as_str()) {
+                        return ArgumentError::new(CommandType::Tag, 2).into();
+                    }
+                    let content = vec_string_to_string(&args, Some(3));
+                    if content.is_empty() {
+                        return ArgumentError::missing(CommandType::Tag, 3).into();
+                    }
+                    Command::Tag(TagAction::Add {
+                        name: tag_key(name),
+                        content,
+                    })
+                }
+                Some("remove") => match args.get(2) {
+                    Some(name) => Command::Tag(TagAction::Remove(tag_key(name))),
+                    None => ArgumentError::missing(CommandType::Tag, 2).into(),
+                },
+                Some(name) => Command::Tag(TagAction::Show(tag_key(name))),
+            },
+            CommandType::UserId => match vec_string_to_string(&args, Some(1)) {
+                name if name.trim().is_empty() => {
+                    ArgumentError::missing(CommandType::UserId, 1).into()
+                }
+                name => Command::UserId(name),
+            },
+            CommandType::ChannelInfo => match args.get(1) {
+                None => Command::ChannelInfo(None),
+                Some(channel) => match channel_from_string(channel) {
+                    Some(channel) => Command::ChannelInfo(Some(ChannelId(channel))),
+                    None => ArgumentError::new(CommandType::ChannelInfo, 1).into(),
+                },
+            },
+            CommandType::Emojis => Command::Emojis,
+            CommandType::StealEmoji => {
+                let Some(emoji) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::StealEmoji, 1).into();
+                };
+                let Some(reference) = parse_emoji_reference(emoji) else {
+                    return ArgumentError::new(CommandType::StealEmoji, 1).into();
+                };
+                let name = args.get(2).cloned();
+                if !valid_emoji_name(&emoji_name(&reference, name.as_deref())) {
+                    return Command::NotValid(format!(
+                        "Emoji names have to be {MIN_EMOJI_NAME_LENGTH} to {MAX_EMOJI_NAME_LENGTH} letters, numbers or underscores!"
+                    ));
+                }
+                Command::StealEmoji {
+                    emoji: emoji.clone(),
+                    name,
+                }
+            }
+            CommandType::Snipe => match args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+                None => Command::Snipe,
+                Some("nsfw") => match args.get(2).map(|arg| arg.to_lowercase()).as_deref() {
+                    Some("on") => Command::SnipeNsfw(true),
+                    Some("off") => Command::SnipeNsfw(false),
+                    Some(_) => ArgumentError::new(CommandType::Snipe, 2).into(),
+                    None => ArgumentError::missing(CommandType::Snipe, 2).into(),
+                },
+                Some(_) => ArgumentError::new(CommandType::Snipe, 1).into(),
+            },
+            CommandType::EditSnipe => Command::EditSnipe,
+            CommandType::ModLog => {
+                let Some(setting) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::ModLog, 1).into();
+                };
+                match (setting.to_lowercase().as_str(), args.get(2)) {
+                    ("off" | "disable", _) => Command::ModLog(ModLogSetting::Disable),
+                    ("channel", None) => ArgumentError::missing(CommandType::ModLog, 2).into(),
+                    ("channel", Some(channel)) => match channel_from_string(channel) {
+                        Some(channel) => Command::ModLog(ModLogSetting::Channel(channel)),
+                        None => ArgumentError::new(CommandType::ModLog, 2).into(),
+                    },
+                    ("recent", None) => ArgumentError::missing(CommandType::ModLog, 2).into(),
+                    ("recent", Some(count)) => match count.parse() {
+                        Ok(count @ 1..=MAX_RECENT_ACTIONS) => {
+                            Command::ModLogSearch(ModLogQuery::Recent(count))
+                        }
+                        _ => ArgumentError::new(CommandType::ModLog, 2).into(),
+                    },
+                    (user, _) => match UserId::from_str(user) {
+                        Ok(user) => Command::ModLogSearch(ModLogQuery::User(user)),
+                        Err(_) => ArgumentError::new(CommandType::ModLog, 1).into(),
+                    },
+                }
+            }
+            CommandType::PurgeFrom => {
+                let Some(link) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::PurgeFrom, 1).into();
+                };
+                match MessageLink::from_str(link) {
+                    Ok(link) => Command::PurgeFrom(link),
+                    Err(_) => ArgumentError::new(CommandType::PurgeFrom, 1).into(),
+                }
+            }
+            CommandType::Purge => {
+                let parsed = match PURGE_ARGS.parse(args.get(1..).unwrap_or_default()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_command(CommandType::Purge),
+                };
+                if !parsed
+                    .word("filter")
+                    .is_some_and(|filter| filter.eq_ignore_ascii_case("user"))
+                {
+                    return ArgumentError::new(CommandType::Purge, 1).into();
+                }
+                let (Some(user), Some(count)) = (parsed.user("user"), parsed.integer("count"))
+                else {
+                    return ArgumentError::missing(CommandType::Purge, 2).into();
+                };
+                match usize::try_from(count) {
+                    Ok(count) if (1..=BULK_DELETE_LIMIT).contains(&count) => {
+                        Command::Purge { user, count }
+                    }
+                    _ => Command::NotValid(format!(
+                        "I can only purge between 1 and {BULK_DELETE_LIMIT} messages at once!"
+                    )),
+                }
+            }
+            CommandType::Rules => {
+                let number = |position: usize| match args.get(position) {
+                    None => Err(ArgumentError::missing(CommandType::Rules, position)),
+                    Some(number) => match number.trim_start_matches('#').parse::<u8>() {
+                        Ok(number) if number > 0 => Ok(number),
+                        _ => Err(ArgumentError::new(CommandType::Rules, position)),
+                    },
+                };
+                match args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+                    None => Command::Rules(None),
+                    Some("set") => {
+                        let number = match number(2) {
+                            Ok(number) => number,
+                            Err(e) => return e.into(),
+                        };
+                        let text = vec_string_to_string(&args, Some(3));
+                        if text.is_empty() {
+                            return ArgumentError::missing(CommandType::Rules, 3).into();
+                        }
+                        Command::RuleEdit(RuleEdit::Set { number, text })
+                    }
+                    Some("remove") => match number(2) {
+                        Ok(number) => Command::RuleEdit(RuleEdit::Remove(number)),
+                        Err(e) => e.into(),
+                    },
+                    Some(_) => match number(1) {
+                        Ok(number) => Command::Rules(Some(number)),
+                        Err(e) => e.into(),
+                    },
+                }
+            }
+            CommandType::Report => {
+                let Some(link) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Report, 1).into();
+                };
+                let Ok(message_link) = MessageLink::from_str(link) else {
+                    return ArgumentError::new(CommandType::Report, 1).into();
+                };
+                let reason = vec_string_to_string(&args, Some(2));
+                if reason.trim().is_empty() {
+                    return ArgumentError::missing(CommandType::Report, 2).into();
+                }
+                Command::Report {
+                    message_link,
+                    reason,
+                }
+            }
+            CommandType::Note => {
+                let Some(action) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::Note, 1).into();
+                };
+                let Some(user) = args.get(2) else {
+                    return ArgumentError::missing(CommandType::Note, 2).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::Note, 2).into();
+                };
+                match action.to_lowercase().as_str() {
+                    "add" => match vec_string_to_string(&args, Some(3)) {
+                        text if text.trim().is_empty() => {
+                            ArgumentError::missing(CommandType::Note, 3).into()
+                        }
+                        text => Command::Note(NoteAction::Add { user, text }),
+                    },
+                    "list" => Command::Note(NoteAction::List(user)),
+                    "remove" => match args.get(3).map(|index| index.parse::<usize>()) {
+                        None => ArgumentError::missing(CommandType::Note, 3).into(),
+                        Some(Ok(index @ 1..)) => Command::Note(NoteAction::Remove { user, index }),
+                        Some(_) => ArgumentError::new(CommandType::Note, 3).into(),
+                    },
+                    _ => ArgumentError::new(CommandType::Note, 1).into(),
+                }
+            }
+            CommandType::UserInfo => match args.get(1) {
+                None => Command::UserInfo(None),
+                Some(user) => match UserId::from_str(user) {
+                    Ok(user) => Command::UserInfo(Some(user)),
+                    Err(_) => ArgumentError::new(CommandType::UserInfo, 1).into(),
+                },
+            },
+            CommandType::History => {
+                let Some(user) = args.get(1) else {
+                    return ArgumentError::missing(CommandType::History, 1).into();
+                };
+                let Ok(user) = UserId::from_str(user) else {
+                    return ArgumentError::new(CommandType::History, 1).into();
+                };
+                Command::History(user)
+            }
+            CommandType::MassBan => {
+                let args = args.get(1..).unwrap_or_default();
+                let reason_start = args
+                    .iter()
+                    .position(|arg| arg.to_lowercase().starts_with("reason:"))
+                    .unwrap_or(args.len());
+                let mut users = Vec::new();
+                for (index, user) in args[..reason_start].iter().enumerate() {
+                    let Ok(user) = UserId::from_str(user) else {
+                        return ArgumentError::new(CommandType::MassBan, index + 1).into();
+                    };
+                    if !users.contains(&user) {
+                        users.push(user);
+                    }
+                }
+                if users.is_empty() {
+                    return ArgumentError::missing(CommandType::MassBan, 1).into();
+                }
+                if users.len() > MAX_MASSBAN {
+                    return Command::NotValid(format!(
+                        "You can only ban up to {MAX_MASSBAN} users at once!"
+                    ));
+                }
+                let reason = args[reason_start..].join(" ");
+                let reason = reason
+                    .get("reason:".len()..)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned();
+                Command::MassBan { users, reason }
+            }
+            CommandType::Softban => {
+                let parsed = match BAN_ARGS.parse(args.get(1..).unwrap_or_default()) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_command(CommandType::Softban),
+                };
+                let Some(user_id) = parsed.user("user") else {
+                    return ArgumentError::missing(CommandType::Softban, 1).into();
+                };
+                Command::Softban(user_id, parsed.rest)
+            }
+        }
+    }
+    /// Executes a command, sending any side effects to the given [`CommandSink`].
+    /// The command and any error are recorded in the sink's [`Metrics`](crate::metrics::Metrics).
+    /// Any errors from the process are bubbled up.
+    pub async fn execute_command(self, sink: &impl CommandSink) -> Result<()> {
+        if self != Command::NotACommand {
+            sink.metrics().record_command(CommandType::from(&self));
+        }
+        let result = self.run(sink).await;
+        if result.is_err() {
+            sink.metrics().record_error();
+        }
+        result
+    }
+    async fn run(self, sink: &impl CommandSink) -> Result<()> {
+        match self {
+            Command::Ban(user, reason) => {
+                let reason = match case_reference(&reason) {
+                    Some((id, extra)) => match query_database()
+                        .map_err(Into::into)
+                        .and_then(|db| CaseFile::from_id(&db, id))
+                    {
+                        Ok(case) => case.ban_reason(id, extra),
+                        Err(e) => {
+                            eprintln!("Unable to get casefile #{id} for a ban reason: {e}");
+                            reason
+                        }
+                    },
+                    None => reason,
+                };
+                let message = format!(
+                    "Successfully banned {} for the following reason: \n>{reason}",
+                    sink.user_name(user.0).await?
+                );
+                sink.ban_user(user.0, &reason).await?;
+                log_mod_action(sink, user.0, ModAction::Ban, &reason).await;
+                sink.message_user(user.0, &indoc! {"
+                    You were given a ban in the __Baba is You Discord Server__ for the following reason:
+                    > *[REASON]*
+                    If you think was done in error, you can DM the staff for appeal. 
+                    We recommend waiting at least a week for appeals!
+                    Note that a long time having been passed is not usually enough for an appeal.
+                    
+                    There is no chance for appeal if the ban was for the following reasons:
+                    ❌Being discriminatory in any form.
+                    ❌Breaking discord's ToS or sharing otherwise illegal content.
+                    ❌Pirating Baba is You or sharing other pirated media.
+                    ❌Promoting Cryptocurrencies, misinformation, or other unwarranted advertisements.
+                    
+                    There are cases where appeal is guaranteed:
+                    ✅If your account was compromised and banned for being so, and you have regained access to the account.
+                    ✅Having pirated Baba is You, but then purchasing it legitimately.
+                    ✅Being banned for being underage, but then being of a legal age to join in the user's country.
+                "}.replace("[REASON]", &reason)).await?;
+                sink.send_message(&message).await?;
+            }
+            Command::Mute(user_id, time, reason) => {
+                let message = format!(
+                    "Successfully muted user for {time} for the following reason: \n>{reason}"
+                );
+                sink.mute_user(user_id.0, time, &reason).await?;
+                log_mod_action(sink, user_id.0, ModAction::Mute(time), &reason).await;
+                sink.message_user(user_id.0, &indoc! {"
+                    You were given a mute in the __Baba is You Discord Server__ for the following reason:
+                    > *[REASON]*
+                    If you beleive this to be in error, contact the staff team.
+                "}.replace("[REASON]", &reason)).await?;
+                sink.send_message(&message).await?;
+            }
+            Command::Notice(message) => {
+                sink.send_message(&format!(
+                    "The following is an official announcement from the Baba is You staff team:\n> **{message}**"
+                )).await?;
+            }
+            Command::NoticeRule(number) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Rules can only be posted inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let message = rule_reminder(&query_database()?, guild_id, number)?
+                    .unwrap_or_else(|| format!("There's no rule {number}!"));
+                sink.send_message(&message).await?;
+            }
+            Command::PrivateModMessage { .. } => {
+                sink.send_message("One-Time private mod messages are unimplemented. For now, you can use the modmail system.").await?;
+            }
+            Command::Xkcd(id) => {
+                sink.send_message(&format!("https://xkcd.com/{id}/"))
+                    .await?;
+            }
+            Command::DontAskToAsk => {
+                let preamble = match sink.guild_id() {
+                    Some(guild_id) => guild_setting(&query_database()?, guild_id, DA2A_PREAMBLE)
+                        .unwrap_or_else(|e| {
+                            eprintln!("Unable to get the da2a preamble: {e}");
+                            None
+                        }),
+                    None => None,
+                };
+                sink.send_message(&dont_ask_to_ask_message(preamble.as_deref()))
+                    .await?;
+            }
+            Command::DontAskToAskPreamble(preamble) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The da2a preamble can only be set inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match preamble {
+                    Some(preamble) => {
+                        set_guild_setting(&db, guild_id, DA2A_PREAMBLE, &preamble)?;
+                        format!("`{PREFIX}da2a` will now say \"{preamble}\" before the link.")
+                    }
+                    None => {
+                        clear_guild_setting(&db, guild_id, DA2A_PREAMBLE)?;
+                        format!("`{PREFIX}da2a` will now only post the link.")
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Help(command) => {
+                if let Some(command) = command {
+                    sink.send_response(&Response::ephemeral(command.help_message()))
+                        .await?;
+                } else {
+                    let commands = CommandType::ALL
+                        .iter()
+                        .map(|command| {
+                            let summary = command.description().lines().next().unwrap_or("");
+                            format!("`{}` - {summary}", command.usage())
+                        })
+                        .fold("Availible Commands:".to_owned(), |list, line| {
+                            format!("{list}\n{line}")
+                        });
+                    sink.send_pages(paginate(&commands)).await?;
+                }
+            }
+            Command::HelpSearch(term) => {
+                let matches = CommandType::search(&term);
+                if matches.is_empty() {
+                    sink.send_message(&format!("No commands matched `{term}`."))
+                        .await?;
                 } else {
-                    Command::NotValid(
-                        "Couldn't parse an integer from the given arguments!".to_owned(),
+                    let lines = matches
+                        .iter()
+                        .map(|command| {
+                            let summary = command.description().lines().next().unwrap_or("");
+                            format!("`{}` - {summary}", command.usage())
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    sink.send_message(&format!("Commands matching `{term}`:\n{lines}"))
+                        .await?;
+                }
+            }
+            Command::Suggestion(suggestion) => {
+                sink.message_user(
+                    CAMILA,
+                    &format!("Heads up Cami! Someone sent in a suggestion:\n> {suggestion}"),
+                )
+                .await?;
+                sink.send_message("Successfully sent suggestion off to Cami!\nIf this is an emergency, I'd reccomend pinging her.").await?;
+            }
+            Command::NotValid(reason) => {
+                sink.reply(&Response::ephemeral(
+                    "Oops! That command was invalid for the following reason: \n> [REASON]"
+                        .replace("[REASON]", &reason),
+                ))
+                .await?;
+            }
+            Command::NotACommand => { /*intentionally do nothing*/ }
+            Command::Dev(action) => {
+                let (action, rest) = action.split_once(' ').unwrap_or((&action, ""));
+                match action {
+                    "stop" | "halt" => {
+                        let _ = sink.send_message("Shutting down...").await;
+                        std::process::abort();
+                    }
+                    "toggle" => {
+                        let command = rest.parse::<CommandType>()?;
+                        let message = if command.aliases().is_empty() {
+                            format!("I don't know of a command called `{rest}`!")
+                        } else if command == CommandType::Dev {
+                            "Dev commands can't be disabled!".to_owned()
+                        } else if let Some(guild_id) = sink.guild_id() {
+                            let enabled = toggle_command(&query_database()?, guild_id, command)?;
+                            let state = if enabled { "enabled" } else { "disabled" };
+                            format!(
+                                "`{PREFIX}{}` is now {state} in this server.",
+                                command.name()
+                            )
+                        } else {
+                            "Commands can only be toggled inside a server!".to_owned()
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    "backup" => {
+                        let now = Timestamp::now().unix_timestamp();
+                        let path = backup_database(&query_database()?, BACKUP_DIRECTORY, now)?;
+                        sink.send_message(&format!(
+                            "Backed up the database to `{}`.",
+                            path.display()
+                        ))
+                        .await?;
+                    }
+                    "restore" => {
+                        let message = match restore_database(&mut query_database()?, rest.trim()) {
+                            Ok(()) => format!("Restored the database from `{}`.", rest.trim()),
+                            Err(e) => format!("Couldn't restore the database: {e}"),
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    "sql" => {
+                        let message = match run_select(&query_database_read_only()?, rest) {
+                            Ok(rows) => rows,
+                            Err(e) => format!("Couldn't run that query: {e}"),
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    "reload" => {
+                        let changes = config::reload();
+                        let message = match changes.is_empty() {
+                            true => "Reloaded the config; nothing changed.".to_owned(),
+                            false => format!(
+                                "Reloaded the config:\n{}",
+                                changes
+                                    .iter()
+                                    .map(ToString::to_string)
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            ),
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    "stats" => {
+                        let stats = ProcessStats::gather(&query_database()?, sink.cache_counts())?;
+                        sink.send_embed(&stats.to_embed()).await?;
+                    }
+                    "permcheck" => {
+                        let message = match sink.guild_id() {
+                            Some(_) => permission_report(sink.bot_permissions().await?),
+                            None => "Permissions can only be checked inside a server!".to_owned(),
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    _ => {}
+                }
+            }
+            Command::CoinFlip(count) => {
+                let flips = (0..count).map(|_| random::<bool>()).collect::<Vec<_>>();
+                let origin = MessageOrigin::from_guild(sink.guild_id());
+                sink.send_message(&coin_flip_message(&flips, origin))
+                    .await?;
+            }
+            Command::RandomInt(min, max) => {
+                let int = thread_rng().gen_range(min..=max);
+                let origin = MessageOrigin::from_guild(sink.guild_id());
+                sink.send_message(&random_int_message(min, max, int, origin))
+                    .await?;
+            }
+            Command::Optin => set_opted_in(&query_database()?, sink.author_id(), true)?,
+            Command::Optout => {
+                let user = sink.author_id();
+                let db = query_database()?;
+                set_opted_in(&db, user, false)?;
+                if let Some(guild_id) = sink.guild_id() {
+                    let reset = NicknameReset::decide(original_nickname(&db, guild_id, user)?);
+                    if let NicknameReset::Restore(nickname) = reset {
+                        match sink.set_nickname(user, nickname.as_deref()).await {
+                            Ok(()) => forget_original_nickname(&db, guild_id, user)?,
+                            Err(e) => eprintln!("Unable to restore a keke'd nickname: {e}"),
+                        }
+                    }
+                }
+            }
+            Command::Keke => {
+                sink.send_message(
+                    "https://cdn.discordapp.com/attachments/563196186912096256/799820975666888764/SPOILER_Untitled_28_1080p.mp4"
+                ).await?;
+            }
+            Command::KekeList => {
+                let users = kekeable_users(&query_database()?)?;
+                if users.is_empty() {
+                    sink.send_message("Nobody is opted into keke.").await?;
+                    return Ok(());
+                }
+                let list = users
+                    .iter()
+                    .map(|user| format!("<@{user}>"))
+                    .fold(format!("{} opted into keke:", users.len()), |list, user| {
+                        format!("{list}\n{user}")
+                    });
+                sink.send_pages(paginate(&list)).await?;
+            }
+            Command::Stats => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Stats are only availible inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let stats = with_typing(sink, async {
+                    CommandStats::for_guild(&query_database()?, guild_id)
+                })
+                .await?;
+                sink.send_embed(&stats.to_embed()).await?;
+            }
+            Command::TimeParse(time) => {
+                sink.send_message(&format!("That duration is {}.", time.describe()))
+                    .await?;
+            }
+            Command::Nickname { user, name } => {
+                sink.set_nickname(user.0, name.as_deref()).await?;
+                let user_name = sink.user_name(user.0).await?;
+                let message = match name {
+                    Some(name) => format!("Changed {user_name}'s nickname to `{name}`."),
+                    None => format!("Reset {user_name}'s nickname."),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Role { action, user, role } => {
+                let roles = sink.guild_roles().await?;
+                let Some((role_id, role_name)) = resolve_role(&roles, &role) else {
+                    sink.send_message(&format!("I couldn't find a role called `{role}`."))
+                        .await?;
+                    return Ok(());
+                };
+                if let Some(refusal) = role_guard(sink, *role_id).await? {
+                    sink.send_message(&format!(
+                        "I can't manage `{role_name}`, since it {refusal}."
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                let has_role = sink.member_roles(user.0).await?.contains(role_id);
+                let user_name = sink.user_name(user.0).await?;
+                let message = match (action, has_role) {
+                    (RoleAction::Add, true) => {
+                        format!("{user_name} already has the `{role_name}` role.")
+                    }
+                    (RoleAction::Remove, false) => {
+                        format!("{user_name} doesn't have the `{role_name}` role.")
+                    }
+                    (RoleAction::Add, false) => {
+                        sink.add_role(user.0, *role_id).await?;
+                        format!("Gave {user_name} the `{role_name}` role.")
+                    }
+                    (RoleAction::Remove, true) => {
+                        sink.remove_role(user.0, *role_id).await?;
+                        format!("Took the `{role_name}` role from {user_name}.")
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::SelfRole { action, role } => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Roles can only be given inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let roles = sink.guild_roles().await?;
+                let Some((role_id, role_name)) = resolve_role(&roles, &role) else {
+                    sink.send_message(&format!("I couldn't find a role called `{role}`."))
+                        .await?;
+                    return Ok(());
+                };
+                if !self_role_allowed(&query_database()?, guild_id, *role_id)? {
+                    sink.send_message(&format!(
+                        "`{role_name}` isn't a role you can give yourself."
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                let author = sink.author_id();
+                let has_role = sink.member_roles(author).await?.contains(role_id);
+                let message = match (action, has_role) {
+                    (RoleAction::Add, true) => format!("You already have the `{role_name}` role."),
+                    (RoleAction::Remove, false) => {
+                        format!("You don't have the `{role_name}` role.")
+                    }
+                    (RoleAction::Add, false) => {
+                        sink.add_role(author, *role_id).await?;
+                        format!("You now have the `{role_name}` role.")
+                    }
+                    (RoleAction::Remove, true) => {
+                        sink.remove_role(author, *role_id).await?;
+                        format!("You no longer have the `{role_name}` role.")
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::SelfRoleAllow { allowed, role } => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Roles can only be managed inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let roles = sink.guild_roles().await?;
+                let Some((role_id, role_name)) = resolve_role(&roles, &role) else {
+                    sink.send_message(&format!("I couldn't find a role called `{role}`."))
+                        .await?;
+                    return Ok(());
+                };
+                if allowed {
+                    if let Some(refusal) = role_guard(sink, *role_id).await? {
+                        sink.send_message(&format!(
+                            "`{role_name}` can't be made self-assignable, since it {refusal}."
+                        ))
+                        .await?;
+                        return Ok(());
+                    }
+                }
+                set_self_role_allowed(&query_database()?, guild_id, *role_id, allowed)?;
+                let message = match allowed {
+                    true => format!(
+                        "`{role_name}` can now be given with `{PREFIX}role give {role_name}`."
+                    ),
+                    false => format!("`{role_name}` can no longer be self-assigned."),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::ReactionRole { link, emoji, role } => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Reaction roles can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                if link.guild_id != Some(guild_id) {
+                    sink.send_message("That message isn't in this server!")
+                        .await?;
+                    return Ok(());
+                }
+                let roles = sink.guild_roles().await?;
+                let Some((role_id, role_name)) = resolve_role(&roles, &role) else {
+                    sink.send_message(&format!("I couldn't find a role called `{role}`."))
+                        .await?;
+                    return Ok(());
+                };
+                if let Some(refusal) = role_guard(sink, *role_id).await? {
+                    sink.send_message(&format!(
+                        "`{role_name}` can't be given by reactions, since it {refusal}."
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                add_reaction_role(
+                    &query_database()?,
+                    guild_id,
+                    link.message_id,
+                    &emoji_key(&emoji),
+                    *role_id,
+                )?;
+                sink.send_message(&format!(
+                    "Reacting with {emoji} on that message will now give the `{role_name}` role."
+                ))
+                .await?;
+            }
+            Command::Starboard(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The starboard can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                setting.apply(&query_database()?, guild_id)?;
+                sink.send_message(&setting.to_string()).await?;
+            }
+            Command::ModLogSearch(query) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The mod log can only be searched inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let (actions, heading) = match query {
+                    ModLogQuery::User(user) => (
+                        user_mod_actions(&db, guild_id, user.0)?,
+                        format!("Logged actions against {}:", sink.user_name(user.0).await?),
+                    ),
+                    ModLogQuery::Recent(count) => (
+                        recent_mod_actions(&db, guild_id, count)?,
+                        "Latest logged actions:".to_owned(),
+                    ),
+                };
+                let message = match actions.as_slice() {
+                    [] => "No actions have been logged.".to_owned(),
+                    actions => actions
+                        .iter()
+                        .fold(heading, |message, action| format!("{message}\n{action}")),
+                };
+                sink.send_pages(paginate(&message)).await?;
+            }
+            Command::Welcome(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Welcome messages can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                setting.apply(&query_database()?, guild_id)?;
+                sink.send_message(&setting.to_string()).await?;
+            }
+            Command::Goodbye(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Goodbye messages can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                setting.apply(&query_database()?, guild_id)?;
+                sink.send_message(&setting.to_string()).await?;
+            }
+            Command::Autorole(role) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Autoroles can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let Some(role) = role else {
+                    clear_guild_setting(&query_database()?, guild_id, AUTOROLE)?;
+                    sink.send_message("New members will no longer be given a role.")
+                        .await?;
+                    return Ok(());
+                };
+                let roles = sink.guild_roles().await?;
+                let Some((role_id, role_name)) = resolve_role(&roles, &role) else {
+                    sink.send_message(&format!("I couldn't find a role called `{role}`."))
+                        .await?;
+                    return Ok(());
+                };
+                set_guild_setting(&query_database()?, guild_id, AUTOROLE, &role_id.to_string())?;
+                sink.send_message(&format!(
+                    "New members will now be given the `{role_name}` role."
+                ))
+                .await?;
+            }
+            Command::AntiRaid(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Raid protection can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match setting {
+                    AntiRaidSetting::Limit(config) => {
+                        RaidConfig::apply(config, &db, guild_id)?;
+                        match config {
+                            Some(config) => config.to_string(),
+                            None => "Raid protection has been turned off.".to_owned(),
+                        }
+                    }
+                    AntiRaidSetting::Alerts(channel_id) => {
+                        set_guild_setting(&db, guild_id, MOD_CHANNEL, &channel_id.to_string())?;
+                        format!("Mod alerts will now be posted in <#{channel_id}>.")
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Filter(action) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The word filter can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match action {
+                    FilterAction::Add(term) => {
+                        add_filter_term(&db, guild_id, &term)?;
+                        format!("Now blocking {term}.")
+                    }
+                    FilterAction::Remove(term) => match remove_filter_term(&db, guild_id, &term)? {
+                        true => format!("No longer blocking `{term}`."),
+                        false => format!("`{term}` wasn't being blocked."),
+                    },
+                    FilterAction::List => {
+                        let terms = filter_terms(&db, guild_id)?;
+                        match terms.is_empty() {
+                            true => "No terms are being blocked.".to_owned(),
+                            false => format!(
+                                "Blocked terms:\n{}",
+                                terms
+                                    .iter()
+                                    .map(ToString::to_string)
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            ),
+                        }
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::InviteFilter(action) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The invite filter can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match action {
+                    InviteFilterAction::Enable(enabled) => {
+                        set_guild_setting(&db, guild_id, INVITE_FILTER, &enabled.to_string())?;
+                        match enabled {
+                            true => "Invite links will now be removed.".to_owned(),
+                            false => "Invite links will no longer be removed.".to_owned(),
+                        }
+                    }
+                    InviteFilterAction::Allow(code, allowed) => {
+                        set_invite_allowed(&db, guild_id, &code, allowed)?;
+                        match allowed {
+                            true => format!("The invite `{code}` is now allowed."),
+                            false => format!("The invite `{code}` is no longer allowed."),
+                        }
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::AntiSpam(config) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Anti-spam can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                SpamConfig::apply(config, &query_database()?, guild_id)?;
+                let message = match config {
+                    Some(config) => config.to_string(),
+                    None => "Anti-spam has been turned off.".to_owned(),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Warn(user, reason) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Warnings can only be given inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let now = Timestamp::now().unix_timestamp();
+                add_warning(&db, guild_id, user.0, sink.author_id(), &reason, now)?;
+                let count = active_warning_count(&db, guild_id, user.0, now)?;
+                log_mod_action(sink, user.0, ModAction::Warn, &reason).await;
+                let notified = sink
+                    .message_user(
+                        user.0,
+                        &format!(
+                            "You were given a warning in the __Baba is You Discord Server__ \
+                            for the following reason:\n> *{reason}*"
+                        ),
                     )
+                    .await;
+                if let Err(e) = notified {
+                    eprintln!("Unable to tell a user about their warning: {e}");
+                }
+                let mut message = format!(
+                    "Warned {} for the following reason: \n>{reason}\nThey now have {count} active warning{}.",
+                    sink.user_name(user.0).await?,
+                    if count == 1 { "" } else { "s" }
+                );
+                let escalation = escalation_for(&escalation_policy(&db, guild_id)?, count);
+                let escalation_reason = format!("Reached {count} warnings");
+                match escalation {
+                    Some(Escalation::Mute(time)) => {
+                        sink.mute_user(user.0, time, &escalation_reason).await?;
+                        log_mod_action(sink, user.0, ModAction::Mute(time), &escalation_reason)
+                            .await;
+                    }
+                    Some(Escalation::Ban) => {
+                        sink.ban_user(user.0, &escalation_reason).await?;
+                        log_mod_action(sink, user.0, ModAction::Ban, &escalation_reason).await;
+                    }
+                    None => {}
+                }
+                if let Some(escalation) = escalation {
+                    message.push_str(&format!("\nThey were automatically given {escalation}."));
+                }
+                sink.send_message(&message).await?;
+            }
+            Command::Warns(user) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Warnings can only be listed inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let now = Timestamp::now().unix_timestamp();
+                let expiry = warn_expiry(&db, guild_id)?;
+                let user_name = sink.user_name(user.0).await?;
+                let warnings = warnings(&db, guild_id, user.0)?;
+                let message = match warnings.is_empty() {
+                    true => format!("{user_name} has no warnings."),
+                    false => warnings.iter().fold(
+                        format!("{user_name} has {} warning(s):", warnings.len()),
+                        |message, warning| {
+                            format!(
+                                "{message}\n`#{}` <t:{}:f> by <@{}>: {}{}",
+                                warning.id,
+                                warning.time,
+                                warning.moderator,
+                                warning.reason,
+                                if warning.is_active(now, expiry) {
+                                    ""
+                                } else {
+                                    " *(expired)*"
+                                }
+                            )
+                        },
+                    ),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::ClearWarns(user) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Warnings can only be cleared inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let cleared = clear_warnings(&query_database()?, guild_id, user.0)?;
+                let message = format!(
+                    "Cleared {cleared} warning(s) from {}.",
+                    sink.user_name(user.0).await?
+                );
+                sink.send_message(&message).await?;
+            }
+            Command::WarnExpiry(time) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Warning expiry can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let expiry = time.map(|time| time.total_seconds() as i64);
+                set_warn_expiry(&query_database()?, guild_id, expiry)?;
+                let message = match time {
+                    Some(time) => format!("Warnings will now expire after {time}."),
+                    None => "Warnings will no longer expire.".to_owned(),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Escalation(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Escalation can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match setting {
+                    EscalationSetting::Set(warnings, escalation) => {
+                        set_escalation(&db, guild_id, warnings, escalation)?;
+                        match escalation {
+                            Some(escalation) => {
+                                format!(
+                                    "Users will now be given {escalation} at {warnings} warnings."
+                                )
+                            }
+                            None => format!("Nothing will happen at {warnings} warnings anymore."),
+                        }
+                    }
+                    EscalationSetting::List => {
+                        let policy = escalation_policy(&db, guild_id)?;
+                        match policy.is_empty() {
+                            true => "Warnings don't escalate in this server.".to_owned(),
+                            false => policy.iter().fold(
+                                "Warnings escalate as follows:".to_owned(),
+                                |message, (warnings, escalation)| {
+                                    format!("{message}\n{warnings} warnings: {escalation}")
+                                },
+                            ),
+                        }
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Lockdown { all } => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Only server channels can be locked down!")
+                        .await?;
+                    return Ok(());
+                };
+                let channels = match all {
+                    true => sink.text_channels().await?,
+                    false => vec![sink.channel_id()],
+                };
+                let db = Mutex::new(query_database()?);
+                let report = lock_channels(&db, sink, guild_id, &channels).await;
+                let message = match all {
+                    true => report.to_string(),
+                    false => report.channel_message(),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Unlock { all } => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Only server channels can be unlocked!")
+                        .await?;
+                    return Ok(());
+                }
+                let channels = match all {
+                    true => sink.text_channels().await?,
+                    false => vec![sink.channel_id()],
+                };
+                let db = Mutex::new(query_database()?);
+                let report = unlock_channels(&db, sink, &channels).await;
+                let message = match all {
+                    true => report.to_string(),
+                    false => report.channel_message(),
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::Announce { channels, message } => {
+                let mut results = Vec::new();
+                for channel in channels {
+                    let result = match in_this_guild(sink, channel.0).await {
+                        Ok(true) => sink.send_message_to(channel.0, &message).await,
+                        Ok(false) => Err(eyre::eyre!("That channel isn't in this server")),
+                        Err(e) => Err(e),
+                    };
+                    results.push((channel.0, result));
+                }
+                sink.send_message(&announcement_summary(&results)).await?;
+            }
+            Command::Remind { target, time, text } => {
+                let due = Timestamp::now().unix_timestamp() + time.total_seconds() as i64;
+                add_reminder(
+                    &query_database()?,
+                    sink.author_id(),
+                    sink.channel_id(),
+                    target,
+                    due,
+                    &text,
+                )?;
+                sink.send_message(&format!("Okay! I'll remind you {target} <t:{due}:R>."))
+                    .await?;
+            }
+            Command::Giveaway(action) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Giveaways can only be run inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                match action {
+                    GiveawayAction::Start {
+                        time,
+                        winners,
+                        prize,
+                    } => {
+                        let ends = Timestamp::now().unix_timestamp() + time.total_seconds() as i64;
+                        let message = format!(
+                            "{GIVEAWAY_EMOJI} **GIVEAWAY** {GIVEAWAY_EMOJI}\n\
+                            Prize: **{prize}**\n\
+                            Winners: {winners}\n\
+                            Ends <t:{ends}:R>. React with {GIVEAWAY_EMOJI} to enter!"
+                        );
+                        let message_id = sink.send_with_reaction(&message, GIVEAWAY_EMOJI).await?;
+                        let db = query_database()?;
+                        add_giveaway(
+                            &db,
+                            guild_id,
+                            sink.channel_id(),
+                            message_id,
+                            &prize,
+                            winners,
+                            ends,
+                        )?;
+                    }
+                    GiveawayAction::Reroll(id) => {
+                        let Some(giveaway) = giveaway(&query_database()?, guild_id, id)? else {
+                            sink.send_message(&format!("There's no giveaway `#{id}`!"))
+                                .await?;
+                            return Ok(());
+                        };
+                        if !giveaway.ended {
+                            sink.send_message(&format!(
+                                "Giveaway `#{id}` hasn't ended yet! It ends <t:{}:R>.",
+                                giveaway.ends
+                            ))
+                            .await?;
+                            return Ok(());
+                        }
+                        let entrants = sink
+                            .reaction_users(giveaway.channel, giveaway.message, GIVEAWAY_EMOJI)
+                            .await?;
+                        let winners = pick_winners(&entrants, giveaway.winners, &mut thread_rng());
+                        sink.send_message(&winners_message(&giveaway.prize, &winners))
+                            .await?;
+                    }
+                }
+            }
+            Command::Tag(action) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Tags can only be used inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match action {
+                    TagAction::Show(name) => match tag(&db, guild_id, &name)? {
+                        Some(content) => fill_placeholders(&content, sink.author_id()),
+                        None => format!("There's no tag called `{name}`!"),
+                    },
+                    TagAction::Add { name, content } => {
+                        match set_tag(&db, guild_id, &name, &content)? {
+                            true => format!("Updated the `{name}` tag."),
+                            false => format!("Saved the `{name}` tag."),
+                        }
+                    }
+                    TagAction::Remove(name) => match remove_tag(&db, guild_id, &name)? {
+                        true => format!("Deleted the `{name}` tag."),
+                        false => format!("There's no tag called `{name}`!"),
+                    },
+                    TagAction::List => match tags(&db, guild_id)?.as_slice() {
+                        [] => "There are no tags yet!".to_owned(),
+                        names => format!(
+                            "Tags: {}",
+                            names
+                                .iter()
+                                .map(|name| format!("`{name}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    },
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::UserId(name) => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Members can only be looked up inside a server!")
+                        .await?;
+                    return Ok(());
+                }
+                let members = sink.guild_members().await?;
+                let matches = match_members(&members, &name);
+                let message = match matches.as_slice() {
+                    [] => format!("No members are called `{name}`."),
+                    [member] => member.to_string(),
+                    found => {
+                        let listed = found
+                            .iter()
+                            .take(MAX_MEMBER_MATCHES)
+                            .map(|member| format!("- {member}"))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        let more = match found.len().saturating_sub(MAX_MEMBER_MATCHES) {
+                            0 => String::new(),
+                            more => format!("\n...and {more} more. Try a longer name!"),
+                        };
+                        format!("{} members match `{name}`:\n{listed}{more}", found.len())
+                    }
+                };
+                sink.send_message(&message).await?;
+            }
+            Command::ChannelInfo(channel) => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Channel info can only be shown inside a server!")
+                        .await?;
+                    return Ok(());
+                }
+                let channel_id = channel.map_or(sink.channel_id(), |channel| channel.0);
+                if !in_this_guild(sink, channel_id).await? {
+                    sink.send_message("That channel isn't in this server!")
+                        .await?;
+                    return Ok(());
+                }
+                let details = sink.channel_details(channel_id).await?;
+                sink.send_embed(&details.to_embed()).await?;
+            }
+            Command::Emojis => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Emojis can only be listed inside a server!")
+                        .await?;
+                    return Ok(());
+                }
+                let emojis = sink.guild_emojis().await?;
+                sink.send_pages(emoji_pages(&emojis)).await?;
+            }
+            Command::StealEmoji { emoji, name } => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Emojis can only be added inside a server!")
+                        .await?;
+                    return Ok(());
                 }
-            }
-            CommandType::Optin => Command::Optin,
-            CommandType::Optout => Command::Optout,
-            CommandType::Keke => Command::Keke,
-        }
-    }
-    /// Executes a command.
-    /// Any errors from the process are bubbled up.
-    pub async fn execute_command(self, shard: BotShard<'_>) -> Result<()> {
-        match self {
-            Command::Ban(user, reason) => {
-                let user = shard.member_request(user).await?;
-                let message = format!(
-                    "Successfully banned {} for the following reason: \n>{reason}",
-                    user.user.name
-                );
-                user.ban_with_reason(shard.http_server(), 0, &reason)
+                let Some(reference) = parse_emoji_reference(&emoji) else {
+                    sink.send_message(&format!("`{emoji}` isn't a custom emoji!"))
+                        .await?;
+                    return Ok(());
+                };
+                let name = emoji_name(&reference, name.as_deref());
+                let created = sink.create_emoji(&reference, &name).await?;
+                sink.send_message(&format!("Added {created} to the server!"))
                     .await?;
-                shard.message_user(user.user.id.0, indoc! {"
-                    You were given a ban in the __Baba is You Discord Server__ for the following reason:
-                    > *[REASON]*
-                    If you think was done in error, you can DM the staff for appeal. 
-                    We recommend waiting at least a week for appeals!
-                    Note that a long time having been passed is not usually enough for an appeal.
-                    
-                    There is no chance for appeal if the ban was for the following reasons:
-                    ❌Being discriminatory in any form.
-                    ❌Breaking discord's ToS or sharing otherwise illegal content.
-                    ❌Pirating Baba is You or sharing other pirated media.
-                    ❌Promoting Cryptocurrencies, misinformation, or other unwarranted advertisements.
-                    
-                    There are cases where appeal is guaranteed:
-                    ✅If your account was compromised and banned for being so, and you have regained access to the account.
-                    ✅Having pirated Baba is You, but then purchasing it legitimately.
-                    ✅Being banned for being underage, but then being of a legal age to join in the user's country.
-                "}.replace("[REASON]", &reason)).await?;
-                shard.send_message(message).await?;
             }
-            Command::Mute(user_id, time, reason) => {
-                let message =
-                    format!("Successfully muted user for the following reason: \n>{reason}");
-                shard.mute_user(user_id, time, &reason).await?;
-                shard.message_user(user_id, indoc! {"
-                    You were given a mute in the __Baba is You Discord Server__ for the following reason:
-                    > *[REASON]*
-                    If you beleive this to be in error, contact the staff team.
-                "}.replace("[REASON]", &reason)).await?;
-                shard.send_message(message).await?;
+            Command::Snipe => {
+                let now = Timestamp::now().unix_timestamp();
+                let Some(sniped) = SNIPES.get(sink.channel_id(), now) else {
+                    sink.send_message("There's nothing to snipe!").await?;
+                    return Ok(());
+                };
+                let embed = Embed {
+                    title: format!("{} said...", sink.user_name(sniped.author).await?),
+                    description: sniped.content,
+                    fields: vec![("Sent".to_owned(), format!("<t:{}:R>", sniped.time))],
+                };
+                sink.send_embed(&embed).await?;
             }
-            Command::Notice(message) => {
-                shard.send_message(format!(
-                    "The following is an official announcement from the Baba is You staff team:\n> **{message}**"
-                )).await?;
+            Command::SnipeNsfw(allowed) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Sniping can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                set_guild_setting(
+                    &query_database()?,
+                    guild_id,
+                    SNIPE_NSFW,
+                    &allowed.to_string(),
+                )?;
+                let message = match allowed {
+                    true => "Messages deleted in NSFW channels can now be sniped.",
+                    false => "Messages deleted in NSFW channels can no longer be sniped.",
+                };
+                sink.send_message(message).await?;
             }
-            Command::PrivateModMessage { .. } => {
-                shard.send_message("One-Time private mod messages are unimplemented. For now, you can use the modmail system.").await?;
+            Command::EditSnipe => {
+                let now = Timestamp::now().unix_timestamp();
+                let Some(edited) = EDIT_SNIPES.get(sink.channel_id(), now) else {
+                    sink.send_message("There's nothing to snipe!").await?;
+                    return Ok(());
+                };
+                let embed = Embed {
+                    title: format!("{} edited...", sink.user_name(edited.author).await?),
+                    description: String::new(),
+                    fields: vec![
+                        ("Before".to_owned(), field_value(&edited.before)),
+                        ("After".to_owned(), field_value(&edited.after)),
+                        ("Edited".to_owned(), format!("<t:{}:R>", edited.time)),
+                    ],
+                };
+                sink.send_embed(&embed).await?;
             }
-            Command::Xkcd(id) => {
-                shard
-                    .send_message(format!("https://xkcd.com/{id}/"))
+            Command::ModLog(setting) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("The mod log can only be set up inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                setting.apply(&query_database()?, guild_id)?;
+                sink.send_message(&setting.to_string()).await?;
+            }
+            Command::PurgeFrom(link) => {
+                if link.guild_id != sink.guild_id() || !in_this_guild(sink, link.channel_id).await?
+                {
+                    sink.send_message("Messages can only be purged from this server!")
+                        .await?;
+                    return Ok(());
+                }
+                let history = sink
+                    .channel_history(link.channel_id, Some(link.message_id), MAX_PURGE_SCAN)
                     .await?;
+                let purge =
+                    Purge::after(link.message_id, &history, Timestamp::now().unix_timestamp());
+                if !purge.ids.is_empty() {
+                    sink.delete_messages(link.channel_id, &purge.ids).await?;
+                }
+                sink.send_message(&purge.to_string()).await?;
             }
-            Command::DontAskToAsk => {
-                shard.send_message("https://dontasktoask.com/").await?;
+            Command::Purge { user, count } => {
+                if sink.guild_id().is_none() {
+                    sink.send_message("Messages can only be purged inside a server!")
+                        .await?;
+                    return Ok(());
+                }
+                let channel_id = sink.channel_id();
+                let history = sink
+                    .channel_history(channel_id, None, MAX_PURGE_SCAN)
+                    .await?;
+                let purge =
+                    Purge::by_user(user.0, count, &history, Timestamp::now().unix_timestamp());
+                if !purge.ids.is_empty() {
+                    sink.delete_messages(channel_id, &purge.ids).await?;
+                }
+                sink.send_message(&purge.to_string()).await?;
             }
-            Command::Help(command) => {
-                if let Some(command) = command {
-                    shard.send_message(command.help_message()).await?;
-                } else {
-                    shard
-                        .send_message(indoc! {"
-                        Availible Commands:
-                    "})
+            Command::Rules(number) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Rules can only be posted inside a server!")
                         .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                match number {
+                    Some(number) => {
+                        let message = match rule(&db, guild_id, number)? {
+                            Some(text) => format_rule(number, &text),
+                            None => format!("There's no rule {number}!"),
+                        };
+                        sink.send_message(&message).await?;
+                    }
+                    None => match rules(&db, guild_id)?.as_slice() {
+                        [] => sink.send_message("There are no rules yet!").await?,
+                        rules => {
+                            let list = rules
+                                .iter()
+                                .map(|(number, text)| format_rule(*number, text))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            sink.send_pages(paginate(&list)).await?;
+                        }
+                    },
                 }
             }
-            Command::Suggestion(suggestion) => {
-                shard
-                    .message_user(
-                        CAMILA,
-                        format!("Heads up Cami! Someone sent in a suggestion:\n> {suggestion}"),
-                    )
-                    .await?;
-                shard.send_message("Successfully sent suggestion off to Cami!\nIf this is an emergency, I'd reccomend pinging her.").await?;
+            Command::RuleEdit(edit) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Rules can only be set inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match edit {
+                    RuleEdit::Set { number, text } => match set_rule(&db, guild_id, number, &text)?
+                    {
+                        true => format!("Updated rule {number}."),
+                        false => format!("Saved rule {number}."),
+                    },
+                    RuleEdit::Remove(number) => match remove_rule(&db, guild_id, number)? {
+                        true => format!("Deleted rule {number}."),
+                        false => format!("There's no rule {number}!"),
+                    },
+                };
+                sink.send_message(&message).await?;
             }
-            Command::NotValid(reason) => {
-                shard
-                    .send_message(
-                        "Oops! That command was invalid for the following reason: \n> [REASON]"
-                            .replace("[REASON]", &reason),
-                    )
+            Command::Report {
+                message_link,
+                reason,
+            } => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Messages can only be reported inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                if message_link.guild_id != Some(guild_id)
+                    || !in_this_guild(sink, message_link.channel_id).await?
+                {
+                    sink.reply(&Response::ephemeral(
+                        "You can only report messages from this server!",
+                    ))
                     .await?;
-            }
-            Command::NotACommand => { /*intentionally do nothing*/ }
-            Command::Dev(action) => match action.as_str() {
-                "stop" | "halt" => {
-                    let _ = shard.send_message("Shutting down...").await;
-                    std::process::abort();
+                    return Ok(());
                 }
-                _ => {}
-            },
-            Command::CoinFlip => {
-                let flip = match random::<bool>() {
-                    true => "heads",
-                    false => "tails",
+                let db = query_database()?;
+                let Some(mod_channel) = mod_channel(&db, guild_id)? else {
+                    sink.reply(&Response::ephemeral(
+                        "Reports aren't set up in this server yet!",
+                    ))
+                    .await?;
+                    return Ok(());
                 };
-                shard
-                    .send_message(format!("The result of the coin flip was... ||{flip}!||"))
+                let message = sink
+                    .fetch_message(message_link.channel_id, message_link.message_id)
                     .await?;
-            }
-            Command::RandomInt(bound) => {
-                let int = (random::<f64>() * bound as f64) as u64;
-                shard
-                    .send_message(format!("Between 0 and {bound}, I choose... ||{int}!||"))
+                // only reports that could be sent count towards the cooldown
+                let now = Timestamp::now().unix_timestamp();
+                if !REPORT_COOLDOWN.try_use(sink.author_id(), now) {
+                    sink.reply(&Response::ephemeral(format!(
+                        "You can only send a report every {} minutes!",
+                        REPORT_COOLDOWN_SECONDS / 60
+                    )))
                     .await?;
+                    return Ok(());
+                }
+                let mut embed = report_embed(sink.author_id(), &message_link, &message, &reason);
+                match file_report(&db, sink.author_id(), &message_link, &message, &reason) {
+                    Ok(case) => embed.fields.push(case.field()),
+                    Err(e) => eprintln!("Unable to file a report in a casefile: {e}"),
+                }
+                sink.send_embed_to(mod_channel, &embed).await?;
+                sink.reply(&Response::ephemeral(
+                    "Thanks! Your report was sent to the mods.",
+                ))
+                .await?;
             }
-            Command::Optin => {
-                let user = shard.author();
-                let mut file = files::read_to_string("optin.txt")?
-                    .lines()
-                    .map(ToOwned::to_owned)
-                    .collect::<Vec<_>>();
-                if !file.contains(&format!("{}", user.id.0)) {
-                    file.push(format!("{}", user.id.0));
+            Command::Note(action) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Notes can only be kept inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let message = match action {
+                    NoteAction::Add { user, text } => {
+                        let now = Timestamp::now().unix_timestamp();
+                        add_note(&db, guild_id, user.0, sink.author_id(), &text, now)?;
+                        format!("Added a note about {}.", sink.user_name(user.0).await?)
+                    }
+                    NoteAction::List(user) => {
+                        let user_name = sink.user_name(user.0).await?;
+                        match notes(&db, guild_id, user.0)?.as_slice() {
+                            [] => format!("There are no notes about {user_name}."),
+                            notes => format!("Notes about {user_name}:\n{}", note_lines(notes)),
+                        }
+                    }
+                    NoteAction::Remove { user, index } => {
+                        match remove_note(&db, guild_id, user.0, index)? {
+                            Some(note) => format!("Deleted note {index}: {}", note.text),
+                            None => format!("There's no note {index} about that user!"),
+                        }
+                    }
+                };
+                sink.send_pages(paginate(&message)).await?;
+            }
+            Command::UserInfo(user) => {
+                let user_id = user.map_or(sink.author_id(), |user| user.0);
+                let info = with_typing(sink, async {
+                    // notes are private, so they're only shown in the mod channel
+                    let private = match sink.guild_id() {
+                        Some(guild_id) if sink.author_is_mod().await? => {
+                            let db = query_database()?;
+                            (mod_channel(&db, guild_id)? == Some(sink.channel_id()))
+                                .then_some((guild_id, db))
+                        }
+                        _ => None,
+                    };
+                    let (notes, moderation) = match private {
+                        Some((guild_id, db)) => {
+                            let now = Timestamp::now().unix_timestamp();
+                            let notes = notes(&db, guild_id, user_id)?;
+                            let moderation = ModerationSummary::new(
+                                current_timeout(sink, user_id).await,
+                                now,
+                                active_warning_count(&db, guild_id, user_id, now)?,
+                                notes.len(),
+                            );
+                            (Some(notes), Some(moderation))
+                        }
+                        None => (None, None),
+                    };
+                    Ok::<_, eyre::Report>(UserInfo {
+                        user_id,
+                        name: sink.user_name(user_id).await?,
+                        notes,
+                        moderation,
+                    })
+                })
+                .await?;
+                sink.send_embed(&info.to_embed()).await?;
+            }
+            Command::History(user) => {
+                let Some(guild_id) = sink.guild_id() else {
+                    sink.send_message("Histories can only be shown inside a server!")
+                        .await?;
+                    return Ok(());
+                };
+                let db = query_database()?;
+                let entries = timeline(
+                    &warnings(&db, guild_id, user.0)?,
+                    &notes(&db, guild_id, user.0)?,
+                    &user_mod_actions(&db, guild_id, user.0)?,
+                );
+                let name = sink.user_name(user.0).await?;
+                sink.send_embed(&history_embed(&name, &entries)).await?;
+            }
+            Command::MassBan { users, reason } => {
+                let mut report = MassBanReport::default();
+                for user in users {
+                    if let Some(skipped) = skip_reason(user.0, sink.author_id(), sink.bot_id()) {
+                        report.record(user.0, MassBanOutcome::Skipped(skipped.to_owned()));
+                        continue;
+                    }
+                    match bot_preflight(sink, Permissions::BAN_MEMBERS, user.0).await {
+                        Preflight::Proceed => {}
+                        refused => {
+                            report.record(user.0, MassBanOutcome::Skipped(refused.to_string()));
+                            continue;
+                        }
+                    }
+                    match sink.ban_user(user.0, &reason).await {
+                        Ok(()) => {
+                            log_mod_action(sink, user.0, ModAction::Ban, &reason).await;
+                            report.record(user.0, MassBanOutcome::Banned);
+                        }
+                        Err(e) => report.record(user.0, MassBanOutcome::Failed(e.to_string())),
+                    }
                 }
-                files::write("optin.txt", vec_string_to_string(&file, None))
-            }?,
-            Command::Optout => {
-                let user = shard.author();
-                let mut file = files::read_to_string("optin.txt")?
-                    .lines()
-                    .map(ToOwned::to_owned)
-                    .collect::<Vec<_>>();
-                if file.contains(&format!("{}", user.id.0)) {
-                    file.retain(|item| item != &format!("{}", user.id.0));
+                sink.send_pages(paginate(&report.to_string())).await?;
+            }
+            Command::Softban(user, reason) => {
+                // they can't be messaged once they no longer share a server with the bot
+                if let Err(e) = sink.message_user(user.0, &softban_notice(&reason)).await {
+                    eprintln!("Unable to tell a user about their softban: {e}");
                 }
-                files::write("optin.txt", vec_string_to_string(&file, None))
-            }?,
-            Command::Keke => {
-                shard.send_message(
-                    "https://cdn.discordapp.com/attachments/563196186912096256/799820975666888764/SPOILER_Untitled_28_1080p.mp4"
-                ).await?;
+                sink.ban_user_purging(user.0, SOFTBAN_DELETE_DAYS, &reason)
+                    .await?;
+                if let Err(e) = sink.unban_user(user.0).await {
+                    eprintln!("Unable to unban a softbanned user: {e}");
+                    log_mod_action(sink, user.0, ModAction::Ban, &reason).await;
+                    sink.send_message(&format!(
+                        "Banned {}, but I couldn't unban them afterwards! They're still banned, so please unban them manually.",
+                        sink.user_name(user.0).await?
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                log_mod_action(sink, user.0, ModAction::Softban, &reason).await;
+                sink.send_message(&format!(
+                    "Softbanned {} and cleared their recent messages for the following reason: \n>{reason}",
+                    sink.user_name(user.0).await?
+                ))
+                .await?;
             }
         }
         Ok(())
     }
 }
 
-/// A representation of a time string (e.g. "2h30m")
+/// A representation of a time string (e.g. "2h30m").
+/// Weeks (`w`) are stored as 7 days each; see [`Time::weeks`].
+/// Months and years are intentionally unsupported,
+/// since how long they are depends on when they start.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Time {
     /// Number of seconds
-    pub seconds: u8,
+    pub seconds: u32,
     /// Number of minutes
-    pub minutes: u8,
+    pub minutes: u32,
     /// number of hours
-    pub hours: u8,
+    pub hours: u32,
     /// number of days
-    pub days: u8,
+    pub days: u32,
+}
+
+impl Time {
+    /// Returns the number of whole weeks in the duration.
+    pub fn weeks(&self) -> u32 {
+        self.days / 7
+    }
+    /// Returns the total length of the duration, in seconds.
+    pub fn total_seconds(&self) -> u64 {
+        u64::from(self.seconds)
+            + u64::from(self.minutes) * 60
+            + u64::from(self.hours) * 60 * 60
+            + u64::from(self.days) * 60 * 60 * 24
+    }
+    /// Describes the duration in seconds and in its largest units,
+    /// e.g. `"9000 seconds (2h 30m)"`.
+    pub fn describe(&self) -> String {
+        format!("{} seconds ({})", self.total_seconds(), self.normalize())
+    }
+    /// Creates a normalized [`Time`] from a number of seconds.
+    pub fn from_seconds(total: u64) -> Self {
+        Time {
+            seconds: (total % 60) as u32,
+            minutes: (total / 60 % 60) as u32,
+            hours: (total / (60 * 60) % 24) as u32,
+            days: (total / (60 * 60 * 24)).try_into().unwrap_or(u32::MAX),
+        }
+    }
+    /// Carries any overflowing units into the next largest one,
+    /// so `90s` becomes `1m30s` and `25h` becomes `1d1h`.
+    pub fn normalize(self) -> Self {
+        Self::from_seconds(self.total_seconds())
+    }
+}
+
+impl Add for Time {
+    type Output = Time;
+    fn add(self, rhs: Self) -> Self::Output {
+        Time::from_seconds(self.total_seconds().saturating_add(rhs.total_seconds()))
+    }
+}
+
+/// Shows only the nonzero units, e.g. `"2h 30m"`, or `"0s"` for an empty [`Time`].
+impl Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units = [
+            (self.weeks(), 'w'),
+            (self.days % 7, 'd'),
+            (self.hours, 'h'),
+            (self.minutes, 'm'),
+            (self.seconds, 's'),
+        ]
+        .into_iter()
+        .filter(|(amount, _)| *amount != 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect::<Vec<_>>();
+        match units.is_empty() {
+            true => write!(f, "0s"),
+            false => write!(f, "{}", units.join(" ")),
+        }
+    }
+}
+
+/// Subtracting a longer [`Time`] from a shorter one gives an empty [`Time`].
+impl Sub for Time {
+    type Output = Time;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Time::from_seconds(self.total_seconds().saturating_sub(rhs.total_seconds()))
+    }
+}
+
+/// [`Time`]s are ordered by their total length, regardless of how they're split into units.
+impl PartialOrd for Time {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Time {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total_seconds().cmp(&other.total_seconds())
+    }
 }
 
 impl TryFrom<Time> for Timestamp {
     type Error = eyre::Report;
     fn try_from(value: Time) -> Result<Self> {
-        let duration = {
-            let mut duration = StdDuration::default();
-            // seconds
-            duration += StdDuration::new(value.seconds.into(), 0);
-            // minutes
-            duration += StdDuration::new((value.minutes * 60).into(), 0);
-            // hours
-            duration += StdDuration::new((value.hours * 60 * 60).into(), 0);
-            // days
-            duration += StdDuration::new((value.days * 60 * 60 * 24).into(), 0);
-            Duration::from_std(duration)
-        }?;
+        let duration = Duration::from_std(StdDuration::from_secs(value.total_seconds()))?;
         let stamp = Timestamp::now()
             .checked_add_signed(duration)
             .ok_or_else(|| SerenityError::Other("Timestamp overflow"))?;
@@ -357,38 +2707,82 @@ impl TryFrom<Time> for Timestamp {
 impl FromStr for Time {
     type Err = TimeErr;
 
+    /// Parses groups of a number followed by a unit, e.g. `"2h30m"`.
+    /// Groups may be separated by whitespace (`"1h 30m"`),
+    /// and numbers may be decimals (`"1.5h"`), but each unit can only be used once.
+    /// Units are case-insensitive.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let allowed_chars = ['s', 'm', 'h', 'd'];
-        let mut time = Time::default();
-        for each in s.split_inclusive(|chr: char| allowed_chars.contains(&chr)) {
-            let (time_change, duration): (String, String) =
-                each.chars().partition(|x| !x.is_alphabetic());
-            match time_change.clone().parse::<u8>() {
-                Ok(val) => {
-                    match duration.chars().next().unwrap_or('\\') {
-                        's' => time.seconds = val,
-                        'm' => time.minutes = val,
-                        'h' => time.hours = val,
-                        'd' => time.days = val,
-                        '\\' => return Err(TimeErr::NoTimeSpecifier),
-                        chr => return Err(TimeErr::InvalidTimeSpecifier(chr)),
-                    };
+        if s.trim().is_empty() {
+            return Err(TimeErr::Empty);
+        }
+        let mut total: u64 = 0;
+        let mut used_units = Vec::new();
+        let mut number = String::new();
+        for (position, chr) in s.chars().enumerate() {
+            if chr.is_ascii_digit() || chr == '.' {
+                number.push(chr);
+                continue;
+            }
+            if chr.is_whitespace() {
+                match number.is_empty() {
+                    true => continue,
+                    false => return Err(TimeErr::NoTimeSpecifier),
                 }
-                Err(e) => return Err(TimeErr::ParseIntError(e)),
             }
+            let unit = chr.to_ascii_lowercase();
+            let unit_seconds: u64 = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 60 * 60 * 24,
+                'w' => 60 * 60 * 24 * 7,
+                _ => return Err(TimeErr::InvalidTimeSpecifier(chr, position)),
+            };
+            if used_units.contains(&unit) {
+                return Err(TimeErr::RepeatedTimeSpecifier(unit));
+            }
+            used_units.push(unit);
+            let seconds = match number.contains('.') {
+                true => (number.parse::<f64>()? * unit_seconds as f64).round() as u64,
+                false => number.parse::<u64>()?.saturating_mul(unit_seconds),
+            };
+            total = total.saturating_add(seconds);
+            number.clear();
         }
-        Ok(time)
+        if !number.is_empty() {
+            return Err(TimeErr::NoTimeSpecifier);
+        }
+        Ok(Time::from_seconds(total))
     }
 }
 /// Represents an error from parsing a timestamp
 #[derive(Debug)]
 pub enum TimeErr {
-    /// There was an invalid time specifier (only valid ones are 's', 'm', 'h', and 'd')
-    InvalidTimeSpecifier(char),
+    /// There was an invalid time specifier (only valid ones are 's', 'm', 'h', 'd', and 'w'),
+    /// at the given character offset (starting at 0)
+    InvalidTimeSpecifier(char, usize),
     /// There was an error when parsing an integer
     ParseIntError(ParseIntError),
+    /// There was an error when parsing a decimal
+    ParseFloatError(ParseFloatError),
     /// No time specifier was given
     NoTimeSpecifier,
+    /// The same time specifier was given twice (e.g. "1h2h")
+    RepeatedTimeSpecifier(char),
+    /// The time string was empty or only whitespace
+    Empty,
+}
+
+impl From<ParseIntError> for TimeErr {
+    fn from(value: ParseIntError) -> Self {
+        Self::ParseIntError(value)
+    }
+}
+
+impl From<ParseFloatError> for TimeErr {
+    fn from(value: ParseFloatError) -> Self {
+        Self::ParseFloatError(value)
+    }
 }
 
 impl Error for TimeErr {}
@@ -396,12 +2790,20 @@ impl Error for TimeErr {}
 impl Display for TimeErr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TimeErr::InvalidTimeSpecifier(chr) => write!(
+            TimeErr::InvalidTimeSpecifier(chr, position) => write!(
                 f,
-                "{chr} is not a valid time specifier - only 's', 'm', 'h', and 'd' are valie"
+                "{chr} (at position {position}) is not a valid time specifier - only 's', 'm', 'h', 'd', and 'w' are valid"
             ),
             TimeErr::ParseIntError(e) => write!(f, "parse int error: {e}"),
+            TimeErr::ParseFloatError(e) => write!(f, "parse decimal error: {e}"),
             TimeErr::NoTimeSpecifier => write!(f, "no time specifier was given"),
+            TimeErr::RepeatedTimeSpecifier(chr) => {
+                write!(
+                    f,
+                    "{chr} was given more than once - use each time specifier only once"
+                )
+            }
+            TimeErr::Empty => write!(f, "no time was given"),
         }
     }
 }
@@ -425,6 +2827,8 @@ pub enum CommandType {
     NotValid,
     /// Not a command
     NotACommand,
+    /// Only the prefix was given, with no command after it
+    Empty,
     /// A help command
     Help,
     /// A suggestion
@@ -441,166 +2845,715 @@ pub enum CommandType {
     Optout,
     /// kekes
     Keke,
+    /// Command usage statistics
+    Stats,
+    /// Shows how a duration is parsed
+    TimeParse,
+    /// Sets a member's nickname
+    Nickname,
+    /// Gives or takes a role
+    Role,
+    /// Sets up a reaction role
+    ReactionRole,
+    /// Changes the starboard settings
+    Starboard,
+    /// Changes the welcome message settings
+    Welcome,
+    /// Changes the goodbye message settings
+    Goodbye,
+    /// Sets the role given to new members
+    Autorole,
+    /// Changes the raid protection settings
+    AntiRaid,
+    /// Changes the word filter
+    Filter,
+    /// Changes the invite filter
+    InviteFilter,
+    /// Changes a guild's anti-spam settings
+    AntiSpam,
+    /// Warns a user
+    Warn,
+    /// Lists a user's warnings
+    Warns,
+    /// Deletes a user's warnings
+    ClearWarns,
+    /// Changes how long warnings count for
+    WarnExpiry,
+    /// Changes what happens automatically after enough warnings
+    Escalation,
+    /// Stops everyone from posting in a channel
+    Lockdown,
+    /// Lets everyone post in a locked channel again
+    Unlock,
+    /// Posts an announcement in several channels
+    Announce,
+    /// A reminder delivered by DM or in the channel
+    Remind,
+    /// A reminder delivered by DM
+    RemindMe,
+    /// A giveaway
+    Giveaway,
+    /// A canned response
+    Tag,
+    /// A member ID lookup
+    UserId,
+    /// A channel's details
+    ChannelInfo,
+    /// The guild's custom emojis
+    Emojis,
+    /// A copied emoji
+    StealEmoji,
+    /// A recently deleted message
+    Snipe,
+    /// A recently edited message
+    EditSnipe,
+    /// Setting up the mod log
+    ModLog,
+    /// Deletes every message sent after a message
+    PurgeFrom,
+    /// Deletes a user's recent messages in the channel
+    Purge,
+    /// Posts the server's rules
+    Rules,
+    /// Flags a message for the mods
+    Report,
+    /// Keeps private notes about users
+    Note,
+    /// Shows a summary of a user
+    UserInfo,
+    /// Shows a user's moderation history
+    History,
+    /// Bans several users at once
+    MassBan,
+    /// Kicks a user and clears their recent messages
+    Softban,
 }
 
 impl CommandType {
-    #[allow(dead_code)]
-    /// Returns the associated (and pre-formatted) help message
-    /// for a given [`Command`].
-    pub fn help_message(&self) -> String {
+    /// Every command a user can invoke, in the order they're listed in help.
+    pub const ALL: [CommandType; 55] = [
+        CommandType::Ban,
+        CommandType::Mute,
+        CommandType::Notice,
+        CommandType::PrivateModMessage,
+        CommandType::Xkcd,
+        CommandType::DontAskToAsk,
+        CommandType::Help,
+        CommandType::Suggestion,
+        CommandType::Dev,
+        CommandType::CoinFlip,
+        CommandType::RandomInt,
+        CommandType::Optin,
+        CommandType::Optout,
+        CommandType::Keke,
+        CommandType::Stats,
+        CommandType::TimeParse,
+        CommandType::Nickname,
+        CommandType::Role,
+        CommandType::ReactionRole,
+        CommandType::Starboard,
+        CommandType::Welcome,
+        CommandType::Goodbye,
+        CommandType::Autorole,
+        CommandType::AntiRaid,
+        CommandType::Filter,
+        CommandType::InviteFilter,
+        CommandType::AntiSpam,
+        CommandType::Warn,
+        CommandType::Warns,
+        CommandType::ClearWarns,
+        CommandType::WarnExpiry,
+        CommandType::Escalation,
+        CommandType::Lockdown,
+        CommandType::Unlock,
+        CommandType::Announce,
+        CommandType::Remind,
+        CommandType::RemindMe,
+        CommandType::Giveaway,
+        CommandType::Tag,
+        CommandType::UserId,
+        CommandType::ChannelInfo,
+        CommandType::Emojis,
+        CommandType::StealEmoji,
+        CommandType::Snipe,
+        CommandType::EditSnipe,
+        CommandType::ModLog,
+        CommandType::PurgeFrom,
+        CommandType::Purge,
+        CommandType::Rules,
+        CommandType::Report,
+        CommandType::Note,
+        CommandType::UserInfo,
+        CommandType::History,
+        CommandType::MassBan,
+        CommandType::Softban,
+    ];
+    /// Returns the commands whose usage or description contain the given term,
+    /// ignoring case.
+    pub fn search(term: &str) -> Vec<CommandType> {
+        let term = term.trim().to_lowercase();
+        if term.is_empty() {
+            return Vec::new();
+        }
+        Self::ALL
+            .into_iter()
+            .filter(|command| {
+                command.usage().to_lowercase().contains(&term)
+                    || command.description().to_lowercase().contains(&term)
+            })
+            .collect()
+    }
+    /// Returns the main name of a given [`Command`], e.g. `"coinflip"`.
+    pub fn name(&self) -> &'static str {
+        self.aliases().first().copied().unwrap_or("invalid")
+    }
+    /// Returns every name a given [`Command`] can be invoked with, in lowercase.
+    /// The first alias is the command's main name.
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            CommandType::Ban => &["ban"],
+            CommandType::Mute => &["mute"],
+            CommandType::Notice => &["notice"],
+            CommandType::PrivateModMessage => &["pvm", "private"],
+            CommandType::Xkcd => &["xkcd"],
+            CommandType::DontAskToAsk => &["da2a", "dontasktoask"],
+            CommandType::NotValid | CommandType::NotACommand | CommandType::Empty => &[],
+            CommandType::Help => &["help"],
+            CommandType::Suggestion => &["suggest"],
+            CommandType::Dev => &["dev"],
+            CommandType::CoinFlip => &["coinflip", "flip"],
+            CommandType::RandomInt => &["randint", "rand"],
+            CommandType::Optin => &["optin"],
+            CommandType::Optout => &["optout"],
+            CommandType::Keke => &["keke"],
+            CommandType::Stats => &["stats"],
+            CommandType::TimeParse => &["timeparse", "duration"],
+            CommandType::Nickname => &["nickname", "nick"],
+            CommandType::Role => &["role"],
+            CommandType::ReactionRole => &["reactionrole", "rr"],
+            CommandType::Starboard => &["starboard"],
+            CommandType::Welcome => &["welcome"],
+            CommandType::Goodbye => &["goodbye"],
+            CommandType::Autorole => &["autorole"],
+            CommandType::AntiRaid => &["antiraid", "raid"],
+            CommandType::Filter => &["filter"],
+            CommandType::InviteFilter => &["invitefilter", "invites"],
+            CommandType::AntiSpam => &["antispam", "spam"],
+            CommandType::Warn => &["warn"],
+            CommandType::Warns => &["warns", "warnings"],
+            CommandType::ClearWarns => &["clearwarns"],
+            CommandType::WarnExpiry => &["warnexpiry"],
+            CommandType::Escalation => &["escalation", "escalate"],
+            CommandType::Lockdown => &["lockdown", "lock"],
+            CommandType::Unlock => &["unlock"],
+            CommandType::Announce => &["announce"],
+            CommandType::Remind => &["remind"],
+            CommandType::RemindMe => &["remindme"],
+            CommandType::Giveaway => &["giveaway"],
+            CommandType::Tag => &["tag", "tags"],
+            CommandType::UserId => &["userid", "whois"],
+            CommandType::ChannelInfo => &["channelinfo", "channel"],
+            CommandType::Emojis => &["emojis", "emotes"],
+            CommandType::StealEmoji => &["steal", "stealemoji"],
+            CommandType::Snipe => &["snipe"],
+            CommandType::EditSnipe => &["editsnipe", "esnipe"],
+            CommandType::ModLog => &["modlog"],
+            CommandType::PurgeFrom => &["purgefrom", "purge-from"],
+            CommandType::Purge => &["purge"],
+            CommandType::Rules => &["rules", "rule"],
+            CommandType::Report => &["report"],
+            CommandType::Note => &["note", "notes"],
+            CommandType::UserInfo => &["userinfo", "user"],
+            CommandType::History => &["history"],
+            CommandType::MassBan => &["massban", "mass-ban"],
+            CommandType::Softban => &["softban"],
+        }
+    }
+    /// Returns the alias closest to a mistyped command name, if one is close enough.
+    /// An alias is close enough if it's at most 2 edits away,
+    /// and less than half of it needs to change.
+    pub fn closest(name: &str) -> Option<&'static str> {
+        let name = name.to_lowercase();
+        Self::ALL
+            .iter()
+            .flat_map(|command| command.aliases())
+            .map(|alias| (levenshtein(&name, alias), *alias))
+            .filter(|(distance, alias)| *distance <= 2 && distance * 2 <= alias.chars().count())
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, alias)| alias)
+    }
+    /// Returns the usage line for a given [`Command`], e.g. `-ban [user] [...reason]`.
+    pub fn usage(&self) -> String {
+        let usage = match self {
+            CommandType::Ban => "{prefix}ban [user] [...reason]",
+            CommandType::Mute => "{prefix}mute [user] [time] [...reason]",
+            CommandType::Notice => "{prefix}notice [...message]",
+            CommandType::PrivateModMessage => "{prefix}pvm [...message]",
+            CommandType::Xkcd => "{prefix}xkcd [<index:number> OR <phrase:word(s)>]",
+            CommandType::DontAskToAsk => "{prefix}da2a <preamble [text|on|off]>",
+            CommandType::NotValid | CommandType::NotACommand | CommandType::Empty => "",
+            CommandType::Help => "{prefix}help <command> | {prefix}help search [term]",
+            CommandType::Suggestion => "{prefix}suggest [phrase:word(s)]",
+            CommandType::Dev => "{prefix}dev [command]",
+            CommandType::CoinFlip => "{prefix}coinflip <count:[count]>",
+            CommandType::RandomInt => "{prefix}randint <[min]> [max]",
+            CommandType::Optin => "{prefix}optin",
+            CommandType::Optout => "{prefix}optout",
+            CommandType::Keke => "{prefix}keke <list>",
+            CommandType::Stats => "{prefix}stats",
+            CommandType::TimeParse => "{prefix}timeparse [time]",
+            CommandType::Nickname => "{prefix}nickname [user] [...name]",
+            CommandType::Role => "{prefix}role [action] [user] [...role] | {prefix}role give|take|allow|disallow [...role]",
+            CommandType::ReactionRole => "{prefix}reactionrole [message-link] [emoji] [...role]",
+            CommandType::Starboard => "{prefix}starboard [setting] <[value]>",
+            CommandType::Welcome => "{prefix}welcome [setting] <[...value]>",
+            CommandType::Goodbye => "{prefix}goodbye [setting] <[...value]>",
+            CommandType::Autorole => "{prefix}autorole [...role]",
+            CommandType::AntiRaid => "{prefix}antiraid [joins|off|alerts] <[seconds|channel]>",
+            CommandType::Filter => "{prefix}filter [action] <[...term]>",
+            CommandType::InviteFilter => "{prefix}invitefilter [action] <[invite]>",
+            CommandType::AntiSpam => "{prefix}antispam [repeats|off] <[messages]> <[seconds]>",
+            CommandType::Warn => "{prefix}warn [user] [...reason]",
+            CommandType::Warns => "{prefix}warns [user]",
+            CommandType::ClearWarns => "{prefix}clearwarns [user]",
+            CommandType::WarnExpiry => "{prefix}warnexpiry [time|off]",
+            CommandType::Escalation => "{prefix}escalation [warnings|list] <[action]>",
+            CommandType::Lockdown => "{prefix}lockdown <all>",
+            CommandType::Unlock => "{prefix}unlock <all>",
+            CommandType::Announce => "{prefix}announce [...channels] [...message]",
+            CommandType::Remind => "{prefix}remind [me|here] [time] [...text]",
+            CommandType::RemindMe => "{prefix}remindme [time] [...text]",
+            CommandType::Giveaway => "{prefix}giveaway [time|reroll] <winners:[count]> [...prize|id]",
+            CommandType::Tag => "{prefix}tag [name|add|remove|list] <[name]> <[...content]>",
+            CommandType::UserId => "{prefix}userid [...name]",
+            CommandType::ChannelInfo => "{prefix}channelinfo <[channel]>",
+            CommandType::Emojis => "{prefix}emojis",
+            CommandType::StealEmoji => "{prefix}steal [emoji] <[name]>",
+            CommandType::Snipe => "{prefix}snipe <nsfw [on|off]>",
+            CommandType::EditSnipe => "{prefix}editsnipe",
+            CommandType::ModLog => "{prefix}modlog [setting|user] <[value]>",
+            CommandType::PurgeFrom => "{prefix}purgefrom [message link]",
+            CommandType::Purge => "{prefix}purge user [user] [count]",
+            CommandType::Rules => "{prefix}rules <[number]>",
+            CommandType::Report => "{prefix}report [message link] [...reason]",
+            CommandType::Note => "{prefix}note [action] [user] <[...text]>",
+            CommandType::UserInfo => "{prefix}userinfo <[user]>",
+            CommandType::History => "{prefix}history [user]",
+            CommandType::MassBan => "{prefix}massban [...users] reason: [...reason]",
+            CommandType::Softban => "{prefix}softban [user] [...reason]",
+        };
+        usage.replace("{prefix}", PREFIX)
+    }
+    /// Returns the names of the arguments in [`CommandType::usage`], in order.
+    /// The name at index 0 is argument 1.
+    pub fn arguments(&self) -> Vec<String> {
+        self.usage()
+            .split_whitespace()
+            .filter_map(|word| Some(word.split_once('[')?.1.split_once(']')?.0))
+            .map(|word| word.trim_start_matches("...").to_owned())
+            .collect()
+    }
+    /// Returns whether a given [`Command`] can only be used by moderators.
+    pub fn mod_only(&self) -> bool {
+        matches!(
+            self,
+            CommandType::Ban
+                | CommandType::Mute
+                | CommandType::Notice
+                | CommandType::Nickname
+                | CommandType::ReactionRole
+                | CommandType::Starboard
+                | CommandType::Welcome
+                | CommandType::Goodbye
+                | CommandType::Autorole
+                | CommandType::AntiRaid
+                | CommandType::Filter
+                | CommandType::InviteFilter
+                | CommandType::AntiSpam
+                | CommandType::Warn
+                | CommandType::Warns
+                | CommandType::ClearWarns
+                | CommandType::WarnExpiry
+                | CommandType::Escalation
+                | CommandType::Lockdown
+                | CommandType::Unlock
+                | CommandType::Announce
+                | CommandType::Giveaway
+                | CommandType::StealEmoji
+                | CommandType::ModLog
+                | CommandType::PurgeFrom
+                | CommandType::Note
+                | CommandType::History
+                | CommandType::MassBan
+                | CommandType::Softban
+                | CommandType::Purge
+        )
+    }
+    /// Returns who is allowed to use a given [`Command`], if it's restricted.
+    pub fn restriction(&self) -> Option<&'static str> {
+        match self {
+            command if command.mod_only() => Some("Mod Only!"),
+            CommandType::Role => Some("Mod Only, except give and take!"),
+            CommandType::Dev => Some("Dev Only!"),
+            _ => None,
+        }
+    }
+    /// Returns a description of what a given [`Command`] does.
+    pub fn description(&self) -> &'static str {
         match self {
             CommandType::Ban => indoc! {"
-                ```
-                {prefix}ban [user] - Mod Only!
-                ================================
                 Bans a user from the server. Note that bans require, at least,
                 half or more of the mod team to agree to ban someone in most cases.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                Starting the reason with case:[id] uses that casefile's name as the reason."},
             CommandType::Mute => indoc! {"
-                ```
-                {prefix}mute [user] [time] [reason] - Mod Only!
-                ================================
                 Mutes a user for a specified time.
                 This uses discord's 'Time Out' feature,
-                rather than
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::Notice => indoc! {"
-                ```
-                {prefix}notice [...message] - Mod Only!
-                ================================
-                Anonymously gives a broadcast to the channel.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::PrivateModMessage => indoc! {"
-                ```
-                {prefix}pvm [...message]
-                ================================
-                Sends a one-time message to the mod channel.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                rather than a muted role."},
+            CommandType::Notice => "Anonymously gives a broadcast to the channel.",
+            CommandType::PrivateModMessage => "Sends a one-time message to the mod channel.",
             CommandType::Xkcd => indoc! {"
-                ```
-                {prefix}xkcd [<index:number> OR <phrase:word(s)>]
-                ================================
                 Sends a pre-formatted XKCD link.
-                Some phrases have link mappings (e.g. 'tautology' maps to XKCD 703.)
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                Some phrases have link mappings (e.g. 'tautology' maps to XKCD 703.)"},
             CommandType::DontAskToAsk => indoc! {"
-                ```
-                {prefix}da2a | {prefix}dontasktoask
-                ================================
                 Sends the link 'https://dontasktoask.com/', verbatim.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::NotValid => indoc! {"
-                ```
-                iNVALID COMMAND
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::NotACommand => indoc! {"
-                ```
-                INVALID COMMAND
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                preamble [text] - says [text] before the link (mods only); `on` uses a default sentence, `off` turns it off"},
+            CommandType::NotValid | CommandType::NotACommand => "INVALID COMMAND",
+            CommandType::Empty => "NO COMMAND GIVEN",
             CommandType::Help => indoc! {"
-                ```
-                {prefix}help <command>
-                ================================
                 Hey, wait a minute...
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::Suggestion => indoc! {"
-                ```
-                {prefix}suggest [phrase:word(s)]
-                ================================
-                Sends a suggestion to be reviewed at a later date.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                Use 'search' to find commands whose name or description mention a term."},
+            CommandType::Suggestion => "Sends a suggestion to be reviewed at a later date.",
             CommandType::Dev => indoc! {"
-                ```
-                {prefix}dev [command] - Dev Only!
-                ================================
-                Can preform a variety of developer options.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::CoinFlip => indoc! {"
-                ```
-                {prefix}coinflip
-                ================================
-                50/50 chance to return Heads or Tails.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::RandomInt => indoc! {"
-                ```
-                {prefix}randint [max:number]
-                ================================
-                Returns a random number between 0 and max, inclusive of both.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                Can preform a variety of developer options:
+                stop - shuts the bot down
+                toggle [command] - enables or disables a command in this server
+                backup - copies the database into the backups folder
+                restore [path] - replaces the database with a backup
+                sql [query] - runs a read-only SELECT query
+                reload - re-reads the config from the environment
+                stats - shows the bot's memory usage and what it's holding on to
+                permcheck - lists which permissions the bot is missing in this server"},
+            CommandType::CoinFlip => "50/50 chance to return Heads or Tails.\nFlip up to 20 coins at once with `count:`, e.g. `-coinflip count:5`.",
+            CommandType::RandomInt => {
+                "Returns a random number between min (or 0) and max, inclusive of both."
+            }
             CommandType::Optin => indoc! {"
-                ```
-                {prefix}optin
-                ================================
                 Allows you to get keke'd.
-                Specifically, your name can be changed by saying 'I'm ___' or a similar phrase.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
-            CommandType::Optout => indoc! {"
-                ```
-                {prefix}optout
-                ================================
-                Opts out of getting keke'd.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                Specifically, your name can be changed by saying 'I'm ___' or a similar phrase."},
+            CommandType::Optout => "Opts out of getting keke'd, putting back the nickname you had before.",
             CommandType::Keke => indoc! {"
-                ```
-                {prefix}keke
-                ================================
                 Sends the original 'lmao get keke'd' video.
-                ```
-            "}
-            .replace("{prefix}", PREFIX),
+                list - lists everyone opted into getting keke'd (mods only)"},
+            CommandType::Stats => indoc! {"
+                Shows how many commands have been run in this server,
+                the most used commands, and how many people have used the bot."},
+            CommandType::TimeParse => indoc! {"
+                Shows how the bot reads a duration (e.g. `2h30m`),
+                in seconds and in days, hours, minutes and seconds.
+                Useful for checking a mute length before using it."},
+            CommandType::Nickname => indoc! {"
+                Sets a member's nickname, up to 32 characters.
+                Use `reset` as the name to clear it."},
+            CommandType::Role => indoc! {"
+                Gives a role to a member with `add`, or takes it away with `remove`.
+                Anyone can `give` or `take` a self-assignable role for themselves;
+                mods choose which roles those are with `allow` and `disallow`.
+                The role can be given by ID, mention, or name."},
+            CommandType::ReactionRole => indoc! {"
+                Gives a role to anyone who reacts to a message with an emoji,
+                and takes it away when they remove their reaction."},
+            CommandType::Starboard => indoc! {"
+                Sets up the starboard, where messages with enough ⭐ reactions are reposted.
+                channel [channel] - posts starred messages in a channel
+                threshold [number] - sets how many stars a message needs (3 by default)
+                off - turns the starboard off"},
+            CommandType::Welcome => indoc! {"
+                Sets up messages for new members.
+                channel [channel] - welcomes new members in a channel
+                message [...message] - sets the welcome message
+                rules [...rules] - DMs new members the rules
+                off - turns welcome messages off
+                Messages can use {user}, {name}, {count} and {server}."},
+            CommandType::Goodbye => indoc! {"
+                Sets up messages for members who leave.
+                channel [channel] - posts goodbye messages in a channel
+                message [...message] - sets the goodbye message
+                off - turns goodbye messages off
+                Messages can use {user}, {name}, {count} and {server}."},
+            CommandType::Autorole => indoc! {"
+                Gives every new member a role when they join.
+                Use `off` to stop giving a role.
+                The bot's highest role has to be above the role it gives."},
+            CommandType::AntiRaid => indoc! {"
+                Protects against raids. If more than [joins] members join within [seconds],
+                new members are timed out for 10 minutes and the mods are alerted.
+                alerts [channel] - sets the channel mod alerts are posted in
+                off - turns raid protection off"},
+            CommandType::Filter => indoc! {"
+                Removes messages from non-mods containing blocked terms, ignoring case.
+                word [...term] - blocks a term when it's a whole word
+                anywhere [...term] - blocks a term anywhere, even inside other words
+                remove [...term] - unblocks a term
+                list - lists every blocked term"},
+            CommandType::InviteFilter => indoc! {"
+                Removes discord invite links in messages from non-mods.
+                on / off - turns the invite filter on or off
+                allow [invite] - lets an invite through the filter
+                disallow [invite] - stops letting an invite through"},
+            CommandType::AntiSpam => indoc! {"
+                Times out members who post the same message [repeats] times in a row,
+                or [messages] messages within [seconds], for 5 minutes and alerts the mods.
+                Mod alerts are posted in the channel set with `antiraid alerts`.
+                off - turns anti-spam off"},
+            CommandType::Warn => indoc! {"
+                Gives a user a warning, letting them know why in their DMs.
+                Warnings can be listed with `warns` and cleared with `clearwarns`."},
+            CommandType::Warns => "Lists every warning a user has been given, and when.",
+            CommandType::ClearWarns => "Deletes every warning a user has been given.",
+            CommandType::WarnExpiry => indoc! {"
+                Sets how long warnings count for, e.g. `30d`.
+                Expired warnings are still listed, but aren't counted.
+                off - makes warnings never expire"},
+            CommandType::Escalation => indoc! {"
+                Automatically acts on users once they have [warnings] active warnings.
+                The step with the most warnings a user has reached is used.
+                [warnings] [time] - mutes the user for that long, e.g. `3 1h`
+                [warnings] ban - bans the user
+                [warnings] off - stops acting at that many warnings
+                list - lists every step"},
+            CommandType::Lockdown => indoc! {"
+                Stops everyone from posting in a channel, by changing what `@everyone` can do there.
+                all - does the same to every text channel"},
+            CommandType::Unlock => indoc! {"
+                Lets everyone post in a locked channel again, by changing what `@everyone` can do there.
+                all - does the same to every text channel"},
+            CommandType::Announce => indoc! {"
+                Posts the same message in every listed channel.
+                Channels can be mentions or IDs."},
+            CommandType::Remind => indoc! {"
+                Reminds you of something after [time], e.g. `-remind here 2h check the oven`.
+                me - reminds you in your DMs
+                here - reminds you in this channel, pinging you"},
+            CommandType::RemindMe => "Reminds you of something in your DMs after [time], e.g. `-remindme 1d renew domain`.",
+            CommandType::Giveaway => indoc! {"
+                Posts a giveaway members enter by reacting with 🎉.
+                After [time], [count] winners (1 by default) are picked at random and announced.
+                reroll [id] - picks new winners for a giveaway that has ended"},
+            CommandType::Tag => indoc! {"
+                Posts the tag called [name].
+                add [name] [content] - saves a tag (mods only); `{user}` is replaced with whoever posts it
+                remove [name] - deletes a tag (mods only)
+                list - lists every tag"},
+            CommandType::UserId => "Lists the IDs of members whose username or nickname contains [name].",
+            CommandType::ChannelInfo => "Shows a channel's type, topic, slowmode, NSFW flag and creation date.\nDefaults to the channel the command was sent in.",
+            CommandType::Emojis => "Lists every custom emoji in the server, with its name and ID.",
+            CommandType::StealEmoji => indoc! {"
+                Copies a custom emoji from another server into this one.
+                The copy keeps the original's name unless [name] is given."},
+            CommandType::Snipe => indoc! {"
+                Reposts the last message deleted in this channel in the past 5 minutes.
+                Messages from bots are never sniped.
+                nsfw [on|off] - lets messages in NSFW channels be sniped (mods only)"},
+            CommandType::EditSnipe => "Shows what the last message edited in this channel in the past 5 minutes said before and after the edit.",
+            CommandType::ModLog => indoc! {"
+                Sets up the mod log, where edited and deleted messages and mod actions are posted.
+                channel [channel] - posts the mod log in a channel
+                off - turns the mod log off
+                [user] - lists the mod actions logged against a user
+                recent [count] - lists the latest logged mod actions, up to 50"},
+            CommandType::PurgeFrom => "Deletes every message in a channel sent after the linked message.\nMessages more than 14 days old can't be bulk-deleted, so they're left alone.",
+            CommandType::Purge => indoc! {"
+                Deletes up to [count] of a user's most recent messages in this channel.
+                Only the last 1000 messages are looked through, and messages more than 14 days old are left alone."},
+            CommandType::Rules => indoc! {"
+                Posts the server's rules, or just rule [number].
+                set [number] [text] - sets a rule (mods only)
+                remove [number] - deletes a rule (mods only)"},
+            CommandType::Report => indoc! {"
+                Sends a message to the mods, along with why you're reporting it.
+                You can send one report every 5 minutes."},
+            CommandType::Note => indoc! {"
+                Keeps private notes about users. Unlike warnings, users are never told about them.
+                add [user] [text] - adds a note
+                list [user] - lists the notes about a user
+                remove [user] [number] - deletes a note, numbered as in the list"},
+            CommandType::UserInfo => indoc! {"
+                Shows a user's ID and when their account was made, defaulting to you.
+                Mods also see the notes kept about them."},
+            CommandType::History => indoc! {"
+                Shows a user's warnings, notes, mutes and bans as one timeline, oldest first."},
+            CommandType::MassBan => indoc! {"
+                Bans up to 50 users at once, such as the accounts in a raid, and reports what happened to each.
+                List the users first, then the reason after reason:
+                Banned users aren't messaged about it."},
+            CommandType::Softban => indoc! {"
+                Bans a user and immediately unbans them, which kicks them
+                and deletes the messages they sent in the last 7 days.
+                They're told it was a cleanup, and are free to rejoin."},
+        }
+    }
+    /// Returns the associated (and pre-formatted) help message
+    /// for a given [`Command`].
+    pub fn help_message(&self) -> String {
+        if let CommandType::NotValid | CommandType::NotACommand | CommandType::Empty = self {
+            return format!("```\n{}\n```\n", self.description());
+        }
+        let restriction = self
+            .restriction()
+            .map(|restriction| format!(" - {restriction}"))
+            .unwrap_or_default();
+        let aliases = if self.aliases().len() > 1 {
+            let aliases = self
+                .aliases()
+                .iter()
+                .map(|alias| format!("{PREFIX}{alias}"))
+                .collect::<Vec<_>>();
+            format!("\nAliases: {}", aliases.join(", "))
+        } else {
+            String::new()
+        };
+        format!(
+            "```\n{}{restriction}\n================================\n{}{aliases}\n```\n",
+            self.usage(),
+            self.description()
+        )
+    }
+}
+
+/// Represents an invalid argument given to a command.
+/// Turns into a [`Command::NotValid`] showing the command's usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentError {
+    /// The command the argument was given to
+    pub command: CommandType,
+    /// The position of the argument, starting at 1
+    pub position: usize,
+    /// Whether the argument was missing entirely, rather than invalid
+    pub missing: bool,
+}
+
+impl ArgumentError {
+    /// Creates a new [`ArgumentError`] for an invalid argument at the given position.
+    pub fn new(command: CommandType, position: usize) -> Self {
+        Self {
+            command,
+            position,
+            missing: false,
         }
     }
+    /// Creates a new [`ArgumentError`] for a missing argument at the given position.
+    pub fn missing(command: CommandType, position: usize) -> Self {
+        Self {
+            command,
+            position,
+            missing: true,
+        }
+    }
+}
+
+impl Display for ArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .command
+            .arguments()
+            .get(self.position.saturating_sub(1))
+            .cloned()
+            .unwrap_or_else(|| "unknown".to_owned());
+        let problem = match self.missing {
+            true => "missing",
+            false => "invalid",
+        };
+        write!(
+            f,
+            "argument {} ({name}) {problem}; usage: `{}`",
+            self.position,
+            self.command.usage()
+        )
+    }
+}
+
+impl Error for ArgumentError {}
+
+impl From<ArgumentError> for Command {
+    fn from(value: ArgumentError) -> Self {
+        Command::NotValid(value.to_string())
+    }
 }
 
 impl From<Command> for CommandType {
     fn from(value: Command) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl From<&Command> for CommandType {
+    fn from(value: &Command) -> Self {
         match value {
             Command::Ban(..) => Self::Ban,
             Command::Mute(..) => Self::Mute,
-            Command::Notice(_) => Self::Notice,
+            Command::Notice(_) | Command::NoticeRule(_) => Self::Notice,
             Command::PrivateModMessage { .. } => Self::PrivateModMessage,
             Command::Xkcd(_) => Self::Xkcd,
-            Command::DontAskToAsk => Self::DontAskToAsk,
+            Command::DontAskToAsk | Command::DontAskToAskPreamble(_) => Self::DontAskToAsk,
             Command::NotValid(_) => Self::NotValid,
             Command::NotACommand => Self::NotACommand,
-            Command::Help(_) => Self::Help,
+            Command::Help(_) | Command::HelpSearch(_) => Self::Help,
             Command::Suggestion(_) => Self::Suggestion,
             Command::Dev(_) => Self::Dev,
-            Command::CoinFlip => Self::CoinFlip,
-            Command::RandomInt(_) => Self::RandomInt,
+            Command::CoinFlip(_) => Self::CoinFlip,
+            Command::RandomInt(..) => Self::RandomInt,
             Command::Optin => Self::Optin,
             Command::Optout => Self::Optout,
-            Command::Keke => Self::Keke,
+            Command::Keke | Command::KekeList => Self::Keke,
+            Command::Stats => Self::Stats,
+            Command::TimeParse(_) => Self::TimeParse,
+            Command::Nickname { .. } => Self::Nickname,
+            Command::Role { .. } | Command::SelfRole { .. } | Command::SelfRoleAllow { .. } => {
+                Self::Role
+            }
+            Command::ReactionRole { .. } => Self::ReactionRole,
+            Command::Starboard(_) => Self::Starboard,
+            Command::Welcome(_) => Self::Welcome,
+            Command::Goodbye(_) => Self::Goodbye,
+            Command::Autorole(_) => Self::Autorole,
+            Command::AntiRaid(_) => Self::AntiRaid,
+            Command::Filter(_) => Self::Filter,
+            Command::InviteFilter(_) => Self::InviteFilter,
+            Command::AntiSpam(_) => Self::AntiSpam,
+            Command::Warn(..) => Self::Warn,
+            Command::Warns(_) => Self::Warns,
+            Command::ClearWarns(_) => Self::ClearWarns,
+            Command::WarnExpiry(_) => Self::WarnExpiry,
+            Command::Escalation(_) => Self::Escalation,
+            Command::Lockdown { .. } => Self::Lockdown,
+            Command::Unlock { .. } => Self::Unlock,
+            Command::Announce { .. } => Self::Announce,
+            Command::Remind {
+                target: ReminderTarget::Channel,
+                ..
+            } => Self::Remind,
+            Command::Remind {
+                target: ReminderTarget::DirectMessage,
+                ..
+            } => Self::RemindMe,
+            Command::Giveaway(_) => Self::Giveaway,
+            Command::Tag(_) => Self::Tag,
+            Command::UserId(_) => Self::UserId,
+            Command::ChannelInfo(_) => Self::ChannelInfo,
+            Command::Emojis => Self::Emojis,
+            Command::StealEmoji { .. } => Self::StealEmoji,
+            Command::Snipe | Command::SnipeNsfw(_) => Self::Snipe,
+            Command::EditSnipe => Self::EditSnipe,
+            Command::ModLog(_) | Command::ModLogSearch(_) => Self::ModLog,
+            Command::PurgeFrom(_) => Self::PurgeFrom,
+            Command::Purge { .. } => Self::Purge,
+            Command::Rules(_) | Command::RuleEdit(_) => Self::Rules,
+            Command::Report { .. } => Self::Report,
+            Command::Note(_) => Self::Note,
+            Command::UserInfo(_) => Self::UserInfo,
+            Command::History(_) => Self::History,
+            Command::MassBan { .. } => Self::MassBan,
+            Command::Softban(..) => Self::Softban,
         }
     }
 }
@@ -611,32 +3564,23 @@ impl FromStr for CommandType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // remove the prefix and get the first argument
         let binding = s
-            .strip_prefix('-')
+            .strip_prefix(PREFIX)
             .unwrap_or(s)
-            .split(|chr| matches!(chr, ' ' | '\n'))
+            .split([' ', '\n'])
             .collect::<Vec<_>>();
-        let prefix = *binding.first().unwrap_or(&"");
-        Ok(match prefix.to_lowercase().as_str() {
-            "ban" => Self::Ban,
-            "mute" => Self::Mute,
-            "notice" => Self::Notice,
-            "private" | "pvm" => Self::PrivateModMessage,
-            "xkcd" => Self::Xkcd,
-            "dontasktoask" | "da2a" => Self::DontAskToAsk,
-            "help" => Self::Help,
-            "suggest" => Self::Suggestion,
-            "dev" => Self::Dev,
-            "coinflip" | "flip" => Self::CoinFlip,
-            "randint" | "rand" => Self::RandomInt,
-            "optin" => Self::Optin,
-            "optout" => Self::Optout,
-            "keke" => Self::Keke,
-            _ => Self::NotValid,
-        })
+        let prefix = binding.first().unwrap_or(&"").to_lowercase();
+        if prefix.is_empty() {
+            return Ok(Self::Empty);
+        }
+        Ok(Self::ALL
+            .into_iter()
+            .find(|command| command.aliases().contains(&prefix.as_str()))
+            .unwrap_or(Self::NotValid))
     }
 }
 
 /// Represents the origin of a message (either private or public)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageOrigin {
     /// A public channel (inside a server)
     PublicChannel,
@@ -644,23 +3588,227 @@ pub enum MessageOrigin {
     PrivateChannel,
 }
 
+impl MessageOrigin {
+    /// Gets where a message came from, given the guild it was sent in, if any.
+    pub fn from_guild(guild_id: Option<u64>) -> Self {
+        match guild_id {
+            Some(_) => MessageOrigin::PublicChannel,
+            None => MessageOrigin::PrivateChannel,
+        }
+    }
+    /// Hides text behind a spoiler tag, except in DMs where there's nobody to spoil.
+    pub fn spoiler(&self, text: &str) -> String {
+        match self {
+            MessageOrigin::PublicChannel => format!("||{text}||"),
+            MessageOrigin::PrivateChannel => text.to_owned(),
+        }
+    }
+}
+
+/// A link to a discord message, e.g.
+/// `https://discord.com/channels/<guild>/<channel>/<message>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLink {
+    /// The guild the message is in, or [`None`] for a DM
+    pub guild_id: Option<u64>,
+    /// The channel the message is in
+    pub channel_id: u64,
+    /// The message itself
+    pub message_id: u64,
+}
+
+impl FromStr for MessageLink {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, path) = s.trim().split_once("/channels/").ok_or(())?;
+        let ids = path.split('/').collect::<Vec<_>>();
+        let [guild_id, channel_id, message_id] = ids.as_slice() else {
+            return Err(());
+        };
+        Ok(MessageLink {
+            guild_id: match *guild_id {
+                "@me" => None,
+                guild_id => Some(guild_id.parse().map_err(|_| ())?),
+            },
+            channel_id: channel_id.parse().map_err(|_| ())?,
+            message_id: message_id.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl Display for MessageLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let guild = self
+            .guild_id
+            .map(|guild_id| guild_id.to_string())
+            .unwrap_or_else(|| "@me".to_owned());
+        write!(
+            f,
+            "https://discord.com/channels/{guild}/{}/{}",
+            self.channel_id, self.message_id
+        )
+    }
+}
+
+/// A change to a guild's warning escalation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationSetting {
+    /// Sets what happens at a number of warnings
+    Set(usize, Option<Escalation>),
+    /// Lists every step
+    List,
+}
+
+/// A change to a guild's raid protection settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiRaidSetting {
+    /// Sets how many joins are too many, or turns raid protection off if [`None`]
+    Limit(Option<RaidConfig>),
+    /// Posts mod alerts in this channel
+    Alerts(u64),
+}
+
+/// Gets a channel ID from a channel mention (`<#id>`) or a plain ID.
+pub fn channel_from_string(string: &str) -> Option<u64> {
+    let string = string.trim();
+    string
+        .strip_prefix("<#")
+        .and_then(|mention| mention.strip_suffix('>'))
+        .unwrap_or(string)
+        .parse()
+        .ok()
+}
+
+/// Gets a channel to announce in from a mention or a full ID.
+/// Short numbers aren't treated as IDs, so announcements can start with them.
+fn announcement_channel(string: &str) -> Option<u64> {
+    match string.starts_with("<#") || string.len() >= 17 {
+        true => channel_from_string(string),
+        false => None,
+    }
+}
+
+/// Summarizes how posting an announcement went,
+/// given whether posting in each channel succeeded.
+pub fn announcement_summary(results: &[(u64, Result<()>)]) -> String {
+    let posted = results.iter().filter(|(_, result)| result.is_ok()).count();
+    results.iter().fold(
+        format!(
+            "Posted the announcement in {posted}/{} channels.",
+            results.len()
+        ),
+        |summary, (channel_id, result)| match result {
+            Ok(()) => summary,
+            Err(e) => format!("{summary}\nCouldn't post in <#{channel_id}>: {e}"),
+        },
+    )
+}
+
+/// Phrases that link to certain xkcd comics, in lowercase.
+pub const XKCD_KEYWORDS: [(&str, u64); 11] = [
+    ("tautology", 703),
+    ("tautological", 703),
+    ("honor society", 703),
+    ("python", 353),
+    ("import antigravity", 353),
+    ("antigravity", 353),
+    ("haskell", 1312),
+    ("side effects", 1312),
+    ("trolley problem", 1455),
+    ("linux", 272),
+    ("os", 272),
+];
+
 /// Gets an xkcd from a string.
 /// if the string isn't able to be parsed as a number,
-/// some special keywords link to certain comics.
+/// some special keywords link to certain comics (see [`XKCD_KEYWORDS`]).
 pub fn xkcd_from_string(string: &str) -> u64 {
     if let Ok(val) = string.parse() {
         val
     } else {
-        match string.to_lowercase().as_str() {
-            "tautology" | "tautological" | "honor society" => 703,
-            "python" | "import antigravity" | "antigravity" => 353,
-            "haskell" | "side effects" => 1312,
-            "trolley problem" => 1455,
-            "linux" | "OS" => 272,
-            _ => 404,
+        let string = string.to_lowercase();
+        XKCD_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| *keyword == string)
+            .map_or(404, |(_, comic)| *comic)
+    }
+}
+/// Gets the Levenshtein distance between two strings:
+/// the number of single-character insertions, deletions or substitutions
+/// needed to turn one into the other.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_chr) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_chr) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_chr != *b_chr);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Splits a message into arguments on whitespace.
+/// Text in double quotes is kept as a single argument (without the quotes),
+/// and `\"` can be used for a literal quote.
+pub fn tokenize(input: &str) -> Result<Vec<String>, TokenizeError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' if chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+                in_token = true;
+            }
+            '"' => {
+                quoted = !quoted;
+                in_token = true;
+            }
+            chr if chr.is_whitespace() && !quoted => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            chr => {
+                current.push(chr);
+                in_token = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(TokenizeError::UnterminatedQuote);
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Represents an error from splitting a message into arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizeError {
+    /// A double quote was opened but never closed
+    UnterminatedQuote,
+}
+
+impl Error for TokenizeError {}
+
+impl Display for TokenizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenizeError::UnterminatedQuote => write!(f, "a quote was never closed"),
         }
     }
 }
+
 /// Takes a slice of &[`str`] and an optional index, and returns a [`String`]
 /// of the concatenated items.
 /// If an index is provided, only the items from that index and onward
@@ -679,6 +3827,44 @@ pub fn vec_str_to_string(vector: &[&str], idx: Option<usize>) -> String {
     }
 }
 
+/// Gets what `-da2a` posts: the link, after the guild's preamble if it has one.
+pub fn dont_ask_to_ask_message(preamble: Option<&str>) -> String {
+    match preamble {
+        Some(preamble) => format!("{preamble} {DA2A_LINK}"),
+        None => DA2A_LINK.to_owned(),
+    }
+}
+
+/// Describes the result of flipping some coins, where `true` is heads.
+/// A single flip is reported on its own; several are listed with a tally.
+/// Results are spoilered, except in DMs.
+pub fn coin_flip_message(flips: &[bool], origin: MessageOrigin) -> String {
+    let name = |flip: &bool| if *flip { "heads" } else { "tails" };
+    if let [flip] = flips {
+        return format!(
+            "The result of the coin flip was... {}",
+            origin.spoiler(&format!("{}!", name(flip)))
+        );
+    }
+    let sequence = flips.iter().map(name).collect::<Vec<_>>().join(", ");
+    let heads = flips.iter().filter(|flip| **flip).count();
+    format!(
+        "I flipped {} coins... {}\nHeads: {heads}, Tails: {}",
+        flips.len(),
+        origin.spoiler(&sequence),
+        flips.len() - heads
+    )
+}
+
+/// Describes a random number picked between `min` and `max`.
+/// The number is spoilered, except in DMs.
+pub fn random_int_message(min: u64, max: u64, int: u64, origin: MessageOrigin) -> String {
+    format!(
+        "Between {min} and {max}, I choose... {}",
+        origin.spoiler(&format!("{int}!"))
+    )
+}
+
 /// Takes a slice of &[`String`] and an optional index, and returns a [`String`]
 /// of the concatenated items.
 /// If an index is provided, only the items from that index and onward
@@ -1,15 +1,163 @@
 //! Deals with casefiles, abstracted with [`Casefile`] structs.
 
-use crate::backend::{vec_str_to_string, vec_string_to_string, PREFIX};
+use crate::backend::{tokenize, vec_string_to_string, TokenizeError, PREFIX};
+use crate::database::{query_database, Database};
+use crate::pagination::paginate;
 use crate::shard::BotShard;
 use eyre::Result;
 use rusqlite as sql;
-use serenity::Error as SereneError;
-use std::ops::{Deref, DerefMut};
-use std::{error::Error, fmt::Display, io::Error as IOError, num::ParseIntError, str::FromStr};
+use serenity::{
+    model::{prelude::UserId, Timestamp},
+    Error as SereneError,
+};
+use std::{
+    cmp::Reverse, error::Error, fmt::Display, io::Error as IOError, num::ParseIntError,
+    str::FromStr,
+};
+
+/// How serious a case is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Can wait, and is the default for new cases
+    #[default]
+    Low,
+    /// Should be looked at soon
+    Medium,
+    /// Should be looked at as soon as possible
+    High,
+    /// Needs attention right away
+    Critical,
+}
+
+impl Severity {
+    /// Gets how the severity is stored in the database.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Severity::Low),
+            "medium" | "med" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" | "crit" => Ok(Severity::Critical),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// Narrows down which casefiles are listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaseFilter {
+    /// Only casefiles of this severity
+    Severity(Severity),
+    /// Only casefiles with this tag
+    Tag(String),
+}
+
+impl CaseFilter {
+    /// Returns whether a casefile passes the filter.
+    pub fn matches(&self, file: &CaseFile) -> bool {
+        match self {
+            CaseFilter::Severity(severity) => file.severity == *severity,
+            CaseFilter::Tag(tag) => file.tags.contains(tag),
+        }
+    }
+}
+
+/// A recorded change to a casefile, kept in its history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaseChange {
+    /// An item was added
+    AddedItem(String),
+    /// An item was removed
+    RemovedItem(String),
+    /// The case was given a new name
+    Renamed(String),
+    /// The case was marked resolved, or unresolved
+    Resolved(bool),
+}
+
+impl CaseChange {
+    /// Gets how the kind of change is stored in the database.
+    pub fn key(&self) -> &'static str {
+        match self {
+            CaseChange::AddedItem(_) => "add",
+            CaseChange::RemovedItem(_) => "remove",
+            CaseChange::Renamed(_) => "rename",
+            CaseChange::Resolved(_) => "resolve",
+        }
+    }
+    /// Gets the detail of the change stored alongside its [`CaseChange::key`].
+    pub fn detail(&self) -> String {
+        match self {
+            CaseChange::AddedItem(detail)
+            | CaseChange::RemovedItem(detail)
+            | CaseChange::Renamed(detail) => detail.clone(),
+            CaseChange::Resolved(resolved) => resolved.to_string(),
+        }
+    }
+    /// Rebuilds a change from its stored key and detail.
+    pub fn from_key(key: &str, detail: String) -> Option<Self> {
+        match key {
+            "add" => Some(CaseChange::AddedItem(detail)),
+            "remove" => Some(CaseChange::RemovedItem(detail)),
+            "rename" => Some(CaseChange::Renamed(detail)),
+            "resolve" => Some(CaseChange::Resolved(detail == "true")),
+            _ => None,
+        }
+    }
+}
+
+impl Display for CaseChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaseChange::AddedItem(item) => write!(f, "added `{item}`"),
+            CaseChange::RemovedItem(item) => write!(f, "removed `{item}`"),
+            CaseChange::Renamed(name) => write!(f, "renamed the case to '{name}'"),
+            CaseChange::Resolved(true) => write!(f, "marked the case as resolved"),
+            CaseChange::Resolved(false) => write!(f, "marked the case as unresolved"),
+        }
+    }
+}
+
+/// An entry in a casefile's history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseHistoryEntry {
+    /// The ID of the moderator who made the change
+    pub moderator: u64,
+    /// What was changed
+    pub change: CaseChange,
+    /// When the change was made, as a unix timestamp
+    pub time: i64,
+}
+
+impl Display for CaseHistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:f> <@{}> {}",
+            self.time, self.moderator, self.change
+        )
+    }
+}
 
-/// Points to the file that should be used for the internal SQL database
-pub const DATABASE_FILE: &str = "./db.db3";
 /// Represents an action pertaining to a Case File.
 #[derive(Clone, PartialEq, Eq)]
 pub enum CaseFileAction {
@@ -37,13 +185,85 @@ pub enum CaseFileAction {
         #[doc = "docs"]
         index: Option<u64>,
     },
-    /// Deletes a casefile
+    /// Archives a casefile, hiding it from [`CaseFileAction::ViewAll`].
+    /// Archived casefiles can be brought back with [`CaseFileAction::Restore`].
     Delete {
         #[doc = "the relevant id"]
         id: u64,
     },
+    /// Restores an archived casefile
+    Restore {
+        #[doc = "the relevant id"]
+        id: u64,
+    },
+    /// Assigns a moderator to be responsible for a casefile,
+    /// or leaves it unassigned if there's no moderator.
+    Assign {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the id of the moderator responsible for the case"]
+        mod_id: Option<u64>,
+    },
+    /// Sets how serious a casefile is
+    SetSeverity {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the new severity of the case"]
+        severity: Severity,
+    },
+    /// Labels a casefile with tags
+    AddTags {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the tags to add"]
+        tags: Vec<String>,
+    },
+    /// Takes tags off a casefile
+    RemoveTags {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the tags to remove"]
+        tags: Vec<String>,
+    },
+    /// Links a user, such as the accused or the reporter, to a casefile
+    AddUser {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the id of the user to link"]
+        user: u64,
+    },
+    /// Unlinks a user from a casefile
+    RemoveUser {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the id of the user to unlink"]
+        user: u64,
+    },
+    /// Gives a casefile a new name
+    Rename {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the new name of the case"]
+        name: String,
+    },
+    /// Marks a casefile as resolved, or unresolved
+    Resolve {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "whether the case is resolved"]
+        resolved: bool,
+    },
+    /// Shows who changed a casefile, and how
+    History {
+        #[doc = "the relevant id"]
+        id: u64,
+    },
     /// Views a summary of all casefiles
     ViewAll,
+    /// Views a summary of the casefiles that pass a filter
+    ViewFiltered(CaseFilter),
+    /// Views a summary of all archived casefiles
+    ViewArchived,
 }
 
 impl CaseFileAction {
@@ -55,30 +275,37 @@ impl CaseFileAction {
             CaseFileAction::AddItem { id, .. } => Some(*id),
             CaseFileAction::RemoveItem { id, .. } => Some(*id),
             CaseFileAction::Delete { id } => Some(*id),
+            CaseFileAction::Restore { id } => Some(*id),
+            CaseFileAction::Assign { id, .. } => Some(*id),
+            CaseFileAction::SetSeverity { id, .. } => Some(*id),
+            CaseFileAction::AddTags { id, .. } => Some(*id),
+            CaseFileAction::RemoveTags { id, .. } => Some(*id),
+            CaseFileAction::AddUser { id, .. } => Some(*id),
+            CaseFileAction::RemoveUser { id, .. } => Some(*id),
+            CaseFileAction::Rename { id, .. } => Some(*id),
+            CaseFileAction::Resolve { id, .. } => Some(*id),
+            CaseFileAction::History { id } => Some(*id),
             CaseFileAction::ViewAll => None,
+            CaseFileAction::ViewFiltered(_) => None,
+            CaseFileAction::ViewArchived => None,
         }
     }
     /// Gets the lowest ID availible for creating a case file.
-    /// # Panics
-    /// Panics if there are `u64::MAX` casefiles.
-    pub fn lowest_id_availible() -> Result<u64> {
-        let db = query_database()?;
-        let mut id = 0;
-        db.prepare("SELECT TOP 1 FROM cases")?
-            .query_map((), |row| {
-                let x = row.get::<_, u64>(0)?;
-                id = id.max(x);
-                Ok(())
-            })?
-            .collect::<Result<(), _>>()?;
+    /// Archived casefiles still hold on to their ID.
+    pub fn lowest_id_availible(db: &Database) -> Result<u64> {
+        let id = db.query_row("SELECT COALESCE(MAX(id) + 1, 0) FROM cases", (), |row| {
+            row.get::<_, u64>(0)
+        })?;
         Ok(id)
     }
-    /// Executes the action using the given shard.
-    pub async fn execute(self, shard: BotShard<'_>) -> Result<()> {
-        match self {
+    /// Performs the action against the given database on behalf of a moderator,
+    /// returning a [`CaseFileOutcome`] describing what happened.
+    /// Changes to items, names and resolutions are recorded in the case's history.
+    /// Nothing is sent to discord; see [`CaseFileAction::execute`] for that.
+    pub fn perform(self, db: &Database, moderator: u64) -> Result<CaseFileOutcome> {
+        Ok(match self {
             CaseFileAction::Create { name } => {
-                let id = Self::lowest_id_availible()?;
-                let db = query_database()?;
+                let id = Self::lowest_id_availible(db)?;
                 db.prepare(
                     "
                         INSERT INTO cases (id, name, reso, data)
@@ -86,76 +313,344 @@ impl CaseFileAction {
                     ",
                 )?
                 .execute((&id, &name, false, ""))?;
-                shard
-                    .send_message(format!(
-                        "Successfully created file for '{name}'. Access it with id `{id}`."
-                    ))
-                    .await?;
-            }
-            CaseFileAction::Read { id } => {
-                let file = CaseFile::from_id(id)?;
-                let items = file
-                    .items
-                    .clone()
-                    .iter_mut()
-                    .flat_map(|string| {
-                        string.push_str("\n> ");
-                        string.chars()
-                    })
-                    .collect::<String>();
-                let readable = format!("Case #{id} => {}\n{items}", file.name);
-                shard.send_message(readable).await?;
+                CaseFileOutcome::Created { id, name }
             }
+            CaseFileAction::Read { id } => CaseFileOutcome::Read {
+                id,
+                file: CaseFile::from_id(db, id)?,
+            },
             CaseFileAction::AddItem { id, item } => {
-                let mut file = CaseFile::from_id(id)?;
-                file.push_item(item);
-                file.write_to_id(id)?;
-                shard
-                    .send_message(format!("Successfully wrote new item to Casefile #{id}!"))
-                    .await?;
+                let mut file = CaseFile::from_id(db, id)?;
+                file.push_item(&item);
+                file.write_to_id(db, id)?;
+                CaseFile::record_change(db, id, moderator, &CaseChange::AddedItem(item.clone()))?;
+                CaseFileOutcome::AddedItem { id, item }
             }
             CaseFileAction::RemoveItem { id, index } => {
-                let mut file = CaseFile::from_id(id)?;
+                let mut file = CaseFile::from_id(db, id)?;
                 let item = match index {
-                    Some(idx) => Some(file.items.remove(idx as usize)),
+                    Some(idx) if (idx as usize) < file.items.len() => {
+                        Some(file.items.remove(idx as usize))
+                    }
+                    Some(_) => None,
                     None => file.items.pop(),
+                };
+                file.write_to_id(db, id)?;
+                if let Some(item) = &item {
+                    let change = CaseChange::RemovedItem(item.clone());
+                    CaseFile::record_change(db, id, moderator, &change)?;
                 }
-                .unwrap_or("[unable to find item]".to_owned());
-                file.write_to_id(id)?;
-                shard
-                    .send_message(format!("Removed item `{item}` from Casefile #{id}."))
-                    .await?;
+                CaseFileOutcome::RemovedItem { id, item }
             }
             CaseFileAction::Delete { id } => {
-                let db = query_database()?;
-                db.prepare(
-                    "
-                        DELETE FROM cases WHERE id = (?1)
-                    ",
-                )?
-                .execute((&id,))?;
-                shard
-                    .send_message(format!("Successfully removed Casefile #{id}."))
-                    .await?;
+                CaseFile::archive(db, id)?;
+                CaseFileOutcome::Archived { id }
+            }
+            CaseFileAction::Restore { id } => {
+                CaseFile::restore(db, id)?;
+                CaseFileOutcome::Restored { id }
+            }
+            CaseFileAction::Assign { id, mod_id } => {
+                CaseFile::assign(db, id, mod_id)?;
+                CaseFileOutcome::Assigned { id, mod_id }
             }
-            CaseFileAction::ViewAll => {
-                let mut buffer = String::from("Here's all the casefiles: \n");
-                for file in CaseFile::all_files() {
-                    buffer.push_str(format!("[{}] | {}\n", file.resolution(), file.name).as_str());
+            CaseFileAction::SetSeverity { id, severity } => {
+                CaseFile::set_severity(db, id, severity)?;
+                CaseFileOutcome::SeveritySet { id, severity }
+            }
+            CaseFileAction::AddTags { id, tags } => {
+                CaseFile::add_tags(db, id, &tags)?;
+                CaseFileOutcome::Tagged {
+                    id,
+                    tags,
+                    added: true,
                 }
-                shard.send_message(buffer).await?;
             }
-        }
-
+            CaseFileAction::RemoveTags { id, tags } => {
+                CaseFile::remove_tags(db, id, &tags)?;
+                CaseFileOutcome::Tagged {
+                    id,
+                    tags,
+                    added: false,
+                }
+            }
+            CaseFileAction::AddUser { id, user } => {
+                CaseFile::add_user(db, id, user)?;
+                CaseFileOutcome::UserLinked {
+                    id,
+                    user,
+                    linked: true,
+                }
+            }
+            CaseFileAction::RemoveUser { id, user } => {
+                CaseFile::remove_user(db, id, user)?;
+                CaseFileOutcome::UserLinked {
+                    id,
+                    user,
+                    linked: false,
+                }
+            }
+            CaseFileAction::Rename { id, name } => {
+                CaseFile::set_field(db, id, "name", &name)?;
+                CaseFile::record_change(db, id, moderator, &CaseChange::Renamed(name.clone()))?;
+                CaseFileOutcome::Renamed { id, name }
+            }
+            CaseFileAction::Resolve { id, resolved } => {
+                CaseFile::set_field(db, id, "reso", resolved)?;
+                CaseFile::record_change(db, id, moderator, &CaseChange::Resolved(resolved))?;
+                CaseFileOutcome::Resolved { id, resolved }
+            }
+            CaseFileAction::History { id } => {
+                CaseFile::from_id(db, id)?;
+                CaseFileOutcome::History {
+                    id,
+                    entries: CaseFile::history(db, id)?,
+                }
+            }
+            CaseFileAction::ViewAll => CaseFileOutcome::Listed {
+                archived: false,
+                files: CaseFile::all_files(db)?,
+            },
+            CaseFileAction::ViewFiltered(filter) => CaseFileOutcome::Listed {
+                archived: false,
+                files: CaseFile::all_files(db)?
+                    .into_iter()
+                    .filter(|(_, file)| filter.matches(file))
+                    .collect(),
+            },
+            CaseFileAction::ViewArchived => CaseFileOutcome::Listed {
+                archived: true,
+                files: CaseFile::archived_files(db)?,
+            },
+        })
+    }
+    /// Executes the action using the given shard,
+    /// sending the resulting [`CaseFileOutcome`] to the channel.
+    pub async fn execute(self, shard: BotShard<'_>) -> Result<()> {
+        let outcome = shard
+            .with_typing(async { self.perform(&query_database()?, shard.author().id.0) })
+            .await?;
+        shard.send_paginated(paginate(&outcome.to_string())).await?;
         Ok(())
     }
 }
 
+/// The result of performing a [`CaseFileAction`].
+/// Its [`Display`] implementation is the message sent back to the channel.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CaseFileOutcome {
+    /// A casefile was created
+    Created {
+        #[doc = "the id of the new casefile"]
+        id: u64,
+        #[doc = "the name of the case"]
+        name: String,
+    },
+    /// A casefile was read
+    Read {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the casefile that was read"]
+        file: CaseFile,
+    },
+    /// An item was added to a casefile
+    AddedItem {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the item that was added"]
+        item: String,
+    },
+    /// An item was removed from a casefile
+    RemovedItem {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the item that was removed, if one could be found"]
+        item: Option<String>,
+    },
+    /// A casefile was archived
+    Archived {
+        #[doc = "the relevant id"]
+        id: u64,
+    },
+    /// A casefile was restored from the archive
+    Restored {
+        #[doc = "the relevant id"]
+        id: u64,
+    },
+    /// A moderator was assigned to a casefile, or it was unassigned
+    Assigned {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the id of the moderator now responsible for the case"]
+        mod_id: Option<u64>,
+    },
+    /// The severity of a casefile was changed
+    SeveritySet {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the new severity of the case"]
+        severity: Severity,
+    },
+    /// Tags were added to or removed from a casefile
+    Tagged {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the tags that were changed"]
+        tags: Vec<String>,
+        #[doc = "whether the tags were added rather than removed"]
+        added: bool,
+    },
+    /// A user was linked to or unlinked from a casefile
+    UserLinked {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the id of the user"]
+        user: u64,
+        #[doc = "whether the user was linked rather than unlinked"]
+        linked: bool,
+    },
+    /// A casefile was renamed
+    Renamed {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the new name of the case"]
+        name: String,
+    },
+    /// A casefile was marked as resolved, or unresolved
+    Resolved {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "whether the case is now resolved"]
+        resolved: bool,
+    },
+    /// The history of a casefile
+    History {
+        #[doc = "the relevant id"]
+        id: u64,
+        #[doc = "the changes made to the case, oldest first"]
+        entries: Vec<CaseHistoryEntry>,
+    },
+    /// A summary of several casefiles
+    Listed {
+        #[doc = "whether these are the archived casefiles"]
+        archived: bool,
+        #[doc = "the casefiles, alongside their ids"]
+        files: Vec<(u64, CaseFile)>,
+    },
+}
+
+impl Display for CaseFileOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaseFileOutcome::Created { id, name } => write!(
+                f,
+                "Successfully created file for '{name}'. Access it with id `{id}`."
+            ),
+            CaseFileOutcome::Read { id, file } => {
+                write!(f, "Case #{id} => {}", file.name)?;
+                if let Some(assignee) = file.assignee {
+                    write!(f, "\nAssigned to <@{assignee}>")?;
+                }
+                if !file.tags.is_empty() {
+                    write!(f, "\nTags: {}", file.tags.join(", "))?;
+                }
+                if !file.users.is_empty() {
+                    let users = file
+                        .users
+                        .iter()
+                        .map(|user| format!("<@{user}>"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    write!(f, "\nUsers: {users}")?;
+                }
+                for item in &file.items {
+                    write!(f, "\n> {item}")?;
+                }
+                Ok(())
+            }
+            CaseFileOutcome::AddedItem { id, .. } => {
+                write!(f, "Successfully wrote new item to Casefile #{id}!")
+            }
+            CaseFileOutcome::RemovedItem { id, item } => write!(
+                f,
+                "Removed item `{}` from Casefile #{id}.",
+                item.as_deref().unwrap_or("[unable to find item]")
+            ),
+            CaseFileOutcome::Archived { id } => write!(
+                f,
+                "Archived Casefile #{id}. Use `{PREFIX}casefile restore {id}` to bring it back."
+            ),
+            CaseFileOutcome::Restored { id } => {
+                write!(f, "Successfully restored Casefile #{id}.")
+            }
+            CaseFileOutcome::Assigned { id, mod_id } => match mod_id {
+                Some(mod_id) => write!(f, "Assigned Casefile #{id} to <@{mod_id}>."),
+                None => write!(f, "Casefile #{id} is no longer assigned to anyone."),
+            },
+            CaseFileOutcome::SeveritySet { id, severity } => {
+                write!(f, "Casefile #{id} is now of {severity} severity.")
+            }
+            CaseFileOutcome::Tagged { id, tags, added } => {
+                let tags = tags
+                    .iter()
+                    .map(|tag| format!("`{tag}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match added {
+                    true => write!(f, "Tagged Casefile #{id} with {tags}."),
+                    false => write!(f, "Removed {tags} from Casefile #{id}."),
+                }
+            }
+            CaseFileOutcome::UserLinked { id, user, linked } => match linked {
+                true => write!(f, "Linked <@{user}> to Casefile #{id}."),
+                false => write!(f, "Unlinked <@{user}> from Casefile #{id}."),
+            },
+            CaseFileOutcome::Renamed { id, name } => {
+                write!(f, "Casefile #{id} is now called '{name}'.")
+            }
+            CaseFileOutcome::Resolved { id, resolved } => match resolved {
+                true => write!(f, "Casefile #{id} is now resolved."),
+                false => write!(f, "Casefile #{id} is now unresolved."),
+            },
+            CaseFileOutcome::History { id, entries } => {
+                if entries.is_empty() {
+                    return write!(f, "Casefile #{id} hasn't been changed yet.");
+                }
+                write!(f, "History of Casefile #{id}:")?;
+                for entry in entries {
+                    write!(f, "\n{entry}")?;
+                }
+                Ok(())
+            }
+            CaseFileOutcome::Listed { archived, files } => {
+                match archived {
+                    true => writeln!(f, "Here's all the archived casefiles: ")?,
+                    false => writeln!(f, "Here's all the casefiles: ")?,
+                }
+                for (id, file) in files {
+                    write!(
+                        f,
+                        "#{id} [{}] [{}] | {}",
+                        file.resolution(),
+                        file.severity,
+                        file.name
+                    )?;
+                    match file.assignee {
+                        Some(assignee) => writeln!(f, " | assigned to <@{assignee}>")?,
+                        None => writeln!(f)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 impl FromStr for CaseFileAction {
     type Err = CaseFileError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let args = s.split(|chr| chr == ' ' || chr == '\n').collect::<Vec<_>>();
+        let args = tokenize(s)?;
         if args.is_empty() || args[0] != "casefile" {
             Err(CaseFileError::ParsingError(
                 "Not a casefile command".to_owned(),
@@ -165,9 +660,9 @@ impl FromStr for CaseFileAction {
                 "No valid action to take!".to_owned(),
             ))
         } else {
-            Ok(match args[1] {
+            Ok(match args[1].as_str() {
                 "create" => CaseFileAction::Create {
-                    name: vec_str_to_string(&args, Some(2)),
+                    name: vec_string_to_string(&args, Some(2)),
                 },
                 "read" => CaseFileAction::Read {
                     id: {
@@ -193,7 +688,7 @@ impl FromStr for CaseFileAction {
                     item: if args.len() < 4 {
                         return Err(CaseFileError::ParsingError("no item to add".to_owned()));
                     } else {
-                        vec_str_to_string(&args, Some(3))
+                        vec_string_to_string(&args, Some(3))
                     },
                 },
                 "remove" => CaseFileAction::RemoveItem {
@@ -204,13 +699,180 @@ impl FromStr for CaseFileAction {
                     } else {
                         args[2].parse()?
                     },
-                    index: if args.len() < 3 {
+                    index: if args.len() < 4 {
                         None
                     } else {
-                        Some(vec_str_to_string(&args, Some(2)).parse()?)
+                        Some(args[3].parse()?)
+                    },
+                },
+                "delete" | "archive" => CaseFileAction::Delete {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to archive".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                },
+                "restore" => CaseFileAction::Restore {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to restore".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                },
+                "assign" => CaseFileAction::Assign {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to assign".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                    mod_id: match args.get(3) {
+                        Some(moderator) => Some(
+                            UserId::from_str(moderator)
+                                .map_err(|_| {
+                                    CaseFileError::ParsingError(format!(
+                                        "`{moderator}` isn't a moderator"
+                                    ))
+                                })?
+                                .0,
+                        ),
+                        None => {
+                            return Err(CaseFileError::ParsingError(
+                                "no moderator to assign".to_owned(),
+                            ))
+                        }
+                    },
+                },
+                "unassign" => CaseFileAction::Assign {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to unassign".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                    mod_id: None,
+                },
+                "severity" => CaseFileAction::SetSeverity {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to set the severity of".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                    severity: match args.get(3) {
+                        Some(severity) => severity.parse().map_err(|_| {
+                            CaseFileError::ParsingError(format!(
+                                "`{severity}` isn't low, medium, high or critical"
+                            ))
+                        })?,
+                        None => {
+                            return Err(CaseFileError::ParsingError(
+                                "no severity to set".to_owned(),
+                            ))
+                        }
+                    },
+                },
+                "tag" | "untag" => {
+                    let id = if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(format!(
+                            "no given index to {}",
+                            args[1]
+                        )));
+                    } else {
+                        args[2].parse()?
+                    };
+                    let tags = args[3..]
+                        .iter()
+                        .map(|tag| tag.to_lowercase())
+                        .collect::<Vec<_>>();
+                    if tags.is_empty() {
+                        return Err(CaseFileError::ParsingError(format!(
+                            "no tags to {}",
+                            args[1]
+                        )));
+                    }
+                    match args[1].as_str() {
+                        "tag" => CaseFileAction::AddTags { id, tags },
+                        _ => CaseFileAction::RemoveTags { id, tags },
+                    }
+                }
+                "adduser" | "removeuser" => {
+                    let id = if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(format!(
+                            "no given index to {}",
+                            args[1]
+                        )));
+                    } else {
+                        args[2].parse()?
+                    };
+                    let Some(user) = args.get(3) else {
+                        return Err(CaseFileError::ParsingError(format!(
+                            "no user to {}",
+                            args[1]
+                        )));
+                    };
+                    let user = UserId::from_str(user)
+                        .map_err(|_| CaseFileError::ParsingError(format!("`{user}` isn't a user")))?
+                        .0;
+                    match args[1].as_str() {
+                        "adduser" => CaseFileAction::AddUser { id, user },
+                        _ => CaseFileAction::RemoveUser { id, user },
+                    }
+                }
+                "rename" => CaseFileAction::Rename {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to rename".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                    name: if args.len() < 4 {
+                        return Err(CaseFileError::ParsingError("no new name".to_owned()));
+                    } else {
+                        vec_string_to_string(&args, Some(3))
                     },
                 },
-                "view" => CaseFileAction::ViewAll,
+                "resolve" | "unresolve" => CaseFileAction::Resolve {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(format!(
+                            "no given index to {}",
+                            args[1]
+                        )));
+                    } else {
+                        args[2].parse()?
+                    },
+                    resolved: args[1] == "resolve",
+                },
+                "history" => CaseFileAction::History {
+                    id: if args.len() < 3 {
+                        return Err(CaseFileError::ParsingError(
+                            "no given index to get the history of".to_owned(),
+                        ));
+                    } else {
+                        args[2].parse()?
+                    },
+                },
+                "view" => match args.get(2).map(String::as_str) {
+                    Some("archived") => CaseFileAction::ViewArchived,
+                    Some(filter) if filter.starts_with("tag:") => CaseFileAction::ViewFiltered(
+                        CaseFilter::Tag(filter["tag:".len()..].to_lowercase()),
+                    ),
+                    Some(filter) => match filter.parse() {
+                        Ok(severity) => {
+                            CaseFileAction::ViewFiltered(CaseFilter::Severity(severity))
+                        }
+                        Err(_) => CaseFileAction::ViewAll,
+                    },
+                    None => CaseFileAction::ViewAll,
+                },
                 _ => return Err(CaseFileError::ParsingError(format!("{PREFIX}{}", args[1]))),
             })
         }
@@ -218,7 +880,9 @@ impl FromStr for CaseFileAction {
 }
 /// A representation of a case file.
 /// This format should be followed for the [FromStr] implementation to succeed.
+/// With the `serde` feature, it can also be (de)serialized, such as to JSON.
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CaseFile {
     /// The name of the casefile
     pub name: String,
@@ -226,6 +890,14 @@ pub struct CaseFile {
     pub resolved: bool,
     /// The related evidence or other noteworthy items
     pub items: Vec<String>,
+    /// The ID of the moderator responsible for the case, if one was assigned
+    pub assignee: Option<u64>,
+    /// How serious the case is
+    pub severity: Severity,
+    /// The labels the case is categorized with
+    pub tags: Vec<String>,
+    /// The IDs of the users the case is about, such as the accused or the reporter
+    pub users: Vec<u64>,
 }
 
 impl CaseFile {
@@ -241,43 +913,94 @@ impl CaseFile {
         }
         .to_owned()
     }
+    /// Exports this casefile as JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+    /// Imports a casefile from JSON made by [`CaseFile::to_json`].
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> serde_json::Result<CaseFile> {
+        serde_json::from_str(json)
+    }
+    /// Builds a ban reason pointing at this casefile, with any extra text after it.
+    pub fn ban_reason(&self, id: u64, extra: &str) -> String {
+        match extra.trim() {
+            "" => format!("Casefile #{id}: {}", self.name),
+            extra => format!("Casefile #{id}: {} — {extra}", self.name),
+        }
+    }
     /// Attempts to write a new item to this casefile
     pub fn push_item(&mut self, item: impl AsRef<str>) {
         self.items.push(item.as_ref().to_owned());
     }
     /// Attempts to get a casefile given an ID.
-    pub fn from_id(id: u64) -> Result<CaseFile> {
-        let db = query_database()?;
+    /// Archived casefiles can still be read.
+    pub fn from_id(db: &Database, id: u64) -> Result<CaseFile> {
         let mut statement =
-            db.prepare(format!("SELECT name, reso, data FROM cases WHERE id = {id}").as_str())?;
-        let mut case = statement.query_map([], |row| {
-            let name = row.get::<_, String>(0)?;
-            let resolved = row.get::<_, bool>(1)?;
-            let items = row
-                .get::<_, String>(2)?
-                .lines()
-                .map(ToOwned::to_owned)
-                .collect::<Vec<_>>();
-            Ok(CaseFile {
-                name,
-                resolved,
-                items,
-            })
-        })?;
-        let case = case.next().ok_or_else(|| {
+            db.prepare("SELECT name, reso, data, assignee, severity FROM cases WHERE id = (?1)")?;
+        let mut case = statement.query_map((&id,), Self::from_row)?;
+        let mut case = case.next().ok_or_else(|| {
             CaseFileError::ParsingError("Couldn't get the case from the SQL database".to_owned())
         })??;
+        case.tags = Self::tags(db, id)?;
+        case.users = Self::users(db, id)?;
         Ok(case)
     }
-    /// Gets an iterator of all the stored casefiles.
-    /// Any errors returned are thrown out.
-    pub fn all_files() -> impl Iterator<Item = Self> {
-        (0..CaseFileAction::lowest_id_availible().unwrap_or_default()).flat_map(Self::from_id)
+    /// Builds a casefile from a row starting with `name, reso, data, assignee, severity`.
+    /// Unknown severities are treated as [`Severity::Low`].
+    fn from_row(row: &sql::Row<'_>) -> sql::Result<CaseFile> {
+        let name = row.get::<_, String>(0)?;
+        let resolved = row.get::<_, bool>(1)?;
+        let items = row
+            .get::<_, String>(2)?
+            .lines()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        let assignee = row.get::<_, Option<u64>>(3)?;
+        let severity = row.get::<_, String>(4)?.parse().unwrap_or_default();
+        Ok(CaseFile {
+            name,
+            resolved,
+            items,
+            assignee,
+            severity,
+            tags: Vec::new(),
+            users: Vec::new(),
+        })
+    }
+    /// Gets all the stored casefiles that aren't archived, alongside their IDs.
+    /// The most severe casefiles come first.
+    pub fn all_files(db: &Database) -> Result<Vec<(u64, Self)>> {
+        Self::files_with_archival(db, false)
+    }
+    /// Gets all the archived casefiles, alongside their IDs.
+    /// The most severe casefiles come first.
+    pub fn archived_files(db: &Database) -> Result<Vec<(u64, Self)>> {
+        Self::files_with_archival(db, true)
+    }
+    fn files_with_archival(db: &Database, archived: bool) -> Result<Vec<(u64, Self)>> {
+        let mut statement = db.prepare(
+            "
+            SELECT name, reso, data, assignee, severity, id FROM cases
+            WHERE archived = (?1) ORDER BY id
+            ",
+        )?;
+        let mut files = statement
+            .query_map((archived,), |row| {
+                Ok((row.get::<_, u64>(5)?, Self::from_row(row)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (id, file) in &mut files {
+            file.tags = Self::tags(db, *id)?;
+            file.users = Self::users(db, *id)?;
+        }
+        files.sort_by_key(|(id, file)| (Reverse(file.severity), *id));
+        Ok(files)
     }
     /// Writes the contents of this casefile to the relevant id.
-    pub fn write_to_id(&self, id: u64) -> Result<()> {
-        let db = query_database()?;
-        let data = vec_string_to_string(&self.items, None);
+    pub fn write_to_id(&self, db: &Database, id: u64) -> Result<()> {
+        let data = self.items.join("\n");
         db.prepare(
             "
             UPDATE cases
@@ -285,7 +1008,176 @@ impl CaseFile {
             WHERE id = (?2)
         ",
         )?
-        .execute((&id, &data))?;
+        .execute((&data, &id))?;
+        Ok(())
+    }
+    /// Returns whether the casefile with the given id is archived.
+    pub fn is_archived(db: &Database, id: u64) -> Result<bool> {
+        let archived = db.query_row(
+            "SELECT archived FROM cases WHERE id = (?1)",
+            (&id,),
+            |row| row.get::<_, bool>(0),
+        )?;
+        Ok(archived)
+    }
+    /// Archives the casefile with the given id.
+    /// The casefile is kept in the database, but is hidden from [`CaseFile::all_files`].
+    pub fn archive(db: &Database, id: u64) -> Result<()> {
+        Self::set_archived(db, id, true)
+    }
+    /// Restores the archived casefile with the given id.
+    pub fn restore(db: &Database, id: u64) -> Result<()> {
+        Self::set_archived(db, id, false)
+    }
+    /// Sets the moderator responsible for the casefile with the given id,
+    /// or leaves it unassigned.
+    pub fn assign(db: &Database, id: u64, mod_id: Option<u64>) -> Result<()> {
+        let changed = db.execute(
+            "UPDATE cases SET assignee = (?1) WHERE id = (?2)",
+            (mod_id, &id),
+        )?;
+        if changed == 0 {
+            return Err(CaseFileError::ParsingError(format!("No casefile with id {id}")).into());
+        }
+        Ok(())
+    }
+    /// Sets how serious the casefile with the given id is.
+    pub fn set_severity(db: &Database, id: u64, severity: Severity) -> Result<()> {
+        let changed = db.execute(
+            "UPDATE cases SET severity = (?1) WHERE id = (?2)",
+            (severity.key(), &id),
+        )?;
+        if changed == 0 {
+            return Err(CaseFileError::ParsingError(format!("No casefile with id {id}")).into());
+        }
+        Ok(())
+    }
+    /// Gets the tags of the casefile with the given id, in alphabetical order.
+    pub fn tags(db: &Database, id: u64) -> Result<Vec<String>> {
+        let mut statement = db.prepare("SELECT tag FROM case_tags WHERE id = (?1) ORDER BY tag")?;
+        let tags = statement
+            .query_map((&id,), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+    /// Labels the casefile with the given id with tags.
+    /// Tags it already has are left alone.
+    pub fn add_tags(db: &Database, id: u64, tags: &[String]) -> Result<()> {
+        Self::from_id(db, id)?;
+        for tag in tags {
+            db.execute(
+                "INSERT OR IGNORE INTO case_tags (id, tag) VALUES ((?1), (?2))",
+                (&id, tag),
+            )?;
+        }
+        Ok(())
+    }
+    /// Takes tags off the casefile with the given id.
+    pub fn remove_tags(db: &Database, id: u64, tags: &[String]) -> Result<()> {
+        for tag in tags {
+            db.execute(
+                "DELETE FROM case_tags WHERE id = (?1) AND tag = (?2)",
+                (&id, tag),
+            )?;
+        }
+        Ok(())
+    }
+    /// Gets the IDs of the users linked to the casefile with the given id,
+    /// in the order they were linked.
+    pub fn users(db: &Database, id: u64) -> Result<Vec<u64>> {
+        let mut statement =
+            db.prepare("SELECT user FROM case_users WHERE id = (?1) ORDER BY rowid")?;
+        let users = statement
+            .query_map((&id,), |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(users)
+    }
+    /// Links a user to the casefile with the given id.
+    pub fn add_user(db: &Database, id: u64, user: u64) -> Result<()> {
+        Self::from_id(db, id)?;
+        db.execute(
+            "INSERT OR IGNORE INTO case_users (id, user) VALUES ((?1), (?2))",
+            (&id, &user),
+        )?;
+        Ok(())
+    }
+    /// Unlinks a user from the casefile with the given id.
+    pub fn remove_user(db: &Database, id: u64, user: u64) -> Result<()> {
+        db.execute(
+            "DELETE FROM case_users WHERE id = (?1) AND user = (?2)",
+            (&id, &user),
+        )?;
+        Ok(())
+    }
+    /// Records a change made to the casefile with the given id in its history.
+    pub fn record_change(
+        db: &Database,
+        id: u64,
+        moderator: u64,
+        change: &CaseChange,
+    ) -> Result<()> {
+        db.execute(
+            "
+            INSERT INTO case_history (id, moderator, action, detail, time)
+            VALUES ((?1), (?2), (?3), (?4), (?5))
+            ",
+            (
+                &id,
+                &moderator,
+                change.key(),
+                change.detail(),
+                Timestamp::now().unix_timestamp(),
+            ),
+        )?;
+        Ok(())
+    }
+    /// Gets the changes made to the casefile with the given id, oldest first.
+    pub fn history(db: &Database, id: u64) -> Result<Vec<CaseHistoryEntry>> {
+        let mut statement = db.prepare(
+            "
+            SELECT moderator, action, detail, time FROM case_history
+            WHERE id = (?1) ORDER BY time, rowid
+            ",
+        )?;
+        let rows = statement
+            .query_map((&id,), |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(moderator, action, detail, time)| {
+                Some(CaseHistoryEntry {
+                    moderator,
+                    change: CaseChange::from_key(&action, detail)?,
+                    time,
+                })
+            })
+            .collect())
+    }
+    fn set_field(db: &Database, id: u64, column: &str, value: impl sql::ToSql) -> Result<()> {
+        let changed = db.execute(
+            &format!("UPDATE cases SET {column} = (?1) WHERE id = (?2)"),
+            (value, &id),
+        )?;
+        if changed == 0 {
+            return Err(CaseFileError::ParsingError(format!("No casefile with id {id}")).into());
+        }
+        Ok(())
+    }
+    fn set_archived(db: &Database, id: u64, archived: bool) -> Result<()> {
+        let changed = db.execute(
+            "UPDATE cases SET archived = (?1) WHERE id = (?2)",
+            (archived, &id),
+        )?;
+        if changed == 0 {
+            return Err(CaseFileError::ParsingError(format!("No casefile with id {id}")).into());
+        }
         Ok(())
     }
 }
@@ -295,7 +1187,11 @@ impl Display for CaseFile {
         let items = self
             .items
             .iter()
-            .flat_map(|string| string.chars().chain(std::iter::once('\n')))
+            .flat_map(|string| {
+                "- ".chars()
+                    .chain(string.chars())
+                    .chain(std::iter::once('\n'))
+            })
             .collect::<String>();
         let resolution = match self.is_resolved() {
             true => "resolved",
@@ -324,15 +1220,39 @@ impl FromStr for CaseFile {
                 ));
             }
         };
-        let items = items.split("\n- ").map(str::to_owned).collect();
+        let items = items
+            .lines()
+            .map(|line| line.strip_prefix("- ").unwrap_or(line))
+            .filter(|line| !line.is_empty())
+            .map(str::to_owned)
+            .collect();
         Ok(CaseFile {
             name: name.to_owned(),
             resolved,
             items,
+            assignee: None,
+            severity: Severity::default(),
+            tags: Vec::new(),
+            users: Vec::new(),
         })
     }
 }
 
+/// Splits a reason starting with a `case:3` token into the case ID and the rest of the reason.
+/// Returns [`None`] if the reason doesn't start with one.
+pub fn case_reference(reason: &str) -> Option<(u64, &str)> {
+    let reason = reason.trim_start();
+    let (token, rest) = reason
+        .split_once(char::is_whitespace)
+        .unwrap_or((reason, ""));
+    let (key, id) = token.split_once(':')?;
+    if !key.eq_ignore_ascii_case("case") {
+        return None;
+    }
+    let id = id.strip_prefix('#').unwrap_or(id).parse().ok()?;
+    Some((id, rest.trim()))
+}
+
 /// Represents a number of errors that can occur from interacting with [`CaseFile`]s.
 #[derive(Debug)]
 pub enum CaseFileError {
@@ -369,61 +1289,14 @@ impl From<SereneError> for CaseFileError {
     }
 }
 
-impl From<ParseIntError> for CaseFileError {
-    fn from(value: ParseIntError) -> Self {
+impl From<TokenizeError> for CaseFileError {
+    fn from(value: TokenizeError) -> Self {
         Self::ParsingError(format!("{value}"))
     }
 }
 
-/// Represents a connection to the internal database.
-pub struct Database(sql::Connection);
-
-impl Deref for Database {
-    type Target = sql::Connection;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl DerefMut for Database {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
-
-/// Attempts to connect to the database file.
-pub fn query_database() -> Result<Database, sql::Error> {
-    Ok(Database(sql::Connection::open(DATABASE_FILE)?))
-}
-
-/// Attempts to create and inditalize the database.
-/// Only does so if the database exists
-pub fn create_database() -> Result<(), sql::Error> {
-    // if file doesn't exist
-    if std::fs::File::open(DATABASE_FILE).is_err() {
-        let db = query_database()?;
-        db.execute(
-            "
-            CREATE TABLE users (
-                id   INTEGER PRIMARY KEY
-                keke BOOLEAN
-                blck BOOLEAN
-            )
-            ",
-            (),
-        )?;
-        db.execute(
-            "
-            CREATE TABLE cases (
-                id   INTEGER PRIMARY KEY
-                name TINYTEXT
-                reso BOOLEAN
-                data LONGTEXT
-            )
-            ",
-            (),
-        )?;
+impl From<ParseIntError> for CaseFileError {
+    fn from(value: ParseIntError) -> Self {
+        Self::ParsingError(format!("{value}"))
     }
-    Ok(())
 }
@@ -0,0 +1,85 @@
+//! Deals with describing channels, for `-channelinfo`.
+
+use crate::sink::Embed;
+use eyre::{eyre, Result};
+use serenity::{http::Http, model::channel::GuildChannel};
+
+/// What `-channelinfo` shows about a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelDetails {
+    /// The ID of the channel
+    pub id: u64,
+    /// The name of the channel
+    pub name: String,
+    /// The type of channel, such as `text` or `voice`
+    pub kind: String,
+    /// The topic of the channel, if any
+    pub topic: Option<String>,
+    /// How long members have to wait between messages, in seconds, if at all
+    pub slowmode: Option<u64>,
+    /// Whether the channel is marked as NSFW
+    pub nsfw: bool,
+    /// When the channel was created, as a unix timestamp
+    pub created: i64,
+}
+
+impl From<&GuildChannel> for ChannelDetails {
+    fn from(channel: &GuildChannel) -> Self {
+        Self {
+            id: channel.id.0,
+            name: channel.name.clone(),
+            kind: channel.kind.name().to_owned(),
+            topic: channel.topic.clone().filter(|topic| !topic.is_empty()),
+            slowmode: channel.rate_limit_per_user.filter(|seconds| *seconds > 0),
+            nsfw: channel.nsfw,
+            created: channel.id.created_at().unix_timestamp(),
+        }
+    }
+}
+
+/// Gets the details of a guild channel.
+pub async fn channel_details(http: &Http, channel_id: u64) -> Result<ChannelDetails> {
+    let Some(channel) = http.get_channel(channel_id).await?.guild() else {
+        return Err(eyre!("Only server channels have details to show"));
+    };
+    Ok(ChannelDetails::from(&channel))
+}
+
+/// Gets the ID of the guild a channel belongs to,
+/// or [`None`] if it's a direct message channel.
+pub async fn channel_guild(http: &Http, channel_id: u64) -> Result<Option<u64>> {
+    Ok(http
+        .get_channel(channel_id)
+        .await?
+        .guild()
+        .map(|channel| channel.guild_id.0))
+}
+
+impl ChannelDetails {
+    /// Renders the details as an [`Embed`].
+    pub fn to_embed(&self) -> Embed {
+        Embed {
+            title: format!("#{}", self.name),
+            description: self
+                .topic
+                .clone()
+                .unwrap_or_else(|| "*No topic set.*".to_owned()),
+            fields: vec![
+                ("Type".to_owned(), self.kind.clone()),
+                (
+                    "Slowmode".to_owned(),
+                    match self.slowmode {
+                        Some(seconds) => format!("{seconds} seconds"),
+                        None => "Off".to_owned(),
+                    },
+                ),
+                (
+                    "NSFW".to_owned(),
+                    if self.nsfw { "Yes" } else { "No" }.to_owned(),
+                ),
+                ("Created".to_owned(), format!("<t:{}:F>", self.created)),
+                ("ID".to_owned(), format!("`{}`", self.id)),
+            ],
+        }
+    }
+}
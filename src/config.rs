@@ -0,0 +1,135 @@
+//! Deals with bot-wide settings read from [`CONFIG_FILE`] or the environment,
+//! which can be re-read while the bot runs with `-dev reload`.
+
+use crate::starboard::DEFAULT_THRESHOLD;
+use std::{collections::BTreeMap, env, fmt::Display, fs, sync::RwLock};
+
+/// The file holding the bot's config as `NAME=value` lines.
+/// It's re-read on every reload, so it can be edited while the bot runs.
+pub const CONFIG_FILE: &str = "bababot.env";
+
+/// The environment variable holding how many stars messages need by default.
+pub const STAR_THRESHOLD_VARIABLE: &str = "BABA_BOT_STAR_THRESHOLD";
+/// The environment variable holding the channel mod alerts go to
+/// in servers that haven't chosen one.
+pub const MOD_CHANNEL_VARIABLE: &str = "BABA_BOT_MOD_CHANNEL";
+
+/// The config of the running bot.
+static CONFIG: RwLock<BotConfig> = RwLock::new(BotConfig::DEFAULT);
+
+/// Settings that apply to every server, unless a server overrides them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotConfig {
+    /// How many stars messages need to be starred, by default
+    pub star_threshold: u64,
+    /// The channel mod alerts go to in servers that haven't chosen one
+    pub mod_channel: Option<u64>,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl BotConfig {
+    /// The config used when nothing's been set.
+    pub const DEFAULT: BotConfig = BotConfig {
+        star_threshold: DEFAULT_THRESHOLD,
+        mod_channel: None,
+    };
+    /// Reads the config from [`CONFIG_FILE`], using the environment variable
+    /// of the same name for anything the file doesn't set.
+    pub fn load() -> Self {
+        let file = fs::read_to_string(CONFIG_FILE)
+            .map(|contents| parse_config_file(&contents))
+            .unwrap_or_default();
+        Self::from_values(|name| file.get(name).cloned().or_else(|| env::var(name).ok()))
+    }
+    /// Reads the config from [`STAR_THRESHOLD_VARIABLE`] and [`MOD_CHANNEL_VARIABLE`]
+    /// as given by `value`, falling back to the defaults for anything missing or invalid.
+    pub fn from_values(value: impl Fn(&str) -> Option<String>) -> Self {
+        let default = Self::default();
+        Self {
+            star_threshold: value(STAR_THRESHOLD_VARIABLE)
+                .and_then(|threshold| threshold.parse().ok())
+                .filter(|threshold| *threshold > 0)
+                .unwrap_or(default.star_threshold),
+            mod_channel: value(MOD_CHANNEL_VARIABLE)
+                .and_then(|channel| channel.parse().ok())
+                .or(default.mod_channel),
+        }
+    }
+    /// Gets every value that differs between this config and a newer one.
+    pub fn changes(&self, new: &BotConfig) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        if self.star_threshold != new.star_threshold {
+            changes.push(ConfigChange {
+                name: "star threshold",
+                old: self.star_threshold.to_string(),
+                new: new.star_threshold.to_string(),
+            });
+        }
+        if self.mod_channel != new.mod_channel {
+            let channel = |channel: Option<u64>| match channel {
+                Some(channel) => format!("<#{channel}>"),
+                None => "none".to_owned(),
+            };
+            changes.push(ConfigChange {
+                name: "mod channel",
+                old: channel(self.mod_channel),
+                new: channel(new.mod_channel),
+            });
+        }
+        changes
+    }
+}
+
+/// Parses the `NAME=value` lines of a config file.
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_config_file(contents: &str) -> BTreeMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect()
+}
+
+/// A value that changed when the config was reloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    /// What the value is
+    pub name: &'static str,
+    /// The value before reloading
+    pub old: String,
+    /// The value after reloading
+    pub new: String,
+}
+
+impl Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} -> {}", self.name, self.old, self.new)
+    }
+}
+
+/// Gets the config of the running bot.
+pub fn config() -> BotConfig {
+    CONFIG
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+/// Re-reads the config from [`CONFIG_FILE`] and the environment,
+/// swapping it in all at once, and returns what changed.
+pub fn reload() -> Vec<ConfigChange> {
+    let new = BotConfig::load();
+    let Ok(mut config) = CONFIG.write() else {
+        return Vec::new();
+    };
+    let changes = config.changes(&new);
+    *config = new;
+    changes
+}
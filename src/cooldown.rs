@@ -0,0 +1,34 @@
+//! Deals with cooldowns: stopping users from doing something too often.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// Tracks when each user last did something, so they can't do it again too soon.
+#[derive(Debug)]
+pub struct Cooldown {
+    seconds: i64,
+    uses: Mutex<BTreeMap<u64, i64>>,
+}
+
+impl Cooldown {
+    /// Creates a new [`Cooldown`] lasting `seconds`, with no uses recorded.
+    pub const fn new(seconds: i64) -> Self {
+        Self {
+            seconds,
+            uses: Mutex::new(BTreeMap::new()),
+        }
+    }
+    /// Returns whether a user is off cooldown at a unix timestamp,
+    /// recording the use if they are.
+    pub fn try_use(&self, user_id: u64, now: i64) -> bool {
+        let Ok(mut uses) = self.uses.lock() else {
+            return false;
+        };
+        match uses.get(&user_id) {
+            Some(last) if now - last < self.seconds => false,
+            _ => {
+                uses.insert(user_id, now);
+                true
+            }
+        }
+    }
+}
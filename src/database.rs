@@ -0,0 +1,435 @@
+//! Deals with the internal SQL database, abstracted with a [`Database`] struct.
+
+use rusqlite as sql;
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+};
+
+/// Points to the file that should be used for the internal SQL database
+pub const DATABASE_FILE: &str = "./db.db3";
+/// Points to the directory database backups are written to
+pub const BACKUP_DIRECTORY: &str = "./backups";
+/// The most rows `-dev sql` shows.
+pub const MAX_QUERY_ROWS: usize = 25;
+/// The most characters `-dev sql` shows, so the result fits in a message.
+pub const MAX_QUERY_LENGTH: usize = 1800;
+/// The version of the database's layout, stored as its `user_version`.
+/// Backups from a newer version can't be restored.
+pub const SCHEMA_VERSION: i32 = 1;
+/// The tables every backup has to have: the ones that existed at schema version 1.
+/// Tables added since are created by [`initialize_database`] after restoring,
+/// so older backups without them can still be restored.
+pub const REQUIRED_TABLES: [&str; 18] = [
+    "users",
+    "cases",
+    "case_history",
+    "case_tags",
+    "case_users",
+    "command_log",
+    "command_settings",
+    "guild_settings",
+    "self_roles",
+    "reaction_roles",
+    "word_filter",
+    "allowed_invites",
+    "warnings",
+    "warn_escalations",
+    "starboard_posts",
+    "giveaways",
+    "tags",
+    "reminders",
+];
+
+/// Represents a connection to the internal database.
+pub struct Database(sql::Connection);
+
+impl Deref for Database {
+    type Target = sql::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Database {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Attempts to connect to the database file.
+pub fn query_database() -> Result<Database, sql::Error> {
+    Ok(Database(sql::Connection::open(DATABASE_FILE)?))
+}
+
+impl Database {
+    /// Opens a fresh database that only lives in memory.
+    /// Useful for testing, as nothing touches [`DATABASE_FILE`].
+    pub fn open_in_memory() -> Result<Database, sql::Error> {
+        Ok(Database(sql::Connection::open_in_memory()?))
+    }
+}
+
+/// Copies the given database into `directory` as a file named after a unix timestamp,
+/// returning the path of the copy.
+/// This uses SQLite's online backup API, so the database can still be used meanwhile.
+pub fn backup_database(
+    db: &Database,
+    directory: impl AsRef<Path>,
+    now: i64,
+) -> eyre::Result<PathBuf> {
+    let directory = directory.as_ref();
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!("db-{now}.db3"));
+    db.backup(sql::DatabaseName::Main, &path, None)?;
+    Ok(path)
+}
+
+/// Attempts to connect to the database file without being able to change it.
+pub fn query_database_read_only() -> Result<Database, sql::Error> {
+    Ok(Database(sql::Connection::open_with_flags(
+        DATABASE_FILE,
+        sql::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?))
+}
+
+/// Returns whether a query is a single `SELECT` statement (optionally starting with `WITH`).
+pub fn is_select(query: &str) -> bool {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let first = query
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    !query.contains(';') && (first == "select" || first == "with")
+}
+
+/// Runs a read-only query, returning its rows as a code block.
+/// Anything that isn't a `SELECT` is refused,
+/// and the result is cut off at [`MAX_QUERY_ROWS`] rows or [`MAX_QUERY_LENGTH`] characters.
+pub fn run_select(db: &Database, query: &str) -> eyre::Result<String> {
+    if !is_select(query) {
+        return Err(eyre::eyre!("Only single SELECT statements can be run"));
+    }
+    let mut statement = db.prepare(query)?;
+    if !statement.readonly() {
+        return Err(eyre::eyre!("Only read-only statements can be run"));
+    }
+    let mut output = statement
+        .column_names()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let columns = statement.column_count();
+    let mut rows = statement.query(())?;
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        if count == MAX_QUERY_ROWS {
+            output.push_str("\n...");
+            break;
+        }
+        let values = (0..columns)
+            .map(|column| {
+                Ok(match row.get_ref(column)? {
+                    sql::types::ValueRef::Null => "NULL".to_owned(),
+                    sql::types::ValueRef::Integer(int) => int.to_string(),
+                    sql::types::ValueRef::Real(real) => real.to_string(),
+                    sql::types::ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+                    sql::types::ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+                })
+            })
+            .collect::<Result<Vec<_>, sql::Error>>()?;
+        output.push('\n');
+        output.push_str(&values.join(" | "));
+        count += 1;
+    }
+    if output.chars().count() > MAX_QUERY_LENGTH {
+        output = output.chars().take(MAX_QUERY_LENGTH).collect();
+        output.push_str("\n...");
+    }
+    Ok(format!(
+        "```\n{}\n```",
+        output.replace("```", "`\u{200b}``")
+    ))
+}
+
+/// Represents the reasons a backup can't be restored.
+#[derive(Debug)]
+pub enum RestoreError {
+    /// SQLite found the backup to be damaged.
+    Corrupt(String),
+    /// The backup was made by a newer version of the bot.
+    IncompatibleVersion(i32),
+    /// The backup is missing some of the bot's tables.
+    MissingTables(Vec<String>),
+    /// SQLite couldn't read the backup at all.
+    SqlError(sql::Error),
+}
+
+impl Display for RestoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestoreError::Corrupt(e) => write!(f, "the backup is corrupt: {e}"),
+            RestoreError::IncompatibleVersion(version) => write!(
+                f,
+                "the backup is from schema version {version}, but only up to {SCHEMA_VERSION} is supported"
+            ),
+            RestoreError::MissingTables(tables) => {
+                write!(f, "the backup is missing tables: {}", tables.join(", "))
+            }
+            RestoreError::SqlError(e) => write!(f, "sql error: {e}"),
+        }
+    }
+}
+
+impl Error for RestoreError {}
+
+impl From<sql::Error> for RestoreError {
+    fn from(value: sql::Error) -> Self {
+        Self::SqlError(value)
+    }
+}
+
+fn table_names(db: &sql::Connection) -> Result<Vec<String>, sql::Error> {
+    let mut statement =
+        db.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")?;
+    let tables = statement
+        .query_map((), |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tables)
+}
+
+/// Checks that the file at `path` is an intact database the bot can use:
+/// it passes SQLite's integrity check, isn't from a newer [`SCHEMA_VERSION`],
+/// and has every table in [`REQUIRED_TABLES`].
+pub fn validate_backup(path: impl AsRef<Path>) -> Result<(), RestoreError> {
+    let backup = sql::Connection::open_with_flags(path, sql::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let integrity =
+        backup.query_row("PRAGMA integrity_check", (), |row| row.get::<_, String>(0))?;
+    if integrity != "ok" {
+        return Err(RestoreError::Corrupt(integrity));
+    }
+    let version = backup.query_row("PRAGMA user_version", (), |row| row.get::<_, i32>(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(RestoreError::IncompatibleVersion(version));
+    }
+    let tables = table_names(&backup)?;
+    let missing = REQUIRED_TABLES
+        .into_iter()
+        .filter(|table| !tables.iter().any(|name| name == table))
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(RestoreError::MissingTables(missing));
+    }
+    Ok(())
+}
+
+/// Replaces the contents of the given database with the backup at `path`,
+/// once it's passed [`validate_backup`].
+/// This uses SQLite's online backup API, so other connections see the swap all at once.
+pub fn restore_database(db: &mut Database, path: impl AsRef<Path>) -> Result<(), RestoreError> {
+    validate_backup(&path)?;
+    db.restore(
+        sql::DatabaseName::Main,
+        path,
+        None::<fn(sql::backup::Progress)>,
+    )?;
+    initialize_database(db)?;
+    Ok(())
+}
+
+/// Columns added to tables after they were first created, as `(table, column, definition)`.
+/// Databases made before a column existed get it added by [`migrate_database`].
+const ADDED_COLUMNS: [(&str, &str, &str); 2] = [
+    ("cases", "assignee", "INTEGER"),
+    ("cases", "severity", "TINYTEXT NOT NULL DEFAULT 'low'"),
+];
+
+/// Attempts to create and initialize the database file.
+/// Tables that already exist are left alone.
+pub fn create_database() -> Result<(), sql::Error> {
+    initialize_database(&query_database()?)
+}
+
+/// Creates any missing tables and columns in the given database.
+pub fn initialize_database(db: &Database) -> Result<(), sql::Error> {
+    db.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS users (
+            id   INTEGER PRIMARY KEY,
+            keke BOOLEAN,
+            blck BOOLEAN
+        );
+        CREATE TABLE IF NOT EXISTS keke_nicknames (
+            guild    INTEGER NOT NULL,
+            user     INTEGER NOT NULL,
+            nickname TINYTEXT,
+            PRIMARY KEY (guild, user)
+        );
+        CREATE TABLE IF NOT EXISTS cases (
+            id       INTEGER PRIMARY KEY,
+            name     TINYTEXT,
+            reso     BOOLEAN,
+            data     LONGTEXT,
+            archived BOOLEAN NOT NULL DEFAULT 0,
+            assignee INTEGER,
+            severity TINYTEXT NOT NULL DEFAULT 'low'
+        );
+        CREATE TABLE IF NOT EXISTS case_history (
+            id        INTEGER NOT NULL,
+            moderator INTEGER NOT NULL,
+            action    TINYTEXT NOT NULL,
+            detail    TEXT NOT NULL,
+            time      INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS case_tags (
+            id  INTEGER NOT NULL,
+            tag TINYTEXT NOT NULL,
+            PRIMARY KEY (id, tag)
+        );
+        CREATE TABLE IF NOT EXISTS case_users (
+            id   INTEGER NOT NULL,
+            user INTEGER NOT NULL,
+            PRIMARY KEY (id, user)
+        );
+        CREATE TABLE IF NOT EXISTS command_log (
+            guild   INTEGER,
+            user    INTEGER NOT NULL,
+            command TINYTEXT NOT NULL,
+            time    INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS command_settings (
+            guild   INTEGER NOT NULL,
+            command TINYTEXT NOT NULL,
+            enabled BOOLEAN NOT NULL,
+            PRIMARY KEY (guild, command)
+        );
+        CREATE TABLE IF NOT EXISTS guild_settings (
+            guild INTEGER NOT NULL,
+            key   TINYTEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (guild, key)
+        );
+        CREATE TABLE IF NOT EXISTS self_roles (
+            guild INTEGER NOT NULL,
+            role  INTEGER NOT NULL,
+            PRIMARY KEY (guild, role)
+        );
+        CREATE TABLE IF NOT EXISTS reaction_roles (
+            guild   INTEGER NOT NULL,
+            message INTEGER NOT NULL,
+            emoji   TINYTEXT NOT NULL,
+            role    INTEGER NOT NULL,
+            PRIMARY KEY (message, emoji)
+        );
+        CREATE TABLE IF NOT EXISTS word_filter (
+            guild      INTEGER NOT NULL,
+            term       TINYTEXT NOT NULL,
+            whole_word BOOLEAN NOT NULL,
+            PRIMARY KEY (guild, term)
+        );
+        CREATE TABLE IF NOT EXISTS allowed_invites (
+            guild INTEGER NOT NULL,
+            code  TINYTEXT NOT NULL,
+            PRIMARY KEY (guild, code)
+        );
+        CREATE TABLE IF NOT EXISTS warnings (
+            id        INTEGER PRIMARY KEY,
+            guild     INTEGER NOT NULL,
+            user      INTEGER NOT NULL,
+            moderator INTEGER NOT NULL,
+            reason    TEXT NOT NULL,
+            time      INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS mod_log (
+            id        INTEGER PRIMARY KEY,
+            guild     INTEGER NOT NULL,
+            user      INTEGER NOT NULL,
+            moderator INTEGER NOT NULL,
+            action    TINYTEXT NOT NULL,
+            duration  INTEGER,
+            reason    TEXT NOT NULL,
+            time      INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS notes (
+            id        INTEGER PRIMARY KEY,
+            guild     INTEGER NOT NULL,
+            user      INTEGER NOT NULL,
+            moderator INTEGER NOT NULL,
+            text      TEXT NOT NULL,
+            time      INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS warn_escalations (
+            guild    INTEGER NOT NULL,
+            warnings INTEGER NOT NULL,
+            action   TINYTEXT NOT NULL,
+            PRIMARY KEY (guild, warnings)
+        );
+        CREATE TABLE IF NOT EXISTS starboard_posts (
+            message INTEGER PRIMARY KEY,
+            guild   INTEGER NOT NULL,
+            post    INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS giveaways (
+            id      INTEGER PRIMARY KEY,
+            guild   INTEGER NOT NULL,
+            channel INTEGER NOT NULL,
+            message INTEGER NOT NULL,
+            prize   TEXT NOT NULL,
+            winners INTEGER NOT NULL,
+            ends    INTEGER NOT NULL,
+            ended   BOOLEAN NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS tags (
+            guild   INTEGER NOT NULL,
+            name    TINYTEXT NOT NULL,
+            content TEXT NOT NULL,
+            PRIMARY KEY (guild, name)
+        );
+        CREATE TABLE IF NOT EXISTS rules (
+            guild  INTEGER NOT NULL,
+            number INTEGER NOT NULL,
+            text   TEXT NOT NULL,
+            PRIMARY KEY (guild, number)
+        );
+        CREATE TABLE IF NOT EXISTS reminders (
+            id      INTEGER PRIMARY KEY,
+            user    INTEGER NOT NULL,
+            channel INTEGER NOT NULL,
+            target  TINYTEXT NOT NULL,
+            due     INTEGER NOT NULL,
+            text    TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS lockdowns (
+            channel INTEGER PRIMARY KEY,
+            guild   INTEGER NOT NULL,
+            allow   INTEGER,
+            deny    INTEGER
+        );
+        ",
+    )?;
+    migrate_database(db)?;
+    db.pragma_update(None, "user_version", SCHEMA_VERSION)
+}
+
+/// Adds any of the [`ADDED_COLUMNS`] that are missing from the given database.
+pub fn migrate_database(db: &Database) -> Result<(), sql::Error> {
+    for (table, column, definition) in ADDED_COLUMNS {
+        let exists = db.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info((?1)) WHERE name = (?2)",
+            (table, column),
+            |row| row.get::<_, bool>(0),
+        )?;
+        if !exists {
+            db.execute_batch(&format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {definition};"
+            ))?;
+        }
+    }
+    Ok(())
+}
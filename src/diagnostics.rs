@@ -0,0 +1,101 @@
+//! Deals with diagnostics about the running bot process,
+//! such as its memory usage and what's cached, to help find leaks.
+
+use crate::{database::Database, sink::Embed};
+use eyre::Result;
+use serenity::cache::Cache;
+use std::fs;
+
+/// How many things serenity's cache is holding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheCounts {
+    /// How many guilds are cached
+    pub guilds: usize,
+    /// How many guild channels are cached
+    pub channels: usize,
+    /// How many users are cached
+    pub users: usize,
+}
+
+impl CacheCounts {
+    /// Counts what's in a cache.
+    pub fn from_cache(cache: &Cache) -> Self {
+        Self {
+            guilds: cache.guild_count(),
+            channels: cache.guild_channel_count(),
+            users: cache.user_count(),
+        }
+    }
+}
+
+/// A snapshot of the running bot process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProcessStats {
+    /// How much memory the process is using, in KiB, if it could be read
+    pub memory_kib: Option<u64>,
+    /// What serenity's cache is holding
+    pub cache: CacheCounts,
+    /// How many reminders are waiting to be delivered
+    pub pending_reminders: u64,
+    /// How many giveaways haven't ended yet
+    pub running_giveaways: u64,
+}
+
+impl ProcessStats {
+    /// Takes a snapshot of the process, given what's in the cache.
+    pub fn gather(db: &Database, cache: CacheCounts) -> Result<Self> {
+        let count = |query: &str| db.query_row(query, (), |row| row.get::<_, u64>(0));
+        Ok(Self {
+            memory_kib: resident_memory_kib(),
+            cache,
+            pending_reminders: count("SELECT COUNT(*) FROM reminders")?,
+            running_giveaways: count("SELECT COUNT(*) FROM giveaways WHERE NOT ended")?,
+        })
+    }
+    /// Formats the snapshot as an embed.
+    pub fn to_embed(&self) -> Embed {
+        let memory = match self.memory_kib {
+            Some(kib) => format!("{:.1} MiB", kib as f64 / 1024.0),
+            None => "unknown".to_owned(),
+        };
+        Embed {
+            title: "Process stats".to_owned(),
+            description: String::new(),
+            fields: vec![
+                ("Memory".to_owned(), memory),
+                ("Cached guilds".to_owned(), self.cache.guilds.to_string()),
+                (
+                    "Cached channels".to_owned(),
+                    self.cache.channels.to_string(),
+                ),
+                ("Cached users".to_owned(), self.cache.users.to_string()),
+                (
+                    "Pending reminders".to_owned(),
+                    self.pending_reminders.to_string(),
+                ),
+                (
+                    "Running giveaways".to_owned(),
+                    self.running_giveaways.to_string(),
+                ),
+            ],
+        }
+    }
+}
+
+/// Gets the resident memory from the contents of `/proc/self/status`, in KiB.
+pub fn parse_resident_memory(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Gets how much memory the process is using, in KiB.
+/// This is only known on Linux.
+pub fn resident_memory_kib() -> Option<u64> {
+    parse_resident_memory(&fs::read_to_string("/proc/self/status").ok()?)
+}
@@ -0,0 +1,164 @@
+//! Deals with a guild's custom emojis: listing them,
+//! and copying emojis from elsewhere into the guild.
+
+use crate::pagination::paginate;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use eyre::{eyre, Result};
+use serenity::{
+    http::Http,
+    model::prelude::{GuildId, PremiumTier},
+};
+use std::fmt::Display;
+
+/// The shortest an emoji's name can be.
+pub const MIN_EMOJI_NAME_LENGTH: usize = 2;
+/// The longest an emoji's name can be.
+pub const MAX_EMOJI_NAME_LENGTH: usize = 32;
+
+/// A custom emoji in a guild.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildEmoji {
+    /// The ID of the emoji
+    pub id: u64,
+    /// The name of the emoji
+    pub name: String,
+    /// Whether the emoji is animated
+    pub animated: bool,
+}
+
+impl Display for GuildEmoji {
+    /// Shows the emoji itself, followed by its name and ID.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = if self.animated { "a" } else { "" };
+        write!(
+            f,
+            "<{prefix}:{}:{}> `:{}:` `{}`",
+            self.name, self.id, self.name, self.id
+        )
+    }
+}
+
+impl GuildEmoji {
+    /// Gets where discord hosts the emoji's image.
+    pub fn image_url(&self) -> String {
+        let extension = if self.animated { "gif" } else { "png" };
+        format!("https://cdn.discordapp.com/emojis/{}.{extension}", self.id)
+    }
+}
+
+/// Gets a custom emoji from how it's written in a message, `<:name:id>` or `<a:name:id>`.
+pub fn parse_emoji_reference(reference: &str) -> Option<GuildEmoji> {
+    let inner = reference.trim().strip_prefix('<')?.strip_suffix('>')?;
+    let (animated, inner) = match inner.strip_prefix("a:") {
+        Some(inner) => (true, inner),
+        None => (false, inner.strip_prefix(':')?),
+    };
+    let (name, id) = inner.split_once(':')?;
+    Some(GuildEmoji {
+        id: id.parse().ok()?,
+        name: name.to_owned(),
+        animated,
+    })
+}
+
+/// Returns whether a name can be used for an emoji:
+/// 2 to 32 letters, numbers and underscores.
+pub fn valid_emoji_name(name: &str) -> bool {
+    (MIN_EMOJI_NAME_LENGTH..=MAX_EMOJI_NAME_LENGTH).contains(&name.chars().count())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Gets the name a copied emoji is given: the chosen name if there is one,
+/// otherwise the original emoji's name.
+pub fn emoji_name(emoji: &GuildEmoji, name: Option<&str>) -> String {
+    name.unwrap_or(&emoji.name).trim_matches(':').to_owned()
+}
+
+/// Gets how many static emojis, and how many animated ones, a guild can have.
+pub fn emoji_limit(tier: PremiumTier) -> usize {
+    match tier {
+        PremiumTier::Tier1 => 100,
+        PremiumTier::Tier2 => 150,
+        PremiumTier::Tier3 => 250,
+        _ => 50,
+    }
+}
+
+/// Returns whether a guild has room for another emoji,
+/// given its existing emojis and whether the new one is animated.
+pub fn has_emoji_slot(existing: &[GuildEmoji], animated: bool, limit: usize) -> bool {
+    existing
+        .iter()
+        .filter(|emoji| emoji.animated == animated)
+        .count()
+        < limit
+}
+
+/// Copies an emoji into a guild under a name, returning the new emoji.
+/// Fails without downloading anything if the guild has no room for it.
+pub async fn steal_emoji(
+    http: &Http,
+    guild_id: u64,
+    emoji: &GuildEmoji,
+    name: &str,
+) -> Result<GuildEmoji> {
+    let guild = http.get_guild(guild_id).await?;
+    let existing = guild
+        .emojis
+        .values()
+        .map(|emoji| GuildEmoji {
+            id: emoji.id.0,
+            name: emoji.name.clone(),
+            animated: emoji.animated,
+        })
+        .collect::<Vec<_>>();
+    let limit = emoji_limit(guild.premium_tier);
+    if !has_emoji_slot(&existing, emoji.animated, limit) {
+        let kind = if emoji.animated { "animated" } else { "static" };
+        return Err(eyre!(
+            "This server already has all {limit} {kind} emojis it can have"
+        ));
+    }
+    let image = reqwest::get(emoji.image_url())
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let kind = if emoji.animated { "gif" } else { "png" };
+    let data = format!("data:image/{kind};base64,{}", STANDARD.encode(image));
+    let created = GuildId(guild_id).create_emoji(http, name, &data).await?;
+    Ok(GuildEmoji {
+        id: created.id.0,
+        name: created.name,
+        animated: created.animated,
+    })
+}
+
+/// Lists emojis one per line, alphabetically,
+/// split into pages that each fit in a message.
+pub fn emoji_pages(emojis: &[GuildEmoji]) -> Vec<String> {
+    if emojis.is_empty() {
+        return vec!["This server has no custom emojis!".to_owned()];
+    }
+    let mut emojis = emojis.iter().collect::<Vec<_>>();
+    emojis.sort_by_key(|emoji| emoji.name.to_lowercase());
+    let list = emojis
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    paginate(&format!("{} custom emojis:\n{list}", emojis.len()))
+}
+
+/// Gets every custom emoji in a guild.
+pub async fn guild_emojis(http: &Http, guild_id: u64) -> Result<Vec<GuildEmoji>> {
+    let emojis = http.get_emojis(guild_id).await?;
+    Ok(emojis
+        .into_iter()
+        .map(|emoji| GuildEmoji {
+            id: emoji.id.0,
+            name: emoji.name,
+            animated: emoji.animated,
+        })
+        .collect())
+}
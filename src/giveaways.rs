@@ -0,0 +1,170 @@
+//! Deals with giveaways: collecting entries through reactions,
+//! and picking winners at random once they end.
+
+use crate::{
+    backend::{Time, PREFIX},
+    database::{query_database, Database},
+    reactions::reaction_users,
+};
+use eyre::Result;
+use rand::{seq::SliceRandom, Rng};
+use serenity::{
+    http::Http,
+    model::{prelude::ChannelId, Timestamp},
+};
+
+/// The reaction members enter giveaways with.
+pub const GIVEAWAY_EMOJI: &str = "🎉";
+/// The most winners a single giveaway can have.
+pub const MAX_GIVEAWAY_WINNERS: usize = 20;
+
+/// A change to the giveaways of a guild.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GiveawayAction {
+    /// Starts a giveaway in the channel the command was sent in
+    Start {
+        #[doc = "how long members have to enter"]
+        time: Time,
+        #[doc = "how many winners are picked"]
+        winners: usize,
+        #[doc = "what's being given away"]
+        prize: String,
+    },
+    /// Picks new winners for a giveaway that has ended
+    Reroll(u64),
+}
+
+/// A giveaway, running or ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Giveaway {
+    /// The ID of the giveaway
+    pub id: u64,
+    /// The ID of the channel the giveaway was posted in
+    pub channel: u64,
+    /// The ID of the message members react to to enter
+    pub message: u64,
+    /// What's being given away
+    pub prize: String,
+    /// How many winners are picked
+    pub winners: usize,
+    /// When the giveaway ends, as a unix timestamp
+    pub ends: i64,
+    /// Whether the winners have been picked
+    pub ended: bool,
+}
+
+/// Records a giveaway until it ends, returning its ID.
+pub fn add_giveaway(
+    db: &Database,
+    guild_id: u64,
+    channel_id: u64,
+    message_id: u64,
+    prize: &str,
+    winners: usize,
+    ends: i64,
+) -> Result<u64> {
+    db.execute(
+        "
+        INSERT INTO giveaways (guild, channel, message, prize, winners, ends, ended)
+        VALUES ((?1), (?2), (?3), (?4), (?5), (?6), FALSE)
+        ",
+        (guild_id, channel_id, message_id, prize, winners, ends),
+    )?;
+    Ok(db.last_insert_rowid() as u64)
+}
+
+fn query_giveaways(
+    db: &Database,
+    condition: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Giveaway>> {
+    let mut statement = db.prepare(&format!(
+        "
+        SELECT id, channel, message, prize, winners, ends, ended FROM giveaways
+        WHERE {condition} ORDER BY ends, id
+        "
+    ))?;
+    let giveaways = statement
+        .query_map(params, |row| {
+            Ok(Giveaway {
+                id: row.get(0)?,
+                channel: row.get(1)?,
+                message: row.get(2)?,
+                prize: row.get(3)?,
+                winners: row.get(4)?,
+                ends: row.get(5)?,
+                ended: row.get(6)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(giveaways)
+}
+
+/// Gets a giveaway from a guild by its ID.
+pub fn giveaway(db: &Database, guild_id: u64, id: u64) -> Result<Option<Giveaway>> {
+    Ok(
+        query_giveaways(db, "guild = (?1) AND id = (?2)", (guild_id, id))?
+            .into_iter()
+            .next(),
+    )
+}
+
+/// Gets every running giveaway that has ended at a unix timestamp.
+pub fn due_giveaways(db: &Database, now: i64) -> Result<Vec<Giveaway>> {
+    query_giveaways(db, "NOT ended AND ends <= (?1)", [now])
+}
+
+/// Marks a giveaway as ended, once its winners are picked.
+pub fn end_giveaway(db: &Database, id: u64) -> Result<()> {
+    db.execute("UPDATE giveaways SET ended = TRUE WHERE id = (?1)", [id])?;
+    Ok(())
+}
+
+/// Picks up to `count` different winners from the members who entered.
+/// Members who reacted more than once still only have one chance to win.
+pub fn pick_winners(entrants: &[u64], count: usize, rng: &mut impl Rng) -> Vec<u64> {
+    let mut entrants = entrants.to_vec();
+    entrants.sort_unstable();
+    entrants.dedup();
+    entrants.choose_multiple(rng, count).copied().collect()
+}
+
+/// Gets the message announcing the winners of a giveaway.
+pub fn winners_message(prize: &str, winners: &[u64]) -> String {
+    if winners.is_empty() {
+        return format!("Nobody entered the giveaway for **{prize}**, so there's no winner.");
+    }
+    let mentions = winners
+        .iter()
+        .map(|winner| format!("<@{winner}>"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{GIVEAWAY_EMOJI} Congratulations {mentions}! You won **{prize}**!")
+}
+
+/// Picks the winners of every giveaway that has ended, and announces them.
+pub async fn end_due(http: &Http) -> Result<()> {
+    let giveaways = due_giveaways(&query_database()?, Timestamp::now().unix_timestamp())?;
+    for giveaway in giveaways {
+        end_giveaway(&query_database()?, giveaway.id)?;
+        let entrants = reaction_users(http, giveaway.channel, giveaway.message, GIVEAWAY_EMOJI);
+        let entrants = match entrants.await {
+            Ok(entrants) => entrants,
+            Err(e) => {
+                eprintln!("Unable to get entrants of giveaway {}: {e}", giveaway.id);
+                continue;
+            }
+        };
+        let winners = pick_winners(&entrants, giveaway.winners, &mut rand::thread_rng());
+        let message = format!(
+            "{}\n*Giveaway `#{}` can be rerolled with `{PREFIX}giveaway reroll {}`.*",
+            winners_message(&giveaway.prize, &winners),
+            giveaway.id,
+            giveaway.id
+        );
+        if let Err(e) = ChannelId(giveaway.channel).say(http, message).await {
+            eprintln!("Unable to announce giveaway {}: {e}", giveaway.id);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Deals with moderating a guild over HTTP: bans, timeouts, nicknames and roles.
+//! Every [`CommandSink`](crate::sink::CommandSink) that talks to discord shares these.
+
+use crate::backend::Time;
+use eyre::Result;
+use serenity::{
+    http::Http,
+    model::{
+        channel::ChannelType,
+        prelude::{GuildId, UserId},
+    },
+};
+
+/// Sends a direct message to a user, whether or not they're in a guild.
+pub async fn message_user(http: &Http, user_id: u64, message: &str) -> Result<()> {
+    UserId(user_id)
+        .create_dm_channel(http)
+        .await?
+        .say(http, message)
+        .await?;
+    Ok(())
+}
+
+/// Gets the name of a user.
+pub async fn user_name(http: &Http, user_id: u64) -> Result<String> {
+    Ok(http.get_user(user_id).await?.name)
+}
+
+/// Bans a user from a guild with a reason,
+/// deleting the messages they sent in the last `delete_days` days.
+pub async fn ban_user(
+    http: &Http,
+    guild_id: u64,
+    user_id: u64,
+    delete_days: u8,
+    reason: &str,
+) -> Result<()> {
+    GuildId(guild_id)
+        .ban_with_reason(http, user_id, delete_days, reason)
+        .await?;
+    Ok(())
+}
+
+/// Lifts a user's ban from a guild.
+pub async fn unban_user(http: &Http, guild_id: u64, user_id: u64) -> Result<()> {
+    GuildId(guild_id).unban(http, user_id).await?;
+    Ok(())
+}
+
+/// Times out a member of a guild for a specified [`Time`].
+pub async fn mute_member(http: &Http, guild_id: u64, user_id: u64, time: Time) -> Result<()> {
+    GuildId(guild_id)
+        .member(http, user_id)
+        .await?
+        .disable_communication_until_datetime(http, time.try_into()?)
+        .await?;
+    Ok(())
+}
+
+/// Sets a member's nickname, or resets it if [`None`].
+pub async fn set_nickname(
+    http: &Http,
+    guild_id: u64,
+    user_id: u64,
+    nickname: Option<&str>,
+) -> Result<()> {
+    GuildId(guild_id)
+        .edit_member(http, user_id, |member| {
+            member.nickname(nickname.unwrap_or_default())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Gets the `(id, name)` of every role in a guild.
+pub async fn guild_roles(http: &Http, guild_id: u64) -> Result<Vec<(u64, String)>> {
+    let roles = http.get_guild_roles(guild_id).await?;
+    Ok(roles
+        .into_iter()
+        .map(|role| (role.id.0, role.name))
+        .collect())
+}
+
+/// Gets the IDs of the roles a member has.
+pub async fn member_roles(http: &Http, guild_id: u64, user_id: u64) -> Result<Vec<u64>> {
+    let member = http.get_member(guild_id, user_id).await?;
+    Ok(member.roles.iter().map(|role| role.0).collect())
+}
+
+/// Gets the unix timestamp a member's timeout ends at, if they have one.
+pub async fn timed_out_until(http: &Http, guild_id: u64, user_id: u64) -> Result<Option<i64>> {
+    let member = http.get_member(guild_id, user_id).await?;
+    Ok(member
+        .communication_disabled_until
+        .map(|until| until.unix_timestamp()))
+}
+
+/// Gives a member a role.
+pub async fn add_role(http: &Http, guild_id: u64, user_id: u64, role_id: u64) -> Result<()> {
+    http.add_member_role(guild_id, user_id, role_id, None)
+        .await?;
+    Ok(())
+}
+
+/// Takes a role from a member.
+pub async fn remove_role(http: &Http, guild_id: u64, user_id: u64, role_id: u64) -> Result<()> {
+    http.remove_member_role(guild_id, user_id, role_id, None)
+        .await?;
+    Ok(())
+}
+
+/// Gets the IDs of every text channel in a guild.
+pub async fn text_channels(http: &Http, guild_id: u64) -> Result<Vec<u64>> {
+    let channels = GuildId(guild_id).channels(http).await?;
+    Ok(channels
+        .into_values()
+        .filter(|channel| channel.kind == ChannelType::Text)
+        .map(|channel| channel.id.0)
+        .collect())
+}
@@ -0,0 +1,126 @@
+//! Deals with the optional health-check HTTP server,
+//! which reports whether the bot is up for deployment tooling.
+//! The server only runs if [`HEALTH_PORT_VARIABLE`] is set.
+//! With the `metrics` feature, it also serves [`METRICS`] at `/metrics`.
+
+use crate::metrics::METRICS;
+use eyre::Result;
+use serde_json::json;
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// The environment variable holding the port for the health-check server.
+pub const HEALTH_PORT_VARIABLE: &str = "BABA_BOT_HEALTH_PORT";
+
+/// The live status of the bot, shared between the event handler and the server.
+#[derive(Debug)]
+pub struct BotStatus {
+    started: Instant,
+    connected: AtomicBool,
+}
+
+impl BotStatus {
+    /// Creates a new [`BotStatus`], starting the uptime clock now.
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            connected: AtomicBool::new(false),
+        }
+    }
+    /// Records whether the bot is currently connected to discord.
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+    /// Takes a [`HealthSnapshot`] of the current status.
+    pub fn snapshot(&self) -> HealthSnapshot {
+        HealthSnapshot {
+            uptime_seconds: self.started.elapsed().as_secs(),
+            connected: self.connected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for BotStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The status of the bot at a single point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    /// How long the bot has been running, in seconds
+    pub uptime_seconds: u64,
+    /// Whether the bot is connected to discord
+    pub connected: bool,
+}
+
+/// Gets the port for the health-check server from [`HEALTH_PORT_VARIABLE`], if set.
+pub fn health_port() -> Option<u16> {
+    env::var(HEALTH_PORT_VARIABLE).ok()?.parse().ok()
+}
+
+/// Builds the full HTTP response for a request, given its first line
+/// (e.g. `GET /health HTTP/1.1`).
+pub fn respond(request_line: &str, snapshot: HealthSnapshot) -> String {
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, content_type, body) = match path {
+        "/" | "/health" => (
+            "200 OK",
+            "application/json",
+            json!({
+                "status": "ok",
+                "connected": snapshot.connected,
+                "uptime_seconds": snapshot.uptime_seconds,
+            })
+            .to_string(),
+        ),
+        "/metrics" if cfg!(feature = "metrics") => {
+            ("200 OK", "text/plain; version=0.0.4", METRICS.render())
+        }
+        _ => (
+            "404 Not Found",
+            "application/json",
+            json!({ "status": "not found" }).to_string(),
+        ),
+    };
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Serves health checks on the given port until an error occurs.
+pub async fn serve(port: u16, status: Arc<BotStatus>) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let status = status.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &status).await {
+                eprintln!("Unable to answer health check: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, status: &BotStatus) -> Result<()> {
+    let mut buffer = [0; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let request_line = request.lines().next().unwrap_or("");
+    let response = respond(request_line, status.snapshot());
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
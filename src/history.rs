@@ -0,0 +1,111 @@
+//! Deals with `-history`: one timeline of everything
+//! the mods have done about a member.
+
+use crate::{
+    modactions::{LoggedAction, ModAction},
+    notes::Note,
+    sink::Embed,
+    warnings::Warning,
+};
+use std::fmt::Display;
+
+/// The most entries a history embed lists, keeping the newest.
+pub const HISTORY_LIMIT: usize = 25;
+
+/// What kind of event a history entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    /// The member was warned
+    Warning,
+    /// A mod kept a note about the member
+    Note,
+    /// The member was banned, muted, etc.
+    Action(ModAction),
+}
+
+impl Display for HistoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryKind::Warning => write!(f, "Warning"),
+            HistoryKind::Note => write!(f, "Note"),
+            HistoryKind::Action(action) => write!(f, "{action}"),
+        }
+    }
+}
+
+/// One event in a member's moderation history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// When it happened, as a unix timestamp
+    pub time: i64,
+    /// What kind of event it was
+    pub kind: HistoryKind,
+    /// The ID of the mod responsible
+    pub moderator: u64,
+    /// The reason or note text
+    pub text: String,
+}
+
+impl Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:d> **{}** by <@{}>: {}",
+            self.time, self.kind, self.moderator, self.text
+        )
+    }
+}
+
+/// Merges a member's warnings, notes and logged actions into one timeline, oldest first.
+/// Events at the same time keep that order.
+/// Logged warnings are skipped, since `warnings` already has them.
+pub fn timeline(
+    warnings: &[Warning],
+    notes: &[Note],
+    actions: &[LoggedAction],
+) -> Vec<HistoryEntry> {
+    let warnings = warnings.iter().map(|warning| HistoryEntry {
+        time: warning.time,
+        kind: HistoryKind::Warning,
+        moderator: warning.moderator,
+        text: warning.reason.clone(),
+    });
+    let notes = notes.iter().map(|note| HistoryEntry {
+        time: note.time,
+        kind: HistoryKind::Note,
+        moderator: note.moderator,
+        text: note.text.clone(),
+    });
+    let actions = actions
+        .iter()
+        .filter(|action| action.action != ModAction::Warn)
+        .map(|action| HistoryEntry {
+            time: action.time,
+            kind: HistoryKind::Action(action.action),
+            moderator: action.moderator,
+            text: action.reason.clone(),
+        });
+    let mut entries = warnings.chain(notes).chain(actions).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.time);
+    entries
+}
+
+/// Builds the embed `-history` posts, listing the newest [`HISTORY_LIMIT`] entries.
+pub fn history_embed(name: &str, entries: &[HistoryEntry]) -> Embed {
+    let skipped = entries.len().saturating_sub(HISTORY_LIMIT);
+    let mut lines = entries[skipped..]
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if skipped > 0 {
+        lines.insert(0, format!("*…and {skipped} older entries*"));
+    }
+    Embed {
+        title: format!("Moderation history of {name}"),
+        description: match lines.as_slice() {
+            [] => "Nothing on record.".to_owned(),
+            _ => lines.join("\n"),
+        },
+        fields: Vec::new(),
+    }
+}
@@ -0,0 +1,169 @@
+//! Deals with keke'ing: renaming opted-in members after whatever follows their "I'm".
+
+use crate::{
+    automod::{filter_terms, find_invites, FilterTerm},
+    backend::{MAX_NICKNAME_LENGTH, PREFIX},
+    cooldown::Cooldown,
+    database::Database,
+};
+use eyre::Result;
+use rusqlite::OptionalExtension;
+
+/// The keke cooldowns for the running bot.
+pub static KEKE_COOLDOWN: Cooldown = Cooldown::new(KEKE_COOLDOWN_SECONDS);
+
+/// How long a member has to wait between keke renames, in seconds.
+pub const KEKE_COOLDOWN_SECONDS: i64 = 60;
+
+/// Returns whether a user is opted into getting keke'd.
+/// Users the bot has never stored are opted out.
+pub fn opted_in(db: &Database, user_id: u64) -> Result<bool> {
+    let keke = db
+        .query_row(
+            "SELECT keke FROM users WHERE id = (?1)",
+            (user_id,),
+            |row| row.get::<_, Option<bool>>(0),
+        )
+        .optional()?;
+    Ok(keke.flatten().unwrap_or(false))
+}
+
+/// Opts a user into or out of getting keke'd, keeping the rest of their row.
+pub fn set_opted_in(db: &Database, user_id: u64, opted_in: bool) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO users (id, keke) VALUES ((?1), (?2))
+        ON CONFLICT (id) DO UPDATE SET keke = excluded.keke
+        ",
+        (user_id, opted_in),
+    )?;
+    Ok(())
+}
+
+/// Gets the IDs of everyone opted into getting keke'd.
+pub fn kekeable_users(db: &Database) -> Result<Vec<u64>> {
+    let mut statement = db.prepare("SELECT id FROM users WHERE keke = TRUE ORDER BY id")?;
+    let users = statement
+        .query_map((), |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(users)
+}
+
+/// The nickname a member had before they were first keke'd,
+/// or [`None`] if they had no nickname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalNickname(pub Option<String>);
+
+/// Saves the nickname a member has before keke'ing them.
+/// Only their first nickname is kept, so kekes can be undone all at once.
+pub fn save_original_nickname(
+    db: &Database,
+    guild_id: u64,
+    user_id: u64,
+    nickname: Option<&str>,
+) -> Result<()> {
+    db.execute(
+        "INSERT OR IGNORE INTO keke_nicknames (guild, user, nickname) VALUES ((?1), (?2), (?3))",
+        (guild_id, user_id, nickname),
+    )?;
+    Ok(())
+}
+
+/// Gets the nickname a member had before they were first keke'd in a guild,
+/// or [`None`] if they haven't been keke'd there.
+pub fn original_nickname(
+    db: &Database,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<Option<OriginalNickname>> {
+    let mut statement =
+        db.prepare("SELECT nickname FROM keke_nicknames WHERE guild = (?1) AND user = (?2)")?;
+    let mut rows = statement.query((guild_id, user_id))?;
+    match rows.next()? {
+        Some(row) => Ok(Some(OriginalNickname(row.get(0)?))),
+        None => Ok(None),
+    }
+}
+
+/// Forgets the nickname a member had before they were keke'd in a guild.
+pub fn forget_original_nickname(db: &Database, guild_id: u64, user_id: u64) -> Result<()> {
+    db.execute(
+        "DELETE FROM keke_nicknames WHERE guild = (?1) AND user = (?2)",
+        (guild_id, user_id),
+    )?;
+    Ok(())
+}
+
+/// What happens to a member's nickname when they opt out of keke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NicknameReset {
+    /// They were never keke'd, so their nickname is left alone
+    Keep,
+    /// Their nickname is set back to what it was, clearing it if [`None`]
+    Restore(Option<String>),
+}
+
+impl NicknameReset {
+    /// Decides what to do from the nickname saved before they were keke'd, if any.
+    pub fn decide(original: Option<OriginalNickname>) -> Self {
+        match original {
+            Some(OriginalNickname(nickname)) => Self::Restore(nickname),
+            None => Self::Keep,
+        }
+    }
+}
+
+/// What a member would be keke'd to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KekeName {
+    /// A nickname that's fine to use
+    Valid(String),
+    /// A nickname longer than discord allows
+    TooLong(String),
+    /// A nickname with a filtered word in it, or nothing left once cleaned up
+    Blocked,
+}
+
+impl KekeName {
+    /// Cleans up a would-be nickname, stripping mentions and links from it,
+    /// and checks it against a guild's word filter.
+    pub fn sanitize(name: &str, blocklist: &[FilterTerm]) -> Self {
+        let name = name
+            .split_whitespace()
+            .filter(|word| !is_mention_or_link(word))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if name.is_empty() || blocklist.iter().any(|term| term.matches(&name)) {
+            Self::Blocked
+        } else if name.chars().count() > MAX_NICKNAME_LENGTH {
+            Self::TooLong(name)
+        } else {
+            Self::Valid(name)
+        }
+    }
+    /// Cleans up a would-be nickname against a guild's word filter;
+    /// see [`KekeName::sanitize`].
+    pub fn for_guild(db: &Database, guild_id: u64, name: &str) -> Result<Self> {
+        Ok(Self::sanitize(name, &filter_terms(db, guild_id)?))
+    }
+    /// Gets the message announcing whether a member was keke'd.
+    pub fn announcement(&self, member: &str) -> String {
+        let outcome = match self {
+            KekeName::Valid(name) => format!("{member} is `{name}`!"),
+            KekeName::TooLong(name) => format!("{member} is NOT `{name}`!"),
+            KekeName::Blocked => format!("{member} is NOT that!"),
+        };
+        format!("{outcome}\n\nWanna optout? use {PREFIX}keke!")
+    }
+}
+
+/// Returns whether a word is a mention (of a user, role, channel or everyone) or a link.
+fn is_mention_or_link(word: &str) -> bool {
+    let lowercase = word.to_lowercase();
+    (word.starts_with('<') && word.ends_with('>'))
+        || lowercase.contains("@everyone")
+        || lowercase.contains("@here")
+        || lowercase.contains("://")
+        || lowercase.starts_with("www.")
+        || !find_invites(word).is_empty()
+}
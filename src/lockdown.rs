@@ -0,0 +1,306 @@
+//! Deals with lockdowns: stopping `@everyone` from posting in channels,
+//! and letting them post again afterwards.
+
+use crate::{database::Database, sink::CommandSink};
+use eyre::{eyre, Result};
+use rusqlite::OptionalExtension;
+use serenity::{
+    http::Http,
+    model::{
+        channel::{GuildChannel, PermissionOverwrite, PermissionOverwriteType},
+        prelude::RoleId,
+        Permissions,
+    },
+};
+use std::{fmt::Display, sync::Mutex};
+
+/// What a channel's `@everyone` permission overwrite allows and denies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EveryoneOverwrite {
+    /// The permissions explicitly allowed
+    pub allow: Permissions,
+    /// The permissions explicitly denied
+    pub deny: Permissions,
+}
+
+impl EveryoneOverwrite {
+    /// Returns whether the overwrite already stops `@everyone` from posting.
+    pub fn denies_sending(&self) -> bool {
+        self.deny.contains(Permissions::SEND_MESSAGES)
+    }
+}
+
+/// Builds the `@everyone` overwrite for a channel being locked,
+/// keeping every other permission the channel already sets for `@everyone`.
+pub fn locked_overwrite(existing: Option<EveryoneOverwrite>) -> EveryoneOverwrite {
+    let mut overwrite = existing.unwrap_or(EveryoneOverwrite {
+        allow: Permissions::empty(),
+        deny: Permissions::empty(),
+    });
+    overwrite.allow.remove(Permissions::SEND_MESSAGES);
+    overwrite.deny.insert(Permissions::SEND_MESSAGES);
+    overwrite
+}
+
+/// Gets a guild channel, failing for any other kind of channel.
+async fn guild_channel(http: &Http, channel_id: u64) -> Result<GuildChannel> {
+    http.get_channel(channel_id)
+        .await?
+        .guild()
+        .ok_or_else(|| eyre!("Only server channels can be locked"))
+}
+
+/// The overwrite kind for `@everyone`, whose role shares its ID with the guild.
+fn everyone(channel: &GuildChannel) -> PermissionOverwriteType {
+    PermissionOverwriteType::Role(RoleId(channel.guild_id.0))
+}
+
+/// Gets a channel's `@everyone` overwrite, if it has one.
+pub async fn everyone_overwrite(http: &Http, channel_id: u64) -> Result<Option<EveryoneOverwrite>> {
+    let channel = guild_channel(http, channel_id).await?;
+    let kind = everyone(&channel);
+    Ok(channel
+        .permission_overwrites
+        .iter()
+        .find(|overwrite| overwrite.kind == kind)
+        .map(|overwrite| EveryoneOverwrite {
+            allow: overwrite.allow,
+            deny: overwrite.deny,
+        }))
+}
+
+/// Sets a channel's `@everyone` overwrite, or removes it if given [`None`].
+pub async fn set_everyone_overwrite(
+    http: &Http,
+    channel_id: u64,
+    overwrite: Option<EveryoneOverwrite>,
+) -> Result<()> {
+    let channel = guild_channel(http, channel_id).await?;
+    let kind = everyone(&channel);
+    match overwrite {
+        Some(overwrite) => {
+            channel
+                .create_permission(
+                    http,
+                    &PermissionOverwrite {
+                        allow: overwrite.allow,
+                        deny: overwrite.deny,
+                        kind,
+                    },
+                )
+                .await?
+        }
+        None => channel.delete_permission(http, kind).await?,
+    }
+    Ok(())
+}
+
+/// Remembers a channel's `@everyone` overwrite from before it was locked down,
+/// replacing anything remembered before.
+pub fn save_lockdown(
+    db: &Database,
+    guild_id: u64,
+    channel_id: u64,
+    previous: Option<EveryoneOverwrite>,
+) -> Result<()> {
+    db.execute(
+        "INSERT OR REPLACE INTO lockdowns (channel, guild, allow, deny) VALUES ((?1), (?2), (?3), (?4))",
+        (
+            channel_id,
+            guild_id,
+            previous.map(|overwrite| overwrite.allow.bits()),
+            previous.map(|overwrite| overwrite.deny.bits()),
+        ),
+    )?;
+    Ok(())
+}
+
+/// Gets a channel's `@everyone` overwrite from before it was locked down.
+/// The outer [`None`] means the channel wasn't locked down by the bot,
+/// and the inner one means it had no overwrite beforehand.
+pub fn saved_lockdown(db: &Database, channel_id: u64) -> Result<Option<Option<EveryoneOverwrite>>> {
+    let saved = db
+        .query_row(
+            "SELECT allow, deny FROM lockdowns WHERE channel = (?1)",
+            [channel_id],
+            |row| Ok((row.get::<_, Option<u64>>(0)?, row.get::<_, Option<u64>>(1)?)),
+        )
+        .optional()?;
+    Ok(saved.map(|saved| match saved {
+        (Some(allow), Some(deny)) => Some(EveryoneOverwrite {
+            allow: Permissions::from_bits_truncate(allow),
+            deny: Permissions::from_bits_truncate(deny),
+        }),
+        _ => None,
+    }))
+}
+
+/// Forgets a channel's saved overwrite, once it's been unlocked.
+pub fn remove_lockdown(db: &Database, channel_id: u64) -> Result<()> {
+    db.execute("DELETE FROM lockdowns WHERE channel = (?1)", [channel_id])?;
+    Ok(())
+}
+
+/// Runs a query on a shared database connection, only holding the lock while it runs.
+fn with_database<T>(db: &Mutex<Database>, query: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+    let db = db
+        .lock()
+        .map_err(|_| eyre!("The database connection was poisoned"))?;
+    query(&db)
+}
+
+/// What happened to one channel when locking or unlocking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockOutcome {
+    /// The channel was locked or unlocked
+    Done,
+    /// The channel was already in the requested state, so it was left alone
+    Skipped,
+    /// Something went wrong with the channel
+    Failed(String),
+}
+
+impl From<Result<()>> for LockOutcome {
+    fn from(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => LockOutcome::Done,
+            Err(e) => LockOutcome::Failed(e.to_string()),
+        }
+    }
+}
+
+/// The result of locking or unlocking channels, channel by channel.
+/// Its [`Display`] implementation is the message sent back to the channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockReport {
+    /// Whether the channels were being locked rather than unlocked
+    pub locked: bool,
+    /// Each channel, in order, with what happened to it
+    pub results: Vec<(u64, LockOutcome)>,
+}
+
+impl LockReport {
+    /// Counts the channels with the given outcome.
+    fn count(&self, outcome: &LockOutcome) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| result == outcome)
+            .count()
+    }
+    /// Describes what happened to the only channel, when just the current one was changed.
+    pub fn channel_message(&self) -> String {
+        match (self.results.first(), self.locked) {
+            (Some((_, LockOutcome::Done)), true) => {
+                "🔒 This channel has been locked down.".to_owned()
+            }
+            (Some((_, LockOutcome::Done)), false) => {
+                "🔓 This channel has been unlocked.".to_owned()
+            }
+            (Some((_, LockOutcome::Skipped)), true) => "This channel is already locked.".to_owned(),
+            (Some((_, LockOutcome::Skipped)), false) => {
+                "This channel wasn't locked down by me.".to_owned()
+            }
+            (Some((_, LockOutcome::Failed(error))), _) => {
+                format!("I couldn't change this channel: {error}")
+            }
+            (None, _) => self.to_string(),
+        }
+    }
+}
+
+impl Display for LockReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (verb, skipped) = match self.locked {
+            true => ("Locked down", "already locked"),
+            false => ("Unlocked", "not locked down by me"),
+        };
+        write!(
+            f,
+            "{verb} {} of {} channels.",
+            self.count(&LockOutcome::Done),
+            self.results.len()
+        )?;
+        match self.count(&LockOutcome::Skipped) {
+            0 => {}
+            1 => write!(f, " 1 was {skipped}.")?,
+            count => write!(f, " {count} were {skipped}.")?,
+        }
+        for (channel_id, result) in &self.results {
+            if let LockOutcome::Failed(error) = result {
+                write!(f, "\n❌ <#{channel_id}>: {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locks down channels in a guild, remembering how each was set up beforehand.
+/// Channels that already stop `@everyone` from posting are skipped,
+/// so unlocking won't open them up.
+/// The database sits behind a [`Mutex`] so it can be used between awaits.
+pub async fn lock_channels(
+    db: &Mutex<Database>,
+    sink: &impl CommandSink,
+    guild_id: u64,
+    channels: &[u64],
+) -> LockReport {
+    let mut results = Vec::new();
+    for &channel_id in channels {
+        let previous = match sink.everyone_overwrite(channel_id).await {
+            Ok(Some(previous)) if previous.denies_sending() => {
+                results.push((channel_id, LockOutcome::Skipped));
+                continue;
+            }
+            Ok(previous) => previous,
+            Err(e) => {
+                results.push((channel_id, LockOutcome::Failed(e.to_string())));
+                continue;
+            }
+        };
+        if let Err(e) = with_database(db, |db| save_lockdown(db, guild_id, channel_id, previous)) {
+            results.push((channel_id, LockOutcome::Failed(e.to_string())));
+            continue;
+        }
+        let locked = sink
+            .set_everyone_overwrite(channel_id, Some(locked_overwrite(previous)))
+            .await;
+        if locked.is_err() {
+            // the channel wasn't locked, so there's nothing to restore later
+            if let Err(e) = with_database(db, |db| remove_lockdown(db, channel_id)) {
+                eprintln!("Unable to forget a failed lockdown: {e}");
+            }
+        }
+        results.push((channel_id, LockOutcome::from(locked)));
+    }
+    LockReport {
+        locked: true,
+        results,
+    }
+}
+
+/// Unlocks channels, restoring exactly how they were set up before their lockdown.
+/// Channels the bot didn't lock down are skipped.
+pub async fn unlock_channels(
+    db: &Mutex<Database>,
+    sink: &impl CommandSink,
+    channels: &[u64],
+) -> LockReport {
+    let mut results = Vec::new();
+    for &channel_id in channels {
+        let outcome = match with_database(db, |db| saved_lockdown(db, channel_id)) {
+            Ok(Some(previous)) => match sink.set_everyone_overwrite(channel_id, previous).await {
+                Ok(()) => {
+                    LockOutcome::from(with_database(db, |db| remove_lockdown(db, channel_id)))
+                }
+                Err(e) => LockOutcome::Failed(e.to_string()),
+            },
+            Ok(None) => LockOutcome::Skipped,
+            Err(e) => LockOutcome::Failed(e.to_string()),
+        };
+        results.push((channel_id, outcome));
+    }
+    LockReport {
+        locked: false,
+        results,
+    }
+}
@@ -1,41 +1,164 @@
 //! See readme
 
 #![warn(missing_docs)]
+#![allow(clippy::result_large_err)]
 
+pub mod args;
+pub mod automod;
 pub mod backend;
 pub mod casefile;
+pub mod channels;
+pub mod config;
+pub mod cooldown;
+pub mod database;
+pub mod diagnostics;
+pub mod emojis;
+pub mod giveaways;
+pub mod guild;
+pub mod health;
+pub mod history;
+pub mod keke;
+pub mod lockdown;
+pub mod massban;
+pub mod members;
+pub mod metrics;
+pub mod modactions;
+pub mod modlog;
+pub mod notes;
+pub mod pagination;
+pub mod permissions;
+pub mod purge;
+pub mod raid;
+pub mod reactions;
+pub mod reconnect;
+pub mod relay;
+pub mod reminders;
+pub mod report;
+pub mod roles;
+pub mod rules;
+pub mod scheduler;
+pub mod settings;
 pub mod shard;
+pub mod sink;
+pub mod slash;
+pub mod snipe;
+pub mod softban;
+pub mod spam;
+pub mod starboard;
+pub mod stats;
+pub mod tags;
+pub mod userinfo;
+pub mod warnings;
 
 use backend::*;
 use eyre::Result;
+use health::BotStatus;
+use reconnect::{ReconnectConfig, MAX_DELAY};
 use serenity::{
-    model::prelude::{GatewayIntents, Message},
+    client::bridge::gateway::event::ShardStageUpdateEvent,
+    gateway::ConnectionStage,
+    http::Http,
+    model::{
+        application::interaction::Interaction,
+        event::MessageUpdateEvent,
+        prelude::{
+            ChannelId, GatewayIntents, GuildId, Member, Message, MessageId, Reaction, Ready, User,
+        },
+    },
     prelude::{Client, Context, EventHandler, SerenityError},
 };
 use shard::BotShard;
-use std::env;
+use std::{env, sync::Arc, time::Instant};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut client = Client::builder(get_secret()?, intents())
-        .event_handler(Bot::new())
-        .await?;
-    client.start().await?;
-    Ok(())
+    database::create_database()?;
+    config::reload();
+    let status = Arc::new(BotStatus::new());
+    if let Some(port) = health::health_port() {
+        let status = status.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(port, status).await {
+                eprintln!("Health check server stopped: {e}");
+            }
+        });
+    }
+    tokio::spawn(scheduler::run_scheduler(Arc::new(
+        Http::new(&get_secret()?),
+    )));
+    let reconnect = ReconnectConfig::from_env();
+    let mut attempt = 0;
+    loop {
+        let mut client = Client::builder(get_secret()?, intents())
+            .cache_settings(|settings| settings.max_messages(snipe::CACHED_MESSAGES))
+            .event_handler(Bot::new(status.clone()))
+            .await?;
+        let started = Instant::now();
+        let Err(e) = client.start().await else {
+            return Ok(());
+        };
+        status.set_connected(false);
+        // a client that ran for a while before stopping starts the backoff over
+        if started.elapsed() > MAX_DELAY {
+            attempt = 0;
+        }
+        let Some(delay) = reconnect.delay(attempt) else {
+            eprintln!("Client stopped after {attempt} reconnect attempts: {e}");
+            return Err(e.into());
+        };
+        attempt += 1;
+        eprintln!("Client stopped: {e}; reconnect attempt {attempt} in {delay:?}");
+        tokio::time::sleep(delay).await;
+    }
 }
 
-struct Bot;
+struct Bot {
+    status: Arc<BotStatus>,
+}
 
 impl Bot {
-    fn new() -> Self {
-        Self
+    fn new(status: Arc<BotStatus>) -> Self {
+        Self { status }
     }
 }
 
 #[async_trait::async_trait]
 impl EventHandler for Bot {
+    async fn ready(&self, ctx: Context, _ready: Ready) {
+        self.status.set_connected(true);
+        if let Err(e) = slash::register(&ctx).await {
+            eprintln!("Unable to register slash commands: {e}");
+        }
+    }
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::ApplicationCommand(command) => slash::dispatch(&ctx, &command).await,
+            Interaction::Autocomplete(autocomplete) => {
+                slash::autocomplete(&ctx, &autocomplete).await
+            }
+            _ => {}
+        }
+    }
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        self.status
+            .set_connected(event.new == ConnectionStage::Connected);
+    }
     async fn message(&self, ctx: Context, message: Message) {
+        // the bot's own messages, such as the DMs it sends, come back here too
+        if message.author.id == ctx.cache.current_user_id() {
+            return;
+        }
         let shard = BotShard::new(&ctx, &message);
+        match automod::moderate(&shard).await {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => eprintln!("Unable to run automod: {e}"),
+        }
+        match spam::guard_message(&shard).await {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => eprintln!("Unable to check for spam: {e}"),
+        }
         // keke override: if message starts with "i'm" or "i am",
         // and user is opted in, change username
         if shard.is_kekeable().await.unwrap_or(false) {
@@ -43,25 +166,88 @@ impl EventHandler for Bot {
         }
         // DM override: if message is sent to bot,
         // send message to cami
-        if let MessageOrigin::PrivateChannel = shard.message_origin() {
-            if let Err(e) = shard
-                .message_user(
-                    CAMILA,
-                    format!(
-                        "Incoming message from {}:\n> {}",
-                        shard.author(),
-                        shard.original_message().content.clone()
-                    ),
-                )
-                .await
-            {
-                eprintln!("Unable to send message: {e}");
+        if relay::should_relay(shard.message_origin(), shard.author().bot) {
+            let attachments = shard
+                .original_message()
+                .attachments
+                .iter()
+                .map(|attachment| relay::RelayedAttachment {
+                    name: attachment.filename.clone(),
+                    url: attachment.url.clone(),
+                })
+                .collect::<Vec<_>>();
+            let relayed = relay::relayed_message(
+                &shard.author().to_string(),
+                &shard.original_message().content,
+                &attachments,
+            );
+            if let Err(e) = shard.message_user_direct(CAMILA, relayed).await {
+                metrics::METRICS.record_error();
+                eprintln!("Unable to forward a direct message to the dev: {e}");
+            } else {
+                metrics::METRICS.record_dm_forward();
             }
         }
         if let Err(e) = shard.execute_command().await {
             eprintln!("Unable to execute command: {e}");
         }
     }
+    async fn guild_member_addition(&self, ctx: Context, mut new_member: Member) {
+        if let Err(e) = raid::guard_join(&ctx, &mut new_member).await {
+            eprintln!("Unable to check for raids: {e}");
+        }
+        if let Err(e) = members::welcome_member(&ctx, &new_member).await {
+            eprintln!("Unable to welcome member: {e}");
+        }
+        if let Err(e) = members::assign_autorole(&ctx, &mut new_member).await {
+            eprintln!("Unable to give autorole: {e}");
+        }
+    }
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        _member: Option<Member>,
+    ) {
+        if let Err(e) = members::farewell_member(&ctx, guild_id, &user).await {
+            eprintln!("Unable to say goodbye to member: {e}");
+        }
+    }
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        if let Err(e) = snipe::record_deletion(&ctx, channel_id, deleted_message_id, guild_id) {
+            eprintln!("Unable to remember deleted message: {e}");
+        }
+        if let Err(e) = modlog::log_deletion(&ctx, channel_id, deleted_message_id, guild_id).await {
+            eprintln!("Unable to log deleted message: {e}");
+        }
+    }
+    async fn message_update(
+        &self,
+        ctx: Context,
+        old_if_available: Option<Message>,
+        new: Option<Message>,
+        _event: MessageUpdateEvent,
+    ) {
+        if let Err(e) = snipe::record_edit(&ctx, old_if_available.as_ref(), new.as_ref()) {
+            eprintln!("Unable to remember edited message: {e}");
+        }
+        if let Err(e) = modlog::log_edit(&ctx, old_if_available.as_ref(), new.as_ref()).await {
+            eprintln!("Unable to log edited message: {e}");
+        }
+    }
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        reactions::dispatch(&ctx, &reaction, true).await;
+    }
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        reactions::dispatch(&ctx, &reaction, false).await;
+    }
 }
 
 fn intents() -> GatewayIntents {
@@ -86,11 +272,429 @@ fn get_secret() -> Result<String> {
 
 #[cfg(test)]
 mod test {
-    use std::str::FromStr;
+    use std::{str::FromStr, sync::Mutex};
 
     use indoc::indoc;
+    use serenity::model::{
+        prelude::{ChannelId, ReactionType, UserId},
+        Permissions, Timestamp,
+    };
+
+    use crate::{
+        args::{Arg, ArgKind, ArgSpec, ArgSpecError, ArgValue},
+        automod::{
+            add_filter_term, filter_terms, find_invites, remove_filter_term, set_invite_allowed,
+            violation, FilterAction, FilterTerm, InviteFilterAction,
+        },
+        casefile::{
+            case_reference, CaseChange, CaseFile, CaseFileAction, CaseFileOutcome, CaseFilter,
+            Severity,
+        },
+        channels::ChannelDetails,
+        config::{parse_config_file, BotConfig, MOD_CHANNEL_VARIABLE, STAR_THRESHOLD_VARIABLE},
+        cooldown::Cooldown,
+        database::{
+            backup_database, initialize_database, is_select, migrate_database, restore_database,
+            run_select, validate_backup, Database, RestoreError, MAX_QUERY_ROWS, SCHEMA_VERSION,
+        },
+        diagnostics::{parse_resident_memory, CacheCounts, ProcessStats},
+        emojis::GuildEmoji,
+        history::{history_embed, timeline, HistoryEntry, HistoryKind, HISTORY_LIMIT},
+        keke::{
+            forget_original_nickname, kekeable_users, opted_in, original_nickname,
+            save_original_nickname, set_opted_in, KekeName, NicknameReset, OriginalNickname,
+            KEKE_COOLDOWN_SECONDS,
+        },
+        lockdown::{
+            lock_channels, locked_overwrite, saved_lockdown, unlock_channels, EveryoneOverwrite,
+            LockOutcome,
+        },
+        massban::{skip_reason, MassBanOutcome, MassBanReport},
+        members::{
+            autorole_decision, render_template, AutoroleDecision, GoodbyeConfig, GoodbyeSetting,
+            MemberInfo, MemberName, WelcomeConfig, WelcomeSetting,
+        },
+        metrics::Metrics,
+        modactions::{
+            recent_mod_actions, record_mod_action, user_mod_actions, LoggedAction, ModAction,
+            ModLogQuery,
+        },
+        modlog::{
+            deletion_embed, edit_embed, field_value, loggable, mod_log_channel, ModLogSetting,
+            MAX_FIELD_LENGTH,
+        },
+        notes::{add_note, note_lines, notes, remove_note, Note, NoteAction},
+        pagination::{page_content, paginate, turn_page, Paginators, MAX_PAGE_LENGTH, NEXT_PAGE},
+        permissions::{
+            missing_permissions, permission_report, preflight, Preflight, RolePositions,
+            RoleStanding, REQUIRED_PERMISSIONS,
+        },
+        purge::{bulk_deletable, snowflake_time, HistoryMessage, Purge, DISCORD_EPOCH_MILLIS},
+        raid::{RaidConfig, RaidGuard, RaidState},
+        reactions::{route, ReactionEvent, ReactionHandler},
+        relay::{attachment_lines, relayed_message, should_relay, RelayedAttachment},
+        report::{
+            evidence_item, file_report, open_case_for, report_embed, ReportCase, ReportedMessage,
+        },
+        roles::{
+            add_reaction_role, emoji_key, reaction_emoji_key, reaction_role, resolve_role,
+            role_refusal, self_role_allowed, set_self_role_allowed, RoleAction,
+        },
+        rules::{
+            format_rule, format_rule_reminder, remove_rule, rule, rule_reference, rule_reminder,
+            rules, set_rule, RuleEdit,
+        },
+        sink::{with_typing, CommandSink, Embed, Response},
+        spam::{SpamConfig, SpamGuard, SpamVerdict},
+        starboard::{
+            set_starboard_post, star_header, starboard_action, starboard_post, StarboardAction,
+            StarboardConfig, StarboardSetting,
+        },
+        stats::{log_command, CommandStats},
+        userinfo::{current_timeout, ModerationSummary, UserInfo},
+        warnings::{
+            active_warning_count, add_warning, clear_warnings, escalation_for, escalation_policy,
+            set_escalation, set_warn_expiry, warnings, Escalation,
+        },
+        *,
+    };
+
+    /// A [`CommandSink`] that records everything instead of talking to discord.
+    #[derive(Default)]
+    struct MockSink {
+        sent: Mutex<Vec<String>>,
+        embeds: Mutex<Vec<Embed>>,
+        channel_embeds: Mutex<Vec<(u64, Embed)>>,
+        direct_messages: Mutex<Vec<(u64, String)>>,
+        bans: Mutex<Vec<(u64, String)>>,
+        mutes: Mutex<Vec<(u64, Time, String)>>,
+        ban_sequence: Mutex<Vec<(&'static str, u64)>>,
+        nicknames: Mutex<Vec<(u64, Option<String>)>>,
+        member_roles: Mutex<Vec<(u64, u64)>>,
+        channel_messages: Mutex<Vec<(u64, String)>>,
+        replies: Mutex<Vec<(u64, String)>>,
+        typing: Mutex<usize>,
+        pages: Mutex<Vec<Vec<String>>>,
+        locks: Mutex<Vec<(u64, Option<EveryoneOverwrite>)>>,
+        overwrites: Vec<(u64, EveryoneOverwrite)>,
+        history: Vec<HistoryMessage>,
+        deleted: Mutex<Vec<(u64, Vec<u64>)>>,
+        member_lookups: Mutex<usize>,
+        departed: bool,
+        moderator: bool,
+        outside_guild: bool,
+        permissions: Option<Permissions>,
+        target_position: Option<i64>,
+        role_permissions: Option<Permissions>,
+        foreign_channels: Vec<u64>,
+        channel: Option<u64>,
+        metrics: Metrics,
+        fail: bool,
+        fail_unban: bool,
+    }
+    impl MockSink {
+        fn sent(&self) -> Vec<String> {
+            self.sent.lock().unwrap().clone()
+        }
+        /// Stands in for `BotShard::member_request`, which needs a guild.
+        fn member_request(&self) -> Result<()> {
+            *self.member_lookups.lock().unwrap() += 1;
+            if self.guild_id().is_none() {
+                return Err(SerenityError::Other("no guild to look members up in").into());
+            }
+            if self.departed {
+                return Err(SerenityError::Other("unknown member").into());
+            }
+            Ok(())
+        }
+    }
+    #[async_trait::async_trait]
+    impl CommandSink for MockSink {
+        async fn send_message(&self, message: &str) -> Result<()> {
+            if self.fail {
+                return Err(SerenityError::Other("mock failure").into());
+            }
+            self.sent.lock().unwrap().push(message.to_owned());
+            Ok(())
+        }
+        async fn send_pages(&self, pages: Vec<String>) -> Result<()> {
+            self.pages.lock().unwrap().push(pages);
+            Ok(())
+        }
+        async fn reply(&self, response: &Response) -> Result<()> {
+            self.replies
+                .lock()
+                .unwrap()
+                .push((self.message_id(), response.content.clone()));
+            Ok(())
+        }
+        async fn send_message_to(&self, channel_id: u64, message: &str) -> Result<()> {
+            self.channel_messages
+                .lock()
+                .unwrap()
+                .push((channel_id, message.to_owned()));
+            Ok(())
+        }
+        async fn send_embed(&self, embed: &Embed) -> Result<()> {
+            self.embeds.lock().unwrap().push(embed.clone());
+            Ok(())
+        }
+        async fn send_embed_to(&self, channel_id: u64, embed: &Embed) -> Result<()> {
+            self.channel_embeds
+                .lock()
+                .unwrap()
+                .push((channel_id, embed.clone()));
+            Ok(())
+        }
+        async fn fetch_message(
+            &self,
+            _channel_id: u64,
+            _message_id: u64,
+        ) -> Result<ReportedMessage> {
+            Ok(ReportedMessage {
+                author: 7,
+                content: "baba is rude".to_owned(),
+            })
+        }
+        async fn message_user(&self, user_id: u64, message: &str) -> Result<()> {
+            self.direct_messages
+                .lock()
+                .unwrap()
+                .push((user_id, message.to_owned()));
+            Ok(())
+        }
+        async fn ban_user(&self, user_id: u64, reason: &str) -> Result<()> {
+            self.bans.lock().unwrap().push((user_id, reason.to_owned()));
+            Ok(())
+        }
+        async fn ban_user_purging(
+            &self,
+            user_id: u64,
+            _delete_days: u8,
+            reason: &str,
+        ) -> Result<()> {
+            self.ban_sequence.lock().unwrap().push(("ban", user_id));
+            self.bans.lock().unwrap().push((user_id, reason.to_owned()));
+            Ok(())
+        }
+        async fn unban_user(&self, user_id: u64) -> Result<()> {
+            if self.fail_unban {
+                return Err(SerenityError::Other("mock unban failure").into());
+            }
+            self.ban_sequence.lock().unwrap().push(("unban", user_id));
+            Ok(())
+        }
+        async fn mute_user(&self, user_id: u64, time: Time, reason: &str) -> Result<()> {
+            self.member_request()?;
+            self.mutes
+                .lock()
+                .unwrap()
+                .push((user_id, time, reason.to_owned()));
+            Ok(())
+        }
+        async fn set_nickname(&self, user_id: u64, nickname: Option<&str>) -> Result<()> {
+            self.member_request()?;
+            self.nicknames
+                .lock()
+                .unwrap()
+                .push((user_id, nickname.map(ToOwned::to_owned)));
+            Ok(())
+        }
+        async fn guild_roles(&self) -> Result<Vec<(u64, String)>> {
+            Ok(vec![(10, "Baba".to_owned()), (20, "Keke".to_owned())])
+        }
+        async fn member_roles(&self, user_id: u64) -> Result<Vec<u64>> {
+            self.member_request()?;
+            let member_roles = self.member_roles.lock().unwrap();
+            Ok(member_roles
+                .iter()
+                .filter(|(user, _)| *user == user_id)
+                .map(|(_, role)| *role)
+                .collect())
+        }
+        async fn timed_out_until(&self, _user_id: u64) -> Result<Option<i64>> {
+            self.member_request()?;
+            Ok(None)
+        }
+        async fn add_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+            self.member_roles.lock().unwrap().push((user_id, role_id));
+            Ok(())
+        }
+        async fn remove_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+            self.member_roles
+                .lock()
+                .unwrap()
+                .retain(|member_role| *member_role != (user_id, role_id));
+            Ok(())
+        }
+        async fn text_channels(&self) -> Result<Vec<u64>> {
+            Ok(vec![50, 60])
+        }
+        async fn everyone_overwrite(&self, channel_id: u64) -> Result<Option<EveryoneOverwrite>> {
+            Ok(self
+                .overwrites
+                .iter()
+                .find(|(channel, _)| *channel == channel_id)
+                .map(|(_, overwrite)| *overwrite))
+        }
+        async fn set_everyone_overwrite(
+            &self,
+            channel_id: u64,
+            overwrite: Option<EveryoneOverwrite>,
+        ) -> Result<()> {
+            if self.foreign_channels.contains(&channel_id) {
+                return Err(SerenityError::Other("Missing Access").into());
+            }
+            self.locks.lock().unwrap().push((channel_id, overwrite));
+            Ok(())
+        }
+        async fn user_name(&self, user_id: u64) -> Result<String> {
+            Ok(format!("user{user_id}"))
+        }
+        async fn author_is_mod(&self) -> Result<bool> {
+            Ok(self.moderator)
+        }
+        async fn channel_details(&self, channel_id: u64) -> Result<ChannelDetails> {
+            Ok(ChannelDetails {
+                id: channel_id,
+                name: "general".to_owned(),
+                kind: "text".to_owned(),
+                topic: None,
+                slowmode: Some(5),
+                nsfw: false,
+                created: 1_600_000_000,
+            })
+        }
+        async fn channel_guild(&self, channel_id: u64) -> Result<Option<u64>> {
+            match self.foreign_channels.contains(&channel_id) {
+                true => Ok(Some(200)),
+                false => Ok(Some(100)),
+            }
+        }
+        async fn guild_emojis(&self) -> Result<Vec<GuildEmoji>> {
+            Ok(vec![GuildEmoji {
+                id: 900,
+                name: "baba".to_owned(),
+                animated: false,
+            }])
+        }
+        async fn create_emoji(&self, emoji: &GuildEmoji, name: &str) -> Result<GuildEmoji> {
+            Ok(GuildEmoji {
+                id: 901,
+                name: name.to_owned(),
+                animated: emoji.animated,
+            })
+        }
+        async fn bot_permissions(&self) -> Result<Permissions> {
+            Ok(self.permissions.unwrap_or_else(Permissions::all))
+        }
+        async fn role_positions(&self, user_id: u64) -> Result<RolePositions> {
+            Ok(RolePositions {
+                bot: 10,
+                target: match user_id == self.author_id() {
+                    true => Some(5),
+                    false => self.target_position,
+                },
+            })
+        }
+        async fn role_standing(&self, role_id: u64) -> Result<RoleStanding> {
+            Ok(RoleStanding {
+                position: (role_id / 10) as i64,
+                permissions: self.role_permissions.unwrap_or_else(Permissions::empty),
+            })
+        }
+        async fn channel_history(
+            &self,
+            _channel_id: u64,
+            after: Option<u64>,
+            limit: usize,
+        ) -> Result<Vec<HistoryMessage>> {
+            Ok(self
+                .history
+                .iter()
+                .filter(|message| after.is_none_or(|after| message.id > after))
+                .take(limit)
+                .copied()
+                .collect())
+        }
+        async fn delete_messages(&self, channel_id: u64, message_ids: &[u64]) -> Result<()> {
+            self.deleted
+                .lock()
+                .unwrap()
+                .push((channel_id, message_ids.to_vec()));
+            Ok(())
+        }
+        async fn guild_members(&self) -> Result<Vec<MemberName>> {
+            Ok(vec![
+                MemberName {
+                    user_id: 1,
+                    name: "baba".to_owned(),
+                    nickname: Some("Baba Is You".to_owned()),
+                },
+                MemberName {
+                    user_id: 2,
+                    name: "keke".to_owned(),
+                    nickname: Some("Keke Is Baba".to_owned()),
+                },
+            ])
+        }
+        async fn send_with_reaction(&self, message: &str, _emoji: &str) -> Result<u64> {
+            self.sent.lock().unwrap().push(message.to_owned());
+            Ok(700)
+        }
+        async fn reaction_users(
+            &self,
+            _channel_id: u64,
+            _message_id: u64,
+            _emoji: &str,
+        ) -> Result<Vec<u64>> {
+            Ok(vec![1, 2, 3])
+        }
+        fn author_id(&self) -> u64 {
+            1
+        }
+        fn bot_id(&self) -> u64 {
+            2
+        }
+        fn guild_id(&self) -> Option<u64> {
+            (!self.outside_guild).then_some(100)
+        }
+        fn channel_id(&self) -> u64 {
+            self.channel.unwrap_or(50)
+        }
+        fn message_id(&self) -> u64 {
+            500
+        }
+        fn start_typing(&self) -> Option<serenity::http::Typing> {
+            *self.typing.lock().unwrap() += 1;
+            None
+        }
+        fn metrics(&self) -> &Metrics {
+            &self.metrics
+        }
+        fn cache_counts(&self) -> CacheCounts {
+            CacheCounts {
+                guilds: 1,
+                channels: 5,
+                users: 2,
+            }
+        }
+    }
 
-    use crate::{casefile::CaseFile, *};
+    fn test_database() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        db
+    }
+    fn insert_case(db: &Database, name: &str) -> u64 {
+        let id = CaseFileAction::lowest_id_availible(db).unwrap();
+        db.execute(
+            "INSERT INTO cases (id, name, reso, data) VALUES ((?1), (?2), (?3), (?4))",
+            (&id, name, false, ""),
+        )
+        .unwrap();
+        id
+    }
     #[test]
     fn time_parse_seconds() {
         let target = Time {
@@ -222,8 +826,4258 @@ mod test {
             CaseFile {
                 name: "Foo v. Bar".to_owned(),
                 resolved: false,
-                items: vec!["Among Us".to_owned()]
+                items: vec!["Among us".to_owned()],
+                assignee: None,
+                severity: Severity::Low,
+                tags: Vec::new(),
+                users: Vec::new()
             }
         )
     }
+    #[test]
+    fn casefile_archive_and_restore() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        assert!(!CaseFile::is_archived(&db, id).unwrap());
+        CaseFile::archive(&db, id).unwrap();
+        assert!(CaseFile::is_archived(&db, id).unwrap());
+        CaseFile::restore(&db, id).unwrap();
+        assert!(!CaseFile::is_archived(&db, id).unwrap());
+    }
+    #[test]
+    fn casefile_archive_missing_id() {
+        let db = test_database();
+        assert!(CaseFile::archive(&db, 42).is_err());
+    }
+    #[test]
+    fn casefile_archived_excluded_from_view_all() {
+        let db = test_database();
+        let kept = insert_case(&db, "Foo v. Bar");
+        let archived = insert_case(&db, "Baz v. Qux");
+        CaseFile::archive(&db, archived).unwrap();
+        let all = CaseFile::all_files(&db).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].0, kept);
+        let archived_files = CaseFile::archived_files(&db).unwrap();
+        assert_eq!(archived_files.len(), 1);
+        assert_eq!(archived_files[0].0, archived);
+        assert_eq!(archived_files[0].1.name, "Baz v. Qux");
+    }
+    #[test]
+    fn casefile_archived_keeps_id() {
+        let db = test_database();
+        let first = insert_case(&db, "Foo v. Bar");
+        CaseFile::archive(&db, first).unwrap();
+        assert_eq!(CaseFileAction::lowest_id_availible(&db).unwrap(), first + 1);
+    }
+    #[test]
+    fn casefile_parse_archive_actions() {
+        assert!(matches!(
+            "casefile delete 3".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::Delete { id: 3 })
+        ));
+        assert!(matches!(
+            "casefile restore 3".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::Restore { id: 3 })
+        ));
+        assert!(matches!(
+            "casefile view archived".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::ViewArchived)
+        ));
+        assert!(matches!(
+            "casefile view".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::ViewAll)
+        ));
+    }
+    #[test]
+    fn casefile_outcome_create() {
+        let db = test_database();
+        let outcome = CaseFileAction::Create {
+            name: "Foo v. Bar".to_owned(),
+        }
+        .perform(&db, 1)
+        .unwrap();
+        assert_eq!(
+            outcome,
+            CaseFileOutcome::Created {
+                id: 0,
+                name: "Foo v. Bar".to_owned()
+            }
+        );
+        assert_eq!(CaseFile::from_id(&db, 0).unwrap().name, "Foo v. Bar");
+    }
+    #[test]
+    fn casefile_outcome_add_item() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        let outcome = CaseFileAction::AddItem {
+            id,
+            item: "Among us".to_owned(),
+        }
+        .perform(&db, 1)
+        .unwrap();
+        assert_eq!(
+            outcome,
+            CaseFileOutcome::AddedItem {
+                id,
+                item: "Among us".to_owned()
+            }
+        );
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().items, vec!["Among us"]);
+    }
+    #[test]
+    fn casefile_outcome_remove_item() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        for item in ["first", "second", "third"] {
+            CaseFileAction::AddItem {
+                id,
+                item: item.to_owned(),
+            }
+            .perform(&db, 1)
+            .unwrap();
+        }
+        let outcome = CaseFileAction::RemoveItem { id, index: Some(1) }
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            CaseFileOutcome::RemovedItem {
+                id,
+                item: Some("second".to_owned())
+            }
+        );
+        let outcome = CaseFileAction::RemoveItem { id, index: None }
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            CaseFileOutcome::RemovedItem {
+                id,
+                item: Some("third".to_owned())
+            }
+        );
+        let outcome = CaseFileAction::RemoveItem { id, index: Some(9) }
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(outcome, CaseFileOutcome::RemovedItem { id, item: None });
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().items, vec!["first"]);
+    }
+    #[test]
+    fn casefile_parse_remove_item() {
+        assert!(matches!(
+            "casefile remove 3".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::RemoveItem { id: 3, index: None })
+        ));
+        assert!(matches!(
+            "casefile remove 3 1".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::RemoveItem {
+                id: 3,
+                index: Some(1)
+            })
+        ));
+    }
+    #[tokio::test]
+    async fn execute_coinflip() {
+        let sink = MockSink::default();
+        Command::CoinFlip(1).execute_command(&sink).await.unwrap();
+        let sent = sink.sent();
+        assert_eq!(sent.len(), 1);
+        assert!([
+            "The result of the coin flip was... ||heads!||",
+            "The result of the coin flip was... ||tails!||"
+        ]
+        .contains(&sent[0].as_str()));
+    }
+    #[tokio::test]
+    async fn execute_xkcd() {
+        let sink = MockSink::default();
+        Command::Xkcd(xkcd_from_string("tautology"))
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(sink.sent(), vec!["https://xkcd.com/703/"]);
+    }
+    #[tokio::test]
+    async fn execute_dont_ask_to_ask() {
+        let sink = MockSink::default();
+        Command::DontAskToAsk.execute_command(&sink).await.unwrap();
+        assert_eq!(sink.sent(), vec!["https://dontasktoask.com/"]);
+    }
+    #[tokio::test]
+    async fn execute_ban_messages_user() {
+        let sink = MockSink::default();
+        Command::Ban(UserId(5), "spam".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(*sink.bans.lock().unwrap(), vec![(5, "spam".to_owned())]);
+        let direct_messages = sink.direct_messages.lock().unwrap();
+        assert_eq!(direct_messages.len(), 1);
+        assert_eq!(direct_messages[0].0, 5);
+        assert!(direct_messages[0].1.contains("spam"));
+        assert_eq!(
+            sink.sent(),
+            vec!["Successfully banned user5 for the following reason: \n>spam"]
+        );
+    }
+    #[test]
+    fn parse_ban_bad_user_shows_usage() {
+        let parsed = Command::parse("-ban foo_bar spamming", "tester");
+        assert_eq!(
+            parsed,
+            Command::NotValid(
+                "argument 1 (user) invalid; usage: `-ban [user] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn parse_mute_bad_time_shows_usage() {
+        let parsed = Command::parse("-mute 1234 5x spamming", "tester");
+        assert_eq!(
+            parsed,
+            Command::NotValid(
+                "argument 2 (time) invalid; usage: `-mute [user] [time] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn help_message_uses_usage() {
+        assert_eq!(
+            CommandType::Notice.help_message(),
+            indoc! {"
+                ```
+                -notice [...message] - Mod Only!
+                ================================
+                Anonymously gives a broadcast to the channel.
+                ```
+            "}
+        );
+    }
+    #[test]
+    fn parse_ban_without_arguments() {
+        let parsed = Command::parse("-ban", "tester");
+        assert_eq!(
+            parsed,
+            Command::NotValid(
+                "argument 1 (user) missing; usage: `-ban [user] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn parse_mute_without_arguments() {
+        let parsed = Command::parse("-mute", "tester");
+        assert_eq!(
+            parsed,
+            Command::NotValid(
+                "argument 1 (user) missing; usage: `-mute [user] [time] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn parse_mute_without_time() {
+        let parsed = Command::parse("-mute 1234", "tester");
+        assert_eq!(
+            parsed,
+            Command::NotValid(
+                "argument 2 (time) missing; usage: `-mute [user] [time] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn parse_ban_reason_excludes_user() {
+        let parsed = Command::parse("-ban 1234 being rude", "tester");
+        assert_eq!(parsed, Command::Ban(UserId(1234), "being rude".to_owned()));
+    }
+    #[test]
+    fn command_parse_empty() {
+        let target = CommandType::Empty;
+        let parsed = "-".parse().unwrap();
+        assert_eq!(target, parsed);
+        assert_eq!(Command::parse("-", "tester"), Command::NotACommand);
+    }
+    #[test]
+    fn command_parse_garbage() {
+        let target = CommandType::NotValid;
+        let parsed = "-garbage".parse().unwrap();
+        assert_eq!(target, parsed);
+        assert!(matches!(
+            Command::parse("-garbage", "tester"),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn command_parse_bare_ban() {
+        let target = CommandType::Ban;
+        let parsed = "-ban".parse().unwrap();
+        assert_eq!(target, parsed);
+    }
+    #[test]
+    fn parse_ignores_repeated_whitespace() {
+        let parsed = Command::parse("-ban   1234 \n  being   rude", "tester");
+        assert_eq!(parsed, Command::Ban(UserId(1234), "being rude".to_owned()));
+    }
+    #[test]
+    fn parse_ignores_leading_whitespace() {
+        let parsed = Command::parse("  -ban 1234 being rude", "tester");
+        assert_eq!(parsed, Command::Ban(UserId(1234), "being rude".to_owned()));
+    }
+    #[test]
+    fn tokenize_quoted_argument() {
+        let tokens = tokenize("a \"b c\" d").unwrap();
+        assert_eq!(tokens, vec!["a", "b c", "d"]);
+    }
+    #[test]
+    fn tokenize_escaped_quote() {
+        let tokens = tokenize(r#"say "he said \"hi\"" \"loudly\""#).unwrap();
+        assert_eq!(tokens, vec!["say", "he said \"hi\"", "\"loudly\""]);
+    }
+    #[test]
+    fn tokenize_unterminated_quote() {
+        assert_eq!(tokenize("a \"b c d"), Err(TokenizeError::UnterminatedQuote));
+        assert!(matches!(
+            Command::parse("-notice \"oops", "tester"),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn casefile_parse_quoted_name() {
+        assert!(matches!(
+            "casefile create \"Foo v. Bar\"".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::Create { name }) if name == "Foo v. Bar"
+        ));
+    }
+    #[test]
+    fn command_parse_help_search() {
+        assert_eq!(
+            Command::parse("-help search keke", "tester"),
+            Command::HelpSearch("keke".to_owned())
+        );
+    }
+    #[test]
+    fn help_search_multiple_matches() {
+        let matches = CommandType::search("KEKE");
+        assert_eq!(
+            matches,
+            vec![CommandType::Optin, CommandType::Optout, CommandType::Keke]
+        );
+    }
+    #[test]
+    fn help_search_no_matches() {
+        assert!(CommandType::search("amogus").is_empty());
+        assert!(CommandType::search("   ").is_empty());
+    }
+    #[tokio::test]
+    async fn execute_help_search() {
+        let sink = MockSink::default();
+        Command::HelpSearch("coin".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(
+            sink.sent(),
+            vec![
+                "Commands matching `coin`:\n`-coinflip <count:[count]>` - 50/50 chance to return Heads or Tails."
+            ]
+        );
+        let sink = MockSink::default();
+        Command::HelpSearch("amogus".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(sink.sent(), vec!["No commands matched `amogus`."]);
+    }
+    #[test]
+    fn every_alias_resolves() {
+        for command in CommandType::ALL {
+            assert!(!command.aliases().is_empty());
+            for alias in command.aliases() {
+                let parsed = format!("-{alias}").parse::<CommandType>().unwrap();
+                assert_eq!(parsed, command);
+                let parsed = alias.to_uppercase().parse::<CommandType>().unwrap();
+                assert_eq!(parsed, command);
+            }
+        }
+    }
+    #[test]
+    fn help_lists_aliases() {
+        let help = CommandType::CoinFlip.help_message();
+        assert!(help.contains("Aliases: -coinflip, -flip"));
+        let help = CommandType::PrivateModMessage.help_message();
+        assert!(help.contains("Aliases: -pvm, -private"));
+        assert!(!CommandType::Ban.help_message().contains("Aliases"));
+    }
+    #[test]
+    fn levenshtein_distances() {
+        assert_eq!(levenshtein("bam", "ban"), 1);
+        assert_eq!(levenshtein("mtue", "mute"), 2);
+        assert_eq!(levenshtein("", "keke"), 4);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+    #[test]
+    fn closest_command_suggestion() {
+        assert_eq!(CommandType::closest("bam"), Some("ban"));
+        assert_eq!(CommandType::closest("mtue"), Some("mute"));
+        assert_eq!(CommandType::closest("xylophone"), None);
+        assert_eq!(
+            Command::parse("-bam 1234", "tester"),
+            Command::NotValid("I couldn't parse the command! Did you mean `-ban`?".to_owned())
+        );
+        assert_eq!(
+            Command::parse("-xylophone", "tester"),
+            Command::NotValid("I couldn't parse the command!".to_owned())
+        );
+    }
+    #[test]
+    fn stats_aggregation() {
+        let db = test_database();
+        let rows = [
+            (Some(100), 1, CommandType::CoinFlip),
+            (Some(100), 1, CommandType::CoinFlip),
+            (Some(100), 2, CommandType::CoinFlip),
+            (Some(100), 2, CommandType::Xkcd),
+            (Some(100), 3, CommandType::Xkcd),
+            (Some(100), 3, CommandType::Keke),
+            (Some(100), 3, CommandType::Help),
+            (Some(100), 3, CommandType::RandomInt),
+            (Some(100), 3, CommandType::Optin),
+            (Some(200), 4, CommandType::Ban),
+            (None, 5, CommandType::Ban),
+        ];
+        for (time, (guild, user, command)) in rows.into_iter().enumerate() {
+            log_command(&db, guild, user, command, time as i64).unwrap();
+        }
+        let stats = CommandStats::for_guild(&db, 100).unwrap();
+        assert_eq!(stats.total, 9);
+        assert_eq!(stats.unique_users, 3);
+        assert_eq!(
+            stats.top_commands,
+            vec![
+                ("coinflip".to_owned(), 3),
+                ("xkcd".to_owned(), 2),
+                ("help".to_owned(), 1),
+                ("keke".to_owned(), 1),
+                ("optin".to_owned(), 1),
+            ]
+        );
+        assert_eq!(
+            CommandStats::for_guild(&db, 300).unwrap(),
+            CommandStats::default()
+        );
+    }
+    #[test]
+    fn stats_embed() {
+        let stats = CommandStats {
+            total: 3,
+            top_commands: vec![("coinflip".to_owned(), 2), ("xkcd".to_owned(), 1)],
+            unique_users: 2,
+        };
+        let embed = stats.to_embed();
+        assert_eq!(embed.description, "Total commands run: 3\nUnique users: 2");
+        assert_eq!(
+            embed.fields,
+            vec![(
+                "Most used commands".to_owned(),
+                "1. `coinflip` - 2\n2. `xkcd` - 1".to_owned()
+            )]
+        );
+    }
+    #[test]
+    fn health_response_json() {
+        let snapshot = health::HealthSnapshot {
+            uptime_seconds: 42,
+            connected: true,
+        };
+        let response = health::respond("GET /health HTTP/1.1", snapshot);
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            serde_json::json!({"status": "ok", "connected": true, "uptime_seconds": 42})
+        );
+    }
+    #[test]
+    fn health_response_unknown_path() {
+        let snapshot = health::BotStatus::new().snapshot();
+        assert!(!snapshot.connected);
+        let response = health::respond("GET /amogus HTTP/1.1", snapshot);
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+    #[tokio::test]
+    async fn metrics_count_commands_and_errors() {
+        let sink = MockSink::default();
+        for command in [
+            Command::CoinFlip(1),
+            Command::CoinFlip(1),
+            Command::DontAskToAsk,
+            Command::NotACommand,
+        ] {
+            command.execute_command(&sink).await.unwrap();
+        }
+        assert_eq!(sink.metrics.commands_executed(CommandType::CoinFlip), 2);
+        assert_eq!(sink.metrics.commands_executed(CommandType::DontAskToAsk), 1);
+        assert_eq!(sink.metrics.commands_executed(CommandType::NotACommand), 0);
+        assert_eq!(sink.metrics.errors(), 0);
+        let failing = MockSink {
+            fail: true,
+            ..Default::default()
+        };
+        assert!(Command::Keke.execute_command(&failing).await.is_err());
+        assert_eq!(failing.metrics.commands_executed(CommandType::Keke), 1);
+        assert_eq!(failing.metrics.errors(), 1);
+    }
+    #[test]
+    fn metrics_render() {
+        let metrics = Metrics::new();
+        metrics.record_command(CommandType::Xkcd);
+        metrics.record_command(CommandType::Xkcd);
+        metrics.record_dm_forward();
+        let rendered = metrics.render();
+        assert!(rendered.contains("bababot_commands_total{command=\"xkcd\"} 2\n"));
+        assert!(rendered.contains("bababot_errors_total 0\n"));
+        assert!(rendered.contains("bababot_dm_forwards_total 1\n"));
+    }
+    #[test]
+    fn reconnect_backoff_sequence() {
+        let config = reconnect::ReconnectConfig {
+            max_retries: 12,
+            base_delay: std::time::Duration::from_secs(1),
+        };
+        let delays = (0..13)
+            .map(|attempt| config.delay(attempt).map(|delay| delay.as_secs()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            vec![
+                Some(1),
+                Some(2),
+                Some(4),
+                Some(8),
+                Some(16),
+                Some(32),
+                Some(64),
+                Some(128),
+                Some(256),
+                Some(300),
+                Some(300),
+                Some(300),
+                None
+            ]
+        );
+    }
+    #[test]
+    fn reconnect_backoff_no_overflow() {
+        let config = reconnect::ReconnectConfig {
+            max_retries: u32::MAX,
+            base_delay: std::time::Duration::from_millis(500),
+        };
+        assert_eq!(config.delay(200), Some(reconnect::MAX_DELAY));
+    }
+    #[test]
+    fn command_settings_lookup() {
+        let db = test_database();
+        assert!(settings::command_enabled(&db, 100, CommandType::CoinFlip).unwrap());
+        assert!(!settings::toggle_command(&db, 100, CommandType::CoinFlip).unwrap());
+        assert!(!settings::command_enabled(&db, 100, CommandType::CoinFlip).unwrap());
+        assert!(settings::command_enabled(&db, 200, CommandType::CoinFlip).unwrap());
+        assert!(settings::command_enabled(&db, 100, CommandType::Xkcd).unwrap());
+        assert!(settings::toggle_command(&db, 100, CommandType::CoinFlip).unwrap());
+        assert!(settings::command_enabled(&db, 100, CommandType::CoinFlip).unwrap());
+    }
+    #[test]
+    fn disabled_command_is_not_valid() {
+        let db = test_database();
+        settings::set_command_enabled(&db, 100, CommandType::CoinFlip, false).unwrap();
+        assert_eq!(
+            Command::CoinFlip(1).requires_enabled(&db, Some(100)),
+            Command::NotValid("`-coinflip` has been disabled in this server.".to_owned())
+        );
+        assert_eq!(
+            Command::CoinFlip(1).requires_enabled(&db, Some(200)),
+            Command::CoinFlip(1)
+        );
+        assert_eq!(
+            Command::CoinFlip(1).requires_enabled(&db, None),
+            Command::CoinFlip(1)
+        );
+        settings::set_command_enabled(&db, 100, CommandType::Dev, false).unwrap();
+        assert_eq!(
+            Command::Dev("toggle coinflip".to_owned()).requires_enabled(&db, Some(100)),
+            Command::Dev("toggle coinflip".to_owned())
+        );
+    }
+    #[tokio::test]
+    async fn permission_checks_use_the_given_database() {
+        let db = test_database();
+        settings::set_command_enabled(&db, 100, CommandType::CoinFlip, false).unwrap();
+        let sink = MockSink::default();
+        assert_eq!(
+            Command::CoinFlip(1).check_permissions(&db, &sink).await,
+            Command::NotValid("`-coinflip` has been disabled in this server.".to_owned())
+        );
+        assert_eq!(
+            Command::CoinFlip(1)
+                .check_permissions(&test_database(), &sink)
+                .await,
+            Command::CoinFlip(1)
+        );
+        Command::CoinFlip(1).dispatch(db, &sink, 0).await.unwrap();
+        let replies = sink.replies.lock().unwrap();
+        assert_eq!(replies.len(), 1);
+        assert!(replies[0]
+            .1
+            .contains("`-coinflip` has been disabled in this server."));
+    }
+    #[test]
+    fn coinflip_count_parsing() {
+        assert_eq!(Command::parse("-coinflip", "user"), Command::CoinFlip(1));
+        assert_eq!(
+            Command::parse("-flip count:5", "user"),
+            Command::CoinFlip(5)
+        );
+        assert_eq!(
+            Command::parse("-coinflip count:20", "user"),
+            Command::CoinFlip(20)
+        );
+        assert_eq!(
+            Command::parse("-coinflip count:21", "user"),
+            Command::NotValid("I can only flip between 1 and 20 coins at once!".to_owned())
+        );
+        assert_eq!(
+            Command::parse("-coinflip count:0", "user"),
+            Command::NotValid("I can only flip between 1 and 20 coins at once!".to_owned())
+        );
+        assert_eq!(
+            Command::parse("-coinflip five", "user"),
+            Command::NotValid(
+                "argument 1 (count) invalid; usage: `-coinflip <count:[count]>`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn coinflip_tally() {
+        assert_eq!(
+            coin_flip_message(&[true], MessageOrigin::PublicChannel),
+            "The result of the coin flip was... ||heads!||"
+        );
+        assert_eq!(
+            coin_flip_message(&[true, false, true], MessageOrigin::PublicChannel),
+            "I flipped 3 coins... ||heads, tails, heads||\nHeads: 2, Tails: 1"
+        );
+    }
+    #[test]
+    fn randint_parsing() {
+        assert_eq!(
+            Command::parse("-randint 10", "user"),
+            Command::RandomInt(0, 10)
+        );
+        assert_eq!(
+            Command::parse("-rand 5 10", "user"),
+            Command::RandomInt(5, 10)
+        );
+        assert_eq!(
+            Command::parse("-rand 7 7", "user"),
+            Command::RandomInt(7, 7)
+        );
+        assert_eq!(
+            Command::parse("-randint ten", "user"),
+            Command::NotValid(
+                "argument 2 (max) invalid; usage: `-randint <[min]> [max]`".to_owned()
+            )
+        );
+        assert_eq!(
+            Command::parse("-randint five 10", "user"),
+            Command::NotValid(
+                "argument 1 (min) invalid; usage: `-randint <[min]> [max]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn randint_rejects_reversed_bounds() {
+        assert_eq!(
+            Command::parse("-randint 10 5", "user"),
+            Command::NotValid("The minimum (10) can't be larger than the maximum (5)!".to_owned())
+        );
+    }
+    #[tokio::test]
+    async fn execute_randint_in_range() {
+        let sink = MockSink::default();
+        Command::RandomInt(3, 3)
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(sink.sent(), vec!["Between 3 and 3, I choose... ||3!||"]);
+    }
+    #[test]
+    fn timeparse_parsing() {
+        assert_eq!(
+            Command::parse("-timeparse 2h30m", "user"),
+            Command::TimeParse(Time::from_str("2h30m").unwrap())
+        );
+        assert_eq!(
+            Command::parse("-duration 5x", "user"),
+            Command::NotValid(
+                "I couldn't parse that duration: x (at position 1) is not a valid time specifier - only 's', 'm', 'h', 'd', and 'w' are valid"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            Command::parse("-timeparse", "user"),
+            Command::NotValid("argument 1 (time) missing; usage: `-timeparse [time]`".to_owned())
+        );
+    }
+    #[test]
+    fn time_describe() {
+        assert_eq!(
+            Time::from_str("2h30m").unwrap().describe(),
+            "9000 seconds (2h 30m)"
+        );
+        assert_eq!(Time::default().describe(), "0 seconds (0s)");
+    }
+    #[tokio::test]
+    async fn execute_timeparse() {
+        let sink = MockSink::default();
+        Command::TimeParse(Time::from_str("45s").unwrap())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(sink.sent(), vec!["That duration is 45 seconds (45s)."]);
+    }
+    #[test]
+    fn time_normalizes_carry() {
+        assert_eq!(
+            Time::from_str("90s").unwrap(),
+            Time {
+                minutes: 1,
+                seconds: 30,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Time::from_str("25h").unwrap(),
+            Time {
+                days: 1,
+                hours: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Time {
+                seconds: 3661,
+                ..Default::default()
+            }
+            .normalize(),
+            Time {
+                hours: 1,
+                minutes: 1,
+                seconds: 1,
+                days: 0
+            }
+        );
+    }
+    #[test]
+    fn time_arithmetic() {
+        let sum = Time::from_str("45m").unwrap() + Time::from_str("30m50s").unwrap();
+        assert_eq!(sum, Time::from_str("1h15m50s").unwrap());
+        assert_eq!(sum.total_seconds(), 4550);
+        let difference = Time::from_str("1d").unwrap() - Time::from_str("1h").unwrap();
+        assert_eq!(difference, Time::from_str("23h").unwrap());
+        assert_eq!(
+            Time::from_str("1m").unwrap() - Time::from_str("1h").unwrap(),
+            Time::default()
+        );
+    }
+    #[test]
+    fn time_ordering() {
+        assert!(Time::from_str("30m").unwrap() < Time::from_str("1h").unwrap());
+        assert!(Time::from_str("1d").unwrap() > Time::from_str("23h59m59s").unwrap());
+        let unnormalized = Time {
+            minutes: 60,
+            ..Default::default()
+        };
+        assert_eq!(
+            unnormalized.cmp(&Time::from_str("1h").unwrap()),
+            std::cmp::Ordering::Equal
+        );
+    }
+    #[test]
+    fn time_display() {
+        assert_eq!(Time::from_str("2h30m").unwrap().to_string(), "2h 30m");
+        assert_eq!(Time::from_str("45s").unwrap().to_string(), "45s");
+        assert_eq!(Time::from_str("1d5s").unwrap().to_string(), "1d 5s");
+        assert_eq!(
+            Time::from_str("3d4h5m6s").unwrap().to_string(),
+            "3d 4h 5m 6s"
+        );
+        assert_eq!(Time::default().to_string(), "0s");
+    }
+    #[tokio::test]
+    async fn execute_mute_shows_duration() {
+        let sink = MockSink::default();
+        Command::Mute(
+            UserId(5),
+            Time::from_str("1h30m").unwrap(),
+            "spam".to_owned(),
+        )
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            sink.sent(),
+            vec!["Successfully muted user for 1h 30m for the following reason: \n>spam"]
+        );
+    }
+    #[test]
+    fn time_parse_space_separated() {
+        assert_eq!(
+            Time::from_str("1h 30m").unwrap(),
+            Time::from_str("1h30m").unwrap()
+        );
+        assert_eq!(
+            Command::parse("-mute 12345 \"1h 30m\" spam", "user"),
+            Command::Mute(
+                UserId(12345),
+                Time::from_str("1h30m").unwrap(),
+                "spam".to_owned()
+            )
+        );
+        assert!(matches!(
+            Time::from_str("1 30m"),
+            Err(TimeErr::NoTimeSpecifier)
+        ));
+    }
+    #[test]
+    fn time_parse_fractional() {
+        assert_eq!(
+            Time::from_str("1.5h").unwrap(),
+            Time {
+                hours: 1,
+                minutes: 30,
+                ..Default::default()
+            }
+        );
+        assert_eq!(Time::from_str("0.5m").unwrap().total_seconds(), 30);
+        assert!(matches!(
+            Time::from_str("1.2.3h"),
+            Err(TimeErr::ParseFloatError(_))
+        ));
+    }
+    #[test]
+    fn time_parse_rejects_repeated_units() {
+        assert!(matches!(
+            Time::from_str("1h2h"),
+            Err(TimeErr::RepeatedTimeSpecifier('h'))
+        ));
+        assert_eq!(
+            Time::from_str("1h 2h").unwrap_err().to_string(),
+            "h was given more than once - use each time specifier only once"
+        );
+    }
+    #[test]
+    fn time_parse_weeks() {
+        let two_weeks = Time::from_str("2w").unwrap();
+        assert_eq!(two_weeks.days, 14);
+        assert_eq!(two_weeks.weeks(), 2);
+        assert_eq!(two_weeks.total_seconds(), 2 * 7 * 24 * 60 * 60);
+        assert_eq!(two_weeks.to_string(), "2w");
+        let week_and_days = Time::from_str("1w3d").unwrap();
+        assert_eq!(week_and_days.days, 10);
+        assert_eq!(week_and_days.weeks(), 1);
+        assert_eq!(week_and_days.to_string(), "1w 3d");
+        let stamp = Timestamp::try_from(week_and_days).unwrap();
+        let expected = Timestamp::now().unix_timestamp() + 10 * 24 * 60 * 60;
+        assert!((stamp.unix_timestamp() - expected).abs() <= 5);
+    }
+    #[test]
+    fn time_rejects_months_and_years() {
+        assert!(matches!(
+            Time::from_str("1mo"),
+            Err(TimeErr::InvalidTimeSpecifier('o', 2))
+        ));
+        assert!(matches!(
+            Time::from_str("1y"),
+            Err(TimeErr::InvalidTimeSpecifier('y', 1))
+        ));
+    }
+    #[test]
+    fn time_parse_case_insensitive() {
+        assert_eq!(
+            Time::from_str("2H30M").unwrap(),
+            Time::from_str("2h30m").unwrap()
+        );
+        assert_eq!(Time::from_str("5D").unwrap(), Time::from_str("5d").unwrap());
+        assert!(matches!(
+            Time::from_str("1h1H"),
+            Err(TimeErr::RepeatedTimeSpecifier('h'))
+        ));
+        assert!(matches!(
+            Time::from_str("3X"),
+            Err(TimeErr::InvalidTimeSpecifier('X', 1))
+        ));
+    }
+    #[test]
+    fn time_error_reports_position() {
+        assert!(matches!(
+            Time::from_str("1h 30m 5q"),
+            Err(TimeErr::InvalidTimeSpecifier('q', 8))
+        ));
+        assert_eq!(
+            Time::from_str("12z").unwrap_err().to_string(),
+            "z (at position 2) is not a valid time specifier - only 's', 'm', 'h', 'd', and 'w' are valid"
+        );
+    }
+    #[test]
+    fn time_parse_empty() {
+        assert!(matches!(Time::from_str(""), Err(TimeErr::Empty)));
+        assert_eq!(TimeErr::Empty.to_string(), "no time was given");
+    }
+    #[test]
+    fn time_parse_whitespace_only() {
+        assert!(matches!(Time::from_str("   "), Err(TimeErr::Empty)));
+        assert!(matches!(Time::from_str("\t\n"), Err(TimeErr::Empty)));
+    }
+    #[test]
+    fn mute_rejects_empty_time() {
+        assert_eq!(
+            Command::parse("-mute 12345 \"\" spam", "user"),
+            Command::NotValid(
+                "argument 2 (time) invalid; usage: `-mute [user] [time] [...reason]`".to_owned()
+            )
+        );
+        assert_eq!(
+            Command::parse("-mute 12345 \"  \" spam", "user"),
+            Command::NotValid(
+                "argument 2 (time) invalid; usage: `-mute [user] [time] [...reason]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn nickname_parsing() {
+        assert_eq!(
+            Command::parse("-nickname 12345 Baba", "user"),
+            Command::Nickname {
+                user: UserId(12345),
+                name: Some("Baba".to_owned())
+            }
+        );
+        assert_eq!(
+            Command::parse("-nick 12345 Keke is you", "user"),
+            Command::Nickname {
+                user: UserId(12345),
+                name: Some("Keke is you".to_owned())
+            }
+        );
+        assert_eq!(
+            Command::parse("-nickname 12345 reset", "user"),
+            Command::Nickname {
+                user: UserId(12345),
+                name: None
+            }
+        );
+        assert_eq!(
+            Command::parse("-nickname 12345", "user"),
+            Command::NotValid(
+                "argument 2 (name) missing; usage: `-nickname [user] [...name]`".to_owned()
+            )
+        );
+        assert!(CommandType::Nickname.mod_only());
+    }
+    #[test]
+    fn nickname_length_limit() {
+        let longest = "a".repeat(32);
+        assert_eq!(
+            Command::parse(&format!("-nickname 12345 {longest}"), "user"),
+            Command::Nickname {
+                user: UserId(12345),
+                name: Some(longest)
+            }
+        );
+        assert_eq!(
+            Command::parse(&format!("-nickname 12345 {}", "a".repeat(33)), "user"),
+            Command::NotValid("Nicknames can be at most 32 characters long!".to_owned())
+        );
+    }
+    #[tokio::test]
+    async fn execute_nickname() {
+        let sink = MockSink::default();
+        Command::Nickname {
+            user: UserId(5),
+            name: None,
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(*sink.nicknames.lock().unwrap(), vec![(5, None)]);
+        assert_eq!(sink.sent(), vec!["Reset user5's nickname."]);
+    }
+    #[test]
+    fn role_parsing() {
+        assert_eq!(
+            Command::parse("-role add 12345 Baba Is You", "user"),
+            Command::Role {
+                action: RoleAction::Add,
+                user: UserId(12345),
+                role: "Baba Is You".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-role remove 12345 <@&20>", "user"),
+            Command::Role {
+                action: RoleAction::Remove,
+                user: UserId(12345),
+                role: "<@&20>".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-role swap 12345 Baba", "user"),
+            Command::NotValid(
+                "argument 1 (action) invalid; usage: `-role [action] [user] [...role] | -role give|take|allow|disallow [...role]`".to_owned()
+            )
+        );
+        assert_eq!(
+            Command::parse("-role add 12345", "user"),
+            Command::NotValid(
+                "argument 3 (role) missing; usage: `-role [action] [user] [...role] | -role give|take|allow|disallow [...role]`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn role_resolution() {
+        let roles = vec![(10, "Baba".to_owned()), (20, "Keke Is Move".to_owned())];
+        assert_eq!(resolve_role(&roles, "10"), Some(&roles[0]));
+        assert_eq!(resolve_role(&roles, "<@&20>"), Some(&roles[1]));
+        assert_eq!(resolve_role(&roles, "keke is move"), Some(&roles[1]));
+        assert_eq!(resolve_role(&roles, "BABA"), Some(&roles[0]));
+        assert_eq!(resolve_role(&roles, "30"), None);
+        assert_eq!(resolve_role(&roles, "Rock"), None);
+    }
+    #[tokio::test]
+    async fn execute_role() {
+        let sink = MockSink::default();
+        let give = || Command::Role {
+            action: RoleAction::Add,
+            user: UserId(5),
+            role: "baba".to_owned(),
+        };
+        give().execute_command(&sink).await.unwrap();
+        give().execute_command(&sink).await.unwrap();
+        Command::Role {
+            action: RoleAction::Remove,
+            user: UserId(5),
+            role: "Rock".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(*sink.member_roles.lock().unwrap(), vec![(5, 10)]);
+        assert_eq!(
+            sink.sent(),
+            vec![
+                "Gave user5 the `Baba` role.",
+                "user5 already has the `Baba` role.",
+                "I couldn't find a role called `Rock`."
+            ]
+        );
+    }
+    #[test]
+    fn role_refusals() {
+        let role = |position, permissions| RoleStanding {
+            position,
+            permissions,
+        };
+        assert_eq!(
+            role_refusal(role(3, Permissions::SEND_MESSAGES), 5, 10),
+            None
+        );
+        assert_eq!(
+            role_refusal(role(3, Permissions::ADMINISTRATOR), 5, 10),
+            Some("grants moderation or management permissions")
+        );
+        assert_eq!(
+            role_refusal(role(3, Permissions::MANAGE_ROLES), i64::MAX, 10),
+            Some("grants moderation or management permissions")
+        );
+        assert_eq!(
+            role_refusal(role(5, Permissions::empty()), 5, 10),
+            Some("isn't below your highest role")
+        );
+        assert_eq!(
+            role_refusal(role(10, Permissions::empty()), i64::MAX, 10),
+            Some("isn't below my highest role")
+        );
+    }
+    #[tokio::test]
+    async fn role_refuses_privileged_roles() {
+        let sink = MockSink {
+            role_permissions: Some(Permissions::BAN_MEMBERS),
+            ..Default::default()
+        };
+        Command::Role {
+            action: RoleAction::Add,
+            user: UserId(1),
+            role: "baba".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert!(sink.member_roles.lock().unwrap().is_empty());
+        assert_eq!(
+            sink.sent(),
+            vec!["I can't manage `Baba`, since it grants moderation or management permissions."]
+        );
+    }
+    #[test]
+    fn self_role_parsing() {
+        assert_eq!(
+            Command::parse("-role give Baba Is You", "user"),
+            Command::SelfRole {
+                action: RoleAction::Add,
+                role: "Baba Is You".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-role take keke", "user"),
+            Command::SelfRole {
+                action: RoleAction::Remove,
+                role: "keke".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-role disallow keke", "user"),
+            Command::SelfRoleAllow {
+                allowed: false,
+                role: "keke".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-role give", "user"),
+            Command::NotValid("You need to name a role to give!".to_owned())
+        );
+        assert!(!Command::parse("-role give keke", "user").mod_only());
+        assert!(Command::parse("-role allow keke", "user").mod_only());
+        assert!(Command::parse("-role add 12345 keke", "user").mod_only());
+    }
+    #[test]
+    fn self_role_allowlist() {
+        let db = test_database();
+        assert!(!self_role_allowed(&db, 100, 10).unwrap());
+        set_self_role_allowed(&db, 100, 10, true).unwrap();
+        set_self_role_allowed(&db, 100, 10, true).unwrap();
+        assert!(self_role_allowed(&db, 100, 10).unwrap());
+        assert!(!self_role_allowed(&db, 200, 10).unwrap());
+        assert!(!self_role_allowed(&db, 100, 20).unwrap());
+        set_self_role_allowed(&db, 100, 10, false).unwrap();
+        assert!(!self_role_allowed(&db, 100, 10).unwrap());
+    }
+    #[test]
+    fn message_link_parsing() {
+        assert_eq!(
+            "https://discord.com/channels/1/2/3".parse(),
+            Ok(MessageLink {
+                guild_id: Some(1),
+                channel_id: 2,
+                message_id: 3
+            })
+        );
+        assert_eq!(
+            "https://canary.discordapp.com/channels/@me/2/3".parse(),
+            Ok(MessageLink {
+                guild_id: None,
+                channel_id: 2,
+                message_id: 3
+            })
+        );
+        assert!("https://discord.com/channels/1/2"
+            .parse::<MessageLink>()
+            .is_err());
+        assert!("https://example.com/1/2/3".parse::<MessageLink>().is_err());
+    }
+    #[test]
+    fn reaction_role_parsing() {
+        assert_eq!(
+            Command::parse(
+                "-reactionrole https://discord.com/channels/100/2/3 <:baba:555> Baba",
+                "user"
+            ),
+            Command::ReactionRole {
+                link: MessageLink {
+                    guild_id: Some(100),
+                    channel_id: 2,
+                    message_id: 3
+                },
+                emoji: "<:baba:555>".to_owned(),
+                role: "Baba".to_owned()
+            }
+        );
+        assert!(CommandType::ReactionRole.mod_only());
+    }
+    #[test]
+    fn reaction_role_storage() {
+        let db = test_database();
+        assert_eq!(emoji_key("<:baba:555>"), "555");
+        assert_eq!(emoji_key("<a:keke:777>"), "777");
+        assert_eq!(emoji_key("⭐"), "⭐");
+        assert_eq!(
+            reaction_emoji_key(&ReactionType::Unicode("⭐".to_owned())),
+            emoji_key("⭐")
+        );
+        add_reaction_role(&db, 100, 3, &emoji_key("<:baba:555>"), 10).unwrap();
+        add_reaction_role(&db, 100, 3, "⭐", 20).unwrap();
+        assert_eq!(reaction_role(&db, 3, "555").unwrap(), Some(10));
+        assert_eq!(reaction_role(&db, 3, "⭐").unwrap(), Some(20));
+        assert_eq!(reaction_role(&db, 4, "555").unwrap(), None);
+        add_reaction_role(&db, 100, 3, "⭐", 30).unwrap();
+        assert_eq!(reaction_role(&db, 3, "⭐").unwrap(), Some(30));
+    }
+    #[test]
+    fn reaction_routing() {
+        let event = ReactionEvent {
+            guild_id: Some(100),
+            channel_id: 2,
+            message_id: 3,
+            user_id: Some(5),
+            emoji: "👍".to_owned(),
+            added: true,
+        };
+        assert_eq!(route(&event, 1), vec![ReactionHandler::ReactionRole]);
+        assert!(route(&event, 5).is_empty());
+        let in_dm = ReactionEvent {
+            guild_id: None,
+            ..event.clone()
+        };
+        assert!(route(&in_dm, 1).is_empty());
+        let unknown_user = ReactionEvent {
+            user_id: None,
+            ..event
+        };
+        assert!(route(&unknown_user, 1).is_empty());
+    }
+    #[test]
+    fn star_reactions_route_to_starboard() {
+        let event = ReactionEvent {
+            guild_id: Some(100),
+            channel_id: 2,
+            message_id: 3,
+            user_id: Some(5),
+            emoji: "⭐".to_owned(),
+            added: false,
+        };
+        assert_eq!(
+            route(&event, 1),
+            vec![ReactionHandler::ReactionRole, ReactionHandler::Starboard]
+        );
+    }
+    #[test]
+    fn starboard_threshold_crossing() {
+        assert_eq!(starboard_action(2, 3, None), StarboardAction::Nothing);
+        assert_eq!(starboard_action(3, 3, None), StarboardAction::Post);
+        assert_eq!(starboard_action(4, 3, Some(9)), StarboardAction::Update(9));
+        assert_eq!(starboard_action(2, 3, Some(9)), StarboardAction::Remove(9));
+        assert_eq!(star_header(4, 2), "⭐ **4** in <#2>");
+    }
+    #[test]
+    fn starboard_settings() {
+        let db = test_database();
+        assert_eq!(StarboardConfig::for_guild(&db, 100).unwrap(), None);
+        StarboardSetting::Channel(50).apply(&db, 100).unwrap();
+        assert_eq!(
+            StarboardConfig::for_guild(&db, 100).unwrap(),
+            Some(StarboardConfig {
+                channel_id: 50,
+                threshold: 3
+            })
+        );
+        StarboardSetting::Threshold(5).apply(&db, 100).unwrap();
+        assert_eq!(
+            StarboardConfig::for_guild(&db, 100)
+                .unwrap()
+                .unwrap()
+                .threshold,
+            5
+        );
+        StarboardSetting::Disable.apply(&db, 100).unwrap();
+        assert_eq!(StarboardConfig::for_guild(&db, 100).unwrap(), None);
+        assert_eq!(starboard_post(&db, 3).unwrap(), None);
+        set_starboard_post(&db, 100, 3, 9).unwrap();
+        assert_eq!(starboard_post(&db, 3).unwrap(), Some(9));
+    }
+    #[test]
+    fn starboard_parsing() {
+        assert_eq!(
+            Command::parse("-starboard channel <#50>", "user"),
+            Command::Starboard(StarboardSetting::Channel(50))
+        );
+        assert_eq!(
+            Command::parse("-starboard threshold 5", "user"),
+            Command::Starboard(StarboardSetting::Threshold(5))
+        );
+        assert_eq!(
+            Command::parse("-starboard off", "user"),
+            Command::Starboard(StarboardSetting::Disable)
+        );
+        assert_eq!(
+            Command::parse("-starboard threshold 0", "user"),
+            Command::NotValid(
+                "argument 2 (value) invalid; usage: `-starboard [setting] <[value]>`".to_owned()
+            )
+        );
+    }
+    #[test]
+    fn template_placeholders() {
+        let member = MemberInfo {
+            user_id: 5,
+            name: "baba".to_owned(),
+            member_count: 42,
+            server: "Baba is You".to_owned(),
+        };
+        assert_eq!(
+            render_template(
+                "Welcome to {server}, {user}! You're member #{count}.",
+                &member
+            ),
+            "Welcome to Baba is You, <@5>! You're member #42."
+        );
+        assert_eq!(render_template("{name} {name}", &member), "baba baba");
+        assert_eq!(render_template("{unknown}", &member), "{unknown}");
+    }
+    #[test]
+    fn welcome_settings() {
+        let db = test_database();
+        assert_eq!(WelcomeConfig::for_guild(&db, 100).unwrap(), None);
+        WelcomeSetting::Channel(50).apply(&db, 100).unwrap();
+        let config = WelcomeConfig::for_guild(&db, 100).unwrap().unwrap();
+        assert_eq!(config.message, members::DEFAULT_WELCOME);
+        assert_eq!(config.rules, None);
+        WelcomeSetting::Message("hi {user}".to_owned())
+            .apply(&db, 100)
+            .unwrap();
+        WelcomeSetting::Rules("be nice".to_owned())
+            .apply(&db, 100)
+            .unwrap();
+        assert_eq!(
+            WelcomeConfig::for_guild(&db, 100).unwrap(),
+            Some(WelcomeConfig {
+                channel_id: 50,
+                message: "hi {user}".to_owned(),
+                rules: Some("be nice".to_owned())
+            })
+        );
+        assert_eq!(
+            Command::parse("-welcome message hi {user}!", "user"),
+            Command::Welcome(WelcomeSetting::Message("hi {user}!".to_owned()))
+        );
+    }
+    #[test]
+    fn goodbye_template() {
+        let departing = MemberInfo {
+            user_id: 5,
+            name: "keke".to_owned(),
+            member_count: 41,
+            server: "Baba is You".to_owned(),
+        };
+        assert_eq!(
+            render_template(members::DEFAULT_GOODBYE, &departing),
+            "keke has left Baba is You. We're down to 41 members."
+        );
+    }
+    #[test]
+    fn goodbye_toggles_independently() {
+        let db = test_database();
+        WelcomeSetting::Channel(50).apply(&db, 100).unwrap();
+        GoodbyeSetting::Channel(60).apply(&db, 100).unwrap();
+        GoodbyeSetting::Disable.apply(&db, 100).unwrap();
+        assert_eq!(GoodbyeConfig::for_guild(&db, 100).unwrap(), None);
+        assert!(WelcomeConfig::for_guild(&db, 100).unwrap().is_some());
+        assert_eq!(
+            Command::parse("-goodbye channel <#60>", "user"),
+            Command::Goodbye(GoodbyeSetting::Channel(60))
+        );
+    }
+    #[test]
+    fn autorole_skips_when_misconfigured() {
+        let roles = [(10, 1), (20, 5)];
+        assert_eq!(
+            autorole_decision(None, &roles, 3),
+            AutoroleDecision::NotConfigured
+        );
+        assert_eq!(
+            autorole_decision(Some(10), &roles, 3),
+            AutoroleDecision::Assign(10)
+        );
+        assert_eq!(
+            autorole_decision(Some(20), &roles, 3),
+            AutoroleDecision::RoleTooHigh(20)
+        );
+        assert_eq!(
+            autorole_decision(Some(20), &roles, 5),
+            AutoroleDecision::RoleTooHigh(20)
+        );
+        assert_eq!(
+            autorole_decision(Some(30), &roles, 9),
+            AutoroleDecision::MissingRole(30)
+        );
+    }
+    #[test]
+    fn autorole_parsing() {
+        assert_eq!(
+            Command::parse("-autorole Baba Is You", "user"),
+            Command::Autorole(Some("Baba Is You".to_owned()))
+        );
+        assert_eq!(
+            Command::parse("-autorole off", "user"),
+            Command::Autorole(None)
+        );
+        assert!(CommandType::Autorole.mod_only());
+    }
+    #[test]
+    fn raid_sliding_window() {
+        let guard = RaidGuard::new();
+        let config = RaidConfig {
+            max_joins: 3,
+            window_seconds: 10,
+        };
+        assert_eq!(guard.record_join(100, 0, config), RaidState::Normal);
+        assert_eq!(guard.record_join(100, 4, config), RaidState::Normal);
+        assert_eq!(guard.record_join(100, 8, config), RaidState::Normal);
+        // the join at 0 has left the window
+        assert_eq!(guard.record_join(100, 12, config), RaidState::Normal);
+        assert_eq!(guard.record_join(200, 12, config), RaidState::Normal);
+        assert_eq!(guard.record_join(100, 13, config), RaidState::Started);
+        assert_eq!(guard.record_join(100, 300, config), RaidState::Ongoing);
+        assert_eq!(
+            guard.record_join(100, 13 + raid::RAID_LOCKDOWN_SECONDS, config),
+            RaidState::Normal
+        );
+    }
+    #[test]
+    fn antiraid_settings() {
+        let db = test_database();
+        assert_eq!(RaidConfig::for_guild(&db, 100).unwrap(), None);
+        let config = RaidConfig {
+            max_joins: 5,
+            window_seconds: 10,
+        };
+        assert_eq!(
+            Command::parse("-antiraid 5 10", "user"),
+            Command::AntiRaid(AntiRaidSetting::Limit(Some(config)))
+        );
+        assert_eq!(
+            Command::parse("-antiraid alerts <#7>", "user"),
+            Command::AntiRaid(AntiRaidSetting::Alerts(7))
+        );
+        RaidConfig::apply(Some(config), &db, 100).unwrap();
+        assert_eq!(RaidConfig::for_guild(&db, 100).unwrap(), Some(config));
+        RaidConfig::apply(None, &db, 100).unwrap();
+        assert_eq!(RaidConfig::for_guild(&db, 100).unwrap(), None);
+    }
+    #[test]
+    fn word_filter_matching() {
+        let word = FilterTerm {
+            term: "rock".to_owned(),
+            whole_word: true,
+        };
+        assert!(word.matches("ROCK is push"));
+        assert!(word.matches("i love rock!"));
+        assert!(word.matches("rock"));
+        assert!(!word.matches("rocket is move"));
+        assert!(!word.matches("bedrock"));
+        assert!(word.matches("bedrock and rock"));
+        let anywhere = FilterTerm {
+            term: "rock".to_owned(),
+            whole_word: false,
+        };
+        assert!(anywhere.matches("RoCkEt"));
+        assert!(anywhere.matches("bedrock"));
+        assert!(!anywhere.matches("baba"));
+    }
+    #[test]
+    fn word_filter_storage() {
+        let db = test_database();
+        assert_eq!(violation(&db, 100, "rock").unwrap(), None);
+        add_filter_term(
+            &db,
+            100,
+            &FilterTerm {
+                term: "rock".to_owned(),
+                whole_word: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(filter_terms(&db, 100).unwrap().len(), 1);
+        assert!(violation(&db, 100, "Rock is push").unwrap().is_some());
+        assert_eq!(violation(&db, 200, "Rock is push").unwrap(), None);
+        assert!(remove_filter_term(&db, 100, "ROCK").unwrap());
+        assert!(!remove_filter_term(&db, 100, "rock").unwrap());
+        assert_eq!(
+            Command::parse("-filter anywhere Rock", "user"),
+            Command::Filter(FilterAction::Add(FilterTerm {
+                term: "rock".to_owned(),
+                whole_word: false
+            }))
+        );
+        assert_eq!(
+            Command::parse("-filter WORD Rock", "user"),
+            Command::Filter(FilterAction::Add(FilterTerm {
+                term: "rock".to_owned(),
+                whole_word: true
+            }))
+        );
+    }
+    #[test]
+    fn invite_detection() {
+        assert_eq!(find_invites("join discord.gg/baba!"), vec!["baba"]);
+        assert_eq!(
+            find_invites("https://discord.gg/BabaIsYou"),
+            vec!["BabaIsYou"]
+        );
+        assert_eq!(
+            find_invites("<https://www.discord.com/invite/keke-is-move>"),
+            vec!["keke-is-move"]
+        );
+        assert_eq!(
+            find_invites("http://discordapp.com/invite/abc123 and DISCORD.GG/xyz"),
+            vec!["xyz", "abc123"]
+        );
+        assert_eq!(
+            find_invites("https://canary.discord.com/invite/a1"),
+            vec!["a1"]
+        );
+        assert!(find_invites("notdiscord.gg/baba").is_empty());
+        assert!(find_invites("discord.gg/").is_empty());
+        assert!(find_invites("https://discord.com/channels/1/2/3").is_empty());
+        assert!(find_invites("baba is you").is_empty());
+    }
+    #[test]
+    fn invite_filter_allowlist() {
+        let db = test_database();
+        assert_eq!(violation(&db, 100, "discord.gg/baba").unwrap(), None);
+        settings::set_guild_setting(&db, 100, automod::INVITE_FILTER, "true").unwrap();
+        assert!(violation(&db, 100, "discord.gg/baba").unwrap().is_some());
+        set_invite_allowed(&db, 100, "baba", true).unwrap();
+        assert_eq!(violation(&db, 100, "discord.gg/baba").unwrap(), None);
+        assert!(violation(&db, 100, "discord.gg/baba discord.gg/keke")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            Command::parse("-invitefilter allow https://discord.gg/baba", "user"),
+            Command::InviteFilter(InviteFilterAction::Allow("baba".to_owned(), true))
+        );
+    }
+    #[test]
+    fn spam_repeated_messages() {
+        let guard = SpamGuard::new();
+        let config = SpamConfig {
+            max_repeats: 3,
+            max_messages: 100,
+            window_seconds: 10,
+        };
+        let record = |user, content, now| guard.record_message(100, user, content, now, config);
+        assert_eq!(record(1, "baba", 0), SpamVerdict::Clean);
+        assert_eq!(record(1, "BABA ", 1), SpamVerdict::Clean);
+        assert_eq!(record(2, "baba", 1), SpamVerdict::Clean);
+        assert_eq!(record(1, "baba", 2), SpamVerdict::Repeated);
+        assert_eq!(record(1, "baba", 3), SpamVerdict::Clean);
+        assert_eq!(record(1, "keke", 4), SpamVerdict::Clean);
+        assert_eq!(record(1, "baba", 5), SpamVerdict::Clean);
+        assert_eq!(record(1, "baba", 6), SpamVerdict::Clean);
+        // saying the same thing now and then isn't spam
+        assert_eq!(record(3, "thanks", 0), SpamVerdict::Clean);
+        assert_eq!(record(3, "thanks", 100), SpamVerdict::Clean);
+        assert_eq!(record(3, "thanks", 200), SpamVerdict::Clean);
+        // nor is posting several images, which have no text
+        assert_eq!(record(4, "", 0), SpamVerdict::Clean);
+        assert_eq!(record(4, " ", 1), SpamVerdict::Clean);
+        assert_eq!(record(4, "", 2), SpamVerdict::Clean);
+    }
+    #[test]
+    fn spam_message_rate() {
+        let guard = SpamGuard::new();
+        let config = SpamConfig {
+            max_repeats: 100,
+            max_messages: 3,
+            window_seconds: 10,
+        };
+        let record = |content, now| guard.record_message(100, 1, content, now, config);
+        assert_eq!(record("a", 0), SpamVerdict::Clean);
+        assert_eq!(record("b", 5), SpamVerdict::Clean);
+        assert_eq!(record("c", 10), SpamVerdict::Clean);
+        assert_eq!(record("d", 12), SpamVerdict::Flooding);
+        assert_eq!(record("e", 13), SpamVerdict::Clean);
+        assert_eq!(
+            Command::parse("-antispam 3 5 10", "user"),
+            Command::AntiSpam(Some(SpamConfig {
+                max_repeats: 3,
+                max_messages: 5,
+                window_seconds: 10
+            }))
+        );
+        assert_eq!(
+            Command::parse("-antispam off", "user"),
+            Command::AntiSpam(None)
+        );
+    }
+    #[test]
+    fn clearing_warnings() {
+        let db = test_database();
+        add_warning(&db, 100, 5, 1, "baba", 10).unwrap();
+        add_warning(&db, 100, 5, 1, "keke", 20).unwrap();
+        add_warning(&db, 200, 5, 1, "other server", 30).unwrap();
+        let reasons = warnings(&db, 100, 5)
+            .unwrap()
+            .into_iter()
+            .map(|warning| warning.reason)
+            .collect::<Vec<_>>();
+        assert_eq!(reasons, vec!["baba", "keke"]);
+        assert_eq!(clear_warnings(&db, 100, 5).unwrap(), 2);
+        assert!(warnings(&db, 100, 5).unwrap().is_empty());
+        assert_eq!(warnings(&db, 200, 5).unwrap().len(), 1);
+        assert_eq!(
+            Command::parse("-clearwarns 5", "user"),
+            Command::ClearWarns(UserId(5))
+        );
+    }
+    #[test]
+    fn expired_warnings_are_not_counted() {
+        let db = test_database();
+        add_warning(&db, 100, 5, 1, "old", 0).unwrap();
+        add_warning(&db, 100, 5, 1, "new", 90).unwrap();
+        assert_eq!(active_warning_count(&db, 100, 5, 100).unwrap(), 2);
+        set_warn_expiry(&db, 100, Some(50)).unwrap();
+        assert_eq!(active_warning_count(&db, 100, 5, 100).unwrap(), 1);
+        assert_eq!(active_warning_count(&db, 100, 5, 140).unwrap(), 0);
+        set_warn_expiry(&db, 100, None).unwrap();
+        assert_eq!(active_warning_count(&db, 100, 5, 140).unwrap(), 2);
+        assert_eq!(
+            Command::parse("-warnexpiry 30d", "user"),
+            Command::WarnExpiry(Some(Time::from_str("30d").unwrap()))
+        );
+    }
+    #[test]
+    fn escalation_decisions() {
+        let hour = Time::from_str("1h").unwrap();
+        let policy = [(3, Escalation::Mute(hour)), (5, Escalation::Ban)];
+        assert_eq!(escalation_for(&policy, 0), None);
+        assert_eq!(escalation_for(&policy, 2), None);
+        assert_eq!(escalation_for(&policy, 3), Some(Escalation::Mute(hour)));
+        assert_eq!(escalation_for(&policy, 4), Some(Escalation::Mute(hour)));
+        assert_eq!(escalation_for(&policy, 5), Some(Escalation::Ban));
+        assert_eq!(escalation_for(&policy, 9), Some(Escalation::Ban));
+        assert_eq!(escalation_for(&[], 9), None);
+        let db = test_database();
+        set_escalation(&db, 100, 5, Some(Escalation::Ban)).unwrap();
+        set_escalation(&db, 100, 3, Some(Escalation::Mute(hour))).unwrap();
+        assert_eq!(escalation_policy(&db, 100).unwrap(), policy);
+        set_escalation(&db, 100, 5, None).unwrap();
+        assert_eq!(escalation_policy(&db, 100).unwrap(), policy[..1]);
+    }
+    #[test]
+    fn lockdown_overwrites() {
+        let locked = locked_overwrite(None);
+        assert_eq!(locked.deny, Permissions::SEND_MESSAGES);
+        assert_eq!(locked.allow, Permissions::empty());
+        let existing = EveryoneOverwrite {
+            allow: Permissions::SEND_MESSAGES | Permissions::ADD_REACTIONS,
+            deny: Permissions::ATTACH_FILES,
+        };
+        assert!(!existing.denies_sending());
+        let locked = locked_overwrite(Some(existing));
+        assert!(locked.denies_sending());
+        assert_eq!(locked.allow, Permissions::ADD_REACTIONS);
+        assert_eq!(
+            locked.deny,
+            Permissions::ATTACH_FILES | Permissions::SEND_MESSAGES
+        );
+    }
+    #[tokio::test]
+    async fn lockdown_restores_previous_overwrites() {
+        let explicit = EveryoneOverwrite {
+            allow: Permissions::SEND_MESSAGES | Permissions::ADD_REACTIONS,
+            deny: Permissions::empty(),
+        };
+        let read_only = EveryoneOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::SEND_MESSAGES,
+        };
+        let sink = MockSink {
+            overwrites: vec![(50, explicit), (60, read_only)],
+            foreign_channels: vec![80],
+            ..Default::default()
+        };
+        let db = Mutex::new(test_database());
+        let report = lock_channels(&db, &sink, 100, &[50, 60, 70, 80]).await;
+        assert_eq!(
+            report.results,
+            vec![
+                (50, LockOutcome::Done),
+                (60, LockOutcome::Skipped),
+                (70, LockOutcome::Done),
+                (80, LockOutcome::Failed("Missing Access".to_owned())),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Locked down 2 of 4 channels. 1 was already locked.\n❌ <#80>: Missing Access"
+        );
+        assert_eq!(saved_lockdown(&db.lock().unwrap(), 80).unwrap(), None);
+        sink.locks.lock().unwrap().clear();
+        let report = unlock_channels(&db, &sink, &[50, 60, 70]).await;
+        assert_eq!(
+            *sink.locks.lock().unwrap(),
+            vec![(50, Some(explicit)), (70, None)]
+        );
+        assert_eq!(
+            report.to_string(),
+            "Unlocked 2 of 3 channels. 1 was not locked down by me."
+        );
+        assert_eq!(saved_lockdown(&db.lock().unwrap(), 50).unwrap(), None);
+        assert_eq!(
+            unlock_channels(&db, &sink, &[50]).await.channel_message(),
+            "This channel wasn't locked down by me."
+        );
+    }
+    #[test]
+    fn lockdown_parsing() {
+        assert_eq!(
+            Command::parse("-lockdown", "user"),
+            Command::Lockdown { all: false }
+        );
+        assert_eq!(
+            Command::parse("-lockdown ALL", "user"),
+            Command::Lockdown { all: true }
+        );
+        assert_eq!(
+            Command::parse("-unlock all", "user"),
+            Command::Unlock { all: true }
+        );
+    }
+    #[tokio::test]
+    async fn announce_parsing() {
+        let announce = Command::parse(
+            "-announce <#123456789012345678> 223456789012345678 2 new levels!",
+            "user",
+        );
+        assert_eq!(
+            announce,
+            Command::Announce {
+                channels: vec![ChannelId(123456789012345678), ChannelId(223456789012345678)],
+                message: "2 new levels!".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-announce hello", "user"),
+            ArgumentError::new(CommandType::Announce, 1).into()
+        );
+        assert_eq!(
+            Command::parse("-announce <#5>", "user"),
+            ArgumentError::missing(CommandType::Announce, 2).into()
+        );
+        let sink = MockSink::default();
+        announce.execute_command(&sink).await.unwrap();
+        assert_eq!(sink.channel_messages.lock().unwrap().len(), 2);
+        assert_eq!(
+            sink.sent(),
+            vec!["Posted the announcement in 2/2 channels."]
+        );
+        let sink = MockSink {
+            foreign_channels: vec![223456789012345678],
+            ..Default::default()
+        };
+        Command::parse(
+            "-announce <#123456789012345678> 223456789012345678 2 new levels!",
+            "user",
+        )
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            *sink.channel_messages.lock().unwrap(),
+            vec![(123456789012345678, "2 new levels!".to_owned())]
+        );
+        assert_eq!(
+            sink.sent(),
+            vec!["Posted the announcement in 1/2 channels.\nCouldn't post in <#223456789012345678>: That channel isn't in this server"]
+        );
+    }
+    #[test]
+    fn announcement_results() {
+        let results = [
+            (1, Ok(())),
+            (2, Err(eyre::eyre!("Missing Access"))),
+            (3, Ok(())),
+        ];
+        assert_eq!(
+            announcement_summary(&results),
+            "Posted the announcement in 2/3 channels.\nCouldn't post in <#2>: Missing Access"
+        );
+        assert_eq!(
+            announcement_summary(&[]),
+            "Posted the announcement in 0/0 channels."
+        );
+    }
+    #[test]
+    fn channel_kinds() {
+        use crate::shard::ChannelKind;
+        use serenity::model::channel::ChannelType;
+        let kind = ChannelKind::from;
+        assert_eq!(kind(ChannelType::Text), ChannelKind::Text);
+        assert_eq!(kind(ChannelType::News), ChannelKind::Text);
+        assert_eq!(kind(ChannelType::PublicThread), ChannelKind::Thread);
+        assert_eq!(kind(ChannelType::PrivateThread), ChannelKind::Thread);
+        assert_eq!(kind(ChannelType::NewsThread), ChannelKind::Thread);
+        assert_eq!(kind(ChannelType::Private), ChannelKind::Private);
+        assert_eq!(kind(ChannelType::Category), ChannelKind::Category);
+        assert_eq!(kind(ChannelType::Voice), ChannelKind::Voice);
+        assert_eq!(kind(ChannelType::Stage), ChannelKind::Voice);
+        assert_eq!(kind(ChannelType::Unknown), ChannelKind::Forum);
+        assert_eq!(kind(ChannelType::Directory), ChannelKind::Other);
+    }
+    #[test]
+    fn temp_message_delays() {
+        use crate::shard::{temp_message_delay, MAX_TEMP_MESSAGE_SECONDS};
+        use std::time::Duration;
+        let delay = |time| temp_message_delay(Time::from_str(time).unwrap());
+        assert_eq!(delay("10s"), Duration::from_secs(10));
+        assert_eq!(delay("1m30s"), Duration::from_secs(90));
+        assert_eq!(delay("0s"), Duration::from_secs(1));
+        assert_eq!(delay("1d"), Duration::from_secs(MAX_TEMP_MESSAGE_SECONDS));
+    }
+    #[tokio::test]
+    async fn errors_reply_to_the_command() {
+        let sink = MockSink::default();
+        Command::parse("-randint 5 1", "user")
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert!(sink.sent().is_empty());
+        let replies = sink.replies.lock().unwrap();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].0, 500);
+        assert!(replies[0]
+            .1
+            .contains("The minimum (5) can't be larger than the maximum (1)!"));
+    }
+    #[tokio::test]
+    async fn typing_while_waiting() {
+        let sink = MockSink::default();
+        assert_eq!(with_typing(&sink, async { 7 }).await, 7);
+        let failed: Result<u64> =
+            with_typing(&sink, async { Err(eyre::eyre!("no database")) }).await;
+        assert_eq!(failed.unwrap_err().to_string(), "no database");
+        assert_eq!(*sink.typing.lock().unwrap(), 2);
+    }
+    #[test]
+    fn page_turning() {
+        assert_eq!(turn_page(0, 3, true), 1);
+        assert_eq!(turn_page(2, 3, true), 0);
+        assert_eq!(turn_page(0, 3, false), 2);
+        assert_eq!(turn_page(1, 3, false), 0);
+        assert_eq!(turn_page(0, 1, true), 0);
+        assert_eq!(turn_page(0, 0, false), 0);
+        let pages = vec!["baba".to_owned(), "keke".to_owned()];
+        assert_eq!(page_content(&pages, 1), "keke\n*Page 2/2*");
+        assert_eq!(page_content(&pages[..1], 0), "baba");
+        let paginators = Paginators::new();
+        paginators.insert(9, pages, 0);
+        assert_eq!(
+            paginators.turn(9, true, 1).as_deref(),
+            Some("keke\n*Page 2/2*")
+        );
+        assert_eq!(
+            paginators.turn(9, true, 2).as_deref(),
+            Some("baba\n*Page 1/2*")
+        );
+        assert_eq!(paginators.turn(8, true, 2), None);
+        assert_eq!(
+            paginators.turn(9, true, pagination::PAGINATOR_TTL_SECONDS),
+            None
+        );
+    }
+    #[test]
+    fn paginating_long_text() {
+        assert_eq!(paginate(""), vec![""]);
+        assert_eq!(paginate("baba\nkeke"), vec!["baba\nkeke"]);
+        let line = "a".repeat(MAX_PAGE_LENGTH / 3 - 1);
+        let text = [line.as_str(); 7].join("\n");
+        let pages = paginate(&text);
+        assert_eq!(pages.len(), 3);
+        assert!(pages
+            .iter()
+            .all(|page| page.chars().count() <= MAX_PAGE_LENGTH));
+        assert_eq!(pages.join("\n"), text);
+    }
+    #[tokio::test]
+    async fn help_lists_every_command_in_pages() {
+        let sink = MockSink::default();
+        Command::Help(None).execute_command(&sink).await.unwrap();
+        let pages = sink.pages.lock().unwrap();
+        let text = pages[0].join("\n");
+        assert!(text.starts_with("Availible Commands:"));
+        assert!(text.contains("`-coinflip <count:[count]>`"));
+        let event = ReactionEvent {
+            guild_id: None,
+            channel_id: 2,
+            message_id: 3,
+            user_id: Some(5),
+            emoji: NEXT_PAGE.to_owned(),
+            added: false,
+        };
+        assert_eq!(route(&event, 1), vec![ReactionHandler::Pagination]);
+    }
+    #[test]
+    fn arg_spec_parsing() {
+        const SPEC: ArgSpec = ArgSpec {
+            positional: &[
+                Arg::required("user", ArgKind::User),
+                Arg::optional("count", ArgKind::Integer),
+            ],
+            keywords: &[Arg::optional("for", ArgKind::Time)],
+            rest: None,
+        };
+        let words = |text: &str| {
+            text.split_whitespace()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+        };
+        let parsed = SPEC.parse(&words("<@123> for:1h 5")).unwrap();
+        assert_eq!(parsed.user("user"), Some(UserId(123)));
+        assert_eq!(parsed.integer("count"), Some(5));
+        assert_eq!(parsed.time("for"), Some(Time::from_str("1h").unwrap()));
+        assert_eq!(parsed.get("count"), Some(&ArgValue::Integer(5)));
+        let parsed = SPEC.parse(&words("123")).unwrap();
+        assert_eq!(parsed.integer("count"), None);
+        assert_eq!(parsed.time("for"), None);
+        assert_eq!(
+            SPEC.parse(&[]),
+            Err(ArgSpecError::Missing {
+                position: 1,
+                name: "user"
+            })
+        );
+        assert_eq!(
+            SPEC.parse(&words("123 five")),
+            Err(ArgSpecError::WrongType {
+                position: 2,
+                name: "count",
+                kind: ArgKind::Integer,
+                found: "five".to_owned()
+            })
+        );
+        assert_eq!(
+            SPEC.parse(&words("123 for:soon")),
+            Err(ArgSpecError::WrongType {
+                position: 3,
+                name: "for",
+                kind: ArgKind::Time,
+                found: "soon".to_owned()
+            })
+        );
+        assert_eq!(
+            SPEC.parse(&words("123 5 baba")),
+            Err(ArgSpecError::Extra {
+                found: "baba".to_owned()
+            })
+        );
+        assert_eq!(
+            ArgSpecError::Extra {
+                found: "baba".to_owned()
+            }
+            .into_command(CommandType::Ban),
+            Command::NotValid(
+                "too many arguments, starting at `baba`; usage: `-ban [user] [...reason]`"
+                    .to_owned()
+            )
+        );
+    }
+    #[test]
+    fn interactions_map_to_commands() {
+        use crate::slash::command_from_interaction;
+        let options = |options: &[(&str, &str)]| {
+            options
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            command_from_interaction("coinflip", &options(&[("count", "3")]), "user"),
+            Command::CoinFlip(3)
+        );
+        assert_eq!(
+            command_from_interaction("coinflip", &[], "user"),
+            Command::CoinFlip(1)
+        );
+        assert_eq!(
+            command_from_interaction("randint", &options(&[("max", "10"), ("min", "2")]), "user"),
+            Command::RandomInt(2, 10)
+        );
+        assert_eq!(
+            command_from_interaction("timeparse", &options(&[("time", "1h 30m")]), "user"),
+            Command::TimeParse(Time::from_str("1h30m").unwrap())
+        );
+        assert_eq!(
+            command_from_interaction("da2a", &[], "user"),
+            Command::DontAskToAsk
+        );
+        assert!(matches!(
+            command_from_interaction("ban", &options(&[("user", "5")]), "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[tokio::test]
+    async fn coinflip_through_message_and_interaction() {
+        use crate::slash::command_from_interaction;
+        let from_message = Command::parse("-coinflip count:3", "user");
+        let from_interaction =
+            command_from_interaction("coinflip", &[("count".into(), "3".into())], "user");
+        assert_eq!(from_message, Command::CoinFlip(3));
+        assert_eq!(from_message, from_interaction);
+        for command in [from_message, from_interaction] {
+            let sink = MockSink::default();
+            command.execute_command(&sink).await.unwrap();
+            let sent = sink.sent();
+            assert_eq!(sent.len(), 1);
+            assert!(sent[0].starts_with("I flipped 3 coins..."));
+            assert_eq!(sink.metrics.commands_executed(CommandType::CoinFlip), 1);
+        }
+    }
+    #[tokio::test]
+    async fn mod_only_commands_check_the_sink() {
+        let command = || Command::Lockdown { all: false };
+        let sink = MockSink::default();
+        assert!(matches!(
+            command().requires_mod(&sink).await,
+            Command::NotValid(_)
+        ));
+        let sink = MockSink {
+            moderator: true,
+            ..Default::default()
+        };
+        assert_eq!(command().requires_mod(&sink).await, command());
+        assert!(matches!(
+            Command::CoinFlip(1).requires_dev(&sink),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn ephemeral_flag_reaches_interaction_response() {
+        use crate::slash::{followup_data, response_data};
+        use serenity::builder::{CreateInteractionResponseData, CreateInteractionResponseFollowup};
+        let flags = |ephemeral| {
+            let mut data = CreateInteractionResponseData::default();
+            response_data(&mut data, "hi", None, ephemeral);
+            assert_eq!(data.0.get("content").and_then(|c| c.as_str()), Some("hi"));
+            data.0
+                .get("flags")
+                .and_then(|flags| flags.as_u64())
+                .unwrap_or(0)
+        };
+        assert_eq!(flags(true), 64);
+        assert_eq!(flags(false), 0);
+        let mut followup = CreateInteractionResponseFollowup::default();
+        followup_data(&mut followup, "hi", None, true);
+        assert_eq!(
+            followup.0.get("flags").and_then(|flags| flags.as_u64()),
+            Some(64)
+        );
+    }
+    #[test]
+    fn slash_suggestions_filter_partial_input() {
+        use crate::slash::{filter_suggestions, suggestions, MAX_SUGGESTIONS};
+        assert_eq!(
+            filter_suggestions(["random", "ban", "unban", "banana"], "BAN"),
+            vec!["ban", "banana", "unban"]
+        );
+        assert!(filter_suggestions(["ban"], "keke").is_empty());
+        let help = suggestions(CommandType::Help, "coin");
+        assert_eq!(help, vec!["coinflip"]);
+        assert_eq!(suggestions(CommandType::Help, "").len(), MAX_SUGGESTIONS);
+        assert_eq!(
+            suggestions(CommandType::Xkcd, "anti"),
+            vec!["antigravity", "import antigravity"]
+        );
+        assert!(suggestions(CommandType::CoinFlip, "").is_empty());
+    }
+    #[test]
+    fn parse_reminder_targets() {
+        use crate::reminders::ReminderTarget;
+        let time = Time::from_str("2h").unwrap();
+        assert_eq!(
+            Command::parse("-remind here 2h check the oven", "user"),
+            Command::Remind {
+                target: ReminderTarget::Channel,
+                time,
+                text: "check the oven".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-remind me 2h check the oven", "user"),
+            Command::Remind {
+                target: ReminderTarget::DirectMessage,
+                time,
+                text: "check the oven".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-remindme 2h check the oven", "user"),
+            Command::parse("-remind dm 2h check the oven", "user")
+        );
+        assert_eq!(
+            CommandType::from(&Command::parse("-remindme 2h oven", "user")),
+            CommandType::RemindMe
+        );
+        assert!(matches!(
+            Command::parse("-remind there 2h oven", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-remind here 2h", "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn reminders_are_scheduled_until_due() {
+        use crate::reminders::{add_reminder, due_reminders, remove_reminder, ReminderTarget};
+        let db = test_database();
+        let here = add_reminder(&db, 1, 50, ReminderTarget::Channel, 1000, "oven").unwrap();
+        add_reminder(&db, 2, 60, ReminderTarget::DirectMessage, 2000, "domain").unwrap();
+        assert!(due_reminders(&db, 999).unwrap().is_empty());
+        let due = due_reminders(&db, 1000).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, here);
+        assert_eq!(due[0].target, ReminderTarget::Channel);
+        assert_eq!(due[0].channel, 50);
+        assert_eq!(due[0].message(), "<@1>, reminder: oven");
+        remove_reminder(&db, here).unwrap();
+        let due = due_reminders(&db, 5000).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].target, ReminderTarget::DirectMessage);
+        assert_eq!(due[0].message(), "Reminder: domain");
+    }
+    #[test]
+    fn giveaway_winners_come_from_entrants() {
+        use crate::giveaways::{pick_winners, winners_message};
+        use rand::{rngs::StdRng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(7);
+        let entrants = [1, 2, 3, 3, 3, 4, 5];
+        for _ in 0..50 {
+            let mut winners = pick_winners(&entrants, 3, &mut rng);
+            assert_eq!(winners.len(), 3);
+            assert!(winners.iter().all(|winner| entrants.contains(winner)));
+            winners.sort_unstable();
+            winners.dedup();
+            assert_eq!(winners.len(), 3);
+        }
+        let mut everyone = pick_winners(&entrants, 10, &mut rng);
+        everyone.sort_unstable();
+        assert_eq!(everyone, vec![1, 2, 3, 4, 5]);
+        assert!(pick_winners(&[], 1, &mut rng).is_empty());
+        assert_eq!(
+            winners_message("a hat", &[1, 2]),
+            "🎉 Congratulations <@1>, <@2>! You won **a hat**!"
+        );
+        assert!(winners_message("a hat", &[]).starts_with("Nobody entered"));
+    }
+    #[test]
+    fn giveaways_end_once() {
+        use crate::giveaways::{add_giveaway, due_giveaways, end_giveaway, giveaway};
+        let db = test_database();
+        let id = add_giveaway(&db, 100, 50, 700, "a hat", 2, 1000).unwrap();
+        assert!(due_giveaways(&db, 999).unwrap().is_empty());
+        assert_eq!(due_giveaways(&db, 1000).unwrap()[0].id, id);
+        end_giveaway(&db, id).unwrap();
+        assert!(due_giveaways(&db, 1000).unwrap().is_empty());
+        let ended = giveaway(&db, 100, id).unwrap().unwrap();
+        assert!(ended.ended);
+        assert_eq!(ended.winners, 2);
+        assert!(giveaway(&db, 101, id).unwrap().is_none());
+    }
+    #[test]
+    fn parse_giveaway() {
+        use crate::giveaways::GiveawayAction;
+        assert_eq!(
+            Command::parse("-giveaway 1d winners:2 a cool hat", "user"),
+            Command::Giveaway(GiveawayAction::Start {
+                time: Time::from_str("1d").unwrap(),
+                winners: 2,
+                prize: "a cool hat".to_owned()
+            })
+        );
+        assert_eq!(
+            Command::parse("-giveaway reroll 4", "user"),
+            Command::Giveaway(GiveawayAction::Reroll(4))
+        );
+        assert!(matches!(
+            Command::parse("-giveaway 1d", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-giveaway 1d winners:0 hat", "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn tags_crud() {
+        use crate::tags::{remove_tag, set_tag, tag, tags};
+        let db = test_database();
+        assert!(!set_tag(&db, 100, "Rules", "read the rules").unwrap());
+        assert!(set_tag(&db, 100, "rules", "read the rules, {user}").unwrap());
+        set_tag(&db, 100, "faq", "see the faq").unwrap();
+        set_tag(&db, 101, "other", "another server").unwrap();
+        assert_eq!(
+            tag(&db, 100, "RULES").unwrap().as_deref(),
+            Some("read the rules, {user}")
+        );
+        assert_eq!(tags(&db, 100).unwrap(), vec!["faq", "rules"]);
+        assert!(remove_tag(&db, 100, "faq").unwrap());
+        assert!(!remove_tag(&db, 100, "faq").unwrap());
+        assert_eq!(tag(&db, 100, "faq").unwrap(), None);
+        assert_eq!(tag(&db, 100, "other").unwrap(), None);
+    }
+    #[test]
+    fn tag_placeholders_and_parsing() {
+        use crate::tags::{fill_placeholders, TagAction};
+        assert_eq!(
+            fill_placeholders("hi {user}, welcome {user}!", 5),
+            "hi <@5>, welcome <@5>!"
+        );
+        assert_eq!(fill_placeholders("no placeholders", 5), "no placeholders");
+        let add = Command::parse("-tag add Hello hi {user}", "user");
+        assert_eq!(
+            add,
+            Command::Tag(TagAction::Add {
+                name: "hello".to_owned(),
+                content: "hi {user}".to_owned()
+            })
+        );
+        assert!(add.mod_only());
+        let show = Command::parse("-tag hello", "user");
+        assert_eq!(show, Command::Tag(TagAction::Show("hello".to_owned())));
+        assert!(!show.mod_only());
+        assert!(matches!(
+            Command::parse("-tag add list oops", "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[test]
+    fn match_member_names() {
+        use crate::members::match_members;
+        let member = |user_id, name: &str, nickname: Option<&str>| MemberName {
+            user_id,
+            name: name.to_owned(),
+            nickname: nickname.map(ToOwned::to_owned),
+        };
+        let members = [
+            member(1, "babalover", None),
+            member(2, "keke", Some("Baba")),
+            member(3, "flag", Some("win")),
+            member(4, "BABA", None),
+        ];
+        let ids = |query| {
+            match_members(&members, query)
+                .iter()
+                .map(|member| member.user_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("baba"), vec![2, 4, 1]);
+        assert_eq!(ids("WIN"), vec![3]);
+        assert!(ids("rock").is_empty());
+    }
+    #[tokio::test]
+    async fn execute_userid() {
+        let sink = MockSink::default();
+        Command::UserId("keke".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        Command::UserId("baba".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        let sent = sink.sent();
+        assert_eq!(sent[0], "keke (Keke Is Baba): `2`");
+        assert!(sent[1].starts_with("2 members match `baba`:"));
+        assert!(sent[1].contains("- baba (Baba Is You): `1`"));
+    }
+    #[test]
+    fn parse_channelinfo() {
+        assert_eq!(
+            Command::parse("-channelinfo", "user"),
+            Command::ChannelInfo(None)
+        );
+        assert_eq!(
+            Command::parse("-channelinfo <#123456789012345678>", "user"),
+            Command::ChannelInfo(Some(ChannelId(123456789012345678)))
+        );
+        assert_eq!(
+            Command::parse("-channelinfo 123456789012345678", "user"),
+            Command::ChannelInfo(Some(ChannelId(123456789012345678)))
+        );
+        assert!(matches!(
+            Command::parse("-channelinfo general", "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[tokio::test]
+    async fn execute_channelinfo() {
+        let sink = MockSink::default();
+        Command::ChannelInfo(None)
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        let embeds = sink.embeds.lock().unwrap().clone();
+        assert_eq!(embeds[0].title, "#general");
+        assert!(embeds[0]
+            .fields
+            .contains(&("Slowmode".to_owned(), "5 seconds".to_owned())));
+        assert!(embeds[0]
+            .fields
+            .contains(&("ID".to_owned(), "`50`".to_owned())));
+        let sink = MockSink {
+            foreign_channels: vec![70],
+            ..Default::default()
+        };
+        Command::ChannelInfo(Some(ChannelId(70)))
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert!(sink.embeds.lock().unwrap().is_empty());
+        assert_eq!(sink.sent(), vec!["That channel isn't in this server!"]);
+    }
+    #[test]
+    fn emoji_pages_fit_in_messages() {
+        use crate::emojis::emoji_pages;
+        let emojis = (0..300)
+            .map(|id| GuildEmoji {
+                id: 100_000_000_000_000_000 + id,
+                name: format!("emoji_{id:03}"),
+                animated: id % 2 == 1,
+            })
+            .collect::<Vec<_>>();
+        let pages = emoji_pages(&emojis);
+        assert!(pages.len() > 1);
+        assert!(pages
+            .iter()
+            .all(|page| page.chars().count() <= MAX_PAGE_LENGTH));
+        let lines = pages
+            .iter()
+            .flat_map(|page| page.lines())
+            .collect::<Vec<_>>();
+        assert_eq!(lines[0], "300 custom emojis:");
+        assert_eq!(
+            lines[1],
+            "<:emoji_000:100000000000000000> `:emoji_000:` `100000000000000000`"
+        );
+        assert!(lines[2].starts_with("<a:emoji_001:"));
+        assert_eq!(lines.len(), 301);
+        assert_eq!(
+            emoji_pages(&[]),
+            vec!["This server has no custom emojis!".to_owned()]
+        );
+    }
+    #[tokio::test]
+    async fn execute_emojis() {
+        let sink = MockSink::default();
+        Command::Emojis.execute_command(&sink).await.unwrap();
+        let pages = sink.pages.lock().unwrap().clone();
+        assert_eq!(
+            pages,
+            vec![vec![
+                "1 custom emojis:\n<:baba:900> `:baba:` `900`".to_owned()
+            ]]
+        );
+    }
+    #[test]
+    fn parse_emoji_references() {
+        use crate::emojis::{emoji_name, has_emoji_slot, parse_emoji_reference, valid_emoji_name};
+        let baba = parse_emoji_reference("<:baba:123>").unwrap();
+        assert_eq!(
+            baba,
+            GuildEmoji {
+                id: 123,
+                name: "baba".to_owned(),
+                animated: false
+            }
+        );
+        assert_eq!(
+            baba.image_url(),
+            "https://cdn.discordapp.com/emojis/123.png"
+        );
+        let keke = parse_emoji_reference("<a:keke:456>").unwrap();
+        assert!(keke.animated);
+        assert_eq!(
+            keke.image_url(),
+            "https://cdn.discordapp.com/emojis/456.gif"
+        );
+        assert_eq!(parse_emoji_reference("🎉"), None);
+        assert_eq!(parse_emoji_reference(":baba:"), None);
+        assert_eq!(parse_emoji_reference("<:baba:nope>"), None);
+        assert_eq!(emoji_name(&baba, None), "baba");
+        assert_eq!(emoji_name(&baba, Some(":rock:")), "rock");
+        assert!(valid_emoji_name("baba_is_you"));
+        assert!(!valid_emoji_name("b"));
+        assert!(!valid_emoji_name("baba is you"));
+        assert!(has_emoji_slot(std::slice::from_ref(&keke), true, 2));
+        assert!(!has_emoji_slot(&[keke.clone(), keke], true, 2));
+        assert_eq!(
+            Command::parse("-steal <:baba:123> rock", "user"),
+            Command::StealEmoji {
+                emoji: "<:baba:123>".to_owned(),
+                name: Some("rock".to_owned())
+            }
+        );
+        assert!(matches!(
+            Command::parse("-steal <:b:123>", "user"),
+            Command::NotValid(_)
+        ));
+    }
+    #[tokio::test]
+    async fn execute_steal_emoji() {
+        let sink = MockSink::default();
+        Command::StealEmoji {
+            emoji: "<a:keke:456>".to_owned(),
+            name: None,
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            sink.sent(),
+            vec!["Added <a:keke:901> `:keke:` `901` to the server!"]
+        );
+    }
+    #[test]
+    fn channel_cache_expires() {
+        use crate::snipe::{ChannelCache, SnipedMessage};
+        let cache = ChannelCache::new(60);
+        let message = |content: &str| SnipedMessage {
+            author: 1,
+            content: content.to_owned(),
+            time: 900,
+        };
+        cache.insert(50, message("first"), 1000);
+        cache.insert(50, message("second"), 1010);
+        cache.insert(60, message("elsewhere"), 1000);
+        assert_eq!(cache.get(50, 1020), Some(message("second")));
+        assert_eq!(cache.get(60, 1059), Some(message("elsewhere")));
+        assert_eq!(cache.get(60, 1060), None);
+        assert_eq!(cache.get(50, 1070), None);
+        assert_eq!(cache.get(70, 1000), None);
+    }
+    #[tokio::test]
+    async fn execute_snipe() {
+        use crate::snipe::{SnipedMessage, SNIPES};
+        let sink = MockSink {
+            channel: Some(51),
+            ..Default::default()
+        };
+        Command::Snipe.execute_command(&sink).await.unwrap();
+        assert_eq!(sink.sent(), vec!["There's nothing to snipe!"]);
+        let now = Timestamp::now().unix_timestamp();
+        let sniped = SnipedMessage {
+            author: 2,
+            content: "oops".to_owned(),
+            time: now,
+        };
+        SNIPES.insert(51, sniped, now);
+        Command::Snipe.execute_command(&sink).await.unwrap();
+        let embeds = sink.embeds.lock().unwrap().clone();
+        assert_eq!(embeds[0].title, "user2 said...");
+        assert_eq!(embeds[0].description, "oops");
+        assert_eq!(
+            Command::parse("-snipe nsfw on", "user"),
+            Command::SnipeNsfw(true)
+        );
+        assert!(Command::SnipeNsfw(true).mod_only());
+        assert!(!Command::Snipe.mod_only());
+    }
+    #[tokio::test]
+    async fn execute_editsnipe() {
+        use crate::snipe::{EditedMessage, EDIT_SNIPES};
+        let sink = MockSink {
+            channel: Some(52),
+            ..Default::default()
+        };
+        Command::EditSnipe.execute_command(&sink).await.unwrap();
+        assert_eq!(sink.sent(), vec!["There's nothing to snipe!"]);
+        let now = Timestamp::now().unix_timestamp();
+        let edited = EditedMessage {
+            author: 3,
+            before: "teh".to_owned(),
+            after: "the".to_owned(),
+            time: now,
+        };
+        EDIT_SNIPES.insert(52, edited.clone(), now);
+        assert_eq!(EDIT_SNIPES.get(52, now), Some(edited));
+        Command::EditSnipe.execute_command(&sink).await.unwrap();
+        let embeds = sink.embeds.lock().unwrap().clone();
+        assert_eq!(embeds[0].title, "user3 edited...");
+        assert_eq!(embeds[0].fields[0], ("Before".to_owned(), "teh".to_owned()));
+        assert_eq!(embeds[0].fields[1], ("After".to_owned(), "the".to_owned()));
+        let edited = EditedMessage {
+            author: 3,
+            before: String::new(),
+            after: "baba ".repeat(300),
+            time: now,
+        };
+        EDIT_SNIPES.insert(52, edited, now);
+        Command::EditSnipe.execute_command(&sink).await.unwrap();
+        let embeds = sink.embeds.lock().unwrap().clone();
+        assert_eq!(embeds[1].fields[0].1, "*No text*");
+        assert_eq!(embeds[1].fields[1].1.chars().count(), MAX_FIELD_LENGTH);
+    }
+    #[test]
+    fn mod_log_settings() {
+        let db = test_database();
+        assert_eq!(mod_log_channel(&db, 100).unwrap(), None);
+        ModLogSetting::Channel(60).apply(&db, 100).unwrap();
+        assert_eq!(mod_log_channel(&db, 100).unwrap(), Some(60));
+        ModLogSetting::Disable.apply(&db, 100).unwrap();
+        assert_eq!(mod_log_channel(&db, 100).unwrap(), None);
+        assert!(loggable(false, 50, 60));
+        assert!(!loggable(true, 50, 60));
+        assert!(!loggable(false, 60, 60));
+        assert_eq!(
+            Command::parse("-modlog channel <#60>", "user"),
+            Command::ModLog(ModLogSetting::Channel(60))
+        );
+        assert_eq!(
+            Command::parse("-modlog off", "user"),
+            Command::ModLog(ModLogSetting::Disable)
+        );
+    }
+    #[test]
+    fn mod_log_embeds() {
+        let edit = edit_embed(3, 50, "teh", "the");
+        assert_eq!(edit.title, "Message edited");
+        assert_eq!(edit.description, "Message by <@3> in <#50>");
+        assert_eq!(
+            edit.fields,
+            vec![
+                ("Before".to_owned(), "teh".to_owned()),
+                ("After".to_owned(), "the".to_owned())
+            ]
+        );
+        let deletion = deletion_embed(3, 50, "");
+        assert_eq!(deletion.title, "Message deleted");
+        assert_eq!(
+            deletion.fields,
+            vec![("Content".to_owned(), "*No text*".to_owned())]
+        );
+        let long = field_value(&"a".repeat(2000));
+        assert_eq!(long.chars().count(), MAX_FIELD_LENGTH);
+        assert!(long.ends_with('…'));
+    }
+    #[test]
+    fn casefile_assign_round_trip() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().assignee, None);
+        let outcome = "casefile assign 0 <@42>"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(outcome.to_string(), "Assigned Casefile #0 to <@42>.");
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().assignee, Some(42));
+        let read = CaseFileAction::Read { id }.perform(&db, 1).unwrap();
+        assert_eq!(read.to_string(), "Case #0 => Foo v. Bar\nAssigned to <@42>");
+        let listed = CaseFileAction::ViewAll.perform(&db, 1).unwrap().to_string();
+        assert!(listed.contains("#0 [unresolved] [low] | Foo v. Bar | assigned to <@42>"));
+        let outcome = "casefile unassign 0"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(outcome, CaseFileOutcome::Assigned { id, mod_id: None });
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().assignee, None);
+        assert!(CaseFile::assign(&db, 42, Some(1)).is_err());
+        assert!("casefile assign 0".parse::<CaseFileAction>().is_err());
+        assert!("casefile assign 0 keke".parse::<CaseFileAction>().is_err());
+    }
+    #[test]
+    fn database_migration_adds_columns() {
+        let db = Database::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE cases (id INTEGER PRIMARY KEY, name TINYTEXT, reso BOOLEAN, data LONGTEXT, archived BOOLEAN NOT NULL DEFAULT 0);",
+        )
+        .unwrap();
+        initialize_database(&db).unwrap();
+        let id = insert_case(&db, "Foo v. Bar");
+        CaseFile::assign(&db, id, Some(42)).unwrap();
+        assert_eq!(CaseFile::from_id(&db, id).unwrap().assignee, Some(42));
+        migrate_database(&db).unwrap();
+    }
+    #[test]
+    fn casefile_severity_parsing() {
+        assert_eq!("low".parse(), Ok(Severity::Low));
+        assert_eq!("Medium".parse(), Ok(Severity::Medium));
+        assert_eq!("HIGH".parse(), Ok(Severity::High));
+        assert_eq!("crit".parse(), Ok(Severity::Critical));
+        assert_eq!("urgent".parse::<Severity>(), Err(()));
+        assert!(Severity::Critical > Severity::High && Severity::Medium > Severity::Low);
+        assert!(matches!(
+            "casefile severity 3 high".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::SetSeverity {
+                id: 3,
+                severity: Severity::High
+            })
+        ));
+        assert!(matches!(
+            "casefile view critical".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::ViewFiltered(CaseFilter::Severity(
+                Severity::Critical
+            )))
+        ));
+        assert!("casefile severity 3 urgent"
+            .parse::<CaseFileAction>()
+            .is_err());
+    }
+    #[test]
+    fn casefile_severity_sorting_and_filtering() {
+        let db = test_database();
+        let low = insert_case(&db, "Low");
+        let high = insert_case(&db, "High");
+        let critical = insert_case(&db, "Critical");
+        let other_high = insert_case(&db, "Also High");
+        CaseFile::set_severity(&db, high, Severity::High).unwrap();
+        CaseFile::set_severity(&db, critical, Severity::Critical).unwrap();
+        CaseFile::set_severity(&db, other_high, Severity::High).unwrap();
+        let ids = CaseFile::all_files(&db)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![critical, high, other_high, low]);
+        let outcome = CaseFileAction::ViewFiltered(CaseFilter::Severity(Severity::High))
+            .perform(&db, 1)
+            .unwrap();
+        let CaseFileOutcome::Listed { files, .. } = &outcome else {
+            panic!("expected a listing, got {outcome:?}");
+        };
+        assert_eq!(
+            files.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![high, other_high]
+        );
+        assert!(outcome
+            .to_string()
+            .contains(&format!("#{high} [unresolved] [high] | High")));
+        assert!(CaseFile::set_severity(&db, 42, Severity::Low).is_err());
+    }
+    #[test]
+    fn casefile_tags() {
+        let db = test_database();
+        let spam = insert_case(&db, "Spam");
+        let other = insert_case(&db, "Other");
+        let outcome = "casefile tag 0 Spam harassment"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(
+            outcome.to_string(),
+            "Tagged Casefile #0 with `spam`, `harassment`."
+        );
+        CaseFile::add_tags(&db, other, &["raid".to_owned()]).unwrap();
+        assert_eq!(
+            CaseFile::tags(&db, spam).unwrap(),
+            vec!["harassment", "spam"]
+        );
+        let read = CaseFileAction::Read { id: spam }.perform(&db, 1).unwrap();
+        assert_eq!(read.to_string(), "Case #0 => Spam\nTags: harassment, spam");
+        let filter = "casefile view tag:spam".parse::<CaseFileAction>().unwrap();
+        assert!(matches!(
+            &filter,
+            CaseFileAction::ViewFiltered(CaseFilter::Tag(tag)) if tag == "spam"
+        ));
+        let CaseFileOutcome::Listed { files, .. } = filter.perform(&db, 1).unwrap() else {
+            panic!("expected a listing");
+        };
+        assert_eq!(
+            files.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![spam]
+        );
+        "casefile untag 0 spam"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(CaseFile::tags(&db, spam).unwrap(), vec!["harassment"]);
+        assert!(CaseFile::add_tags(&db, 42, &["spam".to_owned()]).is_err());
+        assert!("casefile tag 0".parse::<CaseFileAction>().is_err());
+    }
+    #[test]
+    fn casefile_users() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        let outcome = "casefile adduser 0 <@20>"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap();
+        assert_eq!(outcome.to_string(), "Linked <@20> to Casefile #0.");
+        CaseFile::add_user(&db, id, 10).unwrap();
+        CaseFile::add_user(&db, id, 20).unwrap();
+        assert_eq!(CaseFile::users(&db, id).unwrap(), vec![20, 10]);
+        let read = CaseFileAction::Read { id }.perform(&db, 1).unwrap();
+        assert_eq!(
+            read.to_string(),
+            "Case #0 => Foo v. Bar\nUsers: <@20>, <@10>"
+        );
+        assert!(matches!(
+            "casefile removeuser 0 20".parse::<CaseFileAction>(),
+            Ok(CaseFileAction::RemoveUser { id: 0, user: 20 })
+        ));
+        CaseFile::remove_user(&db, id, 20).unwrap();
+        assert_eq!(CaseFile::users(&db, id).unwrap(), vec![10]);
+        assert!(CaseFile::add_user(&db, 42, 10).is_err());
+        assert!("casefile adduser 0 keke".parse::<CaseFileAction>().is_err());
+    }
+    #[test]
+    fn casefile_history() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        let history = CaseFileAction::History { id }.perform(&db, 1).unwrap();
+        assert_eq!(history.to_string(), "Casefile #0 hasn't been changed yet.");
+        for command in [
+            "casefile add 0 Among us",
+            "casefile add 0 sus",
+            "casefile remove 0 0",
+            "casefile rename 0 Baba v. Keke",
+            "casefile resolve 0",
+        ] {
+            command
+                .parse::<CaseFileAction>()
+                .unwrap()
+                .perform(&db, 7)
+                .unwrap();
+        }
+        CaseFileAction::RemoveItem { id, index: Some(9) }
+            .perform(&db, 7)
+            .unwrap();
+        let file = CaseFile::from_id(&db, id).unwrap();
+        assert_eq!(file.name, "Baba v. Keke");
+        assert!(file.is_resolved());
+        let changes = CaseFile::history(&db, id)
+            .unwrap()
+            .into_iter()
+            .map(|entry| {
+                assert_eq!(entry.moderator, 7);
+                entry.change
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                CaseChange::AddedItem("Among us".to_owned()),
+                CaseChange::AddedItem("sus".to_owned()),
+                CaseChange::RemovedItem("Among us".to_owned()),
+                CaseChange::Renamed("Baba v. Keke".to_owned()),
+                CaseChange::Resolved(true),
+            ]
+        );
+        let history = "casefile history 0"
+            .parse::<CaseFileAction>()
+            .unwrap()
+            .perform(&db, 1)
+            .unwrap()
+            .to_string();
+        assert!(history.starts_with("History of Casefile #0:\n<t:"));
+        assert!(history.ends_with("<@7> marked the case as resolved"));
+        assert!(CaseFileAction::History { id: 42 }.perform(&db, 1).is_err());
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn casefile_json_round_trip() {
+        let file = CaseFile {
+            name: "Baba v. \"Keke\"".to_owned(),
+            resolved: true,
+            items: vec![
+                "Among us".to_owned(),
+                "said \\o/ in #general".to_owned(),
+                "🦀 ünïcödé\ttab".to_owned(),
+            ],
+            assignee: Some(42),
+            severity: Severity::Critical,
+            tags: vec!["spam".to_owned()],
+            users: vec![1, 2],
+        };
+        let json = file.to_json().unwrap();
+        assert!(json.contains(r#""severity":"critical""#));
+        assert_eq!(CaseFile::from_json(&json).unwrap(), file);
+        assert!(CaseFile::from_json("{}").is_err());
+    }
+    #[test]
+    fn database_backup() {
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        let directory = std::env::temp_dir().join(format!("bababot-backup-{}", std::process::id()));
+        let path = backup_database(&db, &directory, 1_700_000_000).unwrap();
+        assert_eq!(path, directory.join("db-1700000000.db3"));
+        let backup = rusqlite::Connection::open(&path).unwrap();
+        let name = backup
+            .query_row("SELECT name FROM cases WHERE id = (?1)", [id], |row| {
+                row.get::<_, String>(0)
+            })
+            .unwrap();
+        assert_eq!(name, "Foo v. Bar");
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    #[test]
+    fn database_restore_validation() {
+        let directory =
+            std::env::temp_dir().join(format!("bababot-restore-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let db = test_database();
+        let id = insert_case(&db, "Foo v. Bar");
+        let good = backup_database(&db, &directory, 1).unwrap();
+        assert!(validate_backup(&good).is_ok());
+        let mut restored = test_database();
+        restore_database(&mut restored, &good).unwrap();
+        assert_eq!(CaseFile::from_id(&restored, id).unwrap().name, "Foo v. Bar");
+
+        let partial = directory.join("partial.db3");
+        rusqlite::Connection::open(&partial)
+            .unwrap()
+            .execute_batch("CREATE TABLE users (id INTEGER PRIMARY KEY);")
+            .unwrap();
+        let Err(RestoreError::MissingTables(missing)) = validate_backup(&partial) else {
+            panic!("a backup without the bot's tables shouldn't be valid");
+        };
+        assert!(missing.contains(&"cases".to_owned()));
+        assert!(!missing.contains(&"users".to_owned()));
+
+        let older = backup_database(&db, &directory, 3).unwrap();
+        rusqlite::Connection::open(&older)
+            .unwrap()
+            .execute_batch(
+                "DROP TABLE keke_nicknames; DROP TABLE rules; DROP TABLE notes; DROP TABLE mod_log;",
+            )
+            .unwrap();
+        assert!(validate_backup(&older).is_ok());
+        let mut upgraded = test_database();
+        restore_database(&mut upgraded, &older).unwrap();
+        assert!(notes(&upgraded, 100, 5).unwrap().is_empty());
+
+        let newer = backup_database(&db, &directory, 2).unwrap();
+        rusqlite::Connection::open(&newer)
+            .unwrap()
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            validate_backup(&newer),
+            Err(RestoreError::IncompatibleVersion(version)) if version == SCHEMA_VERSION + 1
+        ));
+        assert!(restore_database(&mut restored, &newer).is_err());
+
+        let garbage = directory.join("garbage.db3");
+        std::fs::write(&garbage, "this is not a database, baba is you").unwrap();
+        assert!(validate_backup(&garbage).is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    #[test]
+    fn dev_sql_only_selects() {
+        assert!(is_select("SELECT * FROM cases"));
+        assert!(is_select("  select name from cases;  "));
+        assert!(is_select("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!is_select("UPDATE cases SET name = 'baba'"));
+        assert!(!is_select("DELETE FROM cases"));
+        assert!(!is_select("DROP TABLE cases"));
+        assert!(!is_select("SELECT 1; DROP TABLE cases"));
+        assert!(!is_select(""));
+        let db = test_database();
+        insert_case(&db, "Foo v. Bar");
+        assert_eq!(
+            run_select(&db, "SELECT id, name, assignee FROM cases").unwrap(),
+            "```\nid | name | assignee\n0 | Foo v. Bar | NULL\n```"
+        );
+        assert!(run_select(&db, "DROP TABLE cases").is_err());
+        for _ in 0..MAX_QUERY_ROWS {
+            insert_case(&db, "Baba");
+        }
+        let rows = run_select(&db, "SELECT name FROM cases").unwrap();
+        assert_eq!(rows.lines().count(), MAX_QUERY_ROWS + 4);
+        assert!(rows.ends_with("...\n```"));
+    }
+    #[test]
+    fn config_changes() {
+        let old = BotConfig::default();
+        assert_eq!(old.star_threshold, 3);
+        assert!(old.changes(&old.clone()).is_empty());
+        let new = BotConfig {
+            star_threshold: 5,
+            mod_channel: Some(60),
+        };
+        let changes = old
+            .changes(&new)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec!["star threshold: 3 -> 5", "mod channel: none -> <#60>"]
+        );
+        let moved = BotConfig {
+            mod_channel: Some(61),
+            ..new.clone()
+        };
+        assert_eq!(
+            new.changes(&moved)[0].to_string(),
+            "mod channel: <#60> -> <#61>"
+        );
+    }
+    #[test]
+    fn config_file_values() {
+        let file = parse_config_file(
+            "# settings\nBABA_BOT_STAR_THRESHOLD = 5\n\nnot a setting\nBABA_BOT_MOD_CHANNEL=oops\n",
+        );
+        assert_eq!(file.len(), 2);
+        assert_eq!(file[STAR_THRESHOLD_VARIABLE], "5");
+        let fallback = |name: &str| (name == MOD_CHANNEL_VARIABLE).then(|| "60".to_owned());
+        let config =
+            BotConfig::from_values(|name| file.get(name).cloned().or_else(|| fallback(name)));
+        assert_eq!(
+            config,
+            BotConfig {
+                star_threshold: 5,
+                mod_channel: None,
+            }
+        );
+        let config = BotConfig::from_values(fallback);
+        assert_eq!(
+            config,
+            BotConfig {
+                star_threshold: 3,
+                mod_channel: Some(60),
+            }
+        );
+    }
+    #[test]
+    fn process_stats_formatting() {
+        let status = "Name:\tbababot\nVmPeak:\t  20000 kB\nVmRSS:\t   12800 kB\nThreads:\t8\n";
+        assert_eq!(parse_resident_memory(status), Some(12800));
+        assert_eq!(parse_resident_memory("Name:\tbababot\n"), None);
+        let db = test_database();
+        let sink = MockSink::default();
+        let stats = ProcessStats {
+            memory_kib: Some(12800),
+            ..ProcessStats::gather(&db, sink.cache_counts()).unwrap()
+        };
+        assert_eq!(stats.cache.guilds, 1);
+        assert_eq!(stats.pending_reminders, 0);
+        let embed = stats.to_embed();
+        assert_eq!(embed.title, "Process stats");
+        assert_eq!(
+            embed.fields,
+            [
+                ("Memory", "12.5 MiB"),
+                ("Cached guilds", "1"),
+                ("Cached channels", "5"),
+                ("Cached users", "2"),
+                ("Pending reminders", "0"),
+                ("Running giveaways", "0"),
+            ]
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        );
+        let unknown = ProcessStats::default().to_embed();
+        assert_eq!(unknown.fields[0].1, "unknown");
+    }
+    #[test]
+    fn missing_permission_detection() {
+        assert!(missing_permissions(Permissions::all()).is_empty());
+        assert!(missing_permissions(Permissions::ADMINISTRATOR).is_empty());
+        assert_eq!(
+            missing_permissions(Permissions::empty()).len(),
+            REQUIRED_PERMISSIONS.len()
+        );
+        let most = REQUIRED_PERMISSIONS
+            .iter()
+            .fold(Permissions::empty(), |all, (permission, _)| {
+                all | *permission
+            })
+            - Permissions::BAN_MEMBERS
+            - Permissions::MANAGE_ROLES;
+        assert_eq!(
+            missing_permissions(most),
+            vec!["Ban Members", "Manage Roles"]
+        );
+        let report = permission_report(most);
+        assert!(report.starts_with("❌ Ban Members\n✅ Moderate Members"));
+        assert!(report.ends_with("I'm missing 2 permissions!"));
+    }
+    #[tokio::test]
+    async fn dev_permcheck() {
+        let sink = MockSink {
+            permissions: Some(
+                Permissions::all() - Permissions::ADMINISTRATOR - Permissions::MODERATE_MEMBERS,
+            ),
+            ..Default::default()
+        };
+        Command::Dev("permcheck".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        let sent = sink.sent();
+        assert!(sent[0].contains("❌ Moderate Members"));
+        assert!(sent[0].ends_with("I'm missing 1 permission!"));
+    }
+    #[test]
+    fn mod_action_preflight() {
+        let positions = |target| RolePositions { bot: 10, target };
+        let everything = Permissions::all();
+        assert_eq!(
+            preflight(everything, Permissions::BAN_MEMBERS, positions(Some(3))),
+            Preflight::Proceed
+        );
+        assert_eq!(
+            preflight(everything, Permissions::BAN_MEMBERS, positions(None)),
+            Preflight::Proceed
+        );
+        assert_eq!(
+            preflight(everything, Permissions::BAN_MEMBERS, positions(Some(10))),
+            Preflight::Outranked
+        );
+        assert_eq!(
+            preflight(
+                Permissions::BAN_MEMBERS,
+                Permissions::MODERATE_MEMBERS,
+                positions(Some(3))
+            ),
+            Preflight::MissingPermission("Moderate Members")
+        );
+        assert_eq!(
+            preflight(
+                Permissions::ADMINISTRATOR,
+                Permissions::MODERATE_MEMBERS,
+                positions(Some(i64::MAX))
+            ),
+            Preflight::Outranked
+        );
+    }
+    #[tokio::test]
+    async fn mod_actions_check_the_bot_first() {
+        let ban = || Command::Ban(UserId(5), "spam".to_owned());
+        let sink = MockSink {
+            moderator: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            ban().check_permissions(&test_database(), &sink).await,
+            ban()
+        );
+        let sink = MockSink {
+            moderator: true,
+            permissions: Some(Permissions::SEND_MESSAGES),
+            ..Default::default()
+        };
+        assert_eq!(
+            ban().check_permissions(&test_database(), &sink).await,
+            Command::NotValid("I need the `Ban Members` permission to do that!".to_owned())
+        );
+        let sink = MockSink {
+            moderator: true,
+            target_position: Some(20),
+            ..Default::default()
+        };
+        let mute = Command::Mute(UserId(5), Time::from_str("1h").unwrap(), String::new());
+        assert_eq!(
+            mute.check_permissions(&test_database(), &sink).await,
+            Command::NotValid(Preflight::Outranked.to_string())
+        );
+        assert!(sink.mutes.lock().unwrap().is_empty());
+    }
+    /// Makes a message ID for a message sent at a unix timestamp.
+    fn snowflake_at(time: i64, increment: u64) -> u64 {
+        (((time * 1000 - DISCORD_EPOCH_MILLIS) as u64) << 22) | increment
+    }
+    #[test]
+    fn purge_selection() {
+        let now = 1_700_000_000;
+        assert_eq!(snowflake_time(snowflake_at(now, 7)), now);
+        assert!(bulk_deletable(snowflake_at(now - 60, 0), now));
+        assert!(!bulk_deletable(
+            snowflake_at(now - 15 * 24 * 60 * 60, 0),
+            now
+        ));
+        let message = |id| HistoryMessage { id, author: 2 };
+        let start = snowflake_at(now - 600, 0);
+        let history = [
+            message(snowflake_at(now - 20 * 24 * 60 * 60, 0)),
+            message(start),
+            message(snowflake_at(now - 300, 0)),
+            message(snowflake_at(now - 300, 1)),
+            message(snowflake_at(now - 5, 0)),
+        ];
+        let purge = Purge::after(start, &history, now);
+        assert_eq!(purge.ids, vec![history[2].id, history[3].id, history[4].id]);
+        assert_eq!(purge.too_old, 0);
+        let purge = Purge::after(0, &history, now);
+        assert_eq!(purge.ids.len(), 4);
+        assert_eq!(purge.too_old, 1);
+        assert_eq!(
+            purge.to_string(),
+            "Deleted 4 messages. 1 message was more than 14 days old, so I left it alone."
+        );
+        assert_eq!(
+            Purge::after(history[4].id, &history, now).to_string(),
+            "Deleted 0 messages."
+        );
+    }
+    #[tokio::test]
+    async fn purge_from_link() {
+        assert_eq!(
+            Command::parse("-purgefrom https://discord.com/channels/100/50/7", "user"),
+            Command::PurgeFrom(MessageLink {
+                guild_id: Some(100),
+                channel_id: 50,
+                message_id: 7,
+            })
+        );
+        assert!(matches!(
+            Command::parse("-purgefrom nowhere", "user"),
+            Command::NotValid(_)
+        ));
+        let now = Timestamp::now().unix_timestamp();
+        let history = (0..3)
+            .map(|increment| HistoryMessage {
+                id: snowflake_at(now - 60, increment),
+                author: 2,
+            })
+            .collect::<Vec<_>>();
+        let sink = MockSink {
+            moderator: true,
+            history: history.clone(),
+            ..Default::default()
+        };
+        let link = format!(
+            "-purgefrom https://discord.com/channels/100/60/{}",
+            history[0].id
+        );
+        let command = Command::parse(&link, "baba");
+        command
+            .check_permissions(&test_database(), &sink)
+            .await
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.deleted.lock().unwrap(),
+            vec![(60, vec![history[1].id, history[2].id])]
+        );
+        assert_eq!(sink.sent(), vec!["Deleted 2 messages."]);
+        let elsewhere = Command::parse("-purgefrom https://discord.com/channels/200/60/1", "user");
+        elsewhere.execute_command(&sink).await.unwrap();
+        assert_eq!(sink.deleted.lock().unwrap().len(), 1);
+        let sink = MockSink {
+            history,
+            foreign_channels: vec![70],
+            ..Default::default()
+        };
+        let disguised = Command::parse("-purgefrom https://discord.com/channels/100/70/1", "user");
+        disguised.execute_command(&sink).await.unwrap();
+        assert!(sink.deleted.lock().unwrap().is_empty());
+        assert_eq!(
+            sink.sent(),
+            vec!["Messages can only be purged from this server!"]
+        );
+    }
+    #[test]
+    fn purge_user_selection() {
+        let now = 1_700_000_000;
+        let message = |age, increment, author| HistoryMessage {
+            id: snowflake_at(now - age, increment),
+            author,
+        };
+        let history = [
+            message(10, 0, 5),
+            message(20, 0, 2),
+            message(30, 0, 5),
+            message(40, 0, 5),
+            message(20 * 24 * 60 * 60, 0, 5),
+        ];
+        let purge = Purge::by_user(5, 2, &history, now);
+        assert_eq!(purge.ids, vec![history[0].id, history[2].id]);
+        assert_eq!(purge.too_old, 0);
+        let purge = Purge::by_user(5, 10, &history, now);
+        assert_eq!(purge.ids, vec![history[0].id, history[2].id, history[3].id]);
+        assert_eq!(purge.too_old, 1);
+        assert_eq!(Purge::by_user(9, 10, &history, now), Purge::default());
+    }
+    #[tokio::test]
+    async fn purge_user() {
+        assert_eq!(
+            Command::parse("-purge user <@5> 3", "user"),
+            Command::Purge {
+                user: UserId(5),
+                count: 3
+            }
+        );
+        assert_eq!(
+            Command::parse("-purge user 5 0", "user"),
+            Command::NotValid("I can only purge between 1 and 100 messages at once!".to_owned())
+        );
+        assert!(matches!(
+            Command::parse("-purge everyone 5 3", "user"),
+            Command::NotValid(_)
+        ));
+        let now = Timestamp::now().unix_timestamp();
+        let sink = MockSink {
+            moderator: true,
+            history: vec![
+                HistoryMessage {
+                    id: snowflake_at(now - 5, 0),
+                    author: 5,
+                },
+                HistoryMessage {
+                    id: snowflake_at(now - 10, 0),
+                    author: 6,
+                },
+            ],
+            ..Default::default()
+        };
+        Command::parse("-purge user 5 3", "user")
+            .check_permissions(&test_database(), &sink)
+            .await
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.deleted.lock().unwrap(),
+            vec![(50, vec![sink.history[0].id])]
+        );
+        assert_eq!(sink.sent(), vec!["Deleted 1 message."]);
+    }
+    #[test]
+    fn keke_cooldown() {
+        let cooldown = Cooldown::new(KEKE_COOLDOWN_SECONDS);
+        let attempts = [
+            0,
+            10,
+            KEKE_COOLDOWN_SECONDS - 1,
+            KEKE_COOLDOWN_SECONDS,
+            70,
+            200,
+        ]
+        .map(|time| cooldown.try_use(1, 1000 + time));
+        assert_eq!(attempts, [true, false, false, true, false, true]);
+        assert!(cooldown.try_use(2, 1010));
+    }
+    #[test]
+    fn keke_sanitizing() {
+        let blocklist = [FilterTerm {
+            term: "heck".to_owned(),
+            whole_word: true,
+        }];
+        assert_eq!(
+            KekeName::sanitize("baba <@123> and @everyone", &blocklist),
+            KekeName::Valid("baba and".to_owned())
+        );
+        assert_eq!(
+            KekeName::sanitize("keke https://example.com discord.gg/abc", &blocklist),
+            KekeName::Valid("keke".to_owned())
+        );
+        assert_eq!(
+            KekeName::sanitize("<@&5> <#6>", &blocklist),
+            KekeName::Blocked
+        );
+        assert_eq!(
+            KekeName::sanitize("a HECK of a time", &blocklist),
+            KekeName::Blocked
+        );
+        assert_eq!(
+            KekeName::sanitize(&"baba ".repeat(10), &blocklist),
+            KekeName::TooLong("baba ".repeat(10).trim().to_owned())
+        );
+        assert_eq!(
+            KekeName::Blocked.announcement("Baba"),
+            "Baba is NOT that!\n\nWanna optout? use -keke!"
+        );
+    }
+    #[test]
+    fn keke_original_nicknames() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert_eq!(original_nickname(&db, 100, 1).unwrap(), None);
+        save_original_nickname(&db, 100, 1, Some("Baba")).unwrap();
+        save_original_nickname(&db, 100, 1, Some("Keke Is Silly")).unwrap();
+        save_original_nickname(&db, 100, 2, None).unwrap();
+        assert_eq!(
+            original_nickname(&db, 100, 1).unwrap(),
+            Some(OriginalNickname(Some("Baba".to_owned())))
+        );
+        assert_eq!(
+            original_nickname(&db, 100, 2).unwrap(),
+            Some(OriginalNickname(None))
+        );
+        assert_eq!(original_nickname(&db, 200, 1).unwrap(), None);
+        forget_original_nickname(&db, 100, 1).unwrap();
+        assert_eq!(original_nickname(&db, 100, 1).unwrap(), None);
+    }
+    #[test]
+    fn keke_nickname_reset() {
+        assert_eq!(NicknameReset::decide(None), NicknameReset::Keep);
+        assert_eq!(
+            NicknameReset::decide(Some(OriginalNickname(None))),
+            NicknameReset::Restore(None)
+        );
+        assert_eq!(
+            NicknameReset::decide(Some(OriginalNickname(Some("Baba".to_owned())))),
+            NicknameReset::Restore(Some("Baba".to_owned()))
+        );
+    }
+    #[test]
+    fn relaying_direct_messages() {
+        assert_eq!(
+            relayed_message("<@5>", "help\nplease", &[]),
+            "Incoming message from <@5>:\n> help\nplease"
+        );
+        assert_eq!(
+            relayed_message("<@5>", "baba", &[]),
+            "Incoming message from <@5>:\n> baba"
+        );
+    }
+    #[tokio::test]
+    async fn direct_messages_need_no_guild() {
+        let sink = MockSink {
+            outside_guild: true,
+            ..Default::default()
+        };
+        Command::Suggestion("more keke".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.direct_messages.lock().unwrap(),
+            vec![(
+                CAMILA,
+                "Heads up Cami! Someone sent in a suggestion:\n> more keke".to_owned()
+            )]
+        );
+        assert_eq!(
+            sink.sent(),
+            vec!["Successfully sent suggestion off to Cami!\nIf this is an emergency, I'd reccomend pinging her."]
+        );
+        assert_eq!(*sink.member_lookups.lock().unwrap(), 0);
+        assert!(sink.member_request().is_err());
+    }
+    #[test]
+    fn relaying_attachments() {
+        let attachments = [
+            RelayedAttachment {
+                name: "level.png".to_owned(),
+                url: "https://cdn.discordapp.com/attachments/1/2/level.png".to_owned(),
+            },
+            RelayedAttachment {
+                name: "save.ba".to_owned(),
+                url: "https://cdn.discordapp.com/attachments/1/3/save.ba".to_owned(),
+            },
+        ];
+        assert_eq!(
+            attachment_lines(&attachments),
+            "📎 level.png: https://cdn.discordapp.com/attachments/1/2/level.png\n\
+            📎 save.ba: https://cdn.discordapp.com/attachments/1/3/save.ba"
+        );
+        assert_eq!(attachment_lines(&[]), "");
+        assert_eq!(
+            relayed_message("<@5>", "", &attachments[..1]),
+            "Incoming message from <@5>:\n📎 level.png: https://cdn.discordapp.com/attachments/1/2/level.png"
+        );
+        assert_eq!(
+            relayed_message("<@5>", "look", &attachments)
+                .lines()
+                .count(),
+            4
+        );
+    }
+    #[tokio::test]
+    async fn keke_list() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert!(kekeable_users(&db).unwrap().is_empty());
+        db.execute(
+            "INSERT INTO users (id, keke, blck) VALUES (3, TRUE, FALSE), (1, TRUE, FALSE), (2, FALSE, FALSE), (4, NULL, TRUE)",
+            (),
+        )
+        .unwrap();
+        assert_eq!(kekeable_users(&db).unwrap(), vec![1, 3]);
+        assert_eq!(Command::parse("-keke list", "user"), Command::KekeList);
+        assert_eq!(Command::parse("-keke", "user"), Command::Keke);
+        let sink = MockSink::default();
+        assert!(matches!(
+            Command::KekeList
+                .check_permissions(&test_database(), &sink)
+                .await,
+            Command::NotValid(_)
+        ));
+    }
+    #[tokio::test]
+    async fn no_spoilers_in_direct_messages() {
+        assert_eq!(
+            MessageOrigin::from_guild(Some(100)),
+            MessageOrigin::PublicChannel
+        );
+        assert_eq!(
+            MessageOrigin::from_guild(None),
+            MessageOrigin::PrivateChannel
+        );
+        assert_eq!(
+            coin_flip_message(&[false], MessageOrigin::PrivateChannel),
+            "The result of the coin flip was... tails!"
+        );
+        assert_eq!(
+            coin_flip_message(&[true, false], MessageOrigin::PrivateChannel),
+            "I flipped 2 coins... heads, tails\nHeads: 1, Tails: 1"
+        );
+        assert_eq!(
+            random_int_message(1, 6, 4, MessageOrigin::PublicChannel),
+            "Between 1 and 6, I choose... ||4!||"
+        );
+        assert_eq!(
+            random_int_message(1, 6, 4, MessageOrigin::PrivateChannel),
+            "Between 1 and 6, I choose... 4!"
+        );
+        let sink = MockSink {
+            outside_guild: true,
+            ..Default::default()
+        };
+        Command::RandomInt(3, 3)
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(sink.sent(), vec!["Between 3 and 3, I choose... 3!"]);
+    }
+    #[test]
+    fn dont_ask_to_ask_preamble() {
+        assert_eq!(dont_ask_to_ask_message(None), "https://dontasktoask.com/");
+        assert_eq!(
+            dont_ask_to_ask_message(Some(DEFAULT_DA2A_PREAMBLE)),
+            "Please read this before asking: https://dontasktoask.com/"
+        );
+        assert_eq!(
+            Command::parse("-da2a preamble Read this first!", "user"),
+            Command::DontAskToAskPreamble(Some("Read this first!".to_owned()))
+        );
+        assert_eq!(
+            Command::parse("-da2a preamble on", "user"),
+            Command::DontAskToAskPreamble(Some(DEFAULT_DA2A_PREAMBLE.to_owned()))
+        );
+        assert_eq!(
+            Command::parse("-da2a preamble OFF", "user"),
+            Command::DontAskToAskPreamble(None)
+        );
+        assert_eq!(
+            Command::parse("-da2a please", "user"),
+            Command::DontAskToAsk
+        );
+        assert!(Command::DontAskToAskPreamble(None).mod_only());
+    }
+    #[test]
+    fn rule_storage() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert!(rules(&db, 100).unwrap().is_empty());
+        assert!(!set_rule(&db, 100, 2, "No spam").unwrap());
+        assert!(!set_rule(&db, 100, 1, "Be nice").unwrap());
+        assert!(set_rule(&db, 100, 2, "No spamming").unwrap());
+        set_rule(&db, 200, 1, "Elsewhere").unwrap();
+        assert_eq!(
+            rules(&db, 100).unwrap(),
+            vec![(1, "Be nice".to_owned()), (2, "No spamming".to_owned())]
+        );
+        assert_eq!(rule(&db, 100, 2).unwrap(), Some("No spamming".to_owned()));
+        assert_eq!(rule(&db, 100, 3).unwrap(), None);
+        assert!(remove_rule(&db, 100, 2).unwrap());
+        assert!(!remove_rule(&db, 100, 2).unwrap());
+        assert_eq!(rule(&db, 100, 2).unwrap(), None);
+        assert_eq!(format_rule(1, "Be nice"), "**1.** Be nice");
+    }
+    #[test]
+    fn rules_parsing() {
+        assert_eq!(Command::parse("-rules", "user"), Command::Rules(None));
+        assert_eq!(Command::parse("-rules 5", "user"), Command::Rules(Some(5)));
+        assert_eq!(Command::parse("-rule #5", "user"), Command::Rules(Some(5)));
+        assert!(matches!(
+            Command::parse("-rules 0", "user"),
+            Command::NotValid(_)
+        ));
+        assert_eq!(
+            Command::parse("-rules set 5 No spoilers outside #spoilers", "user"),
+            Command::RuleEdit(RuleEdit::Set {
+                number: 5,
+                text: "No spoilers outside #spoilers".to_owned()
+            })
+        );
+        assert!(matches!(
+            Command::parse("-rules set 5", "user"),
+            Command::NotValid(_)
+        ));
+        assert_eq!(
+            Command::parse("-rules remove 5", "user"),
+            Command::RuleEdit(RuleEdit::Remove(5))
+        );
+        assert!(Command::RuleEdit(RuleEdit::Remove(5)).mod_only());
+        assert!(!Command::Rules(None).mod_only());
+    }
+    #[test]
+    fn notice_rule_references() {
+        let args = |text: &str| text.split(' ').map(ToOwned::to_owned).collect::<Vec<_>>();
+        assert_eq!(rule_reference(&args("rule: 5")), Some(Ok(5)));
+        assert_eq!(rule_reference(&args("RULE:#5")), Some(Ok(5)));
+        assert_eq!(rule_reference(&args("rule: five")), Some(Err(())));
+        assert_eq!(rule_reference(&args("rule:")), Some(Err(())));
+        assert_eq!(rule_reference(&args("rules are important")), None);
+        assert_eq!(rule_reference(&[]), None);
+        assert_eq!(
+            Command::parse("-notice rule: 5", "user"),
+            Command::NoticeRule(5)
+        );
+        assert_eq!(
+            Command::parse("-notice read the rules", "user"),
+            Command::Notice("read the rules".to_owned())
+        );
+        assert!(Command::NoticeRule(5).mod_only());
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        set_rule(&db, 100, 5, "No spoilers").unwrap();
+        assert_eq!(
+            format_rule_reminder(5, "No spoilers"),
+            "Reminder: Rule 5 — No spoilers"
+        );
+        assert_eq!(
+            rule_reminder(&db, 100, 5).unwrap(),
+            Some("Reminder: Rule 5 — No spoilers".to_owned())
+        );
+        assert_eq!(rule_reminder(&db, 100, 6).unwrap(), None);
+    }
+    #[test]
+    fn report_parsing() {
+        let link = MessageLink {
+            guild_id: Some(100),
+            channel_id: 60,
+            message_id: 70,
+        };
+        assert_eq!(link.to_string(), "https://discord.com/channels/100/60/70");
+        assert_eq!(
+            Command::parse(
+                "-report https://discord.com/channels/100/60/70 being rude",
+                "user"
+            ),
+            Command::Report {
+                message_link: link,
+                reason: "being rude".to_owned()
+            }
+        );
+        assert!(matches!(
+            Command::parse("-report https://discord.com/channels/100/60/70", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-report somewhere being rude", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(!Command::parse(
+            "-report https://discord.com/channels/100/60/70 rude",
+            "user"
+        )
+        .mod_only());
+    }
+    #[tokio::test]
+    async fn report_checks_the_real_server() {
+        let sink = MockSink {
+            foreign_channels: vec![60],
+            ..Default::default()
+        };
+        Command::parse(
+            "-report https://discord.com/channels/100/60/70 being rude",
+            "user",
+        )
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            *sink.replies.lock().unwrap(),
+            vec![(
+                500,
+                "You can only report messages from this server!".to_owned()
+            )]
+        );
+        assert!(sink.channel_embeds.lock().unwrap().is_empty());
+    }
+    #[test]
+    fn report_embed_format() {
+        let link = MessageLink {
+            guild_id: Some(100),
+            channel_id: 60,
+            message_id: 70,
+        };
+        let message = ReportedMessage {
+            author: 7,
+            content: String::new(),
+        };
+        assert_eq!(
+            report_embed(5, &link, &message, "spoilers"),
+            Embed {
+                title: "Message reported".to_owned(),
+                description: "<@5> reported a message by <@7> in <#60>".to_owned(),
+                fields: vec![
+                    ("Content".to_owned(), "*No text*".to_owned()),
+                    ("Reason".to_owned(), "spoilers".to_owned()),
+                    (
+                        "Message".to_owned(),
+                        "[Jump to message](https://discord.com/channels/100/60/70)".to_owned()
+                    ),
+                ],
+            }
+        );
+    }
+    #[test]
+    fn reports_become_casefile_items() {
+        let link = MessageLink {
+            guild_id: Some(100),
+            channel_id: 60,
+            message_id: 70,
+        };
+        let message = ReportedMessage {
+            author: 7,
+            content: "baba\nis rude".to_owned(),
+        };
+        let item = evidence_item(5, &link, &message, "spam");
+        assert_eq!(
+            item,
+            "Reported by <@5> for \"spam\": \"baba / is rude\" (https://discord.com/channels/100/60/70)"
+        );
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert_eq!(open_case_for(&db, 7).unwrap(), None);
+        let first = file_report(&db, 5, &link, &message, "spam").unwrap();
+        assert_eq!(
+            first,
+            ReportCase {
+                id: 0,
+                created: true
+            }
+        );
+        let second = file_report(&db, 6, &link, &message, "still spam").unwrap();
+        assert_eq!(
+            second,
+            ReportCase {
+                id: 0,
+                created: false
+            }
+        );
+        let file = CaseFile::from_id(&db, 0).unwrap();
+        assert_eq!(file.name, "Reports against <@7>");
+        assert_eq!(file.users, vec![7]);
+        assert_eq!(file.items.len(), 2);
+        assert_eq!(file.items[0], item);
+        assert_eq!(
+            second.field(),
+            ("Casefile".to_owned(), "Added to Casefile #0".to_owned())
+        );
+        CaseFileAction::Resolve {
+            id: 0,
+            resolved: true,
+        }
+        .perform(&db, 1)
+        .unwrap();
+        assert_eq!(
+            file_report(&db, 5, &link, &message, "again").unwrap(),
+            ReportCase {
+                id: 1,
+                created: true
+            }
+        );
+    }
+    #[test]
+    fn note_storage() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert!(notes(&db, 100, 5).unwrap().is_empty());
+        add_note(&db, 100, 5, 1, "argues in #general", 20).unwrap();
+        add_note(&db, 100, 5, 2, "was nicer today", 30).unwrap();
+        add_note(&db, 100, 6, 1, "someone else", 25).unwrap();
+        add_note(&db, 200, 5, 1, "another server", 25).unwrap();
+        let listed = notes(&db, 100, 5).unwrap();
+        assert_eq!(
+            listed,
+            vec![
+                Note {
+                    moderator: 1,
+                    text: "argues in #general".to_owned(),
+                    time: 20
+                },
+                Note {
+                    moderator: 2,
+                    text: "was nicer today".to_owned(),
+                    time: 30
+                },
+            ]
+        );
+        assert_eq!(
+            note_lines(&listed),
+            "`1.` <t:20:f> by <@1>: argues in #general\n`2.` <t:30:f> by <@2>: was nicer today"
+        );
+        assert_eq!(remove_note(&db, 100, 5, 0).unwrap(), None);
+        assert_eq!(remove_note(&db, 100, 5, 3).unwrap(), None);
+        assert_eq!(
+            remove_note(&db, 100, 5, 1).unwrap().map(|note| note.text),
+            Some("argues in #general".to_owned())
+        );
+        assert_eq!(notes(&db, 100, 5).unwrap().len(), 1);
+        assert_eq!(notes(&db, 100, 6).unwrap().len(), 1);
+    }
+    #[test]
+    fn note_parsing() {
+        assert_eq!(
+            Command::parse("-note add <@5> argues a lot", "user"),
+            Command::Note(NoteAction::Add {
+                user: UserId(5),
+                text: "argues a lot".to_owned()
+            })
+        );
+        assert_eq!(
+            Command::parse("-notes list 5", "user"),
+            Command::Note(NoteAction::List(UserId(5)))
+        );
+        assert_eq!(
+            Command::parse("-note remove 5 2", "user"),
+            Command::Note(NoteAction::Remove {
+                user: UserId(5),
+                index: 2
+            })
+        );
+        assert!(matches!(
+            Command::parse("-note remove 5 0", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-note add 5", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(Command::Note(NoteAction::List(UserId(5))).mod_only());
+        assert_eq!(Command::parse("-userinfo", "user"), Command::UserInfo(None));
+    }
+    #[test]
+    fn userinfo_shows_notes_to_mods() {
+        let mut info = UserInfo {
+            user_id: 5,
+            name: "Baba".to_owned(),
+            notes: None,
+            moderation: None,
+        };
+        let embed = info.to_embed();
+        assert_eq!(embed.title, "Baba");
+        assert_eq!(embed.fields.len(), 2);
+        assert_eq!(embed.fields[1].1, "<t:1420070400:R>");
+        info.notes = Some(vec![Note {
+            moderator: 1,
+            text: "was nice".to_owned(),
+            time: 20,
+        }]);
+        assert_eq!(
+            info.to_embed().fields[2],
+            ("Notes".to_owned(), "• was nice (<@1>)".to_owned())
+        );
+        info.notes = Some(Vec::new());
+        assert_eq!(info.to_embed().fields[2].1, "None");
+    }
+    #[test]
+    fn userinfo_moderation_summary() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        add_warning(&db, 100, 5, 1, "spam", 10).unwrap();
+        add_warning(&db, 100, 5, 1, "more spam", 20).unwrap();
+        add_note(&db, 100, 5, 1, "keeps spamming", 20).unwrap();
+        let summary = ModerationSummary::new(
+            Some(500),
+            100,
+            active_warning_count(&db, 100, 5, 100).unwrap(),
+            notes(&db, 100, 5).unwrap().len(),
+        );
+        assert_eq!(
+            summary,
+            ModerationSummary {
+                timed_out_until: Some(500),
+                active_warnings: 2,
+                notes: 1
+            }
+        );
+        assert_eq!(
+            summary.field_value(),
+            "Timed out until <t:500:f>\n2 active warnings\n1 note"
+        );
+        assert_eq!(
+            ModerationSummary::new(Some(50), 100, 0, 0).field_value(),
+            "Not timed out\n0 active warnings\n0 notes"
+        );
+        let info = UserInfo {
+            user_id: 5,
+            name: "Baba".to_owned(),
+            notes: Some(Vec::new()),
+            moderation: Some(summary),
+        };
+        let fields = info.to_embed().fields;
+        assert_eq!(fields[2].0, "Moderation");
+        assert_eq!(fields[3].0, "Notes");
+    }
+    #[test]
+    fn history_timeline_merges_sources() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        add_warning(&db, 100, 5, 1, "spam", 30).unwrap();
+        add_note(&db, 100, 5, 2, "was warned before", 10).unwrap();
+        let mute = LoggedAction {
+            user: 5,
+            moderator: 1,
+            action: ModAction::Mute(Time::from_str("1h").unwrap()),
+            reason: "more spam".to_owned(),
+            time: 40,
+        };
+        record_mod_action(&db, 100, &mute).unwrap();
+        record_mod_action(
+            &db,
+            100,
+            &LoggedAction {
+                action: ModAction::Ban,
+                reason: "even more spam".to_owned(),
+                time: 30,
+                ..mute.clone()
+            },
+        )
+        .unwrap();
+        record_mod_action(&db, 200, &mute).unwrap();
+        let actions = user_mod_actions(&db, 100, 5).unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[1], mute);
+        let entries = timeline(
+            &warnings(&db, 100, 5).unwrap(),
+            &notes(&db, 100, 5).unwrap(),
+            &actions,
+        );
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.time, entry.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, HistoryKind::Note),
+                (30, HistoryKind::Warning),
+                (30, HistoryKind::Action(ModAction::Ban)),
+                (40, HistoryKind::Action(mute.action)),
+            ]
+        );
+        assert_eq!(
+            entries[3].to_string(),
+            "<t:40:d> **Mute (1h)** by <@1>: more spam"
+        );
+        let embed = history_embed("Baba", &entries);
+        assert_eq!(embed.title, "Moderation history of Baba");
+        assert!(embed.description.starts_with("<t:10:d> **Note** by <@2>"));
+        assert_eq!(history_embed("Baba", &[]).description, "Nothing on record.");
+    }
+    #[test]
+    fn history_embed_keeps_the_newest() {
+        let entries = (0..HISTORY_LIMIT as i64 + 2)
+            .map(|time| HistoryEntry {
+                time,
+                kind: HistoryKind::Warning,
+                moderator: 1,
+                text: "spam".to_owned(),
+            })
+            .collect::<Vec<_>>();
+        let description = history_embed("Baba", &entries).description;
+        let lines = description.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), HISTORY_LIMIT + 1);
+        assert_eq!(lines[0], "*…and 2 older entries*");
+        assert!(lines[1].starts_with("<t:2:d>"));
+        assert!(Command::parse("-history <@5>", "user").mod_only());
+    }
+    #[test]
+    fn mod_action_log_entries() {
+        let entry = LoggedAction {
+            user: 5,
+            moderator: 1,
+            action: ModAction::Mute(Time::from_str("90m").unwrap()),
+            reason: "spam".to_owned(),
+            time: 1_700_000_000,
+        };
+        let embed = entry.to_embed();
+        assert_eq!(embed.title, "Mute (1h 30m)");
+        assert_eq!(
+            embed.fields,
+            vec![
+                ("Target".to_owned(), "<@5> (`5`)".to_owned()),
+                ("Moderator".to_owned(), "<@1>".to_owned()),
+                ("Reason".to_owned(), "spam".to_owned()),
+                ("Time".to_owned(), "<t:1700000000:f>".to_owned()),
+            ]
+        );
+        let warning = LoggedAction {
+            action: ModAction::Warn,
+            reason: " ".to_owned(),
+            ..entry
+        };
+        let embed = warning.to_embed();
+        assert_eq!(embed.title, "Warning");
+        assert_eq!(embed.fields[2].1, "*No reason given*");
+        assert_eq!(
+            ModAction::from_stored(ModAction::Warn.name(), None),
+            Some(ModAction::Warn)
+        );
+        assert_eq!(ModAction::from_stored("mute", None), None);
+        assert!(timeline(&[], &[], &[warning]).is_empty());
+    }
+    #[test]
+    fn mod_log_search() {
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        let action = |user, action, time| LoggedAction {
+            user,
+            moderator: 1,
+            action,
+            reason: "spam".to_owned(),
+            time,
+        };
+        record_mod_action(&db, 100, &action(5, ModAction::Warn, 10)).unwrap();
+        record_mod_action(&db, 100, &action(6, ModAction::Warn, 20)).unwrap();
+        record_mod_action(&db, 100, &action(5, ModAction::Ban, 30)).unwrap();
+        record_mod_action(&db, 200, &action(5, ModAction::Ban, 40)).unwrap();
+        assert_eq!(
+            user_mod_actions(&db, 100, 5).unwrap(),
+            vec![
+                action(5, ModAction::Warn, 10),
+                action(5, ModAction::Ban, 30)
+            ]
+        );
+        assert_eq!(
+            recent_mod_actions(&db, 100, 2).unwrap(),
+            vec![
+                action(5, ModAction::Ban, 30),
+                action(6, ModAction::Warn, 20)
+            ]
+        );
+        assert_eq!(recent_mod_actions(&db, 100, 10).unwrap().len(), 3);
+        assert!(recent_mod_actions(&db, 300, 10).unwrap().is_empty());
+        assert_eq!(
+            action(5, ModAction::Ban, 30).to_string(),
+            "<t:30:f> **Ban** <@5> by <@1>: spam"
+        );
+    }
+    #[test]
+    fn parse_mod_log_search() {
+        assert_eq!(
+            Command::parse("-modlog <@5>", "user"),
+            Command::ModLogSearch(ModLogQuery::User(UserId(5)))
+        );
+        assert_eq!(
+            Command::parse("-modlog recent 10", "user"),
+            Command::ModLogSearch(ModLogQuery::Recent(10))
+        );
+        assert!(matches!(
+            Command::parse("-modlog recent 0", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-modlog recent", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(Command::parse("-modlog 5", "user").mod_only());
+    }
+    #[test]
+    fn ban_reason_from_casefile() {
+        assert_eq!(case_reference("case:3"), Some((3, "")));
+        assert_eq!(
+            case_reference("Case:#12 and spam in dms"),
+            Some((12, "and spam in dms"))
+        );
+        assert_eq!(case_reference("spam case:3"), None);
+        assert_eq!(case_reference("case:three"), None);
+        assert_eq!(case_reference("cases:3"), None);
+        let case = CaseFile {
+            name: "Raid on #general".to_owned(),
+            resolved: false,
+            items: Vec::new(),
+            assignee: None,
+            severity: Severity::High,
+            tags: Vec::new(),
+            users: vec![5],
+        };
+        assert_eq!(case.ban_reason(3, ""), "Casefile #3: Raid on #general");
+        assert_eq!(
+            case.ban_reason(3, "and spam in dms"),
+            "Casefile #3: Raid on #general — and spam in dms"
+        );
+        let db = Database::open_in_memory().unwrap();
+        initialize_database(&db).unwrap();
+        assert!(CaseFile::from_id(&db, 3).is_err());
+        assert_eq!(
+            Command::parse("-ban <@5> case:3", "user"),
+            Command::Ban(UserId(5), "case:3".to_owned())
+        );
+    }
+    #[test]
+    fn parse_massban() {
+        assert_eq!(
+            Command::parse("-massban <@5> 6 <@!7> 5 reason: raid in #general", "user"),
+            Command::MassBan {
+                users: vec![UserId(5), UserId(6), UserId(7)],
+                reason: "raid in #general".to_owned()
+            }
+        );
+        assert_eq!(
+            Command::parse("-massban 5 6", "user"),
+            Command::MassBan {
+                users: vec![UserId(5), UserId(6)],
+                reason: String::new()
+            }
+        );
+        assert_eq!(
+            Command::parse("-massban 5 Reason:raid", "user"),
+            Command::MassBan {
+                users: vec![UserId(5)],
+                reason: "raid".to_owned()
+            }
+        );
+        assert!(matches!(
+            Command::parse("-massban 5 raid", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(matches!(
+            Command::parse("-massban reason: raid", "user"),
+            Command::NotValid(_)
+        ));
+        let too_many = (1..=51).map(|id| id.to_string()).collect::<Vec<_>>();
+        assert!(matches!(
+            Command::parse(
+                &format!("-massban {} reason: raid", too_many.join(" ")),
+                "user"
+            ),
+            Command::NotValid(_)
+        ));
+        assert!(Command::parse("-massban 5 reason: raid", "user").mod_only());
+    }
+    #[tokio::test]
+    async fn massban_reports_each_user() {
+        assert_eq!(skip_reason(1, 1, 2), Some("you can't ban yourself"));
+        assert_eq!(skip_reason(2, 1, 2), Some("I can't ban myself"));
+        assert_eq!(skip_reason(5, 1, 2), None);
+        let mut report = MassBanReport::default();
+        report.record(5, MassBanOutcome::Banned);
+        report.record(6, MassBanOutcome::Failed("Missing Permissions".to_owned()));
+        report.record(
+            1,
+            MassBanOutcome::Skipped("you can't ban yourself".to_owned()),
+        );
+        assert_eq!(report.banned(), 1);
+        assert_eq!(
+            report.to_string(),
+            "Banned 1 of 3 users.\n✅ <@5>\n❌ <@6>: Missing Permissions\n⏭️ <@1>: you can't ban yourself"
+        );
+        let sink = MockSink::default();
+        Command::MassBan {
+            users: vec![UserId(5), UserId(1), UserId(2), UserId(6)],
+            reason: "raid".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            *sink.bans.lock().unwrap(),
+            vec![(5, "raid".to_owned()), (6, "raid".to_owned())]
+        );
+        assert_eq!(
+            sink.pages.lock().unwrap()[0].join(""),
+            "Banned 2 of 4 users.\n✅ <@5>\n⏭️ <@1>: you can't ban yourself\n⏭️ <@2>: I can't ban myself\n✅ <@6>"
+        );
+        assert!(sink.direct_messages.lock().unwrap().is_empty());
+    }
+    #[test]
+    fn parse_softban() {
+        assert_eq!(
+            Command::parse("-softban <@5> spam links", "user"),
+            Command::Softban(UserId(5), "spam links".to_owned())
+        );
+        assert!(matches!(
+            Command::parse("-softban", "user"),
+            Command::NotValid(_)
+        ));
+        assert!(Command::parse("-softban 5 spam", "user").mod_only());
+    }
+    #[tokio::test]
+    async fn softban_bans_then_unbans() {
+        let sink = MockSink::default();
+        Command::Softban(UserId(5), "spam links".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(
+            *sink.ban_sequence.lock().unwrap(),
+            vec![("ban", 5), ("unban", 5)]
+        );
+        assert_eq!(
+            *sink.bans.lock().unwrap(),
+            vec![(5, "spam links".to_owned())]
+        );
+        let direct_messages = sink.direct_messages.lock().unwrap();
+        assert_eq!(direct_messages.len(), 1);
+        assert!(direct_messages[0].1.contains("not a permanent ban"));
+        assert_eq!(
+            sink.sent(),
+            vec!["Softbanned user5 and cleared their recent messages for the following reason: \n>spam links"]
+        );
+    }
+    #[tokio::test]
+    async fn softban_reports_a_failed_unban() {
+        let sink = MockSink {
+            fail_unban: true,
+            ..Default::default()
+        };
+        Command::Softban(UserId(5), "spam links".to_owned())
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(*sink.ban_sequence.lock().unwrap(), vec![("ban", 5)]);
+        assert_eq!(
+            sink.sent(),
+            vec!["Banned user5, but I couldn't unban them afterwards! They're still banned, so please unban them manually."]
+        );
+    }
+    #[tokio::test]
+    async fn userinfo_shows_typing() {
+        let sink = MockSink::default();
+        Command::UserInfo(Some(UserId(5)))
+            .execute_command(&sink)
+            .await
+            .unwrap();
+        assert_eq!(*sink.typing.lock().unwrap(), 1);
+        let embeds = sink.embeds.lock().unwrap();
+        assert_eq!(embeds.len(), 1);
+        assert_eq!(embeds[0].title, "user5");
+        assert_eq!(embeds[0].fields.len(), 2);
+    }
+    #[test]
+    fn keke_opt_in_lookup() {
+        let db = test_database();
+        db.execute(
+            "INSERT INTO users (id, keke, blck) VALUES (1, TRUE, FALSE), (2, FALSE, FALSE), (4, NULL, TRUE)",
+            (),
+        )
+        .unwrap();
+        assert!(opted_in(&db, 1).unwrap());
+        assert!(!opted_in(&db, 2).unwrap());
+        assert!(!opted_in(&db, 4).unwrap());
+        assert!(!opted_in(&db, 9).unwrap());
+    }
+    #[test]
+    fn keke_opt_in_storage() {
+        let db = test_database();
+        db.execute("INSERT INTO users (id, blck) VALUES (2, TRUE)", ())
+            .unwrap();
+        set_opted_in(&db, 3, true).unwrap();
+        set_opted_in(&db, 2, true).unwrap();
+        set_opted_in(&db, 1, true).unwrap();
+        assert_eq!(kekeable_users(&db).unwrap(), vec![1, 2, 3]);
+        set_opted_in(&db, 1, false).unwrap();
+        assert!(!opted_in(&db, 1).unwrap());
+        assert!(opted_in(&db, 2).unwrap());
+        assert_eq!(kekeable_users(&db).unwrap(), vec![2, 3]);
+        let blacklisted = db
+            .query_row("SELECT blck FROM users WHERE id = 2", (), |row| {
+                row.get::<_, bool>(0)
+            })
+            .unwrap();
+        assert!(blacklisted);
+    }
+    #[tokio::test]
+    async fn userinfo_timeout_of_departed_users() {
+        assert_eq!(current_timeout(&MockSink::default(), 5).await, None);
+        let sink = MockSink {
+            departed: true,
+            ..Default::default()
+        };
+        assert!(sink.timed_out_until(5).await.is_err());
+        assert_eq!(current_timeout(&sink, 5).await, None);
+    }
+    #[tokio::test]
+    async fn massban_checks_bot_permissions() {
+        let sink = MockSink {
+            target_position: Some(10),
+            ..Default::default()
+        };
+        Command::MassBan {
+            users: vec![UserId(5), UserId(6)],
+            reason: "raid".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert!(sink.bans.lock().unwrap().is_empty());
+        assert_eq!(
+            sink.pages.lock().unwrap()[0].join(""),
+            "Banned 0 of 2 users.\n⏭️ <@5>: I can't do that to them, since their highest role isn't below mine!\n⏭️ <@6>: I can't do that to them, since their highest role isn't below mine!"
+        );
+        let sink = MockSink {
+            permissions: Some(Permissions::MODERATE_MEMBERS),
+            ..Default::default()
+        };
+        Command::MassBan {
+            users: vec![UserId(5)],
+            reason: "raid".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert!(sink.bans.lock().unwrap().is_empty());
+        assert_eq!(
+            sink.pages.lock().unwrap()[0].join(""),
+            "Banned 0 of 1 users.\n⏭️ <@5>: I need the `Ban Members` permission to do that!"
+        );
+    }
+    #[test]
+    fn relaying_skips_bot_messages() {
+        assert!(should_relay(MessageOrigin::PrivateChannel, false));
+        assert!(!should_relay(MessageOrigin::PrivateChannel, true));
+        assert!(!should_relay(MessageOrigin::PublicChannel, false));
+        assert!(!should_relay(MessageOrigin::PublicChannel, true));
+    }
+    #[tokio::test]
+    async fn self_role_allow_refuses_privileged_roles() {
+        let sink = MockSink {
+            role_permissions: Some(Permissions::ADMINISTRATOR),
+            ..Default::default()
+        };
+        Command::SelfRoleAllow {
+            allowed: true,
+            role: "keke".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            sink.sent(),
+            vec!["`Keke` can't be made self-assignable, since it grants moderation or management permissions."]
+        );
+    }
+    #[tokio::test]
+    async fn reaction_role_refuses_privileged_roles() {
+        let sink = MockSink {
+            role_permissions: Some(Permissions::MANAGE_GUILD),
+            ..Default::default()
+        };
+        Command::ReactionRole {
+            link: MessageLink {
+                guild_id: Some(100),
+                channel_id: 2,
+                message_id: 3,
+            },
+            emoji: "⭐".to_owned(),
+            role: "<@&20>".to_owned(),
+        }
+        .execute_command(&sink)
+        .await
+        .unwrap();
+        assert_eq!(
+            sink.sent(),
+            vec!["`Keke` can't be given by reactions, since it grants moderation or management permissions."]
+        );
+    }
 }
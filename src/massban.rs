@@ -0,0 +1,70 @@
+//! Deals with `-massban`: banning many users at once,
+//! such as the accounts in a raid.
+
+use std::fmt::Display;
+
+/// The most users one `-massban` can ban.
+pub const MAX_MASSBAN: usize = 50;
+
+/// Returns why a user can't be mass-banned by the command's author, if they can't.
+pub fn skip_reason(target: u64, author: u64, bot: u64) -> Option<&'static str> {
+    if target == author {
+        Some("you can't ban yourself")
+    } else if target == bot {
+        Some("I can't ban myself")
+    } else {
+        None
+    }
+}
+
+/// What happened when banning one of the users in a `-massban`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MassBanOutcome {
+    /// The user was banned
+    Banned,
+    /// The user wasn't allowed to be banned
+    Skipped(String),
+    /// Discord refused to ban the user
+    Failed(String),
+}
+
+/// The result of a `-massban`, user by user.
+/// Its [`Display`] implementation is the message sent back to the channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MassBanReport {
+    /// Each user the command named, in order, with what happened to them
+    pub results: Vec<(u64, MassBanOutcome)>,
+}
+
+impl MassBanReport {
+    /// Records what happened to a user.
+    pub fn record(&mut self, user: u64, outcome: MassBanOutcome) {
+        self.results.push((user, outcome));
+    }
+    /// Counts the users that were banned.
+    pub fn banned(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, outcome)| *outcome == MassBanOutcome::Banned)
+            .count()
+    }
+}
+
+impl Display for MassBanReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Banned {} of {} users.",
+            self.banned(),
+            self.results.len()
+        )?;
+        for (user, outcome) in &self.results {
+            match outcome {
+                MassBanOutcome::Banned => write!(f, "\n✅ <@{user}>")?,
+                MassBanOutcome::Skipped(reason) => write!(f, "\n⏭️ <@{user}>: {reason}")?,
+                MassBanOutcome::Failed(error) => write!(f, "\n❌ <@{user}>: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+//! Deals with members joining and leaving a guild,
+//! such as posting welcome and goodbye messages and giving new members a role.
+
+use crate::{
+    database::{query_database, Database},
+    settings::{clear_guild_setting, guild_setting, set_guild_setting},
+};
+use eyre::Result;
+use serenity::{
+    http::Http,
+    model::prelude::{ChannelId, GuildId, Member, User},
+    prelude::Context,
+};
+use std::fmt::Display;
+
+/// The setting holding the channel welcome messages are posted in.
+pub const WELCOME_CHANNEL: &str = "welcome_channel";
+/// The setting holding the welcome message template.
+pub const WELCOME_MESSAGE: &str = "welcome_message";
+/// The setting holding the rules sent to new members, if any.
+pub const WELCOME_RULES: &str = "welcome_rules";
+/// The welcome message used if none was set.
+pub const DEFAULT_WELCOME: &str = "Welcome to {server}, {user}! You're member #{count}.";
+/// The setting holding the channel goodbye messages are posted in.
+pub const GOODBYE_CHANNEL: &str = "goodbye_channel";
+/// The setting holding the goodbye message template.
+pub const GOODBYE_MESSAGE: &str = "goodbye_message";
+/// The setting holding the role given to new members.
+pub const AUTOROLE: &str = "autorole";
+/// The goodbye message used if none was set.
+pub const DEFAULT_GOODBYE: &str = "{name} has left {server}. We're down to {count} members.";
+
+/// The most members `-userid` lists for a single search.
+pub const MAX_MEMBER_MATCHES: usize = 15;
+
+/// The names a member goes by, for finding them from what a user typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberName {
+    /// The member's user ID
+    pub user_id: u64,
+    /// The member's username
+    pub name: String,
+    /// The member's nickname in the guild, if any
+    pub nickname: Option<String>,
+}
+
+impl Display for MemberName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.nickname {
+            Some(nickname) => write!(f, "{} ({nickname}): `{}`", self.name, self.user_id),
+            None => write!(f, "{}: `{}`", self.name, self.user_id),
+        }
+    }
+}
+
+/// Finds the members whose username or nickname contains a query, ignoring case.
+/// Members whose names match the query exactly come first.
+pub fn match_members<'a>(members: &'a [MemberName], query: &str) -> Vec<&'a MemberName> {
+    let query = query.trim().to_lowercase();
+    let names = |member: &MemberName| {
+        std::iter::once(member.name.to_lowercase())
+            .chain(member.nickname.as_deref().map(str::to_lowercase))
+            .collect::<Vec<_>>()
+    };
+    let mut matches = members
+        .iter()
+        .filter(|member| names(member).iter().any(|name| name.contains(&query)))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|member| !names(member).contains(&query));
+    matches
+}
+
+/// Gets the names of every member of a guild.
+pub async fn member_names(http: &Http, guild_id: u64) -> Result<Vec<MemberName>> {
+    let mut names = Vec::new();
+    let mut after = None;
+    loop {
+        let members = GuildId(guild_id).members(http, Some(1000), after).await?;
+        let Some(last) = members.last() else {
+            break;
+        };
+        after = Some(last.user.id);
+        names.extend(members.iter().map(|member| MemberName {
+            user_id: member.user.id.0,
+            name: member.user.name.clone(),
+            nickname: member.nick.clone(),
+        }));
+        if members.len() < 1000 {
+            break;
+        }
+    }
+    Ok(names)
+}
+
+/// What a template can know about a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberInfo {
+    /// The member's user ID
+    pub user_id: u64,
+    /// The member's username
+    pub name: String,
+    /// How many members the guild has
+    pub member_count: u64,
+    /// The name of the guild
+    pub server: String,
+}
+
+/// Fills in a message template for a member. The placeholders are:
+/// - `{user}`: a mention of the member
+/// - `{name}`: the member's username
+/// - `{count}`: how many members the guild has
+/// - `{server}`: the name of the guild
+pub fn render_template(template: &str, member: &MemberInfo) -> String {
+    template
+        .replace("{user}", &format!("<@{}>", member.user_id))
+        .replace("{name}", &member.name)
+        .replace("{count}", &member.member_count.to_string())
+        .replace("{server}", &member.server)
+}
+
+/// A change to a guild's welcome settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WelcomeSetting {
+    /// Post welcome messages in this channel
+    Channel(u64),
+    /// Use this welcome message template
+    Message(String),
+    /// DM these rules to new members
+    Rules(String),
+    /// Stop posting welcome messages
+    Disable,
+}
+
+impl WelcomeSetting {
+    /// Saves the setting for a guild.
+    pub fn apply(&self, db: &Database, guild_id: u64) -> Result<()> {
+        match self {
+            WelcomeSetting::Channel(channel_id) => {
+                set_guild_setting(db, guild_id, WELCOME_CHANNEL, &channel_id.to_string())
+            }
+            WelcomeSetting::Message(message) => {
+                set_guild_setting(db, guild_id, WELCOME_MESSAGE, message)
+            }
+            WelcomeSetting::Rules(rules) => set_guild_setting(db, guild_id, WELCOME_RULES, rules),
+            WelcomeSetting::Disable => clear_guild_setting(db, guild_id, WELCOME_CHANNEL),
+        }
+    }
+}
+
+impl Display for WelcomeSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WelcomeSetting::Channel(channel_id) => {
+                write!(f, "New members will now be welcomed in <#{channel_id}>.")
+            }
+            WelcomeSetting::Message(message) => {
+                write!(f, "New members will now be welcomed with:\n> {message}")
+            }
+            WelcomeSetting::Rules(_) => write!(f, "New members will now be sent the rules."),
+            WelcomeSetting::Disable => write!(f, "Welcome messages have been turned off."),
+        }
+    }
+}
+
+/// A guild's welcome settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WelcomeConfig {
+    /// The channel welcome messages are posted in
+    pub channel_id: u64,
+    /// The welcome message template
+    pub message: String,
+    /// The rules DMed to new members, if any
+    pub rules: Option<String>,
+}
+
+impl WelcomeConfig {
+    /// Gets a guild's welcome settings, or [`None`] if it has no welcome channel.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<Option<Self>> {
+        let Some(channel_id) = guild_setting(db, guild_id, WELCOME_CHANNEL)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            channel_id: channel_id.parse()?,
+            message: guild_setting(db, guild_id, WELCOME_MESSAGE)?
+                .unwrap_or_else(|| DEFAULT_WELCOME.to_owned()),
+            rules: guild_setting(db, guild_id, WELCOME_RULES)?,
+        }))
+    }
+}
+
+/// A change to a guild's goodbye settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoodbyeSetting {
+    /// Post goodbye messages in this channel
+    Channel(u64),
+    /// Use this goodbye message template
+    Message(String),
+    /// Stop posting goodbye messages
+    Disable,
+}
+
+impl GoodbyeSetting {
+    /// Saves the setting for a guild.
+    pub fn apply(&self, db: &Database, guild_id: u64) -> Result<()> {
+        match self {
+            GoodbyeSetting::Channel(channel_id) => {
+                set_guild_setting(db, guild_id, GOODBYE_CHANNEL, &channel_id.to_string())
+            }
+            GoodbyeSetting::Message(message) => {
+                set_guild_setting(db, guild_id, GOODBYE_MESSAGE, message)
+            }
+            GoodbyeSetting::Disable => clear_guild_setting(db, guild_id, GOODBYE_CHANNEL),
+        }
+    }
+}
+
+impl Display for GoodbyeSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoodbyeSetting::Channel(channel_id) => {
+                write!(f, "Goodbye messages will now be posted in <#{channel_id}>.")
+            }
+            GoodbyeSetting::Message(message) => {
+                write!(f, "Leaving members will now get:\n> {message}")
+            }
+            GoodbyeSetting::Disable => write!(f, "Goodbye messages have been turned off."),
+        }
+    }
+}
+
+/// A guild's goodbye settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoodbyeConfig {
+    /// The channel goodbye messages are posted in
+    pub channel_id: u64,
+    /// The goodbye message template
+    pub message: String,
+}
+
+impl GoodbyeConfig {
+    /// Gets a guild's goodbye settings, or [`None`] if it has no goodbye channel.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<Option<Self>> {
+        let Some(channel_id) = guild_setting(db, guild_id, GOODBYE_CHANNEL)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            channel_id: channel_id.parse()?,
+            message: guild_setting(db, guild_id, GOODBYE_MESSAGE)?
+                .unwrap_or_else(|| DEFAULT_GOODBYE.to_owned()),
+        }))
+    }
+}
+
+/// Gets what templates can know about a user in a guild from the cache.
+pub fn member_info(ctx: &Context, guild_id: GuildId, user: &User) -> MemberInfo {
+    let guild = ctx.cache.guild(guild_id);
+    MemberInfo {
+        user_id: user.id.0,
+        name: user.name.clone(),
+        member_count: guild
+            .as_ref()
+            .map(|guild| guild.member_count)
+            .unwrap_or_default(),
+        server: guild.map(|guild| guild.name).unwrap_or_default(),
+    }
+}
+
+/// Welcomes a member who just joined, if the guild has a welcome channel.
+pub async fn welcome_member(ctx: &Context, member: &Member) -> Result<()> {
+    let Some(config) = WelcomeConfig::for_guild(&query_database()?, member.guild_id.0)? else {
+        return Ok(());
+    };
+    let info = member_info(ctx, member.guild_id, &member.user);
+    ChannelId(config.channel_id)
+        .say(&ctx.http, render_template(&config.message, &info))
+        .await?;
+    if let Some(rules) = config.rules {
+        member
+            .user
+            .direct_message(&ctx.http, |message| {
+                message.content(render_template(&rules, &info))
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Says goodbye to a user who just left, if the guild has a goodbye channel.
+pub async fn farewell_member(ctx: &Context, guild_id: GuildId, user: &User) -> Result<()> {
+    let Some(config) = GoodbyeConfig::for_guild(&query_database()?, guild_id.0)? else {
+        return Ok(());
+    };
+    let info = member_info(ctx, guild_id, user);
+    ChannelId(config.channel_id)
+        .say(&ctx.http, render_template(&config.message, &info))
+        .await?;
+    Ok(())
+}
+
+/// Whether a new member should be given the autorole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoroleDecision {
+    /// Give the member this role
+    Assign(u64),
+    /// The guild has no autorole
+    NotConfigured,
+    /// The autorole no longer exists in the guild
+    MissingRole(u64),
+    /// The autorole is at or above the bot's highest role, so the bot can't give it
+    RoleTooHigh(u64),
+}
+
+/// Decides whether to give a new member the autorole,
+/// given the guild's roles as `(id, position)` and the position of the bot's highest role.
+pub fn autorole_decision(
+    autorole: Option<u64>,
+    roles: &[(u64, i64)],
+    bot_position: i64,
+) -> AutoroleDecision {
+    let Some(autorole) = autorole else {
+        return AutoroleDecision::NotConfigured;
+    };
+    match roles.iter().find(|(role_id, _)| *role_id == autorole) {
+        None => AutoroleDecision::MissingRole(autorole),
+        Some((_, position)) if *position >= bot_position => AutoroleDecision::RoleTooHigh(autorole),
+        Some(_) => AutoroleDecision::Assign(autorole),
+    }
+}
+
+/// Gives a member who just joined the guild's autorole, if it has one.
+pub async fn assign_autorole(ctx: &Context, member: &mut Member) -> Result<()> {
+    let autorole = guild_setting(&query_database()?, member.guild_id.0, AUTOROLE)?
+        .and_then(|role| role.parse().ok());
+    let (roles, bot_position) = match ctx.cache.guild(member.guild_id) {
+        Some(guild) => {
+            let roles = guild
+                .roles
+                .values()
+                .map(|role| (role.id.0, role.position))
+                .collect::<Vec<_>>();
+            let bot_position = guild
+                .members
+                .get(&ctx.cache.current_user_id())
+                .into_iter()
+                .flat_map(|bot| &bot.roles)
+                .filter_map(|role_id| guild.roles.get(role_id))
+                .map(|role| role.position)
+                .max()
+                .unwrap_or_default();
+            (roles, bot_position)
+        }
+        None => (Vec::new(), 0),
+    };
+    match autorole_decision(autorole, &roles, bot_position) {
+        AutoroleDecision::Assign(role_id) => member.add_role(&ctx.http, role_id).await?,
+        AutoroleDecision::NotConfigured => {}
+        AutoroleDecision::MissingRole(role_id) => eprintln!(
+            "Autorole {role_id} in guild {} no longer exists; set a new one with -autorole",
+            member.guild_id
+        ),
+        AutoroleDecision::RoleTooHigh(role_id) => eprintln!(
+            "Can't give autorole {role_id} in guild {}: it's above the bot's highest role",
+            member.guild_id
+        ),
+    }
+    Ok(())
+}
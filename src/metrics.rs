@@ -0,0 +1,86 @@
+//! Deals with [`Metrics`], Prometheus-style counters for commands and errors.
+//! They're served at `/metrics` by the health-check server when the `metrics` feature is on.
+
+use crate::backend::CommandType;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// The counters for the running bot.
+pub static METRICS: Metrics = Metrics::new();
+
+/// A set of counters tracking what the bot has done.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    commands: Mutex<BTreeMap<&'static str, u64>>,
+    errors: AtomicU64,
+    dm_forwards: AtomicU64,
+}
+
+impl Metrics {
+    /// Creates a new set of counters, all at zero.
+    pub const fn new() -> Self {
+        Self {
+            commands: Mutex::new(BTreeMap::new()),
+            errors: AtomicU64::new(0),
+            dm_forwards: AtomicU64::new(0),
+        }
+    }
+    /// Records that a command was executed.
+    pub fn record_command(&self, command: CommandType) {
+        if let Ok(mut commands) = self.commands.lock() {
+            *commands.entry(command.name()).or_default() += 1;
+        }
+    }
+    /// Records that something failed with an error.
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    /// Records that a direct message was forwarded to the developer.
+    pub fn record_dm_forward(&self) {
+        self.dm_forwards.fetch_add(1, Ordering::Relaxed);
+    }
+    /// Gets how many times a command was executed.
+    pub fn commands_executed(&self, command: CommandType) -> u64 {
+        self.commands
+            .lock()
+            .map(|commands| commands.get(command.name()).copied().unwrap_or_default())
+            .unwrap_or_default()
+    }
+    /// Gets how many errors were recorded.
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+    /// Gets how many direct messages were forwarded.
+    pub fn dm_forwards(&self) -> u64 {
+        self.dm_forwards.load(Ordering::Relaxed)
+    }
+    /// Renders every counter in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut buffer = String::new();
+        buffer.push_str("# HELP bababot_commands_total Commands executed, by command.\n");
+        buffer.push_str("# TYPE bababot_commands_total counter\n");
+        if let Ok(commands) = self.commands.lock() {
+            for (command, count) in commands.iter() {
+                let _ = writeln!(
+                    buffer,
+                    "bababot_commands_total{{command=\"{command}\"}} {count}"
+                );
+            }
+        }
+        buffer.push_str("# HELP bababot_errors_total Errors raised while handling events.\n");
+        buffer.push_str("# TYPE bababot_errors_total counter\n");
+        let _ = writeln!(buffer, "bababot_errors_total {}", self.errors());
+        buffer.push_str(
+            "# HELP bababot_dm_forwards_total Direct messages forwarded to the developer.\n",
+        );
+        buffer.push_str("# TYPE bababot_dm_forwards_total counter\n");
+        let _ = writeln!(buffer, "bababot_dm_forwards_total {}", self.dm_forwards());
+        buffer
+    }
+}
@@ -0,0 +1,241 @@
+//! Deals with the record of moderation actions (bans, mutes, warnings)
+//! taken against members, kept in the `mod_log` table
+//! and posted in the mod log channel.
+
+use crate::{
+    backend::Time,
+    database::{query_database, Database},
+    modlog::{field_value, mod_log_channel},
+    sink::{CommandSink, Embed},
+};
+use eyre::Result;
+use serenity::model::{prelude::UserId, Timestamp};
+use std::fmt::Display;
+
+/// The most entries `-modlog recent` shows at once.
+pub const MAX_RECENT_ACTIONS: usize = 50;
+
+/// Which logged moderation actions `-modlog` looks up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLogQuery {
+    /// Every action taken against a member
+    User(UserId),
+    /// The latest actions taken in the guild
+    Recent(usize),
+}
+
+/// A moderation action taken against a member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModAction {
+    /// The member was banned
+    Ban,
+    /// The member was timed out for a while
+    Mute(Time),
+    /// The member was warned
+    Warn,
+    /// The member was banned and unbanned to clear their messages
+    Softban,
+}
+
+impl ModAction {
+    /// The name the action is stored under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModAction::Ban => "ban",
+            ModAction::Mute(_) => "mute",
+            ModAction::Warn => "warn",
+            ModAction::Softban => "softban",
+        }
+    }
+    /// How long the action lasts in seconds, for actions that end on their own.
+    pub fn duration(&self) -> Option<u64> {
+        match self {
+            ModAction::Ban | ModAction::Warn | ModAction::Softban => None,
+            ModAction::Mute(time) => Some(time.total_seconds()),
+        }
+    }
+    /// Rebuilds an action from its stored name and duration.
+    pub fn from_stored(name: &str, duration: Option<u64>) -> Option<Self> {
+        match (name, duration) {
+            ("ban", _) => Some(ModAction::Ban),
+            ("mute", Some(duration)) => Some(ModAction::Mute(Time::from_seconds(duration))),
+            ("warn", _) => Some(ModAction::Warn),
+            ("softban", _) => Some(ModAction::Softban),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ModAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModAction::Ban => write!(f, "Ban"),
+            ModAction::Mute(time) => write!(f, "Mute ({time})"),
+            ModAction::Warn => write!(f, "Warning"),
+            ModAction::Softban => write!(f, "Softban"),
+        }
+    }
+}
+
+/// A moderation action as it was recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedAction {
+    /// The ID of the member the action was taken against
+    pub user: u64,
+    /// The ID of the mod who took the action
+    pub moderator: u64,
+    /// What was done
+    pub action: ModAction,
+    /// Why it was done
+    pub reason: String,
+    /// When it was done, as a unix timestamp
+    pub time: i64,
+}
+
+impl Display for LoggedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:f> **{}** <@{}> by <@{}>",
+            self.time, self.action, self.user, self.moderator
+        )?;
+        match self.reason.trim() {
+            "" => Ok(()),
+            reason => write!(f, ": {reason}"),
+        }
+    }
+}
+
+impl LoggedAction {
+    /// Builds the embed posted in the mod log channel for the action.
+    pub fn to_embed(&self) -> Embed {
+        let reason = match self.reason.trim() {
+            "" => "*No reason given*",
+            reason => reason,
+        };
+        Embed {
+            title: self.action.to_string(),
+            description: String::new(),
+            fields: vec![
+                (
+                    "Target".to_owned(),
+                    format!("<@{}> (`{}`)", self.user, self.user),
+                ),
+                ("Moderator".to_owned(), format!("<@{}>", self.moderator)),
+                ("Reason".to_owned(), field_value(reason)),
+                ("Time".to_owned(), format!("<t:{}:f>", self.time)),
+            ],
+        }
+    }
+}
+
+/// Records a moderation action taken against a member of a guild.
+pub fn record_mod_action(db: &Database, guild_id: u64, entry: &LoggedAction) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO mod_log (guild, user, moderator, action, duration, reason, time)
+        VALUES ((?1), (?2), (?3), (?4), (?5), (?6), (?7))
+        ",
+        (
+            guild_id,
+            entry.user,
+            entry.moderator,
+            entry.action.name(),
+            entry.action.duration(),
+            &entry.reason,
+            entry.time,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Records a moderation action the command's author just took,
+/// and posts it in the guild's mod log channel if one is set up.
+/// Failures are only printed, since the action itself already happened.
+pub async fn log_mod_action(sink: &impl CommandSink, user: u64, action: ModAction, reason: &str) {
+    let Some(guild_id) = sink.guild_id() else {
+        return;
+    };
+    let entry = LoggedAction {
+        user,
+        moderator: sink.author_id(),
+        action,
+        reason: reason.to_owned(),
+        time: Timestamp::now().unix_timestamp(),
+    };
+    if let Err(e) = post_mod_action(sink, guild_id, &entry).await {
+        eprintln!("Unable to log a mod action: {e}");
+    }
+}
+
+/// Records a moderation action and posts it in the mod log channel, if there is one.
+async fn post_mod_action(
+    sink: &impl CommandSink,
+    guild_id: u64,
+    entry: &LoggedAction,
+) -> Result<()> {
+    let db = query_database()?;
+    record_mod_action(&db, guild_id, entry)?;
+    if let Some(channel_id) = mod_log_channel(&db, guild_id)? {
+        sink.send_embed_to(channel_id, &entry.to_embed()).await?;
+    }
+    Ok(())
+}
+
+/// Gets every moderation action taken against a member of a guild, oldest first.
+pub fn user_mod_actions(db: &Database, guild_id: u64, user_id: u64) -> Result<Vec<LoggedAction>> {
+    logged_actions(
+        db,
+        "
+        SELECT user, moderator, action, duration, reason, time FROM mod_log
+        WHERE guild = (?1) AND user = (?2) ORDER BY time, id
+        ",
+        (guild_id, user_id),
+    )
+}
+
+/// Gets the latest moderation actions taken in a guild, newest first.
+pub fn recent_mod_actions(db: &Database, guild_id: u64, limit: usize) -> Result<Vec<LoggedAction>> {
+    logged_actions(
+        db,
+        "
+        SELECT user, moderator, action, duration, reason, time FROM mod_log
+        WHERE guild = (?1) ORDER BY time DESC, id DESC LIMIT (?2)
+        ",
+        (guild_id, limit),
+    )
+}
+
+/// Runs a query selecting `user, moderator, action, duration, reason, time` from `mod_log`,
+/// skipping rows whose action isn't recognized.
+fn logged_actions(
+    db: &Database,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<LoggedAction>> {
+    let mut statement = db.prepare(query)?;
+    let rows = statement
+        .query_map(params, |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, u64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<u64>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(user, moderator, action, duration, reason, time)| {
+            Some(LoggedAction {
+                user,
+                moderator,
+                action: ModAction::from_stored(&action, duration)?,
+                reason,
+                time,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,168 @@
+//! Deals with the mod log: posting edited and deleted messages
+//! in a channel only mods can see.
+
+use crate::{
+    database::{query_database, Database},
+    settings::{clear_guild_setting, guild_setting, set_guild_setting},
+    sink::{build_embed, Embed},
+};
+use eyre::Result;
+use serenity::{
+    model::prelude::{ChannelId, GuildId, Message, MessageId},
+    prelude::Context,
+};
+use std::fmt::Display;
+
+/// The setting holding the channel the mod log is posted in.
+pub const MOD_LOG_CHANNEL: &str = "mod_log_channel";
+/// The most characters an embed field can hold.
+pub const MAX_FIELD_LENGTH: usize = 1024;
+
+/// A change to where a guild's mod log is posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLogSetting {
+    /// Post the mod log in this channel
+    Channel(u64),
+    /// Stop posting the mod log
+    Disable,
+}
+
+impl ModLogSetting {
+    /// Saves the setting for a guild.
+    pub fn apply(self, db: &Database, guild_id: u64) -> Result<()> {
+        match self {
+            ModLogSetting::Channel(channel_id) => {
+                set_guild_setting(db, guild_id, MOD_LOG_CHANNEL, &channel_id.to_string())
+            }
+            ModLogSetting::Disable => clear_guild_setting(db, guild_id, MOD_LOG_CHANNEL),
+        }
+    }
+}
+
+impl Display for ModLogSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModLogSetting::Channel(channel_id) => {
+                write!(
+                    f,
+                    "Edited and deleted messages will now be logged in <#{channel_id}>."
+                )
+            }
+            ModLogSetting::Disable => write!(f, "The mod log has been turned off."),
+        }
+    }
+}
+
+/// Gets the channel a guild's mod log is posted in, if it's been set up.
+pub fn mod_log_channel(db: &Database, guild_id: u64) -> Result<Option<u64>> {
+    Ok(guild_setting(db, guild_id, MOD_LOG_CHANNEL)?.and_then(|channel| channel.parse().ok()))
+}
+
+/// Returns whether a message should be logged:
+/// messages from bots and messages in the log channel itself aren't.
+pub fn loggable(author_bot: bool, channel_id: u64, log_channel: u64) -> bool {
+    !author_bot && channel_id != log_channel
+}
+
+/// Fits a message's content into an embed field.
+pub fn field_value(content: &str) -> String {
+    if content.is_empty() {
+        return "*No text*".to_owned();
+    }
+    if content.chars().count() <= MAX_FIELD_LENGTH {
+        return content.to_owned();
+    }
+    let mut value = content
+        .chars()
+        .take(MAX_FIELD_LENGTH - 1)
+        .collect::<String>();
+    value.push('…');
+    value
+}
+
+/// Gets the log entry for an edited message.
+pub fn edit_embed(author: u64, channel_id: u64, before: &str, after: &str) -> Embed {
+    Embed {
+        title: "Message edited".to_owned(),
+        description: format!("Message by <@{author}> in <#{channel_id}>"),
+        fields: vec![
+            ("Before".to_owned(), field_value(before)),
+            ("After".to_owned(), field_value(after)),
+        ],
+    }
+}
+
+/// Gets the log entry for a deleted message.
+pub fn deletion_embed(author: u64, channel_id: u64, content: &str) -> Embed {
+    Embed {
+        title: "Message deleted".to_owned(),
+        description: format!("Message by <@{author}> in <#{channel_id}>"),
+        fields: vec![("Content".to_owned(), field_value(content))],
+    }
+}
+
+/// Posts an entry in a guild's mod log, if it has one.
+/// `make_embed` is only called if the message should be logged.
+async fn post(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    author_bot: bool,
+    make_embed: impl FnOnce() -> Embed,
+) -> Result<()> {
+    let Some(log_channel) = mod_log_channel(&query_database()?, guild_id.0)? else {
+        return Ok(());
+    };
+    if !loggable(author_bot, channel_id.0, log_channel) {
+        return Ok(());
+    }
+    let embed = make_embed();
+    ChannelId(log_channel)
+        .send_message(&ctx.http, |message| {
+            message.embed(|create| build_embed(create, &embed))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Logs a message that was just edited, if serenity's cache had the old version.
+/// Updates that don't change the content, like embeds loading, aren't logged.
+pub async fn log_edit(ctx: &Context, old: Option<&Message>, new: Option<&Message>) -> Result<()> {
+    let (Some(old), Some(new)) = (old, new) else {
+        return Ok(());
+    };
+    let Some(guild_id) = new.guild_id else {
+        return Ok(());
+    };
+    if old.content == new.content {
+        return Ok(());
+    }
+    post(ctx, guild_id, new.channel_id, new.author.bot, || {
+        edit_embed(
+            new.author.id.0,
+            new.channel_id.0,
+            &old.content,
+            &new.content,
+        )
+    })
+    .await
+}
+
+/// Logs a message that was just deleted, if serenity's cache still has it.
+pub async fn log_deletion(
+    ctx: &Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    guild_id: Option<GuildId>,
+) -> Result<()> {
+    let Some(guild_id) = guild_id else {
+        return Ok(());
+    };
+    let Some(message) = ctx.cache.message(channel_id, message_id) else {
+        return Ok(());
+    };
+    post(ctx, guild_id, channel_id, message.author.bot, || {
+        deletion_embed(message.author.id.0, channel_id.0, &message.content)
+    })
+    .await
+}
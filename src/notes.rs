@@ -0,0 +1,124 @@
+//! Deals with notes: private remarks mods keep about members.
+//! Unlike warnings, members are never told about them.
+
+use crate::database::Database;
+use eyre::Result;
+use serenity::model::prelude::UserId;
+
+/// Something to do with the notes kept about a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteAction {
+    /// Adds a note about a member
+    Add {
+        #[doc = "the member the note is about"]
+        user: UserId,
+        #[doc = "what the note says"]
+        text: String,
+    },
+    /// Lists the notes about a member
+    List(UserId),
+    /// Deletes a note about a member
+    Remove {
+        #[doc = "the member the note is about"]
+        user: UserId,
+        #[doc = "the position of the note in the member's list, starting at 1"]
+        index: usize,
+    },
+}
+
+/// A note a mod kept about a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// The ID of the mod who wrote the note
+    pub moderator: u64,
+    /// What the note says
+    pub text: String,
+    /// When the note was written, as a unix timestamp
+    pub time: i64,
+}
+
+/// Adds a note about a member of a guild.
+pub fn add_note(
+    db: &Database,
+    guild_id: u64,
+    user_id: u64,
+    moderator: u64,
+    text: &str,
+    time: i64,
+) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO notes (guild, user, moderator, text, time)
+        VALUES ((?1), (?2), (?3), (?4), (?5))
+        ",
+        (guild_id, user_id, moderator, text, time),
+    )?;
+    Ok(())
+}
+
+/// Gets every note about a member of a guild, oldest first.
+pub fn notes(db: &Database, guild_id: u64, user_id: u64) -> Result<Vec<Note>> {
+    let mut statement = db.prepare(
+        "
+        SELECT moderator, text, time FROM notes
+        WHERE guild = (?1) AND user = (?2) ORDER BY time, id
+        ",
+    )?;
+    let notes = statement
+        .query_map((guild_id, user_id), |row| {
+            Ok(Note {
+                moderator: row.get(0)?,
+                text: row.get(1)?,
+                time: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(notes)
+}
+
+/// Deletes the note at a position (starting at 1) in a member's list,
+/// returning it if it existed.
+pub fn remove_note(
+    db: &Database,
+    guild_id: u64,
+    user_id: u64,
+    index: usize,
+) -> Result<Option<Note>> {
+    let mut statement = db.prepare(
+        "
+        SELECT id, moderator, text, time FROM notes
+        WHERE guild = (?1) AND user = (?2) ORDER BY time, id
+        LIMIT 1 OFFSET (?3)
+        ",
+    )?;
+    let mut rows = statement.query((guild_id, user_id, index.saturating_sub(1)))?;
+    let Some(row) = rows.next()?.filter(|_| index > 0) else {
+        return Ok(None);
+    };
+    let id = row.get::<_, u64>(0)?;
+    let note = Note {
+        moderator: row.get(1)?,
+        text: row.get(2)?,
+        time: row.get(3)?,
+    };
+    db.execute("DELETE FROM notes WHERE id = (?1)", [id])?;
+    Ok(Some(note))
+}
+
+/// Lists notes for a message, numbered from 1.
+pub fn note_lines(notes: &[Note]) -> String {
+    notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| {
+            format!(
+                "`{}.` <t:{}:f> by <@{}>: {}",
+                index + 1,
+                note.time,
+                note.moderator,
+                note.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
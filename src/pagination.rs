@@ -0,0 +1,124 @@
+//! Deals with paginated messages: long outputs split into pages,
+//! which are flipped through by reacting with arrows.
+
+use crate::reactions::ReactionEvent;
+use eyre::Result;
+use serenity::{
+    http::Http,
+    model::{prelude::ChannelId, Timestamp},
+};
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// The paginated messages for the running bot.
+pub static PAGINATORS: Paginators = Paginators::new();
+
+/// The reaction for going back a page.
+pub const PREVIOUS_PAGE: &str = "◀️";
+/// The reaction for going forward a page.
+pub const NEXT_PAGE: &str = "▶️";
+/// How long a paginated message can be flipped through for, in seconds.
+pub const PAGINATOR_TTL_SECONDS: i64 = 10 * 60;
+/// How long a page can get before the rest goes on the next one, in characters.
+/// Discord allows 2000, which leaves room for the page number.
+pub const MAX_PAGE_LENGTH: usize = 1800;
+
+/// Splits text into pages between lines, keeping each within [`MAX_PAGE_LENGTH`].
+/// A single line longer than that gets a page to itself.
+pub fn paginate(text: &str) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut page = String::new();
+    for line in text.lines() {
+        if !page.is_empty() && page.chars().count() + 1 + line.chars().count() > MAX_PAGE_LENGTH {
+            pages.push(std::mem::take(&mut page));
+        }
+        if !page.is_empty() {
+            page.push('\n');
+        }
+        page.push_str(line);
+    }
+    if !page.is_empty() || pages.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+/// Gets the index of the page after turning forward or back,
+/// wrapping around at either end.
+pub fn turn_page(current: usize, pages: usize, forward: bool) -> usize {
+    match (pages, forward) {
+        (0, _) => 0,
+        (_, true) => (current + 1) % pages,
+        (_, false) => (current + pages - 1) % pages,
+    }
+}
+
+/// Gets the content of a page as it's shown, with its page number if there's more than one.
+pub fn page_content(pages: &[String], index: usize) -> String {
+    let page = pages.get(index).map(String::as_str).unwrap_or_default();
+    match pages.len() {
+        0 | 1 => page.to_owned(),
+        count => format!("{page}\n*Page {}/{count}*", index + 1),
+    }
+}
+
+#[derive(Debug)]
+struct Paginator {
+    pages: Vec<String>,
+    page: usize,
+    expires: i64,
+}
+
+/// Tracks which page each paginated message is on, until it expires.
+#[derive(Debug, Default)]
+pub struct Paginators {
+    messages: Mutex<BTreeMap<u64, Paginator>>,
+}
+
+impl Paginators {
+    /// Creates a new [`Paginators`] with no messages.
+    pub const fn new() -> Self {
+        Self {
+            messages: Mutex::new(BTreeMap::new()),
+        }
+    }
+    /// Starts tracking a paginated message, shown from its first page,
+    /// at a unix timestamp.
+    pub fn insert(&self, message_id: u64, pages: Vec<String>, now: i64) {
+        let Ok(mut messages) = self.messages.lock() else {
+            return;
+        };
+        messages.retain(|_, paginator| paginator.expires > now);
+        messages.insert(
+            message_id,
+            Paginator {
+                pages,
+                page: 0,
+                expires: now + PAGINATOR_TTL_SECONDS,
+            },
+        );
+    }
+    /// Turns the page of a message at a unix timestamp,
+    /// returning the new content, or [`None`] if it isn't paginated or has expired.
+    pub fn turn(&self, message_id: u64, forward: bool, now: i64) -> Option<String> {
+        let mut messages = self.messages.lock().ok()?;
+        messages.retain(|_, paginator| paginator.expires > now);
+        let paginator = messages.get_mut(&message_id)?;
+        paginator.page = turn_page(paginator.page, paginator.pages.len(), forward);
+        Some(page_content(&paginator.pages, paginator.page))
+    }
+}
+
+/// Turns the page of a paginated message someone reacted to, editing it in place.
+/// Adding and removing an arrow both turn the page,
+/// since the bot can't remove reactions in DMs.
+pub async fn flip_page(http: &Http, event: &ReactionEvent) -> Result<()> {
+    let forward = event.emoji == NEXT_PAGE;
+    let now = Timestamp::now().unix_timestamp();
+    let Some(content) = PAGINATORS.turn(event.message_id, forward, now) else {
+        return Ok(());
+    };
+    ChannelId(event.channel_id)
+        .edit_message(http, event.message_id, |message| message.content(content))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! Deals with the permissions the bot needs in a guild,
+//! so misconfigured roles can be spotted before mod actions fail.
+
+use eyre::{eyre, Result};
+use serenity::{
+    model::{
+        prelude::{GuildId, Member, RoleId},
+        Permissions,
+    },
+    prelude::Context,
+};
+use std::fmt::Display;
+
+/// The permissions the bot needs for all of its commands, alongside their names.
+pub const REQUIRED_PERMISSIONS: [(Permissions, &str); 10] = [
+    (Permissions::BAN_MEMBERS, "Ban Members"),
+    (Permissions::MODERATE_MEMBERS, "Moderate Members"),
+    (Permissions::MANAGE_MESSAGES, "Manage Messages"),
+    (Permissions::MANAGE_ROLES, "Manage Roles"),
+    (Permissions::MANAGE_CHANNELS, "Manage Channels"),
+    (Permissions::MANAGE_NICKNAMES, "Manage Nicknames"),
+    (
+        Permissions::MANAGE_EMOJIS_AND_STICKERS,
+        "Manage Emojis and Stickers",
+    ),
+    (Permissions::ADD_REACTIONS, "Add Reactions"),
+    (Permissions::EMBED_LINKS, "Embed Links"),
+    (Permissions::READ_MESSAGE_HISTORY, "Read Message History"),
+];
+
+/// Returns whether a set of permissions includes another,
+/// counting administrators as having every permission.
+pub fn has_permission(permissions: Permissions, required: Permissions) -> bool {
+    permissions.administrator() || permissions.contains(required)
+}
+
+/// Gets the names of the [`REQUIRED_PERMISSIONS`] missing from a set of permissions.
+pub fn missing_permissions(permissions: Permissions) -> Vec<&'static str> {
+    REQUIRED_PERMISSIONS
+        .iter()
+        .filter(|(required, _)| !has_permission(permissions, *required))
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Gets a report of which [`REQUIRED_PERMISSIONS`] the bot has, flagging missing ones.
+pub fn permission_report(permissions: Permissions) -> String {
+    let mut report = REQUIRED_PERMISSIONS
+        .iter()
+        .map(
+            |(required, name)| match has_permission(permissions, *required) {
+                true => format!("✅ {name}"),
+                false => format!("❌ {name}"),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n");
+    match missing_permissions(permissions).len() {
+        0 => report.push_str("\nI have every permission I need!"),
+        1 => report.push_str("\nI'm missing 1 permission!"),
+        missing => report.push_str(&format!("\nI'm missing {missing} permissions!")),
+    }
+    report
+}
+
+/// Gets the bot's permissions in a guild, from its cached member if possible.
+pub async fn bot_permissions(ctx: &Context, guild_id: GuildId) -> Result<Permissions> {
+    let bot_id = ctx.cache.current_user_id();
+    let member = match ctx.cache.member(guild_id, bot_id) {
+        Some(member) => member,
+        None => guild_id.member(&ctx.http, bot_id).await?,
+    };
+    Ok(member.permissions(&ctx.cache)?)
+}
+
+/// The highest role positions of the bot and the target of a mod action.
+/// Guild owners count as being above every role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RolePositions {
+    /// The position of the bot's highest role
+    pub bot: i64,
+    /// The position of the target's highest role, or [`None`] if they aren't a member
+    pub target: Option<i64>,
+}
+
+/// Gets the highest role positions of the bot and a user in a guild.
+pub async fn role_positions(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: u64,
+) -> Result<RolePositions> {
+    let guild = ctx
+        .cache
+        .guild(guild_id)
+        .ok_or_else(|| eyre!("The server isn't cached"))?;
+    let position = |member: &Member| match member.user.id == guild.owner_id {
+        true => i64::MAX,
+        false => member
+            .roles
+            .iter()
+            .filter_map(|role| guild.roles.get(role))
+            .map(|role| role.position)
+            .max()
+            .unwrap_or_default(),
+    };
+    let bot_id = ctx.cache.current_user_id();
+    let bot = match ctx.cache.member(guild_id, bot_id) {
+        Some(member) => member,
+        None => guild_id.member(&ctx.http, bot_id).await?,
+    };
+    let target = guild_id.member(&ctx.http, user_id).await.ok();
+    Ok(RolePositions {
+        bot: position(&bot),
+        target: target.as_ref().map(position),
+    })
+}
+
+/// Where a role sits in a guild, and what it allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoleStanding {
+    /// The role's position
+    pub position: i64,
+    /// The permissions the role grants
+    pub permissions: Permissions,
+}
+
+/// Gets the position and permissions of a role in a guild.
+pub fn role_standing(ctx: &Context, guild_id: GuildId, role_id: u64) -> Result<RoleStanding> {
+    let guild = ctx
+        .cache
+        .guild(guild_id)
+        .ok_or_else(|| eyre!("The server isn't cached"))?;
+    let role = guild
+        .roles
+        .get(&RoleId(role_id))
+        .ok_or_else(|| eyre!("The role isn't in the server"))?;
+    Ok(RoleStanding {
+        position: role.position,
+        permissions: role.permissions,
+    })
+}
+
+/// Whether the bot is able to carry out a mod action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preflight {
+    /// Nothing's stopping the action
+    Proceed,
+    /// The bot is missing the permission with this name
+    MissingPermission(&'static str),
+    /// The target's highest role isn't below the bot's
+    Outranked,
+}
+
+impl Display for Preflight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Preflight::Proceed => write!(f, "I can do that!"),
+            Preflight::MissingPermission(name) => {
+                write!(f, "I need the `{name}` permission to do that!")
+            }
+            Preflight::Outranked => write!(
+                f,
+                "I can't do that to them, since their highest role isn't below mine!"
+            ),
+        }
+    }
+}
+
+/// Decides whether a mod action needing the `required` permission can go ahead,
+/// given the bot's permissions and where it stands against the target.
+pub fn preflight(
+    permissions: Permissions,
+    required: Permissions,
+    positions: RolePositions,
+) -> Preflight {
+    if !has_permission(permissions, required) {
+        let name = REQUIRED_PERMISSIONS
+            .iter()
+            .find(|(permission, _)| *permission == required)
+            .map_or("required", |(_, name)| *name);
+        return Preflight::MissingPermission(name);
+    }
+    match positions.target {
+        Some(target) if target >= positions.bot => Preflight::Outranked,
+        _ => Preflight::Proceed,
+    }
+}
@@ -0,0 +1,148 @@
+//! Deals with purging: deleting many messages from a channel at once.
+//! Discord only bulk-deletes messages younger than [`BULK_DELETE_MAX_AGE_SECONDS`],
+//! so older ones are skipped rather than deleted one by one.
+
+use eyre::Result;
+use serenity::{http::Http, model::prelude::ChannelId};
+use std::fmt::Display;
+
+/// How old messages can be and still be bulk-deleted, in seconds.
+pub const BULK_DELETE_MAX_AGE_SECONDS: i64 = 14 * 24 * 60 * 60;
+/// How many messages discord bulk-deletes or fetches in one request.
+pub const BULK_DELETE_LIMIT: usize = 100;
+/// The most messages a single purge looks through.
+pub const MAX_PURGE_SCAN: usize = 1000;
+/// When discord's snowflake IDs start counting from, in unix milliseconds.
+pub const DISCORD_EPOCH_MILLIS: i64 = 1_420_070_400_000;
+
+/// A message fetched from a channel's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryMessage {
+    /// The ID of the message
+    pub id: u64,
+    /// The ID of the message's author
+    pub author: u64,
+}
+
+/// Gets when a snowflake ID (such as a message's) was made, as a unix timestamp.
+pub fn snowflake_time(id: u64) -> i64 {
+    ((id >> 22) as i64 + DISCORD_EPOCH_MILLIS) / 1000
+}
+
+/// Returns whether a message is young enough to be bulk-deleted at a unix timestamp.
+pub fn bulk_deletable(message_id: u64, now: i64) -> bool {
+    now - snowflake_time(message_id) < BULK_DELETE_MAX_AGE_SECONDS
+}
+
+/// The messages a purge will delete.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Purge {
+    /// The IDs of the messages to delete
+    pub ids: Vec<u64>,
+    /// How many messages matched, but were too old to bulk-delete
+    pub too_old: usize,
+}
+
+impl Purge {
+    /// Picks out the matching messages from a fetched batch, at a unix timestamp.
+    pub fn select(
+        messages: &[HistoryMessage],
+        now: i64,
+        matches: impl Fn(&HistoryMessage) -> bool,
+    ) -> Self {
+        let (ids, too_old): (Vec<u64>, Vec<u64>) = messages
+            .iter()
+            .filter(|message| matches(message))
+            .map(|message| message.id)
+            .partition(|id| bulk_deletable(*id, now));
+        Self {
+            ids,
+            too_old: too_old.len(),
+        }
+    }
+    /// Picks out every message sent after the message with ID `start`.
+    pub fn after(start: u64, messages: &[HistoryMessage], now: i64) -> Self {
+        Self::select(messages, now, |message| message.id > start)
+    }
+    /// Picks out the `count` most recent messages sent by a user,
+    /// assuming the fetched batch is ordered newest first.
+    pub fn by_user(user: u64, count: usize, messages: &[HistoryMessage], now: i64) -> Self {
+        let recent = messages
+            .iter()
+            .filter(|message| message.author == user)
+            .take(count)
+            .copied()
+            .collect::<Vec<_>>();
+        Self::select(&recent, now, |_| true)
+    }
+}
+
+impl Display for Purge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.ids.len() {
+            1 => write!(f, "Deleted 1 message.")?,
+            deleted => write!(f, "Deleted {deleted} messages.")?,
+        }
+        match self.too_old {
+            0 => Ok(()),
+            1 => write!(
+                f,
+                " 1 message was more than 14 days old, so I left it alone."
+            ),
+            too_old => write!(
+                f,
+                " {too_old} messages were more than 14 days old, so I left them alone."
+            ),
+        }
+    }
+}
+
+/// Fetches up to `limit` messages from a channel, newest first.
+/// With `after`, only messages sent after that message are fetched, starting with the oldest.
+pub async fn channel_history(
+    http: &Http,
+    channel_id: u64,
+    after: Option<u64>,
+    limit: usize,
+) -> Result<Vec<HistoryMessage>> {
+    let mut history = Vec::<HistoryMessage>::new();
+    while history.len() < limit {
+        let batch_size = (limit - history.len()).min(BULK_DELETE_LIMIT) as u64;
+        let batch = ChannelId(channel_id)
+            .messages(http, |get| {
+                get.limit(batch_size);
+                match (after, history.is_empty()) {
+                    (Some(after), _) => {
+                        get.after(history.iter().map(|m| m.id).max().unwrap_or(after))
+                    }
+                    (None, false) => {
+                        get.before(history.iter().map(|m| m.id).min().unwrap_or_default())
+                    }
+                    (None, true) => get,
+                }
+            })
+            .await?;
+        let fetched = batch.len();
+        history.extend(batch.into_iter().map(|message| HistoryMessage {
+            id: message.id.0,
+            author: message.author.id.0,
+        }));
+        if fetched < batch_size as usize {
+            break;
+        }
+    }
+    Ok(history)
+}
+
+/// Deletes messages from a channel, [`BULK_DELETE_LIMIT`] at a time.
+pub async fn delete_messages(http: &Http, channel_id: u64, ids: &[u64]) -> Result<()> {
+    for chunk in ids.chunks(BULK_DELETE_LIMIT) {
+        ChannelId(channel_id)
+            .delete_messages(
+                http,
+                chunk.iter().map(|id| serenity::model::id::MessageId(*id)),
+            )
+            .await?;
+    }
+    Ok(())
+}
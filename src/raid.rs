@@ -0,0 +1,177 @@
+//! Deals with raid protection: noticing when lots of members join at once,
+//! timing out new members for a while, and alerting the mods.
+
+use crate::{
+    database::{query_database, Database},
+    settings::{clear_guild_setting, guild_setting, mod_channel, set_guild_setting},
+};
+use eyre::Result;
+use serenity::{
+    model::{
+        prelude::{ChannelId, Member},
+        Timestamp,
+    },
+    prelude::Context,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+    sync::Mutex,
+};
+
+/// The join rates for the running bot.
+pub static RAID_GUARD: RaidGuard = RaidGuard::new();
+
+/// The setting holding how many joins in a row trigger raid mode.
+pub const RAID_MAX_JOINS: &str = "raid_max_joins";
+/// The setting holding how long the window for counting joins is, in seconds.
+pub const RAID_WINDOW: &str = "raid_window";
+/// How long raid mode lasts once triggered, and how long new members are timed out, in seconds.
+pub const RAID_LOCKDOWN_SECONDS: i64 = 10 * 60;
+
+/// A guild's raid protection settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaidConfig {
+    /// How many members can join within the window before raid mode starts
+    pub max_joins: usize,
+    /// How long the window is, in seconds
+    pub window_seconds: i64,
+}
+
+impl RaidConfig {
+    /// Gets a guild's raid protection settings, or [`None`] if it's turned off.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<Option<Self>> {
+        let Some(max_joins) = guild_setting(db, guild_id, RAID_MAX_JOINS)? else {
+            return Ok(None);
+        };
+        let Some(window_seconds) = guild_setting(db, guild_id, RAID_WINDOW)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            max_joins: max_joins.parse()?,
+            window_seconds: window_seconds.parse()?,
+        }))
+    }
+    /// Saves the settings for a guild, or turns raid protection off if [`None`].
+    pub fn apply(config: Option<Self>, db: &Database, guild_id: u64) -> Result<()> {
+        match config {
+            Some(config) => {
+                set_guild_setting(db, guild_id, RAID_MAX_JOINS, &config.max_joins.to_string())?;
+                set_guild_setting(
+                    db,
+                    guild_id,
+                    RAID_WINDOW,
+                    &config.window_seconds.to_string(),
+                )
+            }
+            None => {
+                clear_guild_setting(db, guild_id, RAID_MAX_JOINS)?;
+                clear_guild_setting(db, guild_id, RAID_WINDOW)
+            }
+        }
+    }
+}
+
+impl Display for RaidConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Raid mode will start if more than {} members join within {} seconds.",
+            self.max_joins, self.window_seconds
+        )
+    }
+}
+
+/// What a join means for a guild's raid protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaidState {
+    /// Joins are at a normal rate
+    Normal,
+    /// This join pushed the rate over the limit, so raid mode just started
+    Started,
+    /// Raid mode was already on
+    Ongoing,
+}
+
+#[derive(Debug, Default)]
+struct JoinWindow {
+    joins: VecDeque<i64>,
+    raid_until: Option<i64>,
+}
+
+/// Tracks recent joins per guild with a sliding window.
+#[derive(Debug, Default)]
+pub struct RaidGuard {
+    guilds: Mutex<BTreeMap<u64, JoinWindow>>,
+}
+
+impl RaidGuard {
+    /// Creates a new [`RaidGuard`] with no joins recorded.
+    pub const fn new() -> Self {
+        Self {
+            guilds: Mutex::new(BTreeMap::new()),
+        }
+    }
+    /// Records that a member joined a guild at a unix timestamp,
+    /// and returns whether the guild is being raided.
+    pub fn record_join(&self, guild_id: u64, now: i64, config: RaidConfig) -> RaidState {
+        let Ok(mut guilds) = self.guilds.lock() else {
+            return RaidState::Normal;
+        };
+        let window = guilds.entry(guild_id).or_default();
+        window.joins.push_back(now);
+        while window
+            .joins
+            .front()
+            .is_some_and(|join| *join <= now - config.window_seconds)
+        {
+            window.joins.pop_front();
+        }
+        if window.raid_until.is_some_and(|until| until > now) {
+            return RaidState::Ongoing;
+        }
+        if window.joins.len() > config.max_joins {
+            window.raid_until = Some(now + RAID_LOCKDOWN_SECONDS);
+            return RaidState::Started;
+        }
+        RaidState::Normal
+    }
+}
+
+/// Checks a new member against the guild's raid protection,
+/// timing them out and alerting the mods if the guild is being raided.
+pub async fn guard_join(ctx: &Context, member: &mut Member) -> Result<()> {
+    let db = query_database()?;
+    let guild_id = member.guild_id.0;
+    let Some(config) = RaidConfig::for_guild(&db, guild_id)? else {
+        return Ok(());
+    };
+    let now = Timestamp::now().unix_timestamp();
+    let state = RAID_GUARD.record_join(guild_id, now, config);
+    if state == RaidState::Normal {
+        return Ok(());
+    }
+    member
+        .disable_communication_until_datetime(
+            &ctx.http,
+            Timestamp::from_unix_timestamp(now + RAID_LOCKDOWN_SECONDS)?,
+        )
+        .await?;
+    if state == RaidState::Started {
+        if let Some(channel_id) = mod_channel(&db, guild_id)? {
+            ChannelId(channel_id)
+                .say(
+                    &ctx.http,
+                    format!(
+                        "**Possible raid!** More than {} members joined within {} seconds. \
+                        New members will be timed out for the next {} minutes.",
+                        config.max_joins,
+                        config.window_seconds,
+                        RAID_LOCKDOWN_SECONDS / 60
+                    ),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
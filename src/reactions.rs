@@ -0,0 +1,129 @@
+//! Deals with reactions being added to and removed from messages,
+//! routing each one to the features that care about it.
+
+use crate::{
+    pagination::{flip_page, NEXT_PAGE, PREVIOUS_PAGE},
+    roles::{apply_reaction_role, reaction_emoji_key},
+    starboard::{update_starboard, STAR},
+};
+use eyre::Result;
+use serenity::{
+    http::Http,
+    model::prelude::{ChannelId, Reaction, ReactionType},
+    prelude::Context,
+};
+
+/// A reaction being added or removed, independent of serenity's types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactionEvent {
+    /// The guild the message is in, if any
+    pub guild_id: Option<u64>,
+    /// The channel the message is in
+    pub channel_id: u64,
+    /// The message that was reacted to
+    pub message_id: u64,
+    /// The user who reacted, if known
+    pub user_id: Option<u64>,
+    /// The emoji, as stored by [`emoji_key`](crate::roles::emoji_key)
+    pub emoji: String,
+    /// Whether the reaction was added (or removed)
+    pub added: bool,
+}
+
+impl ReactionEvent {
+    /// Creates a [`ReactionEvent`] from a serenity [`Reaction`].
+    pub fn new(reaction: &Reaction, added: bool) -> Self {
+        Self {
+            guild_id: reaction.guild_id.map(|id| id.0),
+            channel_id: reaction.channel_id.0,
+            message_id: reaction.message_id.0,
+            user_id: reaction.user_id.map(|id| id.0),
+            emoji: reaction_emoji_key(&reaction.emoji),
+            added,
+        }
+    }
+}
+
+/// Gets the IDs of every user who reacted to a message with a unicode emoji,
+/// not counting bots.
+pub async fn reaction_users(
+    http: &Http,
+    channel_id: u64,
+    message_id: u64,
+    emoji: &str,
+) -> Result<Vec<u64>> {
+    let mut users = Vec::new();
+    let mut after = None;
+    loop {
+        let page = ChannelId(channel_id)
+            .reaction_users(
+                http,
+                message_id,
+                ReactionType::Unicode(emoji.to_owned()),
+                Some(100),
+                after,
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = Some(last.id);
+        users.extend(page.iter().filter(|user| !user.bot).map(|user| user.id.0));
+        if page.len() < 100 {
+            break;
+        }
+    }
+    Ok(users)
+}
+
+/// A feature a [`ReactionEvent`] can be handled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionHandler {
+    /// Gives or takes roles set up with `-reactionrole`
+    ReactionRole,
+    /// Reposts messages with enough stars
+    Starboard,
+    /// Flips through paginated messages
+    Pagination,
+}
+
+/// Decides which handlers a reaction should go to.
+/// Reactions from the bot itself are ignored, so handlers can react without looping.
+pub fn route(event: &ReactionEvent, bot_id: u64) -> Vec<ReactionHandler> {
+    let Some(user_id) = event.user_id else {
+        return Vec::new();
+    };
+    if user_id == bot_id {
+        return Vec::new();
+    }
+    let mut handlers = Vec::new();
+    if event.guild_id.is_some() {
+        handlers.push(ReactionHandler::ReactionRole);
+        if event.emoji == STAR {
+            handlers.push(ReactionHandler::Starboard);
+        }
+    }
+    if event.emoji == PREVIOUS_PAGE || event.emoji == NEXT_PAGE {
+        handlers.push(ReactionHandler::Pagination);
+    }
+    handlers
+}
+
+/// Sends a reaction to every handler that wants it.
+/// Handlers run in order; an error from one doesn't stop the rest.
+pub async fn dispatch(ctx: &Context, reaction: &Reaction, added: bool) {
+    let event = ReactionEvent::new(reaction, added);
+    for handler in route(&event, ctx.cache.current_user_id().0) {
+        if let Err(e) = handle(ctx, handler, &event).await {
+            eprintln!("Unable to handle reaction with {handler:?}: {e}");
+        }
+    }
+}
+
+async fn handle(ctx: &Context, handler: ReactionHandler, event: &ReactionEvent) -> Result<()> {
+    match handler {
+        ReactionHandler::ReactionRole => apply_reaction_role(&ctx.http, event).await,
+        ReactionHandler::Starboard => update_starboard(ctx, event).await,
+        ReactionHandler::Pagination => flip_page(&ctx.http, event).await,
+    }
+}
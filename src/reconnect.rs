@@ -0,0 +1,63 @@
+//! Deals with restarting the discord client after it stops,
+//! using a [`ReconnectConfig`] to space out attempts with exponential backoff.
+
+use std::{env, time::Duration};
+
+/// The environment variable holding the maximum number of reconnect attempts.
+pub const MAX_RETRIES_VARIABLE: &str = "BABA_BOT_MAX_RETRIES";
+/// The environment variable holding the first reconnect delay, in milliseconds.
+pub const BASE_DELAY_VARIABLE: &str = "BABA_BOT_RETRY_BASE_MS";
+/// The longest the bot will ever wait between reconnect attempts.
+pub const MAX_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// How the bot should retry after the client stops with an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectConfig {
+    /// How many times to retry in a row before giving up
+    pub max_retries: u32,
+    /// How long to wait before the first retry; each retry after waits twice as long
+    pub base_delay: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 10,
+            base_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Reads the config from [`MAX_RETRIES_VARIABLE`] and [`BASE_DELAY_VARIABLE`],
+    /// falling back to the defaults for anything missing or invalid.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_retries: env::var(MAX_RETRIES_VARIABLE)
+                .ok()
+                .and_then(|retries| retries.parse().ok())
+                .unwrap_or(default.max_retries),
+            base_delay: env::var(BASE_DELAY_VARIABLE)
+                .ok()
+                .and_then(|millis| millis.parse().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+        }
+    }
+    /// Gets how long to wait before the given retry (starting at 0),
+    /// or [`None`] if the bot should give up.
+    /// Delays double each attempt, capped at [`MAX_DELAY`].
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        let factor = 2_u32.checked_pow(attempt).unwrap_or(u32::MAX);
+        Some(
+            self.base_delay
+                .checked_mul(factor)
+                .unwrap_or(MAX_DELAY)
+                .min(MAX_DELAY),
+        )
+    }
+}
@@ -0,0 +1,48 @@
+//! Deals with relaying direct messages sent to the bot on to the dev.
+
+use crate::backend::MessageOrigin;
+
+/// Returns whether a message should be relayed to the dev.
+/// Only direct messages from people are relayed. The bot's own DMs,
+/// including the relayed messages themselves, come back as messages too
+/// and would otherwise be relayed again forever.
+pub fn should_relay(origin: MessageOrigin, author_is_bot: bool) -> bool {
+    matches!(origin, MessageOrigin::PrivateChannel) && !author_is_bot
+}
+
+/// A file attached to a relayed message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayedAttachment {
+    /// The name of the file
+    pub name: String,
+    /// Where the file can be downloaded from
+    pub url: String,
+}
+
+/// Lists the files attached to a message, one per line,
+/// or returns an empty string if there are none.
+pub fn attachment_lines(attachments: &[RelayedAttachment]) -> String {
+    attachments
+        .iter()
+        .map(|attachment| format!("📎 {}: {}", attachment.name, attachment.url))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats a direct message for the dev, quoting what the user sent
+/// and linking anything they attached.
+pub fn relayed_message(author: &str, content: &str, attachments: &[RelayedAttachment]) -> String {
+    let quoted = if content.is_empty() {
+        String::new()
+    } else {
+        format!("> {content}")
+    };
+    let mut relayed = format!("Incoming message from {author}:");
+    for part in [quoted, attachment_lines(attachments)] {
+        if !part.is_empty() {
+            relayed.push('\n');
+            relayed.push_str(&part);
+        }
+    }
+    relayed
+}
@@ -0,0 +1,197 @@
+//! Deals with reminders: storing them until they're due,
+//! and delivering them by DM or in the channel they were set in.
+
+use crate::{
+    backend::{vec_string_to_string, ArgumentError, Command, CommandType, Time},
+    database::{query_database, Database},
+};
+use eyre::{eyre, Result};
+use serenity::{
+    http::Http,
+    model::{
+        prelude::{ChannelId, UserId},
+        Timestamp,
+    },
+};
+use std::{fmt::Display, str::FromStr};
+
+/// Where a reminder is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReminderTarget {
+    /// In the user's DMs
+    DirectMessage,
+    /// In the channel the reminder was set in, pinging the user
+    Channel,
+}
+
+impl ReminderTarget {
+    /// Gets how the target is stored in the database.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ReminderTarget::DirectMessage => "dm",
+            ReminderTarget::Channel => "channel",
+        }
+    }
+}
+
+impl FromStr for ReminderTarget {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "me" | "dm" => Ok(ReminderTarget::DirectMessage),
+            "here" | "channel" => Ok(ReminderTarget::Channel),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for ReminderTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReminderTarget::DirectMessage => write!(f, "in your DMs"),
+            ReminderTarget::Channel => write!(f, "here"),
+        }
+    }
+}
+
+/// A reminder waiting to be delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    /// The ID of the reminder
+    pub id: u64,
+    /// The ID of the user to remind
+    pub user: u64,
+    /// The ID of the channel the reminder was set in
+    pub channel: u64,
+    /// Where the reminder is delivered
+    pub target: ReminderTarget,
+    /// When the reminder is due, as a unix timestamp
+    pub due: i64,
+    /// What to remind the user of
+    pub text: String,
+}
+
+impl Reminder {
+    /// Gets the message the reminder is delivered as.
+    pub fn message(&self) -> String {
+        match self.target {
+            ReminderTarget::DirectMessage => format!("Reminder: {}", self.text),
+            ReminderTarget::Channel => format!("<@{}>, reminder: {}", self.user, self.text),
+        }
+    }
+}
+
+/// Parses the `[time] [...text]` of a reminder command,
+/// where `position` is the argument number of the time.
+pub fn parse_reminder(
+    command: CommandType,
+    target: ReminderTarget,
+    args: &[String],
+    position: usize,
+) -> Command {
+    let Some(time) = args.first() else {
+        return ArgumentError::missing(command, position).into();
+    };
+    let time = match Time::from_str(time) {
+        Ok(time) if time.total_seconds() > 0 => time,
+        _ => return ArgumentError::new(command, position).into(),
+    };
+    let text = vec_string_to_string(args, Some(1));
+    if text.trim().is_empty() {
+        return ArgumentError::missing(command, position + 1).into();
+    }
+    Command::Remind { target, time, text }
+}
+
+/// Schedules a reminder, returning its ID.
+pub fn add_reminder(
+    db: &Database,
+    user_id: u64,
+    channel_id: u64,
+    target: ReminderTarget,
+    due: i64,
+    text: &str,
+) -> Result<u64> {
+    db.execute(
+        "
+        INSERT INTO reminders (user, channel, target, due, text)
+        VALUES ((?1), (?2), (?3), (?4), (?5))
+        ",
+        (user_id, channel_id, target.key(), due, text),
+    )?;
+    Ok(db.last_insert_rowid() as u64)
+}
+
+/// Gets every reminder due at a unix timestamp, oldest first.
+pub fn due_reminders(db: &Database, now: i64) -> Result<Vec<Reminder>> {
+    let mut statement = db.prepare(
+        "
+        SELECT id, user, channel, target, due, text FROM reminders
+        WHERE due <= (?1) ORDER BY due, id
+        ",
+    )?;
+    let reminders = statement
+        .query_map([now], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get::<_, String>(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    reminders
+        .into_iter()
+        .map(|(id, user, channel, target, due, text)| {
+            Ok(Reminder {
+                id,
+                user,
+                channel,
+                target: target
+                    .parse()
+                    .map_err(|_| eyre!("Unknown reminder target `{target}`"))?,
+                due,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Deletes a reminder, once it's been delivered.
+pub fn remove_reminder(db: &Database, id: u64) -> Result<()> {
+    db.execute("DELETE FROM reminders WHERE id = (?1)", [id])?;
+    Ok(())
+}
+
+/// Sends a reminder to wherever it's meant to go.
+/// Only the user who set the reminder can be pinged by it,
+/// whatever mentions they wrote into its text.
+pub async fn deliver(http: &Http, reminder: &Reminder) -> Result<()> {
+    let channel = match reminder.target {
+        ReminderTarget::DirectMessage => UserId(reminder.user).create_dm_channel(http).await?.id,
+        ReminderTarget::Channel => ChannelId(reminder.channel),
+    };
+    channel
+        .send_message(http, |message| {
+            message
+                .content(reminder.message())
+                .allowed_mentions(|mentions| mentions.empty_parse().users([reminder.user]))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Delivers every reminder that's due.
+/// Reminders that can't be delivered are dropped rather than retried.
+pub async fn deliver_due(http: &Http) -> Result<()> {
+    let reminders = due_reminders(&query_database()?, Timestamp::now().unix_timestamp())?;
+    for reminder in reminders {
+        if let Err(e) = deliver(http, &reminder).await {
+            eprintln!("Unable to deliver reminder {}: {e}", reminder.id);
+        }
+        remove_reminder(&query_database()?, reminder.id)?;
+    }
+    Ok(())
+}
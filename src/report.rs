@@ -0,0 +1,133 @@
+//! Deals with reports: members flagging messages for the mods to look at.
+
+use crate::{
+    backend::MessageLink,
+    casefile::{CaseFile, CaseFileAction},
+    cooldown::Cooldown,
+    database::Database,
+    modlog::field_value,
+    sink::Embed,
+};
+use eyre::Result;
+use serenity::{http::Http, model::prelude::ChannelId};
+
+/// The report cooldowns for the running bot.
+pub static REPORT_COOLDOWN: Cooldown = Cooldown::new(REPORT_COOLDOWN_SECONDS);
+
+/// How long a member has to wait between reports, in seconds.
+pub const REPORT_COOLDOWN_SECONDS: i64 = 5 * 60;
+
+/// A message someone reported, as fetched from discord.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportedMessage {
+    /// The ID of the message's author
+    pub author: u64,
+    /// What the message said
+    pub content: String,
+}
+
+/// Fetches a message so it can be reported.
+pub async fn fetch_reported_message(
+    http: &Http,
+    channel_id: u64,
+    message_id: u64,
+) -> Result<ReportedMessage> {
+    let message = ChannelId(channel_id).message(http, message_id).await?;
+    Ok(ReportedMessage {
+        author: message.author.id.0,
+        content: message.content,
+    })
+}
+
+/// Builds the embed posted in the mod channel when a member reports a message.
+pub fn report_embed(
+    reporter: u64,
+    link: &MessageLink,
+    message: &ReportedMessage,
+    reason: &str,
+) -> Embed {
+    Embed {
+        title: "Message reported".to_owned(),
+        description: format!(
+            "<@{reporter}> reported a message by <@{}> in <#{}>",
+            message.author, link.channel_id
+        ),
+        fields: vec![
+            ("Content".to_owned(), field_value(&message.content)),
+            ("Reason".to_owned(), field_value(reason)),
+            ("Message".to_owned(), format!("[Jump to message]({link})")),
+        ],
+    }
+}
+
+/// Describes a reported message as a single casefile item,
+/// quoting it and linking back to it as evidence.
+pub fn evidence_item(
+    reporter: u64,
+    link: &MessageLink,
+    message: &ReportedMessage,
+    reason: &str,
+) -> String {
+    let content = message.content.lines().collect::<Vec<_>>().join(" / ");
+    format!("Reported by <@{reporter}> for \"{reason}\": \"{content}\" ({link})")
+}
+
+/// The casefile a report was filed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportCase {
+    /// The ID of the casefile
+    pub id: u64,
+    /// Whether the casefile was opened for this report
+    pub created: bool,
+}
+
+impl ReportCase {
+    /// Gets the embed field noting which casefile a report went into.
+    pub fn field(&self) -> (String, String) {
+        let value = match self.created {
+            true => format!("Opened Casefile #{}", self.id),
+            false => format!("Added to Casefile #{}", self.id),
+        };
+        ("Casefile".to_owned(), value)
+    }
+}
+
+/// Gets the newest unresolved, unarchived casefile linked to a user, if any.
+pub fn open_case_for(db: &Database, user_id: u64) -> Result<Option<u64>> {
+    let mut statement = db.prepare(
+        "
+        SELECT cases.id FROM cases JOIN case_users ON cases.id = case_users.id
+        WHERE case_users.user = (?1) AND cases.reso = FALSE AND cases.archived = FALSE
+        ORDER BY cases.id DESC LIMIT 1
+        ",
+    )?;
+    let mut rows = statement.query([user_id])?;
+    Ok(match rows.next()? {
+        Some(row) => Some(row.get(0)?),
+        None => None,
+    })
+}
+
+/// Files a report as evidence in the reported user's open casefile,
+/// opening a new one linked to them if they don't have one.
+pub fn file_report(
+    db: &Database,
+    reporter: u64,
+    link: &MessageLink,
+    message: &ReportedMessage,
+    reason: &str,
+) -> Result<ReportCase> {
+    let (id, created) = match open_case_for(db, message.author)? {
+        Some(id) => (id, false),
+        None => {
+            let id = CaseFileAction::lowest_id_availible(db)?;
+            let name = format!("Reports against <@{}>", message.author);
+            CaseFileAction::Create { name }.perform(db, reporter)?;
+            CaseFile::add_user(db, id, message.author)?;
+            (id, true)
+        }
+    };
+    let item = evidence_item(reporter, link, message, reason);
+    CaseFileAction::AddItem { id, item }.perform(db, reporter)?;
+    Ok(ReportCase { id, created })
+}
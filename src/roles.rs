@@ -0,0 +1,201 @@
+//! Deals with giving and taking roles,
+//! including finding a role from what a user typed
+//! and giving roles when users react to messages.
+
+use crate::{
+    database::{query_database, Database},
+    permissions::RoleStanding,
+    reactions::ReactionEvent,
+};
+use eyre::Result;
+use rusqlite::OptionalExtension;
+use serenity::{
+    http::Http,
+    model::{prelude::ReactionType, Permissions},
+};
+use std::{fmt::Display, str::FromStr};
+
+/// Whether a role should be given to or taken from a member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleAction {
+    /// Give the role
+    Add,
+    /// Take the role away
+    Remove,
+}
+
+impl FromStr for RoleAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "add" => Ok(Self::Add),
+            "remove" => Ok(Self::Remove),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for RoleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoleAction::Add => write!(f, "add"),
+            RoleAction::Remove => write!(f, "remove"),
+        }
+    }
+}
+
+/// Finds a role in a guild's `(id, name)` roles from what a user typed:
+/// a role ID, a role mention (`<@&id>`), or a role name ignoring case.
+pub fn resolve_role<'a>(roles: &'a [(u64, String)], query: &str) -> Option<&'a (u64, String)> {
+    let query = query.trim();
+    let id = query
+        .strip_prefix("<@&")
+        .and_then(|mention| mention.strip_suffix('>'))
+        .unwrap_or(query)
+        .parse::<u64>();
+    if let Ok(id) = id {
+        if let Some(role) = roles.iter().find(|(role_id, _)| *role_id == id) {
+            return Some(role);
+        }
+    }
+    roles
+        .iter()
+        .find(|(_, name)| name.to_lowercase() == query.to_lowercase())
+}
+
+/// Permissions that roles handed out by the bot may never grant,
+/// since holding any of them would let a member moderate or manage the server.
+pub const PRIVILEGED_PERMISSIONS: Permissions = Permissions::ADMINISTRATOR
+    .union(Permissions::MANAGE_GUILD)
+    .union(Permissions::MANAGE_ROLES)
+    .union(Permissions::MANAGE_CHANNELS)
+    .union(Permissions::MANAGE_MESSAGES)
+    .union(Permissions::MANAGE_NICKNAMES)
+    .union(Permissions::MANAGE_WEBHOOKS)
+    .union(Permissions::MANAGE_EMOJIS_AND_STICKERS)
+    .union(Permissions::MANAGE_EVENTS)
+    .union(Permissions::MANAGE_THREADS)
+    .union(Permissions::BAN_MEMBERS)
+    .union(Permissions::KICK_MEMBERS)
+    .union(Permissions::MODERATE_MEMBERS);
+
+/// Returns why a role can't be handed out by the bot on someone's behalf, if it can't.
+/// `author` is the position of that person's highest role, and `bot` the bot's.
+pub fn role_refusal(role: RoleStanding, author: i64, bot: i64) -> Option<&'static str> {
+    if role.permissions.intersects(PRIVILEGED_PERMISSIONS) {
+        Some("grants moderation or management permissions")
+    } else if role.position >= author {
+        Some("isn't below your highest role")
+    } else if role.position >= bot {
+        Some("isn't below my highest role")
+    } else {
+        None
+    }
+}
+
+/// Returns whether members of a guild can give themselves a role.
+pub fn self_role_allowed(db: &Database, guild_id: u64, role_id: u64) -> Result<bool> {
+    let allowed = db
+        .query_row(
+            "SELECT 1 FROM self_roles WHERE guild = (?1) AND role = (?2)",
+            (guild_id, role_id),
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(allowed.is_some())
+}
+
+/// Sets whether members of a guild can give themselves a role.
+pub fn set_self_role_allowed(
+    db: &Database,
+    guild_id: u64,
+    role_id: u64,
+    allowed: bool,
+) -> Result<()> {
+    match allowed {
+        true => db.execute(
+            "INSERT OR IGNORE INTO self_roles (guild, role) VALUES ((?1), (?2))",
+            (guild_id, role_id),
+        )?,
+        false => db.execute(
+            "DELETE FROM self_roles WHERE guild = (?1) AND role = (?2)",
+            (guild_id, role_id),
+        )?,
+    };
+    Ok(())
+}
+
+/// Gets the key an emoji is stored under from what a user typed:
+/// the ID for a custom emoji (`<:name:id>`), or the emoji itself.
+pub fn emoji_key(emoji: &str) -> String {
+    let emoji = emoji.trim();
+    emoji
+        .strip_prefix('<')
+        .and_then(|custom| custom.strip_suffix('>'))
+        .and_then(|custom| custom.rsplit(':').next())
+        .filter(|id| id.parse::<u64>().is_ok())
+        .unwrap_or(emoji)
+        .to_owned()
+}
+
+/// Gets the key an emoji is stored under from a reaction,
+/// matching [`emoji_key`].
+pub fn reaction_emoji_key(emoji: &ReactionType) -> String {
+    match emoji {
+        ReactionType::Custom { id, .. } => id.0.to_string(),
+        ReactionType::Unicode(emoji) => emoji.clone(),
+        emoji => emoji.to_string(),
+    }
+}
+
+/// Records that reacting to a message with an emoji gives a role.
+/// Any role already given by that emoji on that message is replaced.
+pub fn add_reaction_role(
+    db: &Database,
+    guild_id: u64,
+    message_id: u64,
+    emoji: &str,
+    role_id: u64,
+) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO reaction_roles (guild, message, emoji, role) VALUES ((?1), (?2), (?3), (?4))
+        ON CONFLICT (message, emoji) DO UPDATE SET role = excluded.role
+        ",
+        (guild_id, message_id, emoji, role_id),
+    )?;
+    Ok(())
+}
+
+/// Gets the role given by reacting to a message with an emoji, if any.
+pub fn reaction_role(db: &Database, message_id: u64, emoji: &str) -> Result<Option<u64>> {
+    Ok(db
+        .query_row(
+            "SELECT role FROM reaction_roles WHERE message = (?1) AND emoji = (?2)",
+            (message_id, emoji),
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Gives or takes the role tied to a reaction, if there is one.
+pub async fn apply_reaction_role(http: &Http, event: &ReactionEvent) -> Result<()> {
+    let (Some(guild_id), Some(user_id)) = (event.guild_id, event.user_id) else {
+        return Ok(());
+    };
+    let Some(role_id) = reaction_role(&query_database()?, event.message_id, &event.emoji)? else {
+        return Ok(());
+    };
+    match event.added {
+        true => {
+            http.add_member_role(guild_id, user_id, role_id, Some("Reaction role"))
+                .await?
+        }
+        false => {
+            http.remove_member_role(guild_id, user_id, role_id, Some("Reaction role"))
+                .await?
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Deals with a guild's rules: numbered entries mods set,
+//! which anyone can post in full or one at a time.
+
+use crate::database::Database;
+use eyre::Result;
+use rusqlite::OptionalExtension;
+
+/// A change to a guild's rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleEdit {
+    /// Sets a rule, replacing any with the same number
+    Set {
+        #[doc = "the number of the rule"]
+        number: u8,
+        #[doc = "what the rule says"]
+        text: String,
+    },
+    /// Deletes a rule
+    Remove(u8),
+}
+
+/// Saves a rule in a guild, returning whether it replaced an existing one.
+pub fn set_rule(db: &Database, guild_id: u64, number: u8, text: &str) -> Result<bool> {
+    let replaced = rule(db, guild_id, number)?.is_some();
+    db.execute(
+        "INSERT OR REPLACE INTO rules (guild, number, text) VALUES ((?1), (?2), (?3))",
+        (guild_id, number, text),
+    )?;
+    Ok(replaced)
+}
+
+/// Deletes a rule from a guild, returning whether it existed.
+pub fn remove_rule(db: &Database, guild_id: u64, number: u8) -> Result<bool> {
+    Ok(db.execute(
+        "DELETE FROM rules WHERE guild = (?1) AND number = (?2)",
+        (guild_id, number),
+    )? > 0)
+}
+
+/// Gets what a rule in a guild says, if it exists.
+pub fn rule(db: &Database, guild_id: u64, number: u8) -> Result<Option<String>> {
+    Ok(db
+        .query_row(
+            "SELECT text FROM rules WHERE guild = (?1) AND number = (?2)",
+            (guild_id, number),
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Gets every rule in a guild as `(number, text)`, in order.
+pub fn rules(db: &Database, guild_id: u64) -> Result<Vec<(u8, String)>> {
+    let mut statement =
+        db.prepare("SELECT number, text FROM rules WHERE guild = (?1) ORDER BY number")?;
+    let rules = statement
+        .query_map([guild_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    Ok(rules)
+}
+
+/// Formats a rule for posting, e.g. `**5.** Be nice`.
+pub fn format_rule(number: u8, text: &str) -> String {
+    format!("**{number}.** {text}")
+}
+
+/// Formats a reminder of a rule for `-notice rule:`.
+pub fn format_rule_reminder(number: u8, text: &str) -> String {
+    format!("Reminder: Rule {number} — {text}")
+}
+
+/// Gets a reminder of a rule in a guild, or [`None`] if there's no such rule.
+pub fn rule_reminder(db: &Database, guild_id: u64, number: u8) -> Result<Option<String>> {
+    Ok(rule(db, guild_id, number)?.map(|text| format_rule_reminder(number, &text)))
+}
+
+/// Finds the rule a notice refers to, written as `rule: 5` or `rule:5`
+/// at the start of its arguments.
+/// Returns [`None`] if the notice isn't about a rule,
+/// or `Some(Err(()))` if the rule number isn't valid.
+pub fn rule_reference(args: &[String]) -> Option<Result<u8, ()>> {
+    let (first, rest) = args.split_first()?;
+    let number = first.to_lowercase().strip_prefix("rule:")?.to_owned();
+    let number = match number.as_str() {
+        "" => rest.first().map(String::as_str).unwrap_or_default(),
+        number => number,
+    };
+    Some(match number.trim_start_matches('#').parse::<u8>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(()),
+    })
+}
@@ -0,0 +1,24 @@
+//! Deals with the scheduler, which acts on anything stored in the database
+//! that comes due later, like reminders and giveaways.
+
+use crate::{giveaways, reminders};
+use serenity::http::Http;
+use std::{sync::Arc, time::Duration};
+
+/// How often the database is checked for anything that's come due, in seconds.
+pub const SCHEDULER_POLL_SECONDS: u64 = 30;
+
+/// Delivers reminders and ends giveaways as they come due, forever.
+/// Since everything is stored in the database, nothing is lost across restarts.
+pub async fn run_scheduler(http: Arc<Http>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_POLL_SECONDS));
+    loop {
+        interval.tick().await;
+        if let Err(e) = reminders::deliver_due(&http).await {
+            eprintln!("Unable to deliver reminders: {e}");
+        }
+        if let Err(e) = giveaways::end_due(&http).await {
+            eprintln!("Unable to end giveaways: {e}");
+        }
+    }
+}
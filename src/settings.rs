@@ -0,0 +1,90 @@
+//! Deals with per-guild settings, such as which commands are enabled
+//! and which channels features should post in.
+
+use crate::backend::CommandType;
+use crate::config::config;
+use crate::database::Database;
+use eyre::Result;
+use rusqlite::OptionalExtension;
+
+/// The setting holding the channel alerts for mods are posted in.
+pub const MOD_CHANNEL: &str = "mod_channel";
+
+/// Returns whether a command can be used in the given guild.
+/// Commands are enabled unless they've been turned off.
+pub fn command_enabled(db: &Database, guild_id: u64, command: CommandType) -> Result<bool> {
+    let enabled = db
+        .query_row(
+            "SELECT enabled FROM command_settings WHERE guild = (?1) AND command = (?2)",
+            (guild_id, command.name()),
+            |row| row.get::<_, bool>(0),
+        )
+        .optional()?;
+    Ok(enabled.unwrap_or(true))
+}
+
+/// Sets whether a command can be used in the given guild.
+pub fn set_command_enabled(
+    db: &Database,
+    guild_id: u64,
+    command: CommandType,
+    enabled: bool,
+) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO command_settings (guild, command, enabled) VALUES ((?1), (?2), (?3))
+        ON CONFLICT (guild, command) DO UPDATE SET enabled = excluded.enabled
+        ",
+        (guild_id, command.name(), enabled),
+    )?;
+    Ok(())
+}
+
+/// Flips whether a command can be used in the given guild,
+/// returning whether it's now enabled.
+pub fn toggle_command(db: &Database, guild_id: u64, command: CommandType) -> Result<bool> {
+    let enabled = !command_enabled(db, guild_id, command)?;
+    set_command_enabled(db, guild_id, command, enabled)?;
+    Ok(enabled)
+}
+
+/// Gets a per-guild setting, if it's been set.
+pub fn guild_setting(db: &Database, guild_id: u64, key: &str) -> Result<Option<String>> {
+    Ok(db
+        .query_row(
+            "SELECT value FROM guild_settings WHERE guild = (?1) AND key = (?2)",
+            (guild_id, key),
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Sets a per-guild setting, replacing any previous value.
+pub fn set_guild_setting(db: &Database, guild_id: u64, key: &str, value: &str) -> Result<()> {
+    db.execute(
+        "
+        INSERT INTO guild_settings (guild, key, value) VALUES ((?1), (?2), (?3))
+        ON CONFLICT (guild, key) DO UPDATE SET value = excluded.value
+        ",
+        (guild_id, key, value),
+    )?;
+    Ok(())
+}
+
+/// Gets the channel mod alerts are posted in for a guild,
+/// falling back to the bot's config if the guild hasn't chosen one.
+pub fn mod_channel(db: &Database, guild_id: u64) -> Result<Option<u64>> {
+    match guild_setting(db, guild_id, MOD_CHANNEL)? {
+        Some(channel_id) => Ok(Some(channel_id.parse()?)),
+        None => Ok(config().mod_channel),
+    }
+}
+
+/// Removes a per-guild setting.
+pub fn clear_guild_setting(db: &Database, guild_id: u64, key: &str) -> Result<()> {
+    db.execute(
+        "DELETE FROM guild_settings WHERE guild = (?1) AND key = (?2)",
+        (guild_id, key),
+    )?;
+    Ok(())
+}
@@ -1,14 +1,70 @@
 //! Deals with a [`BotShard`], the main driver that connects to discord.
-use crate::{backend::{Command, MessageOrigin, Time, PREFIX}, casefile::query_database};
+use crate::{
+    backend::{Command, MessageOrigin, Time},
+    database::query_database,
+    keke::{opted_in, save_original_nickname, KekeName, KEKE_COOLDOWN},
+    pagination::{page_content, NEXT_PAGE, PAGINATORS, PREVIOUS_PAGE},
+    sink,
+};
 use eyre::Result;
 use serenity::{
     client::{Cache, Context},
     http::Http,
     model::{
-        channel::{Channel, Message}, guild::{Guild, Member, PartialGuild}, user::User, voice, Permissions
+        channel::{Channel, ChannelType, Message, ReactionType},
+        guild::{Guild, Member, PartialGuild},
+        user::User,
+        voice, Permissions, Timestamp,
     },
     Error as SereneError, Result as SereneResult,
 };
+use std::{future::Future, time::Duration};
+
+/// The longest a temporary message can stay up for, in seconds.
+pub const MAX_TEMP_MESSAGE_SECONDS: u64 = 15 * 60;
+
+/// Gets how long a temporary message stays up before deleting itself.
+/// Messages stay up for at least a second, and at most [`MAX_TEMP_MESSAGE_SECONDS`].
+pub fn temp_message_delay(time: Time) -> Duration {
+    Duration::from_secs(time.total_seconds().clamp(1, MAX_TEMP_MESSAGE_SECONDS))
+}
+/// What kind of channel something is, as far as sending messages goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    /// A text or announcement channel in a guild
+    Text,
+    /// A thread, which messages are posted straight into
+    Thread,
+    /// A direct message channel
+    Private,
+    /// A category holding other channels
+    Category,
+    /// A forum, which only holds threads
+    Forum,
+    /// A voice or stage channel
+    Voice,
+    /// Any other kind of channel, such as a student hub directory
+    Other,
+}
+
+impl From<ChannelType> for ChannelKind {
+    /// Forum channels show up as [`ChannelType::Unknown`] in this version of serenity,
+    /// since they're behind its `unstable_discord_api` feature.
+    fn from(kind: ChannelType) -> Self {
+        match kind {
+            ChannelType::Text | ChannelType::News => ChannelKind::Text,
+            ChannelType::NewsThread | ChannelType::PublicThread | ChannelType::PrivateThread => {
+                ChannelKind::Thread
+            }
+            ChannelType::Private => ChannelKind::Private,
+            ChannelType::Category => ChannelKind::Category,
+            ChannelType::Voice | ChannelType::Stage => ChannelKind::Voice,
+            ChannelType::Unknown => ChannelKind::Forum,
+            _ => ChannelKind::Other,
+        }
+    }
+}
+
 /// Represents a shard of a bot doing calculations for a single message.
 /// Has some helper methods for sending messages and interacting
 /// with the inner HTTP server.
@@ -25,13 +81,16 @@ impl<'a> BotShard<'a> {
     pub fn new(ctx: &'a Context, message: &'a Message) -> Self {
         Self { ctx, message }
     }
-    /// Parses a command from the content of the given [`Message`].
-    pub async fn command(&self) -> Command {
-        Command::parse_from_message(*self).await
+    /// Parses a command from the content of the given [`Message`], without checking permissions.
+    pub fn command(&self) -> Command {
+        let message = self.original_message();
+        Command::parse(&message.content, &message.author.name)
     }
     /// Executes the command from the given content of the internal [`Message`].
+    /// See [`Command::dispatch`].
     pub async fn execute_command(&self) -> Result<()> {
-        self.command().await.execute_command(*self).await
+        let now = self.original_message().timestamp.unix_timestamp();
+        self.command().dispatch(query_database()?, self, now).await
     }
     /// Sends a message to the same channel the given [`Message`] was sent to.
     /// Returns a [`Message`] representing the sent message.
@@ -39,6 +98,71 @@ impl<'a> BotShard<'a> {
         let channel_id = self.original_message().channel_id.0;
         self.send_message_to(message, channel_id).await
     }
+    /// Shows a typing indicator in the original [`Message`]'s channel while a future runs,
+    /// returning what it returns.
+    pub async fn with_typing<T>(&self, future: impl Future<Output = T>) -> T {
+        sink::with_typing(self, future).await
+    }
+    /// Replies to the original [`Message`], so the response is shown as a reply to it.
+    /// Returns a [`Message`] representing the sent message.
+    pub async fn reply(&self, text: impl AsRef<str>) -> SereneResult<Message> {
+        self.original_message()
+            .channel_id
+            .send_message(self.http_server(), |message| {
+                message
+                    .content(text.as_ref())
+                    .reference_message(self.original_message())
+            })
+            .await
+    }
+    /// Sends a message to the same channel the given [`Message`] was sent to,
+    /// then deletes it after a [`Time`] without waiting for it.
+    /// See [`temp_message_delay`] for how long it stays up.
+    pub async fn send_temp_message(
+        &self,
+        message: impl AsRef<str>,
+        time: Time,
+    ) -> SereneResult<Message> {
+        let sent = self.send_message(message).await?;
+        let http = self.ctx.http.clone();
+        let temp = sent.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(temp_message_delay(time)).await;
+            if let Err(e) = temp.delete(&http).await {
+                eprintln!("Unable to delete temporary message: {e}");
+            }
+        });
+        Ok(sent)
+    }
+    /// Sends pages of a long output as one message in the original [`Message`]'s channel.
+    /// If there's more than one page, arrows are added to flip through them.
+    pub async fn send_paginated(&self, pages: Vec<String>) -> SereneResult<Message> {
+        let sent = self.send_message(page_content(&pages, 0)).await?;
+        if pages.len() > 1 {
+            for arrow in [PREVIOUS_PAGE, NEXT_PAGE] {
+                sent.react(self.http_server(), ReactionType::Unicode(arrow.to_owned()))
+                    .await?;
+            }
+            PAGINATORS.insert(sent.id.0, pages, sent.timestamp.unix_timestamp());
+        }
+        Ok(sent)
+    }
+    /// Edits the content of a [`Message`] the bot sent.
+    pub async fn edit_message(
+        &self,
+        message: &mut Message,
+        new_content: impl AsRef<str>,
+    ) -> SereneResult<()> {
+        message
+            .edit(self.http_server(), |edit| {
+                edit.content(new_content.as_ref())
+            })
+            .await
+    }
+    /// Deletes a [`Message`].
+    pub async fn delete_message(&self, message: &Message) -> SereneResult<()> {
+        message.delete(self.http_server()).await
+    }
     /// Sends a message to a given channel based on an ID.
     /// Returns the [`Message`] representing the sent message.
     pub async fn send_message_to(
@@ -47,14 +171,29 @@ impl<'a> BotShard<'a> {
         channel_id: impl Into<u64>,
     ) -> SereneResult<Message> {
         let channel = self.http_server().get_channel(channel_id.into()).await?;
-        if let Some(channel) = channel.clone().guild() {
-            channel.say(self.http_server(), message.as_ref()).await
-        } else if let Some(channel) = channel.clone().private() {
-            channel.say(self.http_server(), message.as_ref()).await
-        } else if channel.category().is_some() {
-            return Err(SereneError::Other("Got a category for some reason"));
-        } else {
-            return Err(SereneError::Other("Not a channel"));
+        let kind = match &channel {
+            Channel::Guild(channel) => channel.kind,
+            Channel::Private(_) => ChannelType::Private,
+            Channel::Category(_) => ChannelType::Category,
+            _ => ChannelType::Unknown,
+        };
+        match (ChannelKind::from(kind), channel) {
+            (ChannelKind::Text | ChannelKind::Thread, Channel::Guild(channel)) => {
+                channel.say(self.http_server(), message.as_ref()).await
+            }
+            (ChannelKind::Private, Channel::Private(channel)) => {
+                channel.say(self.http_server(), message.as_ref()).await
+            }
+            (ChannelKind::Category, _) => Err(SereneError::Other(
+                "That's a category, so messages can't be sent to it",
+            )),
+            (ChannelKind::Forum, _) => Err(SereneError::Other(
+                "That's a forum, so messages have to be sent to one of its posts",
+            )),
+            (ChannelKind::Voice, _) => Err(SereneError::Other(
+                "That's a voice channel, so messages can't be sent to it",
+            )),
+            _ => Err(SereneError::Other("Not a channel")),
         }
     }
     /// Gets the author of the sent message.
@@ -82,7 +221,9 @@ impl<'a> BotShard<'a> {
     }
     /// Attempts to request a [`Guild`] from the cache.
     pub async fn guild_request(&self, server_id: impl Into<u64>) -> SereneResult<Guild> {
-        self.cache().guild(server_id.into()).ok_or(SereneError::Other("Couldn't find guild"))
+        self.cache()
+            .guild(server_id.into())
+            .ok_or(SereneError::Other("Couldn't find guild"))
     }
     /// A reference to the internal [`Http`] server.
     pub fn http_server(&self) -> &Http {
@@ -154,17 +295,18 @@ impl<'a> BotShard<'a> {
             .await?;
         Ok(self.send_message(reason).await?)
     }
-    /// Sends a message to a user.
+    /// Sends a direct message to a user, looking them up directly
+    /// rather than as a member of the guild, so it works from DMs
+    /// and for users who share no guild with the message.
+    /// The sent message comes back as a new DM event, which the handler ignores.
     /// If successful, returns the associated [`Message`].
-    /// Bubbles up errors.
-    pub async fn message_user(
+    pub async fn message_user_direct(
         &self,
         user_id: impl Into<u64>,
         message: impl AsRef<str>,
     ) -> SereneResult<Message> {
-        self.member_request(user_id)
+        self.user_request(user_id)
             .await?
-            .user
             .create_dm_channel(self.http_server())
             .await?
             .say(self.http_server(), message.as_ref())
@@ -191,19 +333,18 @@ impl<'a> BotShard<'a> {
     /// Checks if a user is opted in AND the message is kekeable:
     /// starts with "i'm" or "i am"
     pub async fn is_kekeable(&self) -> Result<bool> {
-        let mut kekeable = false;
-        let db = query_database()?;
-        let sql_command = format!("SELECT keke FROM users WHERE id={}", self.author_id().await);
-        let _ = db.prepare(&sql_command)?.query_map((), |row| {
-            kekeable = row.get::<_, bool>(0)?;
-            Ok(())
-        })?;
-        Ok(kekeable)
+        let user_id = self.author_id().await;
+        opted_in(&query_database()?, user_id)
     }
     /// "Kekes" the author - that is,
     /// if the message starts with "I am" or "I'm",
     /// And the author is opted in,
     /// their nickname is changed to the rest of their message.
+    /// Mentions and links are stripped from the nickname,
+    /// and names with filtered words in them are refused.
+    /// The nickname they had before is saved, so opting out can restore it.
+    /// Members are only keke'd once per [`KEKE_COOLDOWN_SECONDS`](crate::keke::KEKE_COOLDOWN_SECONDS);
+    /// messages within the cooldown are ignored.
     pub async fn keke_author(&self) -> Result<()> {
         let potential_keke = self
             .original_message()
@@ -213,24 +354,28 @@ impl<'a> BotShard<'a> {
             .strip_prefix("i am ")
             .unwrap_or(&self.original_message().content);
         if self.is_kekeable().await? {
+            let now = Timestamp::now().unix_timestamp();
+            if !KEKE_COOLDOWN.try_use(self.author_id().await, now) {
+                return Ok(());
+            }
             let name = self.author().name.clone();
-            if self.original_message().content.chars().count() <= 32 {
+            let db = query_database()?;
+            let keke = KekeName::for_guild(&db, self.guild_id()?, potential_keke)?;
+            if let KekeName::Valid(nickname) = &keke {
                 let member = self.member_request(self.author_id().await).await?;
+                save_original_nickname(
+                    &db,
+                    self.guild_id()?,
+                    member.user.id.0,
+                    member.nick.as_deref(),
+                )?;
                 member
                     .edit(self.http_server(), |editmember| {
-                        editmember.nickname(potential_keke)
+                        editmember.nickname(nickname)
                     })
                     .await?;
-                self.send_message(format!(
-                    "{name} is `{potential_keke}`!\n\nWanna optout? use {PREFIX}keke!"
-                ))
-                .await?;
-            } else {
-                self.send_message(format!(
-                    "{name} is NOT `{potential_keke}`!\n\nWanna optout? use {PREFIX}keke!"
-                ))
-                .await?;
             }
+            self.send_message(keke.announcement(&name)).await?;
             Ok(())
         } else {
             Err(SereneError::Other("Not a KEKE, ignorable").into())
@@ -247,7 +392,10 @@ impl<'a> BotShard<'a> {
     /// Attempts to connect to a voice channel.
     #[cfg(todo)]
     pub async fn connect_to(&self, channel_id: impl Into<u64>) -> SereneResult<()> {
-        self.channel_request(channel_id).await?.guild().ok_or(SereneError::Other("Couldn't find the channel"))?
+        self.channel_request(channel_id)
+            .await?
+            .guild()
+            .ok_or(SereneError::Other("Couldn't find the channel"))?
     }
     /// Gets the origin of a message. This is either [`MessageOrigin::PrivateChannel`]
     /// or [`MessageOrigin::PublicChannel`].
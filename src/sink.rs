@@ -0,0 +1,411 @@
+//! Deals with [`CommandSink`]s, the places a [`Command`](crate::backend::Command)
+//! sends its side effects to.
+use crate::{
+    backend::Time,
+    channels::{channel_details, channel_guild, ChannelDetails},
+    diagnostics::CacheCounts,
+    emojis::{guild_emojis, steal_emoji, GuildEmoji},
+    guild,
+    lockdown::{everyone_overwrite, set_everyone_overwrite, EveryoneOverwrite},
+    members::{member_names, MemberName},
+    metrics::{Metrics, METRICS},
+    permissions::{bot_permissions, role_positions, role_standing, RolePositions, RoleStanding},
+    purge::{channel_history, delete_messages, HistoryMessage},
+    reactions::reaction_users,
+    report::{fetch_reported_message, ReportedMessage},
+    shard::BotShard,
+};
+use eyre::Result;
+use serenity::{
+    builder::CreateEmbed,
+    http::{Http, Typing},
+    model::{
+        prelude::{ChannelId, GuildId, ReactionType},
+        Permissions,
+    },
+};
+use std::future::Future;
+
+/// Sends an embed to a channel.
+pub async fn send_embed_to(http: &Http, channel_id: u64, embed: &Embed) -> Result<()> {
+    ChannelId(channel_id)
+        .send_message(http, |message| {
+            message.embed(|create| build_embed(create, embed))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Everything a [`Command`](crate::backend::Command) needs to do to the outside world.
+/// [`BotShard`] is the real implementation; tests can use a mock instead.
+#[async_trait::async_trait]
+pub trait CommandSink: Sync {
+    /// Sends a message to the channel the command came from.
+    async fn send_message(&self, message: &str) -> Result<()>;
+    /// Sends an [`Embed`] to the channel the command came from.
+    async fn send_embed(&self, embed: &Embed) -> Result<()>;
+    /// Sends a [`Response`] to wherever the command came from.
+    /// Only slash commands can be answered ephemerally,
+    /// so by default it's sent as a normal message.
+    async fn send_response(&self, response: &Response) -> Result<()> {
+        self.send_message(&response.content).await
+    }
+    /// Replies to the message the command came from.
+    async fn reply(&self, response: &Response) -> Result<()>;
+    /// Sends pages of a long output to the channel the command came from,
+    /// as one message that can be flipped through.
+    async fn send_pages(&self, pages: Vec<String>) -> Result<()>;
+    /// Sends a message to another channel.
+    async fn send_message_to(&self, channel_id: u64, message: &str) -> Result<()>;
+    /// Sends an embed to another channel.
+    async fn send_embed_to(&self, channel_id: u64, embed: &Embed) -> Result<()>;
+    /// Fetches a message from a channel.
+    async fn fetch_message(&self, channel_id: u64, message_id: u64) -> Result<ReportedMessage>;
+    /// Sends a direct message to a user, whether or not they're in the guild.
+    async fn message_user(&self, user_id: u64, message: &str) -> Result<()>;
+    /// Bans a user with a reason.
+    async fn ban_user(&self, user_id: u64, reason: &str) -> Result<()>;
+    /// Bans a user, deleting the messages they sent in the last `delete_days` days.
+    async fn ban_user_purging(&self, user_id: u64, delete_days: u8, reason: &str) -> Result<()>;
+    /// Lifts a user's ban.
+    async fn unban_user(&self, user_id: u64) -> Result<()>;
+    /// Mutes a user for a specified [`Time`].
+    async fn mute_user(&self, user_id: u64, time: Time, reason: &str) -> Result<()>;
+    /// Sets a member's nickname, or resets it if [`None`].
+    async fn set_nickname(&self, user_id: u64, nickname: Option<&str>) -> Result<()>;
+    /// Gets the `(id, name)` of every role in the guild the command was sent in.
+    async fn guild_roles(&self) -> Result<Vec<(u64, String)>>;
+    /// Gets the IDs of the roles a member has.
+    async fn member_roles(&self, user_id: u64) -> Result<Vec<u64>>;
+    /// Gets the unix timestamp a member's timeout ends at, if they have one.
+    async fn timed_out_until(&self, user_id: u64) -> Result<Option<i64>>;
+    /// Gives a member a role.
+    async fn add_role(&self, user_id: u64, role_id: u64) -> Result<()>;
+    /// Takes a role from a member.
+    async fn remove_role(&self, user_id: u64, role_id: u64) -> Result<()>;
+    /// Gets the IDs of every text channel in the guild the command was sent in.
+    async fn text_channels(&self) -> Result<Vec<u64>>;
+    /// Stops or lets `@everyone` send messages in a channel.
+    async fn everyone_overwrite(&self, channel_id: u64) -> Result<Option<EveryoneOverwrite>>;
+    /// Sets a channel's `@everyone` permission overwrite, or removes it if given [`None`].
+    async fn set_everyone_overwrite(
+        &self,
+        channel_id: u64,
+        overwrite: Option<EveryoneOverwrite>,
+    ) -> Result<()>;
+    /// Gets the name of a user.
+    async fn user_name(&self, user_id: u64) -> Result<String>;
+    /// Checks whether the user who sent the command is a moderator.
+    async fn author_is_mod(&self) -> Result<bool>;
+    /// Gets the details of a channel in the guild the command was sent in.
+    async fn channel_details(&self, channel_id: u64) -> Result<ChannelDetails>;
+    /// Gets the ID of the guild a channel really belongs to, if any.
+    async fn channel_guild(&self, channel_id: u64) -> Result<Option<u64>>;
+    /// Gets every custom emoji in the guild the command was sent in.
+    async fn guild_emojis(&self) -> Result<Vec<GuildEmoji>>;
+    /// Copies an emoji into the guild the command was sent in, under a name.
+    async fn create_emoji(&self, emoji: &GuildEmoji, name: &str) -> Result<GuildEmoji>;
+    /// Gets the names of every member of the guild the command was sent in.
+    async fn guild_members(&self) -> Result<Vec<MemberName>>;
+    /// Gets the bot's permissions in the guild the command was sent in.
+    async fn bot_permissions(&self) -> Result<Permissions>;
+    /// Gets the highest role positions of the bot and a user
+    /// in the guild the command was sent in.
+    async fn role_positions(&self, user_id: u64) -> Result<RolePositions>;
+    /// Gets the position and permissions of a role
+    /// in the guild the command was sent in.
+    async fn role_standing(&self, role_id: u64) -> Result<RoleStanding>;
+    /// Fetches up to `limit` messages from a channel,
+    /// only counting messages sent after `after` if it's given.
+    async fn channel_history(
+        &self,
+        channel_id: u64,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<HistoryMessage>>;
+    /// Bulk-deletes messages from a channel.
+    async fn delete_messages(&self, channel_id: u64, message_ids: &[u64]) -> Result<()>;
+    /// Sends a message to the channel the command came from and reacts to it,
+    /// returning the ID of the sent message.
+    async fn send_with_reaction(&self, message: &str, emoji: &str) -> Result<u64>;
+    /// Gets the IDs of every user who reacted to a message with an emoji, not counting bots.
+    async fn reaction_users(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        emoji: &str,
+    ) -> Result<Vec<u64>>;
+    /// Gets the ID of the user who sent the command.
+    fn author_id(&self) -> u64;
+    /// Gets the ID of the bot itself.
+    fn bot_id(&self) -> u64;
+    /// Gets the ID of the guild the command was sent in, if any.
+    fn guild_id(&self) -> Option<u64>;
+    /// Gets the ID of the channel the command was sent in.
+    fn channel_id(&self) -> u64;
+    /// Gets the ID of the message the command came from.
+    fn message_id(&self) -> u64;
+    /// Gets the [`Metrics`] commands should be recorded in.
+    fn metrics(&self) -> &Metrics;
+    /// Counts what serenity's cache is holding.
+    fn cache_counts(&self) -> CacheCounts;
+    /// Starts showing a typing indicator in the channel the command came from,
+    /// returning [`None`] if it couldn't be started.
+    fn start_typing(&self) -> Option<Typing>;
+}
+
+/// Shows a typing indicator while a slow future runs, returning what it returns.
+/// Typing stops once the future finishes, whether it succeeded or not.
+pub async fn with_typing<T>(
+    sink: &(impl CommandSink + ?Sized),
+    future: impl Future<Output = T>,
+) -> T {
+    let typing = sink.start_typing();
+    let output = future.await;
+    if let Some(typing) = typing {
+        typing.stop();
+    }
+    output
+}
+
+/// Fills in one of serenity's embed builders from an [`Embed`].
+pub fn build_embed<'a>(create: &'a mut CreateEmbed, embed: &Embed) -> &'a mut CreateEmbed {
+    create.title(&embed.title).description(&embed.description);
+    for (name, value) in &embed.fields {
+        create.field(name, value, false);
+    }
+    create
+}
+
+/// A message sent in response to a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Response {
+    /// The text of the message
+    pub content: String,
+    /// Whether only the user who sent the command can see the message
+    pub ephemeral: bool,
+}
+
+impl Response {
+    /// Creates a [`Response`] everyone can see.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ephemeral: false,
+        }
+    }
+    /// Creates a [`Response`] only the user who sent the command can see.
+    pub fn ephemeral(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ephemeral: true,
+        }
+    }
+}
+
+/// A simple embed, independent of serenity's builders.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Embed {
+    /// The title of the embed
+    pub title: String,
+    /// The main text of the embed
+    pub description: String,
+    /// Named fields shown under the description, as `(name, value)`
+    pub fields: Vec<(String, String)>,
+}
+
+#[async_trait::async_trait]
+impl CommandSink for BotShard<'_> {
+    async fn send_message(&self, message: &str) -> Result<()> {
+        BotShard::send_message(self, message).await?;
+        Ok(())
+    }
+    async fn send_pages(&self, pages: Vec<String>) -> Result<()> {
+        BotShard::send_paginated(self, pages).await?;
+        Ok(())
+    }
+    async fn reply(&self, response: &Response) -> Result<()> {
+        BotShard::reply(self, &response.content).await?;
+        Ok(())
+    }
+    async fn send_message_to(&self, channel_id: u64, message: &str) -> Result<()> {
+        BotShard::send_message_to(self, message, channel_id).await?;
+        Ok(())
+    }
+    async fn send_embed(&self, embed: &Embed) -> Result<()> {
+        self.original_message()
+            .channel_id
+            .send_message(self.http_server(), |message| {
+                message.embed(|create| build_embed(create, embed))
+            })
+            .await?;
+        Ok(())
+    }
+    async fn send_embed_to(&self, channel_id: u64, embed: &Embed) -> Result<()> {
+        send_embed_to(self.http_server(), channel_id, embed).await
+    }
+    async fn fetch_message(&self, channel_id: u64, message_id: u64) -> Result<ReportedMessage> {
+        fetch_reported_message(self.http_server(), channel_id, message_id).await
+    }
+    async fn message_user(&self, user_id: u64, message: &str) -> Result<()> {
+        guild::message_user(self.http_server(), user_id, message).await
+    }
+    async fn ban_user(&self, user_id: u64, reason: &str) -> Result<()> {
+        guild::ban_user(
+            self.http_server(),
+            BotShard::guild_id(self)?,
+            user_id,
+            0,
+            reason,
+        )
+        .await
+    }
+    async fn ban_user_purging(&self, user_id: u64, delete_days: u8, reason: &str) -> Result<()> {
+        let guild_id = BotShard::guild_id(self)?;
+        guild::ban_user(self.http_server(), guild_id, user_id, delete_days, reason).await
+    }
+    async fn unban_user(&self, user_id: u64) -> Result<()> {
+        guild::unban_user(self.http_server(), BotShard::guild_id(self)?, user_id).await
+    }
+    async fn mute_user(&self, user_id: u64, time: Time, _reason: &str) -> Result<()> {
+        guild::mute_member(self.http_server(), BotShard::guild_id(self)?, user_id, time).await
+    }
+    async fn set_nickname(&self, user_id: u64, nickname: Option<&str>) -> Result<()> {
+        guild::set_nickname(
+            self.http_server(),
+            BotShard::guild_id(self)?,
+            user_id,
+            nickname,
+        )
+        .await
+    }
+    async fn guild_roles(&self) -> Result<Vec<(u64, String)>> {
+        guild::guild_roles(self.http_server(), BotShard::guild_id(self)?).await
+    }
+    async fn member_roles(&self, user_id: u64) -> Result<Vec<u64>> {
+        guild::member_roles(self.http_server(), BotShard::guild_id(self)?, user_id).await
+    }
+    async fn timed_out_until(&self, user_id: u64) -> Result<Option<i64>> {
+        guild::timed_out_until(self.http_server(), BotShard::guild_id(self)?, user_id).await
+    }
+    async fn add_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+        guild::add_role(
+            self.http_server(),
+            BotShard::guild_id(self)?,
+            user_id,
+            role_id,
+        )
+        .await
+    }
+    async fn remove_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+        guild::remove_role(
+            self.http_server(),
+            BotShard::guild_id(self)?,
+            user_id,
+            role_id,
+        )
+        .await
+    }
+    async fn text_channels(&self) -> Result<Vec<u64>> {
+        guild::text_channels(self.http_server(), BotShard::guild_id(self)?).await
+    }
+    async fn everyone_overwrite(&self, channel_id: u64) -> Result<Option<EveryoneOverwrite>> {
+        everyone_overwrite(self.http_server(), channel_id).await
+    }
+    async fn set_everyone_overwrite(
+        &self,
+        channel_id: u64,
+        overwrite: Option<EveryoneOverwrite>,
+    ) -> Result<()> {
+        set_everyone_overwrite(self.http_server(), channel_id, overwrite).await
+    }
+    async fn user_name(&self, user_id: u64) -> Result<String> {
+        guild::user_name(self.http_server(), user_id).await
+    }
+    async fn author_is_mod(&self) -> Result<bool> {
+        self.user_is_mod(self.author().id.0).await
+    }
+    async fn channel_details(&self, channel_id: u64) -> Result<ChannelDetails> {
+        channel_details(self.http_server(), channel_id).await
+    }
+    async fn channel_guild(&self, channel_id: u64) -> Result<Option<u64>> {
+        channel_guild(self.http_server(), channel_id).await
+    }
+    async fn guild_emojis(&self) -> Result<Vec<GuildEmoji>> {
+        guild_emojis(self.http_server(), BotShard::guild_id(self)?).await
+    }
+    async fn create_emoji(&self, emoji: &GuildEmoji, name: &str) -> Result<GuildEmoji> {
+        steal_emoji(self.http_server(), BotShard::guild_id(self)?, emoji, name).await
+    }
+    async fn guild_members(&self) -> Result<Vec<MemberName>> {
+        member_names(self.http_server(), BotShard::guild_id(self)?).await
+    }
+    async fn bot_permissions(&self) -> Result<Permissions> {
+        bot_permissions(self.context(), GuildId(BotShard::guild_id(self)?)).await
+    }
+    async fn role_positions(&self, user_id: u64) -> Result<RolePositions> {
+        role_positions(self.context(), GuildId(BotShard::guild_id(self)?), user_id).await
+    }
+    async fn role_standing(&self, role_id: u64) -> Result<RoleStanding> {
+        role_standing(self.context(), GuildId(BotShard::guild_id(self)?), role_id)
+    }
+    async fn channel_history(
+        &self,
+        channel_id: u64,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<HistoryMessage>> {
+        channel_history(self.http_server(), channel_id, after, limit).await
+    }
+    async fn delete_messages(&self, channel_id: u64, message_ids: &[u64]) -> Result<()> {
+        delete_messages(self.http_server(), channel_id, message_ids).await
+    }
+    async fn send_with_reaction(&self, message: &str, emoji: &str) -> Result<u64> {
+        let sent = BotShard::send_message(self, message).await?;
+        sent.react(self.http_server(), ReactionType::Unicode(emoji.to_owned()))
+            .await?;
+        Ok(sent.id.0)
+    }
+    async fn reaction_users(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        emoji: &str,
+    ) -> Result<Vec<u64>> {
+        reaction_users(self.http_server(), channel_id, message_id, emoji).await
+    }
+    fn author_id(&self) -> u64 {
+        self.author().id.0
+    }
+    fn bot_id(&self) -> u64 {
+        self.cache().current_user_id().0
+    }
+    fn guild_id(&self) -> Option<u64> {
+        BotShard::guild_id(self).ok()
+    }
+    fn channel_id(&self) -> u64 {
+        self.original_message().channel_id.0
+    }
+    fn message_id(&self) -> u64 {
+        self.original_message().id.0
+    }
+    fn metrics(&self) -> &Metrics {
+        &METRICS
+    }
+    fn cache_counts(&self) -> CacheCounts {
+        CacheCounts::from_cache(self.cache())
+    }
+    fn start_typing(&self) -> Option<Typing> {
+        match self
+            .original_message()
+            .channel_id
+            .start_typing(&self.context().http)
+        {
+            Ok(typing) => Some(typing),
+            Err(e) => {
+                eprintln!("Unable to start typing: {e}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! Deals with slash commands: registering them with discord,
+//! turning interactions back into [`Command`]s, and responding to them.
+
+use crate::{
+    backend::{Command, CommandType, Time, PREFIX, XKCD_KEYWORDS},
+    channels::{channel_details, channel_guild, ChannelDetails},
+    database::query_database,
+    diagnostics::CacheCounts,
+    emojis::{guild_emojis, steal_emoji, GuildEmoji},
+    guild,
+    lockdown::{everyone_overwrite, set_everyone_overwrite, EveryoneOverwrite},
+    members::{member_names, MemberName},
+    metrics::{Metrics, METRICS},
+    pagination::page_content,
+    permissions::{bot_permissions, role_positions, role_standing, RolePositions, RoleStanding},
+    purge::{channel_history, delete_messages, HistoryMessage},
+    reactions::reaction_users,
+    report::{fetch_reported_message, ReportedMessage},
+    sink::{build_embed, send_embed_to, CommandSink, Embed, Response},
+};
+use eyre::{eyre, Result};
+use serenity::{
+    builder::{CreateInteractionResponseData, CreateInteractionResponseFollowup},
+    http::Typing,
+    json::Value,
+    model::{
+        application::{
+            command::{Command as ApplicationCommand, CommandOptionType},
+            interaction::{
+                application_command::ApplicationCommandInteraction,
+                autocomplete::AutocompleteInteraction, InteractionResponseType,
+            },
+        },
+        prelude::{GuildId, ReactionType},
+        Permissions, Timestamp,
+    },
+    prelude::Context,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The type of a slash command option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashKind {
+    /// Any text
+    String,
+    /// A whole number
+    Integer,
+    /// A user picked from the server
+    User,
+}
+
+/// An option of a slash command, standing in for one of the prefix command's arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlashOption {
+    /// The name of the option
+    pub name: &'static str,
+    /// What the option is for, shown while typing it
+    pub description: &'static str,
+    /// The type of the option
+    pub kind: SlashKind,
+    /// Whether the option has to be given
+    pub required: bool,
+    /// Whether the argument is written as `name:value` in the prefix command
+    pub keyword: bool,
+    /// Whether suggestions are shown while typing the option
+    pub autocomplete: bool,
+}
+
+impl SlashOption {
+    const fn new(name: &'static str, description: &'static str, kind: SlashKind) -> Self {
+        Self {
+            name,
+            description,
+            kind,
+            required: false,
+            keyword: false,
+            autocomplete: false,
+        }
+    }
+    const fn required(self) -> Self {
+        Self {
+            required: true,
+            ..self
+        }
+    }
+    const fn keyword(self) -> Self {
+        Self {
+            keyword: true,
+            ..self
+        }
+    }
+    const fn autocomplete(self) -> Self {
+        Self {
+            autocomplete: true,
+            ..self
+        }
+    }
+}
+
+/// Every command that can be used as a slash command.
+pub const SLASH_COMMANDS: [CommandType; 8] = [
+    CommandType::CoinFlip,
+    CommandType::RandomInt,
+    CommandType::Xkcd,
+    CommandType::DontAskToAsk,
+    CommandType::Keke,
+    CommandType::Help,
+    CommandType::Stats,
+    CommandType::TimeParse,
+];
+
+/// Gets the options of a command's slash command, in the order the prefix command takes them,
+/// or [`None`] if it can't be used as a slash command.
+pub fn slash_options(command: CommandType) -> Option<&'static [SlashOption]> {
+    const COINFLIP: [SlashOption; 1] =
+        [SlashOption::new("count", "How many coins to flip", SlashKind::Integer).keyword()];
+    const RANDOM_INT: [SlashOption; 2] = [
+        SlashOption::new("min", "The smallest number to pick", SlashKind::Integer),
+        SlashOption::new("max", "The largest number to pick", SlashKind::Integer).required(),
+    ];
+    const XKCD: [SlashOption; 1] =
+        [
+            SlashOption::new("comic", "A comic number or keyword", SlashKind::String)
+                .autocomplete(),
+        ];
+    const HELP: [SlashOption; 1] =
+        [SlashOption::new("command", "A command to explain", SlashKind::String).autocomplete()];
+    const TIME_PARSE: [SlashOption; 1] =
+        [SlashOption::new("time", "A duration, like 1h30m", SlashKind::String).required()];
+    Some(match command {
+        CommandType::CoinFlip => &COINFLIP,
+        CommandType::RandomInt => &RANDOM_INT,
+        CommandType::Xkcd => &XKCD,
+        CommandType::Help => &HELP,
+        CommandType::TimeParse => &TIME_PARSE,
+        CommandType::DontAskToAsk | CommandType::Keke | CommandType::Stats => &[],
+        _ => return None,
+    })
+}
+
+/// Turns a slash command's name and `(name, value)` options into the [`Command`]
+/// its prefix command would parse as, so both are handled the same way.
+pub fn command_from_interaction(name: &str, options: &[(String, String)], author: &str) -> Command {
+    let Some((command, slash_options)) = SLASH_COMMANDS
+        .into_iter()
+        .find(|command| command.name() == name)
+        .and_then(|command| Some((command, slash_options(command)?)))
+    else {
+        return Command::NotValid(format!("`/{name}` isn't a slash command I know!"));
+    };
+    let given = slash_options
+        .iter()
+        .filter_map(|option| {
+            let (_, value) = options.iter().find(|(name, _)| name == option.name)?;
+            Some((option, value))
+        })
+        .collect::<Vec<_>>();
+    let words = given.iter().enumerate().map(|(index, (option, value))| {
+        match (
+            option.keyword,
+            index + 1 < given.len() && value.contains(char::is_whitespace),
+        ) {
+            (true, _) => format!("{}:{value}", option.name),
+            (false, true) => format!("\"{value}\""),
+            (false, false) => value.to_string(),
+        }
+    });
+    let text = std::iter::once(format!("{PREFIX}{}", command.name()))
+        .chain(words)
+        .collect::<Vec<_>>()
+        .join(" ");
+    Command::parse(&text, author)
+}
+
+/// Registers every slash command with discord, replacing any old ones.
+pub async fn register(ctx: &Context) -> Result<()> {
+    ApplicationCommand::set_global_application_commands(&ctx.http, |commands| {
+        for command in SLASH_COMMANDS {
+            let options = slash_options(command).unwrap_or_default();
+            commands.create_application_command(|create| {
+                let description = command.description().lines().next().unwrap_or_default();
+                create.name(command.name()).description(description);
+                // discord wants required options listed first
+                for option in options.iter().filter(|option| option.required) {
+                    create.create_option(|create| add_option(create, option));
+                }
+                for option in options.iter().filter(|option| !option.required) {
+                    create.create_option(|create| add_option(create, option));
+                }
+                create
+            });
+        }
+        commands
+    })
+    .await?;
+    Ok(())
+}
+
+fn add_option<'a>(
+    create: &'a mut serenity::builder::CreateApplicationCommandOption,
+    option: &SlashOption,
+) -> &'a mut serenity::builder::CreateApplicationCommandOption {
+    create
+        .name(option.name)
+        .description(option.description)
+        .kind(match option.kind {
+            SlashKind::String => CommandOptionType::String,
+            SlashKind::Integer => CommandOptionType::Integer,
+            SlashKind::User => CommandOptionType::User,
+        })
+        .required(option.required)
+        .set_autocomplete(option.autocomplete)
+}
+
+/// The most suggestions discord shows at once.
+pub const MAX_SUGGESTIONS: usize = 25;
+
+/// Gets the suggestions for an option of a slash command, given what's been typed so far.
+pub fn suggestions(command: CommandType, partial: &str) -> Vec<String> {
+    match command {
+        CommandType::Help => filter_suggestions(
+            CommandType::ALL
+                .iter()
+                .filter(|command| !command.aliases().is_empty())
+                .map(CommandType::name),
+            partial,
+        ),
+        CommandType::Xkcd => {
+            filter_suggestions(XKCD_KEYWORDS.iter().map(|(keyword, _)| *keyword), partial)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Picks the candidates containing what's been typed so far, ignoring case.
+/// Candidates starting with it come first, and at most [`MAX_SUGGESTIONS`] are kept.
+pub fn filter_suggestions<'a>(
+    candidates: impl IntoIterator<Item = &'a str>,
+    partial: &str,
+) -> Vec<String> {
+    let partial = partial.trim().to_lowercase();
+    let mut matches = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().contains(&partial))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|candidate| !candidate.to_lowercase().starts_with(&partial));
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Answers an autocomplete request with suggestions for the option being typed.
+pub async fn autocomplete(ctx: &Context, interaction: &AutocompleteInteraction) {
+    let Some(command) = SLASH_COMMANDS
+        .into_iter()
+        .find(|command| command.name() == interaction.data.name)
+    else {
+        return;
+    };
+    let partial = interaction
+        .data
+        .options
+        .iter()
+        .find(|option| option.focused)
+        .and_then(|option| option.value.as_ref())
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let suggestions = suggestions(command, partial);
+    let result = interaction
+        .create_autocomplete_response(&ctx.http, |response| {
+            for suggestion in &suggestions {
+                response.add_string_choice(suggestion, suggestion);
+            }
+            response
+        })
+        .await;
+    if let Err(e) = result {
+        eprintln!("Unable to send suggestions: {e}");
+    }
+}
+
+/// Handles a slash command by running the [`Command`] it stands for.
+pub async fn dispatch(ctx: &Context, interaction: &ApplicationCommandInteraction) {
+    let options = interaction
+        .data
+        .options
+        .iter()
+        .filter_map(|option| {
+            let value = match option.value.as_ref()? {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            Some((option.name.clone(), value))
+        })
+        .collect::<Vec<_>>();
+    let command =
+        command_from_interaction(&interaction.data.name, &options, &interaction.user.name);
+    let sink = InteractionSink::new(ctx, interaction);
+    let now = Timestamp::now().unix_timestamp();
+    let dispatched = match query_database() {
+        Ok(db) => command.dispatch(db, &sink, now).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = dispatched {
+        eprintln!("Unable to execute slash command: {e}");
+    }
+}
+
+/// A [`CommandSink`] that responds to a slash command.
+/// The first message is the interaction's response; any after it are followups.
+pub struct InteractionSink<'a> {
+    ctx: &'a Context,
+    interaction: &'a ApplicationCommandInteraction,
+    responded: AtomicBool,
+}
+
+impl<'a> InteractionSink<'a> {
+    /// Creates a new [`InteractionSink`] that hasn't responded yet.
+    pub fn new(ctx: &'a Context, interaction: &'a ApplicationCommandInteraction) -> Self {
+        Self {
+            ctx,
+            interaction,
+            responded: AtomicBool::new(false),
+        }
+    }
+    fn guild(&self) -> Result<GuildId> {
+        self.interaction
+            .guild_id
+            .ok_or_else(|| eyre!("This only works inside a server"))
+    }
+    async fn respond(&self, message: &str, embed: Option<&Embed>, ephemeral: bool) -> Result<()> {
+        let http = &self.ctx.http;
+        if self.responded.swap(true, Ordering::SeqCst) {
+            self.interaction
+                .create_followup_message(http, |followup| {
+                    followup_data(followup, message, embed, ephemeral)
+                })
+                .await?;
+        } else {
+            self.interaction
+                .create_interaction_response(http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| {
+                            response_data(data, message, embed, ephemeral)
+                        })
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Fills in the first response to a slash command.
+/// Ephemeral responses are only shown to the user who used the command.
+pub fn response_data<'a, 'b>(
+    data: &'a mut CreateInteractionResponseData<'b>,
+    message: &str,
+    embed: Option<&Embed>,
+    ephemeral: bool,
+) -> &'a mut CreateInteractionResponseData<'b> {
+    if let Some(embed) = embed {
+        data.embed(|create| build_embed(create, embed));
+    }
+    data.content(message).ephemeral(ephemeral)
+}
+
+/// Fills in a followup to a slash command that has already been responded to.
+/// Ephemeral followups are only shown to the user who used the command.
+pub fn followup_data<'a, 'b>(
+    followup: &'a mut CreateInteractionResponseFollowup<'b>,
+    message: &str,
+    embed: Option<&Embed>,
+    ephemeral: bool,
+) -> &'a mut CreateInteractionResponseFollowup<'b> {
+    if let Some(embed) = embed {
+        followup.embed(|create| build_embed(create, embed));
+    }
+    followup.content(message).ephemeral(ephemeral)
+}
+
+#[async_trait::async_trait]
+impl CommandSink for InteractionSink<'_> {
+    async fn send_message(&self, message: &str) -> Result<()> {
+        self.respond(message, None, false).await
+    }
+    async fn send_response(&self, response: &Response) -> Result<()> {
+        self.respond(&response.content, None, response.ephemeral)
+            .await
+    }
+    async fn send_pages(&self, pages: Vec<String>) -> Result<()> {
+        for index in 0..pages.len() {
+            self.respond(&page_content(&pages, index), None, false)
+                .await?;
+        }
+        Ok(())
+    }
+    async fn reply(&self, response: &Response) -> Result<()> {
+        self.send_response(response).await
+    }
+    async fn send_message_to(&self, channel_id: u64, message: &str) -> Result<()> {
+        serenity::model::prelude::ChannelId(channel_id)
+            .say(&self.ctx.http, message)
+            .await?;
+        Ok(())
+    }
+    async fn send_embed(&self, embed: &Embed) -> Result<()> {
+        self.respond("", Some(embed), false).await
+    }
+    async fn send_embed_to(&self, channel_id: u64, embed: &Embed) -> Result<()> {
+        send_embed_to(&self.ctx.http, channel_id, embed).await
+    }
+    async fn fetch_message(&self, channel_id: u64, message_id: u64) -> Result<ReportedMessage> {
+        fetch_reported_message(&self.ctx.http, channel_id, message_id).await
+    }
+    async fn message_user(&self, user_id: u64, message: &str) -> Result<()> {
+        guild::message_user(&self.ctx.http, user_id, message).await
+    }
+    async fn ban_user(&self, user_id: u64, reason: &str) -> Result<()> {
+        guild::ban_user(&self.ctx.http, self.guild()?.0, user_id, 0, reason).await
+    }
+    async fn ban_user_purging(&self, user_id: u64, delete_days: u8, reason: &str) -> Result<()> {
+        guild::ban_user(
+            &self.ctx.http,
+            self.guild()?.0,
+            user_id,
+            delete_days,
+            reason,
+        )
+        .await
+    }
+    async fn unban_user(&self, user_id: u64) -> Result<()> {
+        guild::unban_user(&self.ctx.http, self.guild()?.0, user_id).await
+    }
+    async fn mute_user(&self, user_id: u64, time: Time, _reason: &str) -> Result<()> {
+        guild::mute_member(&self.ctx.http, self.guild()?.0, user_id, time).await
+    }
+    async fn set_nickname(&self, user_id: u64, nickname: Option<&str>) -> Result<()> {
+        guild::set_nickname(&self.ctx.http, self.guild()?.0, user_id, nickname).await
+    }
+    async fn guild_roles(&self) -> Result<Vec<(u64, String)>> {
+        guild::guild_roles(&self.ctx.http, self.guild()?.0).await
+    }
+    async fn member_roles(&self, user_id: u64) -> Result<Vec<u64>> {
+        guild::member_roles(&self.ctx.http, self.guild()?.0, user_id).await
+    }
+    async fn timed_out_until(&self, user_id: u64) -> Result<Option<i64>> {
+        guild::timed_out_until(&self.ctx.http, self.guild()?.0, user_id).await
+    }
+    async fn add_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+        guild::add_role(&self.ctx.http, self.guild()?.0, user_id, role_id).await
+    }
+    async fn remove_role(&self, user_id: u64, role_id: u64) -> Result<()> {
+        guild::remove_role(&self.ctx.http, self.guild()?.0, user_id, role_id).await
+    }
+    async fn text_channels(&self) -> Result<Vec<u64>> {
+        guild::text_channels(&self.ctx.http, self.guild()?.0).await
+    }
+    async fn everyone_overwrite(&self, channel_id: u64) -> Result<Option<EveryoneOverwrite>> {
+        everyone_overwrite(&self.ctx.http, channel_id).await
+    }
+    async fn set_everyone_overwrite(
+        &self,
+        channel_id: u64,
+        overwrite: Option<EveryoneOverwrite>,
+    ) -> Result<()> {
+        set_everyone_overwrite(&self.ctx.http, channel_id, overwrite).await
+    }
+    async fn user_name(&self, user_id: u64) -> Result<String> {
+        guild::user_name(&self.ctx.http, user_id).await
+    }
+    async fn author_is_mod(&self) -> Result<bool> {
+        let permissions = self
+            .interaction
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .ok_or_else(|| eyre!("Unable to get the permissions of the user"))?;
+        Ok(permissions.contains(Permissions::BAN_MEMBERS))
+    }
+    async fn channel_details(&self, channel_id: u64) -> Result<ChannelDetails> {
+        channel_details(&self.ctx.http, channel_id).await
+    }
+    async fn channel_guild(&self, channel_id: u64) -> Result<Option<u64>> {
+        channel_guild(&self.ctx.http, channel_id).await
+    }
+    async fn guild_emojis(&self) -> Result<Vec<GuildEmoji>> {
+        guild_emojis(&self.ctx.http, self.guild()?.0).await
+    }
+    async fn create_emoji(&self, emoji: &GuildEmoji, name: &str) -> Result<GuildEmoji> {
+        steal_emoji(&self.ctx.http, self.guild()?.0, emoji, name).await
+    }
+    async fn guild_members(&self) -> Result<Vec<MemberName>> {
+        member_names(&self.ctx.http, self.guild()?.0).await
+    }
+    async fn bot_permissions(&self) -> Result<Permissions> {
+        bot_permissions(self.ctx, self.guild()?).await
+    }
+    async fn role_positions(&self, user_id: u64) -> Result<RolePositions> {
+        role_positions(self.ctx, self.guild()?, user_id).await
+    }
+    async fn role_standing(&self, role_id: u64) -> Result<RoleStanding> {
+        role_standing(self.ctx, self.guild()?, role_id)
+    }
+    async fn channel_history(
+        &self,
+        channel_id: u64,
+        after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<HistoryMessage>> {
+        channel_history(&self.ctx.http, channel_id, after, limit).await
+    }
+    async fn delete_messages(&self, channel_id: u64, message_ids: &[u64]) -> Result<()> {
+        delete_messages(&self.ctx.http, channel_id, message_ids).await
+    }
+    async fn send_with_reaction(&self, message: &str, emoji: &str) -> Result<u64> {
+        self.respond(message, None, false).await?;
+        let sent = self
+            .interaction
+            .get_interaction_response(&self.ctx.http)
+            .await?;
+        sent.react(&self.ctx.http, ReactionType::Unicode(emoji.to_owned()))
+            .await?;
+        Ok(sent.id.0)
+    }
+    async fn reaction_users(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        emoji: &str,
+    ) -> Result<Vec<u64>> {
+        reaction_users(&self.ctx.http, channel_id, message_id, emoji).await
+    }
+    fn author_id(&self) -> u64 {
+        self.interaction.user.id.0
+    }
+    fn bot_id(&self) -> u64 {
+        self.ctx.cache.current_user_id().0
+    }
+    fn guild_id(&self) -> Option<u64> {
+        self.interaction.guild_id.map(|id| id.0)
+    }
+    fn channel_id(&self) -> u64 {
+        self.interaction.channel_id.0
+    }
+    fn message_id(&self) -> u64 {
+        self.interaction.id.0
+    }
+    fn metrics(&self) -> &Metrics {
+        &METRICS
+    }
+    fn cache_counts(&self) -> CacheCounts {
+        CacheCounts::from_cache(&self.ctx.cache)
+    }
+    fn start_typing(&self) -> Option<Typing> {
+        None
+    }
+}
@@ -0,0 +1,179 @@
+//! Deals with sniping: remembering the last message deleted or edited in each channel
+//! for a short while, so it can be posted again.
+
+use crate::{
+    database::{query_database, Database},
+    settings::guild_setting,
+};
+use eyre::Result;
+use serenity::{
+    model::{
+        prelude::{ChannelId, GuildId, Message, MessageId},
+        Timestamp,
+    },
+    prelude::Context,
+};
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// The last deleted message in each channel, for the running bot.
+pub static SNIPES: ChannelCache<SnipedMessage> = ChannelCache::new(SNIPE_TTL_SECONDS);
+/// The last edited message in each channel, for the running bot.
+pub static EDIT_SNIPES: ChannelCache<EditedMessage> = ChannelCache::new(SNIPE_TTL_SECONDS);
+
+/// How long a deleted message can be sniped for, in seconds.
+pub const SNIPE_TTL_SECONDS: i64 = 5 * 60;
+/// How many messages are kept in serenity's cache per channel,
+/// so deleted messages' content is still known.
+pub const CACHED_MESSAGES: usize = 100;
+/// The setting holding whether messages deleted in NSFW channels can be sniped.
+pub const SNIPE_NSFW: &str = "snipe_nsfw";
+
+/// A deleted message, as it was before being deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnipedMessage {
+    /// The ID of the message's author
+    pub author: u64,
+    /// The content of the message
+    pub content: String,
+    /// When the message was sent, as a unix timestamp
+    pub time: i64,
+}
+
+/// An edited message, as it was before and after the edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditedMessage {
+    /// The ID of the message's author
+    pub author: u64,
+    /// The content before the edit
+    pub before: String,
+    /// The content after the edit
+    pub after: String,
+    /// When the message was edited, as a unix timestamp
+    pub time: i64,
+}
+
+#[derive(Debug)]
+struct Cached<T> {
+    value: T,
+    expires: i64,
+}
+
+/// Keeps the latest value for each channel, until it expires.
+#[derive(Debug)]
+pub struct ChannelCache<T> {
+    entries: Mutex<BTreeMap<u64, Cached<T>>>,
+    ttl: i64,
+}
+
+impl<T: Clone> ChannelCache<T> {
+    /// Creates a new, empty [`ChannelCache`] whose values last `ttl` seconds.
+    pub const fn new(ttl: i64) -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            ttl,
+        }
+    }
+    /// Stores a channel's latest value at a unix timestamp, replacing the previous one.
+    pub fn insert(&self, channel_id: u64, value: T, now: i64) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.retain(|_, cached| cached.expires > now);
+        entries.insert(
+            channel_id,
+            Cached {
+                value,
+                expires: now + self.ttl,
+            },
+        );
+    }
+    /// Gets a channel's latest value at a unix timestamp, if it hasn't expired.
+    pub fn get(&self, channel_id: u64, now: i64) -> Option<T> {
+        let mut entries = self.entries.lock().ok()?;
+        entries.retain(|_, cached| cached.expires > now);
+        entries.get(&channel_id).map(|cached| cached.value.clone())
+    }
+}
+
+/// Returns whether messages deleted in a guild's NSFW channels can be sniped.
+pub fn snipe_nsfw_allowed(db: &Database, guild_id: u64) -> Result<bool> {
+    Ok(guild_setting(db, guild_id, SNIPE_NSFW)?.is_some_and(|allowed| allowed == "true"))
+}
+
+/// Returns whether messages in a channel can be sniped:
+/// anywhere but NSFW channels, unless the guild allows it.
+fn snipeable_channel(
+    ctx: &Context,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+) -> Result<bool> {
+    let nsfw = ctx
+        .cache
+        .guild_channel(channel_id)
+        .is_some_and(|channel| channel.nsfw);
+    match (nsfw, guild_id) {
+        (true, Some(guild_id)) => snipe_nsfw_allowed(&query_database()?, guild_id.0),
+        _ => Ok(true),
+    }
+}
+
+/// Remembers a message that was just deleted, if serenity's cache still has it.
+/// Messages from bots aren't remembered,
+/// and neither are messages in NSFW channels unless the guild allows it.
+pub fn record_deletion(
+    ctx: &Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    guild_id: Option<GuildId>,
+) -> Result<()> {
+    let Some(message) = ctx.cache.message(channel_id, message_id) else {
+        return Ok(());
+    };
+    if message.author.bot
+        || message.content.is_empty()
+        || !snipeable_channel(ctx, channel_id, guild_id)?
+    {
+        return Ok(());
+    }
+    SNIPES.insert(
+        channel_id.0,
+        SnipedMessage {
+            author: message.author.id.0,
+            content: message.content,
+            time: message.timestamp.unix_timestamp(),
+        },
+        Timestamp::now().unix_timestamp(),
+    );
+    Ok(())
+}
+
+/// Remembers a message's content from before it was just edited,
+/// if serenity's cache had the old version.
+/// Updates that don't change the content, like embeds loading, are ignored.
+/// The same messages are skipped as with [`record_deletion`].
+pub fn record_edit(ctx: &Context, old: Option<&Message>, new: Option<&Message>) -> Result<()> {
+    let (Some(old), Some(new)) = (old, new) else {
+        return Ok(());
+    };
+    if new.author.bot
+        || old.content == new.content
+        || !snipeable_channel(ctx, new.channel_id, new.guild_id)?
+    {
+        return Ok(());
+    }
+    let time = new
+        .edited_timestamp
+        .unwrap_or_else(Timestamp::now)
+        .unix_timestamp();
+    EDIT_SNIPES.insert(
+        new.channel_id.0,
+        EditedMessage {
+            author: new.author.id.0,
+            before: old.content.clone(),
+            after: new.content.clone(),
+            time,
+        },
+        Timestamp::now().unix_timestamp(),
+    );
+    Ok(())
+}
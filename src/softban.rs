@@ -0,0 +1,14 @@
+//! Deals with `-softban`: banning and immediately unbanning a user,
+//! which kicks them and clears their recent messages.
+
+/// How many days of messages a softban deletes. Discord allows at most 7.
+pub const SOFTBAN_DELETE_DAYS: u8 = 7;
+
+/// The message sent to a softbanned user, so they know they can come back.
+pub fn softban_notice(reason: &str) -> String {
+    format!(
+        "You were removed from the __Baba is You Discord Server__ and your recent messages were \
+        cleaned up, for the following reason:\n> *{reason}*\n\
+        This is not a permanent ban, so you're free to rejoin."
+    )
+}
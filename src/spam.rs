@@ -0,0 +1,233 @@
+//! Deals with anti-spam: keeping a short history of each member's messages,
+//! timing out members who repeat themselves or post too quickly, and alerting the mods.
+
+use crate::{
+    database::{query_database, Database},
+    settings::{clear_guild_setting, guild_setting, mod_channel, set_guild_setting},
+    shard::BotShard,
+};
+use eyre::Result;
+use serenity::model::{prelude::ChannelId, Timestamp};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+    sync::Mutex,
+};
+
+/// The recent messages for the running bot.
+pub static SPAM_GUARD: SpamGuard = SpamGuard::new();
+
+/// The setting holding how many identical messages in a row count as spam.
+pub const SPAM_MAX_REPEATS: &str = "spam_max_repeats";
+/// The setting holding how many messages within the window count as spam.
+pub const SPAM_MAX_MESSAGES: &str = "spam_max_messages";
+/// The setting holding how long the window for counting messages is, in seconds.
+pub const SPAM_WINDOW: &str = "spam_window";
+/// How long spammers are timed out for, in seconds.
+pub const SPAM_TIMEOUT_SECONDS: i64 = 5 * 60;
+
+/// A guild's anti-spam settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpamConfig {
+    /// How many identical messages in a row count as spam
+    pub max_repeats: usize,
+    /// How many messages within the window count as spam
+    pub max_messages: usize,
+    /// How long the window is, in seconds
+    pub window_seconds: i64,
+}
+
+impl SpamConfig {
+    /// Gets a guild's anti-spam settings, or [`None`] if it's turned off.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<Option<Self>> {
+        let Some(max_repeats) = guild_setting(db, guild_id, SPAM_MAX_REPEATS)? else {
+            return Ok(None);
+        };
+        let Some(max_messages) = guild_setting(db, guild_id, SPAM_MAX_MESSAGES)? else {
+            return Ok(None);
+        };
+        let Some(window_seconds) = guild_setting(db, guild_id, SPAM_WINDOW)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            max_repeats: max_repeats.parse()?,
+            max_messages: max_messages.parse()?,
+            window_seconds: window_seconds.parse()?,
+        }))
+    }
+    /// Saves the settings for a guild, or turns anti-spam off if [`None`].
+    pub fn apply(config: Option<Self>, db: &Database, guild_id: u64) -> Result<()> {
+        match config {
+            Some(config) => {
+                set_guild_setting(
+                    db,
+                    guild_id,
+                    SPAM_MAX_REPEATS,
+                    &config.max_repeats.to_string(),
+                )?;
+                set_guild_setting(
+                    db,
+                    guild_id,
+                    SPAM_MAX_MESSAGES,
+                    &config.max_messages.to_string(),
+                )?;
+                set_guild_setting(
+                    db,
+                    guild_id,
+                    SPAM_WINDOW,
+                    &config.window_seconds.to_string(),
+                )
+            }
+            None => {
+                clear_guild_setting(db, guild_id, SPAM_MAX_REPEATS)?;
+                clear_guild_setting(db, guild_id, SPAM_MAX_MESSAGES)?;
+                clear_guild_setting(db, guild_id, SPAM_WINDOW)
+            }
+        }
+    }
+}
+
+impl Display for SpamConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Members will be timed out for posting the same message {} times in a row, \
+            or {} messages within {} seconds.",
+            self.max_repeats, self.max_messages, self.window_seconds
+        )
+    }
+}
+
+/// Whether a message looks like spam.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpamVerdict {
+    /// The message is fine
+    Clean,
+    /// The same message was posted too many times in a row
+    Repeated,
+    /// Too many messages were posted within the window
+    Flooding,
+}
+
+impl Display for SpamVerdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpamVerdict::Clean => write!(f, "not spamming"),
+            SpamVerdict::Repeated => write!(f, "posting the same message repeatedly"),
+            SpamVerdict::Flooding => write!(f, "posting too many messages too quickly"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MessageHistory {
+    messages: VecDeque<(i64, String)>,
+}
+
+/// Tracks the recent messages of each member of each guild.
+#[derive(Debug, Default)]
+pub struct SpamGuard {
+    members: Mutex<BTreeMap<(u64, u64), MessageHistory>>,
+}
+
+impl SpamGuard {
+    /// Creates a new [`SpamGuard`] with no messages recorded.
+    pub const fn new() -> Self {
+        Self {
+            members: Mutex::new(BTreeMap::new()),
+        }
+    }
+    /// Records that a member posted a message at a unix timestamp,
+    /// and returns whether they're spamming.
+    /// Only repeats within the window count, and messages without text,
+    /// such as attachments and stickers, never count as repeats.
+    /// A member's history is cleared once they're caught, so they aren't caught twice.
+    pub fn record_message(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        content: &str,
+        now: i64,
+        config: SpamConfig,
+    ) -> SpamVerdict {
+        let Ok(mut members) = self.members.lock() else {
+            return SpamVerdict::Clean;
+        };
+        let history = &mut members.entry((guild_id, user_id)).or_default().messages;
+        history.push_back((now, content.trim().to_lowercase()));
+        while history.len() > config.max_repeats.max(config.max_messages) {
+            history.pop_front();
+        }
+        let latest = &history[history.len() - 1].1;
+        let repeats = match latest.is_empty() {
+            true => 0,
+            false => history
+                .iter()
+                .rev()
+                .take_while(|(sent, content)| {
+                    content == latest && *sent > now - config.window_seconds
+                })
+                .count(),
+        };
+        let recent = history
+            .iter()
+            .filter(|(sent, _)| *sent > now - config.window_seconds)
+            .count();
+        let verdict = if repeats >= config.max_repeats {
+            SpamVerdict::Repeated
+        } else if recent >= config.max_messages {
+            SpamVerdict::Flooding
+        } else {
+            SpamVerdict::Clean
+        };
+        if verdict != SpamVerdict::Clean {
+            history.clear();
+        }
+        verdict
+    }
+}
+
+/// Checks a message against the guild's anti-spam settings,
+/// timing out its author and alerting the mods if they're spamming.
+/// Returns whether the author was timed out.
+pub async fn guard_message(shard: &BotShard<'_>) -> Result<bool> {
+    let message = shard.original_message();
+    let Ok(guild_id) = shard.guild_id() else {
+        return Ok(false);
+    };
+    if message.author.bot {
+        return Ok(false);
+    }
+    let db = query_database()?;
+    let Some(config) = SpamConfig::for_guild(&db, guild_id)? else {
+        return Ok(false);
+    };
+    let now = Timestamp::now().unix_timestamp();
+    let verdict =
+        SPAM_GUARD.record_message(guild_id, message.author.id.0, &message.content, now, config);
+    if verdict == SpamVerdict::Clean || shard.user_is_mod(message.author.id.0).await? {
+        return Ok(false);
+    }
+    shard
+        .member_request(message.author.id)
+        .await?
+        .disable_communication_until_datetime(
+            shard.http_server(),
+            Timestamp::from_unix_timestamp(now + SPAM_TIMEOUT_SECONDS)?,
+        )
+        .await?;
+    if let Some(channel_id) = mod_channel(&db, guild_id)? {
+        ChannelId(channel_id)
+            .say(
+                shard.http_server(),
+                format!(
+                    "{} was timed out for {} minutes for {verdict} in <#{}>.",
+                    message.author,
+                    SPAM_TIMEOUT_SECONDS / 60,
+                    message.channel_id
+                ),
+            )
+            .await?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,219 @@
+//! Deals with the starboard, where messages with enough ⭐ reactions
+//! are reposted so they're easy to find later.
+
+use crate::{
+    config::config,
+    database::{query_database, Database},
+    reactions::ReactionEvent,
+    settings::{clear_guild_setting, guild_setting, set_guild_setting},
+};
+use eyre::Result;
+use rusqlite::OptionalExtension;
+use serenity::{
+    model::prelude::{ChannelId, GuildId, ReactionType},
+    prelude::Context,
+};
+use std::fmt::Display;
+
+/// The emoji counted towards the starboard.
+pub const STAR: &str = "⭐";
+/// The setting holding the channel starred messages are posted in.
+pub const STARBOARD_CHANNEL: &str = "starboard_channel";
+/// The setting holding how many stars a message needs.
+pub const STARBOARD_THRESHOLD: &str = "starboard_threshold";
+/// How many stars a message needs if the threshold was never set.
+pub const DEFAULT_THRESHOLD: u64 = 3;
+
+/// A change to a guild's starboard settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarboardSetting {
+    /// Post starred messages in this channel
+    Channel(u64),
+    /// Require this many stars
+    Threshold(u64),
+    /// Stop posting starred messages
+    Disable,
+}
+
+impl StarboardSetting {
+    /// Saves the setting for a guild.
+    pub fn apply(self, db: &Database, guild_id: u64) -> Result<()> {
+        match self {
+            StarboardSetting::Channel(channel_id) => {
+                set_guild_setting(db, guild_id, STARBOARD_CHANNEL, &channel_id.to_string())
+            }
+            StarboardSetting::Threshold(threshold) => {
+                set_guild_setting(db, guild_id, STARBOARD_THRESHOLD, &threshold.to_string())
+            }
+            StarboardSetting::Disable => clear_guild_setting(db, guild_id, STARBOARD_CHANNEL),
+        }
+    }
+}
+
+impl Display for StarboardSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StarboardSetting::Channel(channel_id) => {
+                write!(f, "Starred messages will now be posted in <#{channel_id}>.")
+            }
+            StarboardSetting::Threshold(threshold) => {
+                write!(f, "Messages now need {threshold} {STAR} to be starred.")
+            }
+            StarboardSetting::Disable => write!(f, "The starboard has been turned off."),
+        }
+    }
+}
+
+/// A guild's starboard settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StarboardConfig {
+    /// The channel starred messages are posted in
+    pub channel_id: u64,
+    /// How many stars a message needs
+    pub threshold: u64,
+}
+
+impl StarboardConfig {
+    /// Gets a guild's starboard settings, or [`None`] if it has no starboard channel.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<Option<Self>> {
+        let Some(channel_id) = guild_setting(db, guild_id, STARBOARD_CHANNEL)? else {
+            return Ok(None);
+        };
+        let threshold = guild_setting(db, guild_id, STARBOARD_THRESHOLD)?
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(config().star_threshold);
+        Ok(Some(Self {
+            channel_id: channel_id.parse()?,
+            threshold,
+        }))
+    }
+}
+
+/// What should happen to a message's starboard post after its stars change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarboardAction {
+    /// The message crossed the threshold, so post it
+    Post,
+    /// The message is still over the threshold, so update the post's star count
+    Update(u64),
+    /// The message dropped below the threshold, so remove the post
+    Remove(u64),
+    /// The message isn't (and wasn't) starred
+    Nothing,
+}
+
+/// Decides what to do with a message, given how many stars it has
+/// and its existing starboard post, if any.
+pub fn starboard_action(stars: u64, threshold: u64, post: Option<u64>) -> StarboardAction {
+    match (stars >= threshold, post) {
+        (true, None) => StarboardAction::Post,
+        (true, Some(post)) => StarboardAction::Update(post),
+        (false, Some(post)) => StarboardAction::Remove(post),
+        (false, None) => StarboardAction::Nothing,
+    }
+}
+
+/// The text shown above a starboard post.
+pub fn star_header(stars: u64, channel_id: u64) -> String {
+    format!("{STAR} **{stars}** in <#{channel_id}>")
+}
+
+/// Gets the starboard post for a message, if it has one.
+pub fn starboard_post(db: &Database, message_id: u64) -> Result<Option<u64>> {
+    Ok(db
+        .query_row(
+            "SELECT post FROM starboard_posts WHERE message = (?1)",
+            [message_id],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Records the starboard post for a message.
+pub fn set_starboard_post(db: &Database, guild_id: u64, message_id: u64, post: u64) -> Result<()> {
+    db.execute(
+        "INSERT OR REPLACE INTO starboard_posts (message, guild, post) VALUES ((?1), (?2), (?3))",
+        (message_id, guild_id, post),
+    )?;
+    Ok(())
+}
+
+/// Forgets the starboard post for a message.
+pub fn remove_starboard_post(db: &Database, message_id: u64) -> Result<()> {
+    db.execute(
+        "DELETE FROM starboard_posts WHERE message = (?1)",
+        [message_id],
+    )?;
+    Ok(())
+}
+
+/// Posts, updates, or removes a message's starboard post after a ⭐ reaction changes.
+pub async fn update_starboard(ctx: &Context, event: &ReactionEvent) -> Result<()> {
+    let Some(guild_id) = event.guild_id else {
+        return Ok(());
+    };
+    let db = query_database()?;
+    let Some(config) = StarboardConfig::for_guild(&db, guild_id)? else {
+        return Ok(());
+    };
+    if event.channel_id == config.channel_id {
+        return Ok(());
+    }
+    let message = ctx
+        .http
+        .get_message(event.channel_id, event.message_id)
+        .await?;
+    let stars = message
+        .reactions
+        .iter()
+        .find(|reaction| reaction.reaction_type == ReactionType::Unicode(STAR.to_owned()))
+        .map(|reaction| reaction.count)
+        .unwrap_or_default();
+    let starboard = ChannelId(config.channel_id);
+    let header = star_header(stars, event.channel_id);
+    match starboard_action(
+        stars,
+        config.threshold,
+        starboard_post(&db, event.message_id)?,
+    ) {
+        StarboardAction::Post => {
+            let post = starboard
+                .send_message(&ctx.http, |create| {
+                    create.content(&header).embed(|embed| {
+                        embed
+                            .author(|author| {
+                                author
+                                    .name(&message.author.name)
+                                    .icon_url(message.author.face())
+                            })
+                            .description(&message.content)
+                            .field(
+                                "Source",
+                                // fetched messages don't know their guild, so the link is built here
+                                format!(
+                                    "[Jump to message]({})",
+                                    message
+                                        .id
+                                        .link(ChannelId(event.channel_id), Some(GuildId(guild_id)))
+                                ),
+                                false,
+                            )
+                            .timestamp(message.timestamp)
+                    })
+                })
+                .await?;
+            set_starboard_post(&db, guild_id, event.message_id, post.id.0)?;
+        }
+        StarboardAction::Update(post) => {
+            starboard
+                .edit_message(&ctx.http, post, |edit| edit.content(&header))
+                .await?;
+        }
+        StarboardAction::Remove(post) => {
+            starboard.delete_message(&ctx.http, post).await?;
+            remove_starboard_post(&db, event.message_id)?;
+        }
+        StarboardAction::Nothing => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Deals with the command log and the [`CommandStats`] built from it.
+
+use crate::backend::CommandType;
+use crate::database::Database;
+use crate::sink::Embed;
+use eyre::Result;
+
+/// How many of the most used commands are shown in [`CommandStats`].
+pub const TOP_COMMANDS: usize = 5;
+
+/// Records that a user ran a command.
+/// Commands sent in DMs have no guild.
+pub fn log_command(
+    db: &Database,
+    guild_id: Option<u64>,
+    user_id: u64,
+    command: CommandType,
+    time: i64,
+) -> Result<()> {
+    db.execute(
+        "INSERT INTO command_log (guild, user, command, time) VALUES ((?1), (?2), (?3), (?4))",
+        (guild_id, user_id, command.name(), time),
+    )?;
+    Ok(())
+}
+
+/// A summary of the commands run in a guild.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandStats {
+    /// The total number of commands run
+    pub total: u64,
+    /// The most used commands and how often they were run, most used first
+    pub top_commands: Vec<(String, u64)>,
+    /// The number of different users that ran a command
+    pub unique_users: u64,
+}
+
+impl CommandStats {
+    /// Aggregates the command log for a single guild.
+    pub fn for_guild(db: &Database, guild_id: u64) -> Result<CommandStats> {
+        let (total, unique_users) = db.query_row(
+            "SELECT COUNT(*), COUNT(DISTINCT user) FROM command_log WHERE guild = (?1)",
+            (guild_id,),
+            |row| Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?)),
+        )?;
+        let top_commands = db
+            .prepare(
+                "
+                SELECT command, COUNT(*) AS uses FROM command_log
+                WHERE guild = (?1)
+                GROUP BY command
+                ORDER BY uses DESC, command ASC
+                LIMIT (?2)
+                ",
+            )?
+            .query_map((guild_id, TOP_COMMANDS), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CommandStats {
+            total,
+            top_commands,
+            unique_users,
+        })
+    }
+}
+
+impl CommandStats {
+    /// Renders the statistics as an [`Embed`].
+    pub fn to_embed(&self) -> Embed {
+        let top_commands = if self.top_commands.is_empty() {
+            "Nothing yet!".to_owned()
+        } else {
+            self.top_commands
+                .iter()
+                .enumerate()
+                .map(|(rank, (command, uses))| format!("{}. `{command}` - {uses}", rank + 1))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Embed {
+            title: "Command Statistics".to_owned(),
+            description: format!(
+                "Total commands run: {}\nUnique users: {}",
+                self.total, self.unique_users
+            ),
+            fields: vec![("Most used commands".to_owned(), top_commands)],
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Deals with tags: canned responses mods save under a name,
+//! which anyone can post again later.
+
+use crate::database::Database;
+use eyre::Result;
+use rusqlite::OptionalExtension;
+
+/// The placeholder in a tag's content that's replaced with a mention of whoever posted it.
+pub const USER_PLACEHOLDER: &str = "{user}";
+/// Names that can't be used for tags, since they're `-tag` subcommands.
+pub const RESERVED_TAG_NAMES: [&str; 3] = ["add", "remove", "list"];
+
+/// Something to do with the tags of a guild.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagAction {
+    /// Posts a tag
+    Show(String),
+    /// Saves a tag, replacing any with the same name
+    Add {
+        #[doc = "the name of the tag"]
+        name: String,
+        #[doc = "what the tag posts"]
+        content: String,
+    },
+    /// Deletes a tag
+    Remove(String),
+    /// Lists every tag
+    List,
+}
+
+/// Gets the name a tag is stored under: lowercase, without surrounding whitespace.
+pub fn tag_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Saves a tag in a guild, returning whether it replaced an existing one.
+pub fn set_tag(db: &Database, guild_id: u64, name: &str, content: &str) -> Result<bool> {
+    let replaced = tag(db, guild_id, name)?.is_some();
+    db.execute(
+        "INSERT OR REPLACE INTO tags (guild, name, content) VALUES ((?1), (?2), (?3))",
+        (guild_id, tag_key(name), content),
+    )?;
+    Ok(replaced)
+}
+
+/// Deletes a tag from a guild, returning whether it existed.
+pub fn remove_tag(db: &Database, guild_id: u64, name: &str) -> Result<bool> {
+    Ok(db.execute(
+        "DELETE FROM tags WHERE guild = (?1) AND name = (?2)",
+        (guild_id, tag_key(name)),
+    )? > 0)
+}
+
+/// Gets the content of a tag in a guild, if it exists.
+pub fn tag(db: &Database, guild_id: u64, name: &str) -> Result<Option<String>> {
+    Ok(db
+        .query_row(
+            "SELECT content FROM tags WHERE guild = (?1) AND name = (?2)",
+            (guild_id, tag_key(name)),
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Gets the names of every tag in a guild, in alphabetical order.
+pub fn tags(db: &Database, guild_id: u64) -> Result<Vec<String>> {
+    let mut statement = db.prepare("SELECT name FROM tags WHERE guild = (?1) ORDER BY name")?;
+    let names = statement
+        .query_map([guild_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(names)
+}
+
+/// Fills in the placeholders of a tag's content for the user posting it.
+pub fn fill_placeholders(content: &str, user_id: u64) -> String {
+    content.replace(USER_PLACEHOLDER, &format!("<@{user_id}>"))
+}
@@ -0,0 +1,103 @@
+//! Deals with `-userinfo`: summarizing a user,
+//! with extra moderation details for mods.
+
+use crate::{
+    modlog::field_value,
+    notes::Note,
+    purge::snowflake_time,
+    sink::{CommandSink, Embed},
+};
+
+/// Looks up when a member's timeout ends.
+/// Users who left or were banned can't be timed out,
+/// so a failed member lookup counts as no timeout rather than an error.
+pub async fn current_timeout(sink: &impl CommandSink, user_id: u64) -> Option<i64> {
+    sink.timed_out_until(user_id).await.ok().flatten()
+}
+
+/// What `-userinfo` shows about a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserInfo {
+    /// The ID of the user
+    pub user_id: u64,
+    /// The user's name
+    pub name: String,
+    /// The notes mods kept about the user, or [`None`] if the viewer isn't a mod
+    pub notes: Option<Vec<Note>>,
+    /// The user's current punishments, or [`None`] if the viewer isn't a mod
+    pub moderation: Option<ModerationSummary>,
+}
+
+/// A one-glance view of where a member stands with the mods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModerationSummary {
+    /// When the member's timeout ends, if they're timed out
+    pub timed_out_until: Option<i64>,
+    /// How many of the member's warnings haven't expired
+    pub active_warnings: usize,
+    /// How many notes mods kept about the member
+    pub notes: usize,
+}
+
+impl ModerationSummary {
+    /// Combines a member's timeout with their warning and note counts,
+    /// ignoring a timeout that already ended by the unix timestamp `now`.
+    pub fn new(
+        timed_out_until: Option<i64>,
+        now: i64,
+        active_warnings: usize,
+        notes: usize,
+    ) -> Self {
+        Self {
+            timed_out_until: timed_out_until.filter(|until| *until > now),
+            active_warnings,
+            notes,
+        }
+    }
+    /// The lines of the summary's embed field.
+    pub fn field_value(&self) -> String {
+        let timeout = match self.timed_out_until {
+            Some(until) => format!("Timed out until <t:{until}:f>"),
+            None => "Not timed out".to_owned(),
+        };
+        format!(
+            "{timeout}\n{} active warning{}\n{} note{}",
+            self.active_warnings,
+            if self.active_warnings == 1 { "" } else { "s" },
+            self.notes,
+            if self.notes == 1 { "" } else { "s" },
+        )
+    }
+}
+
+impl UserInfo {
+    /// Builds the embed `-userinfo` posts.
+    pub fn to_embed(&self) -> Embed {
+        let mut fields = vec![
+            ("ID".to_owned(), self.user_id.to_string()),
+            (
+                "Account created".to_owned(),
+                format!("<t:{}:R>", snowflake_time(self.user_id)),
+            ),
+        ];
+        if let Some(moderation) = &self.moderation {
+            fields.push(("Moderation".to_owned(), moderation.field_value()));
+        }
+        if let Some(notes) = &self.notes {
+            let value = match notes.as_slice() {
+                [] => "None".to_owned(),
+                notes => notes
+                    .iter()
+                    .map(|note| format!("• {} (<@{}>)", note.text, note.moderator))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+            fields.push(("Notes".to_owned(), field_value(&value)));
+        }
+        Embed {
+            title: self.name.clone(),
+            description: format!("<@{}>", self.user_id),
+            fields,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Deals with warnings: recording them against members,
+//! listing and clearing them, ignoring old ones once they expire,
+//! and acting automatically once a member has too many.
+
+use crate::{
+    backend::{Time, TimeErr},
+    database::Database,
+    settings::{clear_guild_setting, guild_setting, set_guild_setting},
+};
+use eyre::Result;
+use std::{fmt::Display, str::FromStr};
+
+/// The setting holding how long warnings count for, in seconds.
+pub const WARN_EXPIRY: &str = "warn_expiry";
+
+/// A warning given to a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The ID of the warning
+    pub id: u64,
+    /// The ID of the mod who gave the warning
+    pub moderator: u64,
+    /// Why the warning was given
+    pub reason: String,
+    /// When the warning was given, as a unix timestamp
+    pub time: i64,
+}
+
+impl Warning {
+    /// Returns whether the warning still counts at a unix timestamp,
+    /// given how long warnings count for.
+    pub fn is_active(&self, now: i64, expiry: Option<i64>) -> bool {
+        expiry.is_none_or(|expiry| self.time > now - expiry)
+    }
+}
+
+/// Records a warning against a member, returning its ID.
+pub fn add_warning(
+    db: &Database,
+    guild_id: u64,
+    user_id: u64,
+    moderator: u64,
+    reason: &str,
+    time: i64,
+) -> Result<u64> {
+    db.execute(
+        "
+        INSERT INTO warnings (guild, user, moderator, reason, time)
+        VALUES ((?1), (?2), (?3), (?4), (?5))
+        ",
+        (guild_id, user_id, moderator, reason, time),
+    )?;
+    Ok(db.last_insert_rowid() as u64)
+}
+
+/// Gets every warning a member has in a guild, oldest first.
+pub fn warnings(db: &Database, guild_id: u64, user_id: u64) -> Result<Vec<Warning>> {
+    let mut statement = db.prepare(
+        "
+        SELECT id, moderator, reason, time FROM warnings
+        WHERE guild = (?1) AND user = (?2) ORDER BY time, id
+        ",
+    )?;
+    let warnings = statement
+        .query_map((guild_id, user_id), |row| {
+            Ok(Warning {
+                id: row.get(0)?,
+                moderator: row.get(1)?,
+                reason: row.get(2)?,
+                time: row.get(3)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(warnings)
+}
+
+/// Deletes every warning a member has in a guild, returning how many there were.
+pub fn clear_warnings(db: &Database, guild_id: u64, user_id: u64) -> Result<usize> {
+    Ok(db.execute(
+        "DELETE FROM warnings WHERE guild = (?1) AND user = (?2)",
+        (guild_id, user_id),
+    )?)
+}
+
+/// Gets how long warnings count for in a guild, in seconds,
+/// or [`None`] if they never expire.
+pub fn warn_expiry(db: &Database, guild_id: u64) -> Result<Option<i64>> {
+    Ok(guild_setting(db, guild_id, WARN_EXPIRY)?
+        .map(|expiry| expiry.parse())
+        .transpose()?)
+}
+
+/// Sets how long warnings count for in a guild, in seconds,
+/// or makes them never expire if [`None`].
+pub fn set_warn_expiry(db: &Database, guild_id: u64, expiry: Option<i64>) -> Result<()> {
+    match expiry {
+        Some(expiry) => set_guild_setting(db, guild_id, WARN_EXPIRY, &expiry.to_string()),
+        None => clear_guild_setting(db, guild_id, WARN_EXPIRY),
+    }
+}
+
+/// Counts the warnings a member has in a guild that haven't expired at a unix timestamp.
+pub fn active_warning_count(db: &Database, guild_id: u64, user_id: u64, now: i64) -> Result<usize> {
+    let expiry = warn_expiry(db, guild_id)?;
+    Ok(warnings(db, guild_id, user_id)?
+        .iter()
+        .filter(|warning| warning.is_active(now, expiry))
+        .count())
+}
+
+/// What happens to a member automatically once they have enough warnings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escalation {
+    /// Times the member out
+    Mute(Time),
+    /// Bans the member
+    Ban,
+}
+
+impl FromStr for Escalation {
+    type Err = TimeErr;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ban" => Ok(Escalation::Ban),
+            time => Ok(Escalation::Mute(time.parse()?)),
+        }
+    }
+}
+
+impl Display for Escalation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Escalation::Mute(time) => write!(f, "a {time} mute"),
+            Escalation::Ban => write!(f, "a ban"),
+        }
+    }
+}
+
+/// Gets a guild's escalation policy, as pairs of warning counts and what happens at them,
+/// ordered by warning count.
+pub fn escalation_policy(db: &Database, guild_id: u64) -> Result<Vec<(usize, Escalation)>> {
+    let mut statement = db.prepare(
+        "SELECT warnings, action FROM warn_escalations WHERE guild = (?1) ORDER BY warnings",
+    )?;
+    let policy = statement
+        .query_map([guild_id], |row| {
+            Ok((row.get::<_, usize>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    policy
+        .into_iter()
+        .map(|(warnings, action)| Ok((warnings, action.parse()?)))
+        .collect()
+}
+
+/// Sets what happens once a member has a number of warnings in a guild,
+/// or stops anything happening if [`None`].
+pub fn set_escalation(
+    db: &Database,
+    guild_id: u64,
+    warnings: usize,
+    escalation: Option<Escalation>,
+) -> Result<()> {
+    match escalation {
+        Some(escalation) => {
+            let action = match escalation {
+                Escalation::Mute(time) => time.to_string(),
+                Escalation::Ban => "ban".to_owned(),
+            };
+            db.execute(
+                "
+                INSERT INTO warn_escalations (guild, warnings, action) VALUES ((?1), (?2), (?3))
+                ON CONFLICT (guild, warnings) DO UPDATE SET action = excluded.action
+                ",
+                (guild_id, warnings, action),
+            )?
+        }
+        None => db.execute(
+            "DELETE FROM warn_escalations WHERE guild = (?1) AND warnings = (?2)",
+            (guild_id, warnings),
+        )?,
+    };
+    Ok(())
+}
+
+/// Decides what happens to a member with a number of active warnings,
+/// using the step of the policy with the highest count they've reached.
+pub fn escalation_for(policy: &[(usize, Escalation)], warnings: usize) -> Option<Escalation> {
+    policy
+        .iter()
+        .filter(|(threshold, _)| *threshold <= warnings)
+        .max_by_key(|(threshold, _)| *threshold)
+        .map(|(_, escalation)| *escalation)
+}